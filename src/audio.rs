@@ -0,0 +1,183 @@
+//! PipeWire sink discovery and ducking for wallpapers that play audio.
+//!
+//! Sinks and streams are read from `pw-dump`, so no PipeWire client library is
+//! linked. Ducking lowers audible wallpapers through their IPC socket while any
+//! other application is playing, and restores the previous volume afterwards.
+//! Unless `[audio] allow_multiple` is on, only one wallpaper plays sound at a time.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    process::{Command, Stdio},
+};
+
+use serde_json::{Value, json};
+use tracing::warn;
+
+use crate::{
+    config::{AudioSettings, WallpaperProfileEntry},
+    ipc,
+};
+
+/// A PipeWire output device that mpv can be pointed at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSink {
+    /// `node.name`; empty for the system default output.
+    pub name: String,
+    pub description: String,
+}
+
+impl AudioSink {
+    /// Placeholder entry meaning "let PipeWire decide".
+    pub fn system_default() -> Self {
+        Self {
+            name: String::new(),
+            description: "System default".into(),
+        }
+    }
+}
+
+impl fmt::Display for AudioSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+/// mpv's `--audio-device` value for the PipeWire sink `name`.
+pub fn device_arg(name: &str) -> String {
+    format!("pipewire/{name}")
+}
+
+/// Mute every enabled entry with `audio` after the first, unless `allow_multiple`,
+/// so two wallpapers never talk over each other. Returns the outputs muted.
+pub fn limit_audible(
+    entries: &mut [WallpaperProfileEntry],
+    settings: &AudioSettings,
+) -> Vec<String> {
+    if settings.allow_multiple {
+        return Vec::new();
+    }
+    let mut audible = entries
+        .iter_mut()
+        .filter(|entry| entry.enabled && entry.audio);
+    let Some(first) = audible.next() else {
+        return Vec::new();
+    };
+    let first = first.monitor.clone().unwrap_or_default();
+    audible
+        .map(|entry| {
+            entry.audio = false;
+            let monitor = entry.monitor.clone().unwrap_or_default();
+            warn!(
+                "{} plays sound already; muting {} (set [audio] allow_multiple = true to hear both)",
+                first, monitor
+            );
+            monitor
+        })
+        .collect()
+}
+
+/// List the PipeWire sinks currently available.
+pub fn list_sinks() -> Result<Vec<AudioSink>, Box<dyn Error>> {
+    let sinks = pipewire_nodes()?
+        .into_iter()
+        .filter(|props| props_str(props, "media.class") == Some("Audio/Sink"))
+        .filter_map(|props| {
+            let name = props_str(&props, "node.name")?.to_string();
+            let description = props_str(&props, "node.description")
+                .unwrap_or(&name)
+                .to_string();
+            Some(AudioSink { name, description })
+        })
+        .collect();
+    Ok(sinks)
+}
+
+/// Whether an application other than a wallpaper player is currently playing audio.
+pub fn other_audio_playing() -> Result<bool, Box<dyn Error>> {
+    let playing = pipewire_nodes_with_state()?
+        .into_iter()
+        .filter(|(props, state)| {
+            props_str(props, "media.class") == Some("Stream/Output/Audio") && state == "running"
+        })
+        .any(|(props, _)| props_str(&props, "application.process.binary") != Some("mpvpaper"));
+    Ok(playing)
+}
+
+/// Lowers audible wallpapers while other audio plays.
+#[derive(Debug, Clone, Default)]
+pub struct Ducker {
+    /// Volume each monitor had before it was ducked.
+    saved: HashMap<String, f64>,
+}
+
+impl Ducker {
+    /// Duck or restore `monitors` depending on what else is playing right now.
+    pub fn update(&mut self, monitors: &[String], settings: &AudioSettings) {
+        self.saved.retain(|monitor, _| monitors.contains(monitor));
+        if monitors.is_empty() {
+            return;
+        }
+
+        let duck = settings.duck
+            && other_audio_playing().unwrap_or_else(|err| {
+                warn!("Could not query PipeWire streams: {}", err);
+                false
+            });
+
+        for monitor in monitors {
+            match (duck, self.saved.contains_key(monitor)) {
+                (true, false) => {
+                    let Ok(volume) = ipc::get_property(monitor, "volume") else {
+                        continue;
+                    };
+                    let volume = volume.as_f64().unwrap_or(100.0);
+                    let ducked = volume.min(f64::from(settings.duck_volume));
+                    if ipc::set_property(monitor, "volume", json!(ducked)).is_ok() {
+                        self.saved.insert(monitor.clone(), volume);
+                    }
+                }
+                (false, true) => {
+                    if let Some(volume) = self.saved.remove(monitor) {
+                        let _ = ipc::set_property(monitor, "volume", json!(volume));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn pipewire_nodes() -> Result<Vec<Value>, Box<dyn Error>> {
+    Ok(pipewire_nodes_with_state()?
+        .into_iter()
+        .map(|(props, _)| props)
+        .collect())
+}
+
+/// Properties and state (`running`, `idle`, ...) of every PipeWire node.
+fn pipewire_nodes_with_state() -> Result<Vec<(Value, String)>, Box<dyn Error>> {
+    let output = Command::new("pw-dump")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| format!("Failed to run pw-dump: {err}"))?;
+    if !output.status.success() {
+        return Err("pw-dump could not reach PipeWire".into());
+    }
+
+    let objects: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    Ok(objects
+        .into_iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Node")
+        .map(|object| {
+            let state = object["info"]["state"].as_str().unwrap_or("").to_string();
+            (object["info"]["props"].clone(), state)
+        })
+        .collect())
+}
+
+fn props_str<'a>(props: &'a Value, key: &str) -> Option<&'a str> {
+    props.get(key).and_then(Value::as_str)
+}
@@ -0,0 +1,605 @@
+//! Programs that put a wallpaper on an output.
+//!
+//! Each [`Backend`] a config can name implements [`WallpaperBackend`], which the
+//! launchers go through to start, confirm, and stop players. mpvpaper and
+//! `wpe render` play anything mpv can; swaybg and swww only show still images, but
+//! need no mpv at all, and the web backend hands pages to a browser-based program
+//! of the user's choosing. An entry's `backend` overrides the top-level one.
+
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::ErrorKind,
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use tracing::{info, warn};
+
+use crate::{
+    config::{self, Backend, MediaKind, RuntimeConfig, ScaleMode, Settings},
+    deps,
+    error::WpeError,
+    ipc,
+    mpvpaper::{self, LaunchStage},
+    web,
+};
+
+/// Something that draws wallpapers.
+pub trait WallpaperBackend: Send + Sync {
+    fn kind(&self) -> Backend;
+
+    /// The program started for each wallpaper, for messages.
+    fn program(&self) -> &'static str;
+
+    /// Whether the program is in PATH.
+    fn installed(&self) -> bool;
+
+    /// Why this build of wpe cannot run the backend at all.
+    fn missing(&self) -> Option<String> {
+        None
+    }
+
+    /// Why this backend cannot show `config`, checked before anything is spawned.
+    fn unsupported(&self, config: &RuntimeConfig) -> Option<String>;
+
+    /// The command line that shows `config` on `monitor`.
+    fn command(
+        &self,
+        config: &RuntimeConfig,
+        settings: &Settings,
+        monitor: &str,
+    ) -> Result<Command, Box<dyn Error>>;
+
+    /// Whether the spawned process hands the wallpaper off and exits on purpose.
+    fn detaches(&self, config: &RuntimeConfig) -> bool;
+
+    /// Whether the player answers on mpv's IPC socket, so pausing, `wpe next`, live
+    /// tweaks, and frame capture reach it.
+    fn has_ipc(&self) -> bool {
+        false
+    }
+
+    /// Wait for a freshly spawned player on `monitor` to get through `stage`.
+    fn wait_for_stage(
+        &self,
+        monitor: &str,
+        pid: Option<u32>,
+        stage: LaunchStage,
+    ) -> Result<(), WpeError>;
+
+    /// Take down whatever this backend shows on `monitor`.
+    fn stop(&self, monitor: &str) -> Result<bool, Box<dyn Error>>;
+
+    /// Take down everything this backend shows, returning whether anything was up.
+    fn stop_all(&self) -> Result<bool, Box<dyn Error>>;
+}
+
+/// Every backend, in the order [`stop_all`] goes through them.
+const BACKENDS: &[&dyn WallpaperBackend] = &[&Mpvpaper, &Builtin, &Swaybg, &Swww, &Web];
+
+/// How long a player without IPC gets to fail on a bad file or output name.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// The implementation of `kind`.
+pub fn get(kind: Backend) -> &'static dyn WallpaperBackend {
+    BACKENDS
+        .iter()
+        .copied()
+        .find(|backend| backend.kind() == kind)
+        .expect("every backend kind has an implementation")
+}
+
+/// The backend `config` runs on: its own, or the top-level one.
+pub fn for_config(config: &RuntimeConfig, settings: &Settings) -> &'static dyn WallpaperBackend {
+    get(config.backend.unwrap_or(settings.backend))
+}
+
+/// Start the player for `config` on the backend it selects.
+pub fn spawn_instance(config: &RuntimeConfig, settings: &Settings) -> Result<Child, WpeError> {
+    let monitor = config
+        .monitor
+        .as_deref()
+        .ok_or_else(|| WpeError::config(None, "Wallpaper entry is missing a monitor assignment"))?;
+    let backend = for_config(config, settings);
+    if let Some(reason) = backend.missing() {
+        return Err(WpeError::backend_missing(Some(monitor), reason));
+    }
+    if let Some(reason) = backend.unsupported(config) {
+        return Err(WpeError::config(None, format!("{monitor}: {reason}")));
+    }
+    let mut command = backend
+        .command(config, settings, monitor)
+        .map_err(|err| WpeError::backend(Some(monitor), err))?;
+
+    info!(
+        "Launching {} for {} with source {}",
+        backend.program(),
+        monitor,
+        config.media.path().display()
+    );
+
+    command.spawn().map_err(|err| {
+        let message = format!(
+            "Failed to launch {} for {monitor}: {err}",
+            backend.program()
+        );
+        // The program not found may be the backend or a [process]/[security] wrapper.
+        if err.kind() == ErrorKind::NotFound {
+            WpeError::backend_missing(Some(monitor), message)
+        } else {
+            WpeError::backend(Some(monitor), message)
+        }
+    })
+}
+
+/// Stop every backend's players, returning whether any were running.
+pub fn stop_all() -> Result<bool, Box<dyn Error>> {
+    let mut stopped = false;
+    for backend in BACKENDS {
+        stopped |= backend.stop_all()?;
+    }
+    Ok(stopped)
+}
+
+/// Send the player's output to its log, so a failed launch can say why.
+pub fn attach_log(command: &mut Command, monitor: &str) -> Result<(), Box<dyn Error>> {
+    match ipc::log_path(monitor)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|path| Ok(File::create(path)?))
+    {
+        Ok(log) => {
+            command.stdout(log.try_clone()?);
+            command.stderr(log);
+        }
+        Err(err) => {
+            warn!("Could not open the player log for {monitor}: {err}");
+            command.stdout(Stdio::null());
+            command.stderr(Stdio::null());
+        }
+    }
+    Ok(())
+}
+
+/// `err` from the player on `monitor`, with the error it printed last.
+pub fn launch_failed(monitor: &str, err: impl fmt::Display) -> WpeError {
+    let err = WpeError::backend(Some(monitor), err);
+    match mpvpaper::last_error(monitor) {
+        Some(cause) => err.with_detail(cause),
+        None => err,
+    }
+}
+
+/// Why a still-image backend cannot show `config`.
+fn still_only(program: &str, config: &RuntimeConfig) -> Option<String> {
+    if let Some(reason) = builtin_only(program, config) {
+        return Some(reason);
+    }
+    if config.grid.is_some() || config.pip.is_some() {
+        return Some(format!(
+            "{program} cannot draw grids or picture-in-picture; use backend = \"mpvpaper\""
+        ));
+    }
+    match &config.media {
+        MediaKind::Image(_) => None,
+        media => Some(format!(
+            "{program} only shows still images, not {}; use backend = \"mpvpaper\"",
+            media.path().display()
+        )),
+    }
+}
+
+/// Time-lapse sets crossfade, parallax images follow the pointer, and shaders are
+/// compiled inside `wpe render`, which no other program can do.
+fn builtin_only(program: &str, config: &RuntimeConfig) -> Option<String> {
+    if config.timelapse.is_some() {
+        return Some(format!(
+            "{program} cannot crossfade a time-lapse set; use backend = \"builtin\""
+        ));
+    }
+    if config.shader.is_some() {
+        return Some(format!(
+            "{program} cannot draw a shader; use backend = \"builtin\""
+        ));
+    }
+    config
+        .parallax
+        .as_ref()
+        .map(|_| format!("{program} cannot draw a depth parallax; use backend = \"builtin\""))
+}
+
+/// Give a player that cannot report being up [`SETTLE_TIME`] to fail, once it is
+/// past spawning.
+fn settle(
+    monitor: &str,
+    pid: Option<u32>,
+    stage: LaunchStage,
+    program: &str,
+    what: &str,
+) -> Result<(), WpeError> {
+    if stage != LaunchStage::WaitingForSurface {
+        return Ok(());
+    }
+    thread::sleep(SETTLE_TIME);
+    match pid {
+        Some(pid) if !mpvpaper::process_alive(pid) => Err(launch_failed(
+            monitor,
+            format!("{program} for {monitor} exited before showing {what}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn pkill(args: &[&str]) -> Result<bool, Box<dyn Error>> {
+    Command::new("pkill")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .map_err(|err| format!("Failed to issue pkill: {}", err).into())
+}
+
+/// The external mpvpaper.
+struct Mpvpaper;
+
+impl WallpaperBackend for Mpvpaper {
+    fn kind(&self) -> Backend {
+        Backend::Mpvpaper
+    }
+
+    fn program(&self) -> &'static str {
+        "mpvpaper"
+    }
+
+    fn installed(&self) -> bool {
+        deps::probe().mpvpaper.installed
+    }
+
+    fn unsupported(&self, config: &RuntimeConfig) -> Option<String> {
+        builtin_only(self.program(), config)
+    }
+
+    fn command(
+        &self,
+        config: &RuntimeConfig,
+        settings: &Settings,
+        monitor: &str,
+    ) -> Result<Command, Box<dyn Error>> {
+        mpvpaper::build_command(config, settings, monitor, false).map_err(Into::into)
+    }
+
+    fn detaches(&self, config: &RuntimeConfig) -> bool {
+        config.mpvpaper.fork
+    }
+
+    fn has_ipc(&self) -> bool {
+        true
+    }
+
+    fn wait_for_stage(
+        &self,
+        monitor: &str,
+        pid: Option<u32>,
+        stage: LaunchStage,
+    ) -> Result<(), WpeError> {
+        mpvpaper::wait_for_stage(monitor, pid, stage)
+    }
+
+    fn stop(&self, monitor: &str) -> Result<bool, Box<dyn Error>> {
+        mpvpaper::stop_for_monitor(monitor).map_err(Into::into)
+    }
+
+    fn stop_all(&self) -> Result<bool, Box<dyn Error>> {
+        mpvpaper::stop_all().map_err(Into::into)
+    }
+}
+
+/// libmpv inside wpe, as `wpe render` processes taking mpvpaper's arguments.
+struct Builtin;
+
+impl WallpaperBackend for Builtin {
+    fn kind(&self) -> Backend {
+        Backend::Builtin
+    }
+
+    fn program(&self) -> &'static str {
+        "wpe render"
+    }
+
+    fn installed(&self) -> bool {
+        true
+    }
+
+    fn missing(&self) -> Option<String> {
+        (!cfg!(feature = "builtin"))
+            .then(|| "backend = \"builtin\" needs a wpe built with `--features builtin`".into())
+    }
+
+    fn unsupported(&self, _config: &RuntimeConfig) -> Option<String> {
+        None
+    }
+
+    fn command(
+        &self,
+        config: &RuntimeConfig,
+        settings: &Settings,
+        monitor: &str,
+    ) -> Result<Command, Box<dyn Error>> {
+        mpvpaper::build_command(config, settings, monitor, true).map_err(Into::into)
+    }
+
+    fn detaches(&self, _config: &RuntimeConfig) -> bool {
+        false
+    }
+
+    fn has_ipc(&self) -> bool {
+        true
+    }
+
+    fn wait_for_stage(
+        &self,
+        monitor: &str,
+        pid: Option<u32>,
+        stage: LaunchStage,
+    ) -> Result<(), WpeError> {
+        mpvpaper::wait_for_stage(monitor, pid, stage)
+    }
+
+    // mpvpaper's stop matches `wpe render` by the same command line.
+    fn stop(&self, monitor: &str) -> Result<bool, Box<dyn Error>> {
+        mpvpaper::stop_for_monitor(monitor).map_err(Into::into)
+    }
+
+    // mpvpaper's stop_all already took these down.
+    fn stop_all(&self) -> Result<bool, Box<dyn Error>> {
+        Ok(false)
+    }
+}
+
+/// swaybg, one process per output holding a still image.
+struct Swaybg;
+
+impl WallpaperBackend for Swaybg {
+    fn kind(&self) -> Backend {
+        Backend::Swaybg
+    }
+
+    fn program(&self) -> &'static str {
+        "swaybg"
+    }
+
+    fn installed(&self) -> bool {
+        deps::probe().swaybg.installed
+    }
+
+    fn unsupported(&self, config: &RuntimeConfig) -> Option<String> {
+        still_only(self.program(), config)
+    }
+
+    fn command(
+        &self,
+        config: &RuntimeConfig,
+        settings: &Settings,
+        monitor: &str,
+    ) -> Result<Command, Box<dyn Error>> {
+        // Our `fit` fills the output like swaybg's `stretch`; our `stretch` keeps the
+        // aspect ratio like its `fit`.
+        let mode = match config.scale {
+            ScaleMode::Fit => "stretch",
+            ScaleMode::Stretch => "fit",
+            ScaleMode::Original | ScaleMode::Center => "center",
+            ScaleMode::Fill => "fill",
+            ScaleMode::Tile => "tile",
+        };
+        let mut argv = settings.process.wrapper_args();
+        argv.push(self.program().into());
+        let mut command = Command::new(&argv[0]);
+        command
+            .args(&argv[1..])
+            .arg("-o")
+            .arg(monitor)
+            .arg("-m")
+            .arg(mode)
+            .arg("-i")
+            .arg(config.media.path());
+        attach_log(&mut command, monitor)?;
+        Ok(command)
+    }
+
+    fn detaches(&self, _config: &RuntimeConfig) -> bool {
+        false
+    }
+
+    fn wait_for_stage(
+        &self,
+        monitor: &str,
+        pid: Option<u32>,
+        stage: LaunchStage,
+    ) -> Result<(), WpeError> {
+        // swaybg has no way to report that it is up, but gives up right away on an
+        // unreadable image or an unknown output.
+        settle(monitor, pid, stage, "swaybg", "its image")
+    }
+
+    fn stop(&self, monitor: &str) -> Result<bool, Box<dyn Error>> {
+        let pattern = format!("^swaybg -o {} ", mpvpaper::escape_regex(monitor));
+        pkill(&["-f", pattern.as_str()])
+    }
+
+    fn stop_all(&self) -> Result<bool, Box<dyn Error>> {
+        pkill(&["-f", "^swaybg -o "])
+    }
+}
+
+/// swww, handing images to the session's `swww-daemon`.
+struct Swww;
+
+impl Swww {
+    /// Whether `swww-daemon` is up to take images.
+    fn daemon_running() -> bool {
+        Command::new("swww")
+            .arg("query")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+impl WallpaperBackend for Swww {
+    fn kind(&self) -> Backend {
+        Backend::Swww
+    }
+
+    fn program(&self) -> &'static str {
+        "swww"
+    }
+
+    fn installed(&self) -> bool {
+        deps::probe().swww.installed
+    }
+
+    fn unsupported(&self, config: &RuntimeConfig) -> Option<String> {
+        still_only(self.program(), config)
+    }
+
+    fn command(
+        &self,
+        config: &RuntimeConfig,
+        _settings: &Settings,
+        monitor: &str,
+    ) -> Result<Command, Box<dyn Error>> {
+        if !Self::daemon_running() {
+            return Err("swww-daemon is not running; start it with your session".into());
+        }
+        let resize = match config.scale {
+            ScaleMode::Fit => "stretch",
+            ScaleMode::Stretch => "fit",
+            ScaleMode::Original | ScaleMode::Center | ScaleMode::Tile => "no",
+            ScaleMode::Fill => "crop",
+        };
+        let mut command = Command::new(self.program());
+        command
+            .arg("img")
+            .arg("-o")
+            .arg(monitor)
+            .arg("--resize")
+            .arg(resize)
+            .arg(config.media.path());
+        attach_log(&mut command, monitor)?;
+        Ok(command)
+    }
+
+    // `swww img` exits once the daemon has the image.
+    fn detaches(&self, _config: &RuntimeConfig) -> bool {
+        true
+    }
+
+    fn wait_for_stage(
+        &self,
+        _monitor: &str,
+        _pid: Option<u32>,
+        _stage: LaunchStage,
+    ) -> Result<(), WpeError> {
+        Ok(())
+    }
+
+    fn stop(&self, monitor: &str) -> Result<bool, Box<dyn Error>> {
+        if !Self::daemon_running() {
+            return Ok(false);
+        }
+        Command::new(self.program())
+            .args(["clear", "-o", monitor])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .map_err(|err| format!("Failed to issue swww clear: {}", err).into())
+    }
+
+    // The daemon belongs to the session and may show images wpe never set; the
+    // outputs wpe set are cleared one by one as their players stop.
+    fn stop_all(&self) -> Result<bool, Box<dyn Error>> {
+        Ok(false)
+    }
+}
+
+/// The `[web] command`, one process per output drawing a web page.
+struct Web;
+
+impl WallpaperBackend for Web {
+    fn kind(&self) -> Backend {
+        Backend::Web
+    }
+
+    fn program(&self) -> &'static str {
+        "the web renderer"
+    }
+
+    // An unset command is reported by `missing`, which says what to configure.
+    fn installed(&self) -> bool {
+        config::load_settings()
+            .is_ok_and(|settings| settings.web.command.is_empty() || settings.web.installed())
+    }
+
+    fn missing(&self) -> Option<String> {
+        config::load_settings()
+            .is_ok_and(|settings| settings.web.command.is_empty())
+            .then(|| "backend = \"web\" needs a [web] command to draw the page".into())
+    }
+
+    fn unsupported(&self, config: &RuntimeConfig) -> Option<String> {
+        if let Some(reason) = builtin_only(self.program(), config) {
+            return Some(reason);
+        }
+        if config.grid.is_some() || config.pip.is_some() {
+            return Some(format!(
+                "{} cannot draw grids or picture-in-picture; use backend = \"mpvpaper\"",
+                self.program()
+            ));
+        }
+        web::page_url(&config.media).err()
+    }
+
+    fn command(
+        &self,
+        config: &RuntimeConfig,
+        settings: &Settings,
+        monitor: &str,
+    ) -> Result<Command, Box<dyn Error>> {
+        if settings.security.sandbox {
+            warn!("[security] sandbox does not apply to the web renderer on {monitor}");
+        }
+        let url = web::page_url(&config.media)?;
+        let mut argv = settings.process.wrapper_args();
+        argv.extend(settings.web.command(&url, monitor, config.output_size)?);
+        let mut command = Command::new(&argv[0]);
+        command.args(&argv[1..]).env(web::OUTPUT_ENV, monitor);
+        attach_log(&mut command, monitor)?;
+        Ok(command)
+    }
+
+    fn detaches(&self, _config: &RuntimeConfig) -> bool {
+        false
+    }
+
+    fn wait_for_stage(
+        &self,
+        monitor: &str,
+        pid: Option<u32>,
+        stage: LaunchStage,
+    ) -> Result<(), WpeError> {
+        settle(monitor, pid, stage, "the web renderer", "its page")
+    }
+
+    fn stop(&self, monitor: &str) -> Result<bool, Box<dyn Error>> {
+        web::stop(Some(monitor))
+    }
+
+    fn stop_all(&self) -> Result<bool, Box<dyn Error>> {
+        web::stop(None)
+    }
+}
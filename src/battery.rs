@@ -0,0 +1,98 @@
+//! Battery-aware playback through UPower.
+//!
+//! UPower's `OnBattery` property on the system bus says whether the machine runs
+//! from its battery. `on_battery` in the config decides what wallpapers do then:
+//! keep playing, pause, or swap to a still frame until the charger is back.
+
+use std::{error::Error, thread, time::Duration};
+
+use futures::channel::mpsc::UnboundedSender;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zbus::blocking::{Connection, Proxy};
+
+/// How long to wait before asking UPower again after it went away.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// `on_battery`: what wallpapers do while the machine runs from its battery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BatteryPolicy {
+    /// Play as usual.
+    #[default]
+    Continue,
+    /// Pause every wallpaper.
+    Pause,
+    /// Show a still frame of each wallpaper instead of the video.
+    StaticFrame,
+}
+
+/// `[power]` table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PowerSettings {
+    pub on_battery: BatteryPolicy,
+}
+
+impl PowerSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn upower(connection: &Connection) -> zbus::Result<Proxy<'_>> {
+    Proxy::new(
+        connection,
+        "org.freedesktop.UPower",
+        "/org/freedesktop/UPower",
+        "org.freedesktop.UPower",
+    )
+}
+
+/// Whether the machine runs from its battery; `false` when UPower cannot be asked.
+pub fn on_battery() -> bool {
+    Connection::system()
+        .and_then(|connection| upower(&connection)?.get_property::<bool>("OnBattery"))
+        .unwrap_or(false)
+}
+
+/// Send `OnBattery` now and whenever it changes, until the receiver is gone.
+pub fn watch(tx: UnboundedSender<bool>) {
+    loop {
+        match watch_session(&tx) {
+            Ok(()) => return,
+            Err(err) => {
+                warn!("Lost UPower: {}; assuming AC power", err);
+                if tx.unbounded_send(false).is_err() {
+                    return;
+                }
+                thread::sleep(RETRY_DELAY);
+            }
+        }
+    }
+}
+
+fn watch_session(tx: &UnboundedSender<bool>) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::system()?;
+    let proxy = upower(&connection)?;
+    // Subscribe before reading so a change in between is not missed.
+    let changes = proxy.receive_property_changed::<bool>("OnBattery");
+    let mut last = proxy.get_property::<bool>("OnBattery")?;
+    info!("Running on {}", if last { "battery" } else { "AC power" });
+    if tx.unbounded_send(last).is_err() {
+        return Ok(());
+    }
+    for change in changes {
+        let on_battery = change.get()?;
+        if on_battery == last {
+            continue;
+        }
+        last = on_battery;
+        info!("Switched to {}", if last { "battery" } else { "AC power" });
+        if tx.unbounded_send(last).is_err() {
+            return Ok(());
+        }
+    }
+    Err("UPower stopped sending updates".into())
+}
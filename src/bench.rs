@@ -0,0 +1,307 @@
+//! `wpe bench`: try a source with several decode and scaling settings.
+//!
+//! Each combination plays the file on one output for a short window while CPU time,
+//! GPU load, and mpv's dropped-frame counters are sampled, then the cheapest setting
+//! that keeps up is recommended. mpvpaper always renders through libmpv's render API,
+//! so the video output itself is fixed; the scaling profile is varied instead.
+
+use std::{error::Error, fs, path::Path, thread, time::Duration};
+
+use tracing::warn;
+
+use crate::{
+    backend,
+    config::{self, Backend, MpvTuning, RuntimeConfig, Settings, WallpaperProfileEntry},
+    deps, ipc,
+    monitors::{self, Monitor},
+    mpvpaper::{self, LaunchStage},
+    output::{self, CliError, Failure},
+    power,
+};
+
+/// `--hwdec` and `--profile` pairs to compare, in order of preference on a tie.
+const COMBINATIONS: [(&str, Option<&str>); 4] = [
+    ("auto-safe", None),
+    ("auto-safe", Some("fast")),
+    ("no", None),
+    ("no", Some("fast")),
+];
+
+/// Let the decoder settle before measuring.
+const WARMUP: Duration = Duration::from_secs(1);
+
+/// Extra dropped frames tolerated in exchange for a cheaper setting.
+const DROP_TOLERANCE: u64 = 2;
+
+/// Measurements for one combination.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub tuning: MpvTuning,
+    /// Percent of one CPU core, averaged over the window.
+    pub cpu_percent: f64,
+    /// Busiest card's average load, when the driver reports it.
+    pub gpu_percent: Option<f64>,
+    pub dropped_frames: u64,
+}
+
+impl BenchResult {
+    fn cost(&self) -> f64 {
+        self.cpu_percent + self.gpu_percent.unwrap_or(0.0)
+    }
+}
+
+/// Benchmark `file` on `monitor` (default: the first output), printing a table and a
+/// recommendation. With `apply`, entries playing `file` get the recommended settings.
+pub fn run(
+    file: &Path,
+    monitor: Option<&str>,
+    window: Duration,
+    apply: bool,
+) -> Result<(), CliError> {
+    let file = fs::canonicalize(file).map_err(|err| {
+        CliError::new(
+            Failure::General,
+            format!("Cannot open {}: {}", file.display(), err),
+        )
+    })?;
+    if !deps::probe().mpvpaper.installed {
+        return Err(CliError::new(
+            Failure::BackendMissing,
+            "mpvpaper was not found in PATH; install it to run benchmarks",
+        ));
+    }
+
+    let monitors = monitors::list_monitors()?;
+    let target = match monitor {
+        Some(name) => monitors.iter().find(|monitor| monitor.name == name),
+        None => monitors.first(),
+    }
+    .ok_or_else(|| match monitor {
+        Some(name) => CliError::new(Failure::NoMonitors, format!("{name} is not connected")),
+        None => CliError::new(Failure::NoMonitors, "No monitors detected"),
+    })?;
+    let settings = config::load_settings()?;
+    let entries = config::load_wallpaper_entries()?;
+
+    // The benchmark needs the output to itself; its wallpaper is restarted afterwards.
+    let _ = mpvpaper::stop_for_monitor(&target.name);
+    output::say(format!(
+        "Benchmarking {} on {} ({} runs of {}s)...",
+        file.display(),
+        target.name,
+        COMBINATIONS.len(),
+        window.as_secs()
+    ));
+    output::say("");
+    output::say(format!(
+        "{:<30} {:>7} {:>7} {:>8}",
+        "SETTINGS", "CPU%", "GPU%", "DROPPED"
+    ));
+
+    let mut results = Vec::new();
+    for (hwdec, profile) in COMBINATIONS {
+        let tuning = MpvTuning {
+            hwdec: Some(hwdec.to_string()),
+            profile: profile.map(str::to_string),
+            ..MpvTuning::default()
+        };
+        let label = describe(&tuning);
+        match measure(&file, &target.name, &tuning, &settings, window) {
+            Ok(result) => {
+                let gpu = result
+                    .gpu_percent
+                    .map(|busy| format!("{:.0}", busy))
+                    .unwrap_or_else(|| "-".into());
+                output::say(format!(
+                    "{:<30} {:>6.1}% {:>6}% {:>8}",
+                    label, result.cpu_percent, gpu, result.dropped_frames
+                ));
+                output::record(
+                    "run",
+                    &[
+                        hwdec,
+                        profile.unwrap_or("-"),
+                        &format!("{:.1}", result.cpu_percent),
+                        &gpu,
+                        &result.dropped_frames.to_string(),
+                    ],
+                );
+                results.push(result);
+            }
+            Err(err) => {
+                output::say(format!("{:<30} failed: {}", label, err));
+                output::record("failed", &[hwdec, profile.unwrap_or("-"), &err.to_string()]);
+            }
+        }
+    }
+
+    restore_wallpaper(target, &entries, &settings);
+
+    let Some(best) = recommend(&results) else {
+        return Err(CliError::new(
+            Failure::General,
+            format!("None of the settings could play {}", file.display()),
+        ));
+    };
+    output::say("");
+    output::say(format!("Recommended: {}", describe(&best.tuning)));
+    output::record(
+        "recommended",
+        &[
+            best.tuning.hwdec.as_deref().unwrap_or("-"),
+            best.tuning.profile.as_deref().unwrap_or("-"),
+        ],
+    );
+
+    if apply {
+        let updated = apply_tuning(&file, &best.tuning).map_err(CliError::config)?;
+        if updated == 0 {
+            return Err(CliError::config(format!(
+                "No entry plays {}; add it to the config first",
+                file.display()
+            )));
+        }
+        output::say(format!("Saved to {} entry(ies).", updated));
+    } else {
+        output::say("Rerun with --apply to save this to the entries that play this file.");
+    }
+    Ok(())
+}
+
+/// Play `file` with `tuning` and sample it for `window`.
+fn measure(
+    file: &Path,
+    monitor: &str,
+    tuning: &MpvTuning,
+    settings: &Settings,
+    window: Duration,
+) -> Result<BenchResult, Box<dyn Error>> {
+    let entry = WallpaperProfileEntry {
+        monitor: Some(monitor.to_string()),
+        path: Some(file.to_path_buf()),
+        enabled: true,
+        tuning: tuning.clone(),
+        ..WallpaperProfileEntry::default()
+    };
+    let mut runtime = RuntimeConfig::from_profile_entry(&entry)?;
+    runtime.mpvpaper.fork = false;
+    // The tunings are mpv's, so a still-image backend hands the run to mpvpaper.
+    if !backend::for_config(&runtime, settings).has_ipc() {
+        runtime.backend = Some(Backend::Mpvpaper);
+    }
+    let mut child = backend::spawn_instance(&runtime, settings)?;
+
+    let result = sample_player(monitor, child.id(), window);
+    let _ = child.kill();
+    let _ = child.wait();
+    let _ = mpvpaper::stop_for_monitor(monitor);
+
+    let (cpu_percent, gpu_percent, dropped_frames) = result?;
+    Ok(BenchResult {
+        tuning: tuning.clone(),
+        cpu_percent,
+        gpu_percent,
+        dropped_frames,
+    })
+}
+
+fn sample_player(
+    monitor: &str,
+    pid: u32,
+    window: Duration,
+) -> Result<(f64, Option<f64>, u64), Box<dyn Error>> {
+    for stage in [LaunchStage::WaitingForSurface, LaunchStage::Loading] {
+        mpvpaper::wait_for_stage(monitor, Some(pid), stage)?;
+    }
+    thread::sleep(WARMUP);
+
+    let instances: Vec<power::Instance> = power::find_instances()
+        .into_iter()
+        .filter(|instance| instance.monitor == monitor)
+        .collect();
+    if instances.is_empty() {
+        return Err(format!("Player for {monitor} exited during the benchmark").into());
+    }
+    let dropped_before = dropped_frames(monitor);
+    let (samples, gpus) = power::sample(instances, window);
+    let dropped = dropped_frames(monitor).saturating_sub(dropped_before);
+
+    let cpu = samples.iter().map(|sample| sample.cpu_percent).sum();
+    let gpu = gpus
+        .iter()
+        .filter_map(|gpu| gpu.busy_percent)
+        .max_by(f64::total_cmp);
+    Ok((cpu, gpu, dropped))
+}
+
+/// Frames mpv dropped at output plus those the decoder skipped to keep up.
+fn dropped_frames(monitor: &str) -> u64 {
+    ["frame-drop-count", "decoder-frame-drop-count"]
+        .iter()
+        .filter_map(|property| ipc::get_property(monitor, property).ok()?.as_u64())
+        .sum()
+}
+
+/// Cheapest result among those that dropped (nearly) as few frames as the best one.
+fn recommend(results: &[BenchResult]) -> Option<&BenchResult> {
+    let fewest = results.iter().map(|result| result.dropped_frames).min()?;
+    results
+        .iter()
+        .filter(|result| result.dropped_frames <= fewest + DROP_TOLERANCE)
+        .min_by(|a, b| a.cost().total_cmp(&b.cost()))
+}
+
+fn describe(tuning: &MpvTuning) -> String {
+    format!(
+        "hwdec={}, profile={}",
+        tuning.hwdec.as_deref().unwrap_or("auto-safe"),
+        tuning.profile.as_deref().unwrap_or("default")
+    )
+}
+
+/// Bring back the configured wallpaper the benchmark displaced.
+fn restore_wallpaper(monitor: &Monitor, entries: &[WallpaperProfileEntry], settings: &Settings) {
+    let Some(entry) = entries.iter().find(|entry| {
+        entry.enabled && entry.has_source() && entry.monitor.as_deref() == Some(&monitor.name)
+    }) else {
+        return;
+    };
+    let restored = RuntimeConfig::from_profile_entry(entry).and_then(|mut runtime| {
+        runtime.set_output(monitor);
+        runtime.select_variant(monitor, &settings.variants);
+        backend::spawn_instance(&runtime, settings)
+    });
+    match restored {
+        Ok(_) => output::say(format!(
+            "Restarted the configured wallpaper on {}.",
+            monitor.name
+        )),
+        Err(err) => warn!(
+            "Could not restart the wallpaper on {}: {}",
+            monitor.name, err
+        ),
+    }
+}
+
+/// Write `tuning` into every entry whose path is `file`, returning how many changed.
+fn apply_tuning(file: &Path, tuning: &MpvTuning) -> Result<usize, Box<dyn Error>> {
+    let mut entries = config::load_wallpaper_entries()?;
+    let mut updated = 0;
+    for entry in &mut entries {
+        let plays_file = entry
+            .path
+            .as_deref()
+            .map(config::normalize_entry_path)
+            .is_some_and(|path| path == file);
+        if plays_file {
+            // Only the measured settings; keep the entry's other tuning.
+            entry.tuning.hwdec = tuning.hwdec.clone();
+            entry.tuning.profile = tuning.profile.clone();
+            updated += 1;
+        }
+    }
+    if updated > 0 {
+        config::save_wallpaper_entries(&entries)?;
+    }
+    Ok(updated)
+}
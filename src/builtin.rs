@@ -0,0 +1,1331 @@
+//! The built-in backend: libmpv rendering straight onto a layer-shell surface.
+//!
+//! `backend = "builtin"` makes wpe start `wpe render` instead of mpvpaper, with the
+//! same `-o`, `--slideshow`, output, and path arguments, so the IPC socket, pausing,
+//! and stopping work the same for both. Frames come from libmpv's software renderer
+//! and are copied into shared-memory buffers; only built with the `builtin` feature,
+//! which links against libmpv.
+//!
+//! With `--timelapse`, the path is a folder of images shown by time of day (see
+//! [`crate::timelapse`]): libmpv loads each image when its time comes, and the last
+//! frame of the old one is blended into the new one until the fade is through.
+//! With `--transition`, a playlist fades or slides from each file to the next the
+//! same way (see [`crate::transition`]).
+//!
+//! With `--parallax`, the image is drawn by wpe instead of libmpv, each pixel
+//! shifted by the depth map (see [`crate::parallax`]) towards where the pointer
+//! last was over the surface. libmpv still holds the image, for the IPC socket.
+//!
+//! With `--shader`, the path is a fragment shader (see [`crate::shader`]) that wgpu
+//! draws on the surface instead of the shared-memory buffers, at most `--shader`
+//! frames a second and only when the compositor asks for one. libmpv plays a silent
+//! stream meanwhile, so the IPC socket, pausing, and launch checks still work; the
+//! shader's clock stops while it is paused.
+
+use std::{
+    error::Error,
+    ffi::{CStr, CString, c_int, c_void},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process,
+    ptr::{self, NonNull},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::{Datelike, Local, Timelike};
+use futures::executor::block_on;
+use image::{DynamicImage, imageops::FilterType};
+use raw_window_handle::{
+    RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
+};
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState},
+    delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
+    delegate_seat, delegate_shm,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    seat::{
+        Capability, SeatHandler, SeatState,
+        pointer::{PointerEvent, PointerEventKind, PointerHandler},
+    },
+    shell::{
+        WaylandSurface,
+        wlr_layer::{
+            Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
+            LayerSurfaceConfigure,
+        },
+    },
+    shm::{Shm, ShmHandler, slot::SlotPool},
+};
+use tracing::{info, warn};
+use wayland_client::{
+    Connection, EventQueue, Proxy, QueueHandle,
+    backend::WaylandError,
+    globals::registry_queue_init,
+    protocol::{wl_output, wl_pointer, wl_seat, wl_shm, wl_surface},
+};
+
+use crate::{
+    parallax::ParallaxSettings,
+    shader::{self, Language, ShaderSettings},
+    timelapse::{self, TimelapseSettings},
+    transition::{TransitionEffect, TransitionSettings},
+};
+
+/// How long the render loop sleeps without a new frame before checking Wayland again.
+const IDLE_WAKE: Duration = Duration::from_millis(100);
+/// How often a transition between images, or a parallax view on the move,
+/// is redrawn.
+const ANIMATION_FRAME: Duration = Duration::from_millis(33);
+/// How much of the way to the pointer a parallax view moves each frame.
+const PARALLAX_EASE: f32 = 0.15;
+/// How close to the pointer a parallax view has to be to stop moving.
+const PARALLAX_SETTLED: f32 = 0.002;
+/// libmpv's name for the layout of `wl_shm::Format::Xrgb8888` (little-endian).
+const PIXEL_FORMAT: &CStr = c"bgr0";
+/// What libmpv plays behind a shader: endless silence, to an audio output that
+/// discards it.
+const SILENT_STREAM: &str = "av://lavfi:anullsrc";
+/// Bytes of the uniform block the shader preludes declare.
+const UNIFORM_SIZE: usize = 64;
+/// A triangle covering the whole surface, for the fragment shader to fill.
+const FULLSCREEN_VERTEX: &str = "
+@vertex
+fn main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+/// The parts of libmpv's client and render API the renderer uses.
+#[allow(non_camel_case_types)]
+mod ffi {
+    use std::ffi::{c_char, c_double, c_int, c_void};
+
+    pub enum mpv_handle {}
+    pub enum mpv_render_context {}
+
+    pub const MPV_EVENT_SHUTDOWN: c_int = 1;
+
+    pub const MPV_FORMAT_FLAG: c_int = 3;
+    pub const MPV_FORMAT_INT64: c_int = 4;
+
+    pub const MPV_RENDER_PARAM_INVALID: c_int = 0;
+    pub const MPV_RENDER_PARAM_API_TYPE: c_int = 1;
+    pub const MPV_RENDER_PARAM_SW_SIZE: c_int = 17;
+    pub const MPV_RENDER_PARAM_SW_FORMAT: c_int = 18;
+    pub const MPV_RENDER_PARAM_SW_STRIDE: c_int = 19;
+    pub const MPV_RENDER_PARAM_SW_POINTER: c_int = 20;
+
+    pub const MPV_RENDER_UPDATE_FRAME: u64 = 1;
+
+    #[repr(C)]
+    pub struct mpv_event {
+        pub event_id: c_int,
+        pub error: c_int,
+        pub reply_userdata: u64,
+        pub data: *mut c_void,
+    }
+
+    #[repr(C)]
+    pub struct mpv_render_param {
+        pub kind: c_int,
+        pub data: *mut c_void,
+    }
+
+    #[link(name = "mpv")]
+    unsafe extern "C" {
+        pub fn mpv_create() -> *mut mpv_handle;
+        pub fn mpv_initialize(ctx: *mut mpv_handle) -> c_int;
+        pub fn mpv_terminate_destroy(ctx: *mut mpv_handle);
+        pub fn mpv_error_string(error: c_int) -> *const c_char;
+        pub fn mpv_set_option_string(
+            ctx: *mut mpv_handle,
+            name: *const c_char,
+            data: *const c_char,
+        ) -> c_int;
+        pub fn mpv_command(ctx: *mut mpv_handle, args: *mut *const c_char) -> c_int;
+        pub fn mpv_get_property(
+            ctx: *mut mpv_handle,
+            name: *const c_char,
+            format: c_int,
+            data: *mut c_void,
+        ) -> c_int;
+        pub fn mpv_wait_event(ctx: *mut mpv_handle, timeout: c_double) -> *mut mpv_event;
+        pub fn mpv_wakeup(ctx: *mut mpv_handle);
+
+        pub fn mpv_render_context_create(
+            res: *mut *mut mpv_render_context,
+            mpv: *mut mpv_handle,
+            params: *mut mpv_render_param,
+        ) -> c_int;
+        pub fn mpv_render_context_set_update_callback(
+            ctx: *mut mpv_render_context,
+            callback: Option<unsafe extern "C" fn(*mut c_void)>,
+            callback_ctx: *mut c_void,
+        );
+        pub fn mpv_render_context_update(ctx: *mut mpv_render_context) -> u64;
+        pub fn mpv_render_context_render(
+            ctx: *mut mpv_render_context,
+            params: *mut mpv_render_param,
+        ) -> c_int;
+        pub fn mpv_render_context_free(ctx: *mut mpv_render_context);
+    }
+}
+
+/// Set by libmpv's update callback when it has a frame to draw.
+static FRAME_READY: Mutex<bool> = Mutex::new(false);
+static FRAME_SIGNAL: Condvar = Condvar::new();
+
+unsafe extern "C" fn on_update(_ctx: *mut c_void) {
+    if let Ok(mut ready) = FRAME_READY.lock() {
+        *ready = true;
+        FRAME_SIGNAL.notify_one();
+    }
+}
+
+/// Wait up to `timeout` for a new frame, clearing the flag.
+fn wait_for_frame(timeout: Duration) -> bool {
+    let Ok(ready) = FRAME_READY.lock() else {
+        return false;
+    };
+    let Ok((mut ready, _)) = FRAME_SIGNAL.wait_timeout_while(ready, timeout, |ready| !*ready)
+    else {
+        return false;
+    };
+    std::mem::take(&mut *ready)
+}
+
+/// Play `path` on `monitor` until the player is told to quit or the output goes away.
+/// With `timelapse`, `path` is a folder of images shown by time of day; with
+/// `parallax`, it is an image that shifts with the pointer; with `shader`, it is a
+/// fragment shader drawn on the GPU. `transition` plays between a playlist's files.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    monitor: &str,
+    path: &Path,
+    options: Option<&str>,
+    slideshow: Option<u64>,
+    timelapse: Option<TimelapseSettings>,
+    parallax: Option<ParallaxSettings>,
+    shader: Option<ShaderSettings>,
+    transition: Option<TransitionSettings>,
+) -> Result<(), Box<dyn Error>> {
+    let player = Player::new(options.unwrap_or_default(), slideshow)?;
+    let parallax = parallax
+        .map(|settings| Parallax::new(path, settings))
+        .transpose()?;
+    let mut set = timelapse
+        .map(|settings| TimelapseSet::new(path, settings))
+        .transpose()?;
+    if shader.is_some() {
+        player.set_option("ao", "null")?;
+        player.load(Path::new(SILENT_STREAM))?;
+    } else {
+        player.load(set.as_ref().map_or(path, TimelapseSet::current))?;
+    }
+
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init(&conn)?;
+    let qh = event_queue.handle();
+    let compositor = CompositorState::bind(&globals, &qh)?;
+    let layer_shell = LayerShell::bind(&globals, &qh)?;
+    let mut state = RenderState {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+        seat_state: SeatState::new(&globals, &qh),
+        shm: Shm::bind(&globals, &qh)?,
+        layer: None,
+        pool: None,
+        size: None,
+        needs_draw: false,
+        closed: false,
+        last_frame: (set.is_some() || transition.is_some()).then(Vec::new),
+        transition: None,
+        pointer: None,
+        pointer_at: None,
+        follow_pointer: parallax.is_some() || shader.is_some(),
+        parallax,
+        frame_pending: false,
+    };
+    // One round trip fills in the output names.
+    event_queue.roundtrip(&mut state)?;
+    let output = state
+        .output_state
+        .outputs()
+        .find(|output| {
+            state
+                .output_state
+                .info(output)
+                .is_some_and(|info| info.name.as_deref() == Some(monitor))
+        })
+        .ok_or_else(|| format!("No output named {monitor}"))?;
+
+    let surface = compositor.create_surface(&qh);
+    let layer = layer_shell.create_layer_surface(
+        &qh,
+        surface,
+        Layer::Background,
+        Some("wpe"),
+        Some(&output),
+    );
+    layer.set_anchor(Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT);
+    layer.set_size(0, 0);
+    layer.set_exclusive_zone(-1);
+    layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+    layer.commit();
+    // Declared after `state`, so the GPU lets go of the surface before it is destroyed.
+    let mut view = shader
+        .map(|settings| ShaderView::new(&conn, layer.wl_surface(), path, settings))
+        .transpose()?;
+    state.layer = Some(layer);
+    match view {
+        Some(_) => info!("Rendering {} on {} with wgpu", path.display(), monitor),
+        None => info!("Rendering {} on {} with libmpv", path.display(), monitor),
+    }
+
+    let mut playlist_pos = None;
+    while !state.closed {
+        pump(&mut event_queue, &mut state)?;
+        if let Some(set) = &mut set
+            && let Some(next) = set.next_due()
+        {
+            info!("Fading to {} on {}", next.display(), monitor);
+            state.transition = state.change_from_last(
+                TransitionEffect::Fade,
+                Duration::from_secs(set.settings.fade_seconds),
+            );
+            player.load(&next)?;
+        }
+
+        let target = state.pointer_target();
+        let moved = state
+            .parallax
+            .as_mut()
+            .is_some_and(|parallax| parallax.step(target));
+
+        let wake = match &view {
+            Some(view) => view.interval(),
+            None if state.transition.is_some() || state.parallax.is_some() => ANIMATION_FRAME,
+            None => IDLE_WAKE,
+        };
+        let frame = wait_for_frame(wake) && player.has_new_frame();
+        // The playlist moved on before the next file's first frame; the frame up is
+        // still the old file's.
+        if let Some(settings) = transition
+            && frame
+        {
+            let position = player.playlist_pos();
+            if playlist_pos.is_some_and(|last| last != position) && state.transition.is_none() {
+                state.transition = state.change_from_last(
+                    settings.effect,
+                    Duration::from_millis(settings.milliseconds),
+                );
+            }
+            playlist_pos = Some(position);
+        }
+        if let Some(view) = &mut view {
+            if player.paused() {
+                view.pause();
+            } else if !state.frame_pending
+                && let (Some(layer), Some(size)) = (&state.layer, state.size)
+            {
+                // The compositor stops asking for frames while the surface is hidden.
+                layer.wl_surface().frame(&qh, layer.wl_surface().clone());
+                state.frame_pending = true;
+                view.draw(size, state.pointer_at)?;
+            }
+            continue;
+        }
+        let mut changing = false;
+        if let Some(transition) = &mut state.transition {
+            // The transition starts once the new image has drawn, not while it loads.
+            if frame && transition.started.is_none() {
+                transition.started = Some(Instant::now());
+            }
+            changing = transition.started.is_some();
+        }
+        if frame || changing || moved || state.needs_draw {
+            state.draw(&player)?;
+        }
+    }
+    Ok(())
+}
+
+/// The images of a time-lapse set and which of them is up.
+struct TimelapseSet {
+    frames: Vec<PathBuf>,
+    settings: TimelapseSettings,
+    current: usize,
+}
+
+impl TimelapseSet {
+    fn new(folder: &Path, settings: TimelapseSettings) -> Result<Self, Box<dyn Error>> {
+        let frames = timelapse::frames(folder)?;
+        let current = frame_now(frames.len(), &settings);
+        Ok(Self {
+            frames,
+            settings,
+            current,
+        })
+    }
+
+    fn current(&self) -> &Path {
+        &self.frames[self.current]
+    }
+
+    /// The next image, once its time has come.
+    fn next_due(&mut self) -> Option<PathBuf> {
+        let due = frame_now(self.frames.len(), &self.settings);
+        if due == self.current {
+            return None;
+        }
+        self.current = due;
+        Some(self.frames[due].clone())
+    }
+}
+
+/// Which of `count` images of a set is up now: the day, from `start_hour`, split
+/// into `count` equal slots.
+fn frame_now(count: usize, settings: &TimelapseSettings) -> usize {
+    const DAY: u64 = 24 * 60 * 60;
+    let start = u64::from(settings.start_hour % 24) * 60 * 60;
+    let now = u64::from(Local::now().time().num_seconds_from_midnight());
+    let since_start = (now + DAY - start) % DAY;
+    (since_start * count as u64 / DAY) as usize
+}
+
+/// A fade or slide from the last frame of one image to the next image.
+struct Transition {
+    /// The old image as last drawn, at the surface's size.
+    from: Vec<u8>,
+    effect: TransitionEffect,
+    duration: Duration,
+    /// When the new image first drew.
+    started: Option<Instant>,
+}
+
+impl Transition {
+    /// How far along the transition is, from 0 to 1; `None` while the new image loads.
+    fn progress(&self) -> Option<f32> {
+        let elapsed = self.started?.elapsed().as_secs_f32();
+        Some((elapsed / self.duration.as_secs_f32().max(f32::EPSILON)).min(1.0))
+    }
+}
+
+/// Mix the old image `from` into the freshly rendered `canvas`, `progress` (0 to 1)
+/// of the way from the one to the other.
+fn blend(canvas: &mut [u8], from: &[u8], progress: f32) {
+    let weight = (progress * 256.0) as u16;
+    for (new, old) in canvas.iter_mut().zip(from) {
+        *new = ((u16::from(*new) * weight + u16::from(*old) * (256 - weight)) >> 8) as u8;
+    }
+}
+
+/// Push the old image `from` out to the left as the freshly rendered `canvas` comes
+/// in from the right, `progress` (0 to 1) of the way; rows are `width` pixels.
+fn slide(canvas: &mut [u8], from: &[u8], width: u32, progress: f32) {
+    let row = width as usize * 4;
+    let shift = ((progress * width as f32) as usize).min(width as usize) * 4;
+    for (new, old) in canvas.chunks_exact_mut(row).zip(from.chunks_exact(row)) {
+        // The left of the new image is what has come in so far.
+        new.copy_within(..shift, row - shift);
+        new[..row - shift].copy_from_slice(&old[shift..]);
+    }
+}
+
+/// An image shifted by its depth map, as seen from where the pointer is.
+struct Parallax {
+    settings: ParallaxSettings,
+    image: DynamicImage,
+    depth: DynamicImage,
+    /// The surface size, the image as bgr0 and the depth, both scaled to fill it.
+    scaled: Option<((u32, u32), Vec<u8>, Vec<u8>)>,
+    /// Where the view looks from, -1 to 1 across each axis; 0 is straight on.
+    view: (f32, f32),
+}
+
+impl Parallax {
+    fn new(image: &Path, settings: ParallaxSettings) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            image: image::open(image)?,
+            depth: image::open(&settings.depth_map)?,
+            settings,
+            scaled: None,
+            view: (0.0, 0.0),
+        })
+    }
+
+    /// Move the view part of the way to `target`, returning whether it moved.
+    fn step(&mut self, target: (f32, f32)) -> bool {
+        let (dx, dy) = (target.0 - self.view.0, target.1 - self.view.1);
+        if dx.abs() < PARALLAX_SETTLED && dy.abs() < PARALLAX_SETTLED {
+            let moved = self.view != target;
+            self.view = target;
+            return moved;
+        }
+        self.view.0 += dx * PARALLAX_EASE;
+        self.view.1 += dy * PARALLAX_EASE;
+        true
+    }
+
+    /// Draw the shifted image into `canvas`, `width`x`height` pixels of bgr0.
+    fn render(&mut self, canvas: &mut [u8], width: u32, height: u32) {
+        if self
+            .scaled
+            .as_ref()
+            .is_none_or(|(size, ..)| *size != (width, height))
+        {
+            let image = self
+                .image
+                .resize_to_fill(width, height, FilterType::Triangle)
+                .to_rgb8();
+            let depth = self
+                .depth
+                .resize_to_fill(width, height, FilterType::Triangle)
+                .to_luma8();
+            let image = image
+                .pixels()
+                .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], 0])
+                .collect();
+            self.scaled = Some(((width, height), image, depth.into_raw()));
+        }
+        let Some((_, image, depth)) = &self.scaled else {
+            return;
+        };
+
+        let reach = width as f32 * self.settings.shift_percent as f32 / 100.0;
+        let (shift_x, shift_y) = (-self.view.0 * reach, -self.view.1 * reach);
+        let (width, height) = (width as usize, height as usize);
+        for (y, row) in canvas.chunks_exact_mut(width * 4).take(height).enumerate() {
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                // Mid-gray stays put; nearer parts move against the view, farther with it.
+                let near = f32::from(depth[y * width + x]) / 127.5 - 1.0;
+                let from_x = (x as f32 + shift_x * near)
+                    .round()
+                    .clamp(0.0, (width - 1) as f32);
+                let from_y = (y as f32 + shift_y * near)
+                    .round()
+                    .clamp(0.0, (height - 1) as f32);
+                let from = (from_y as usize * width + from_x as usize) * 4;
+                pixel.copy_from_slice(&image[from..from + 4]);
+            }
+        }
+    }
+}
+
+/// A fragment shader drawn by wgpu straight onto the layer surface.
+struct ShaderView {
+    settings: ShaderSettings,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    configured: bool,
+    pipeline: wgpu::RenderPipeline,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Playing time so far, standing still while paused.
+    time: Duration,
+    /// When the last frame was drawn; cleared by a pause, so the pause is skipped.
+    last_drawn: Option<Instant>,
+    frames: i32,
+}
+
+impl ShaderView {
+    fn new(
+        conn: &Connection,
+        surface: &wl_surface::WlSurface,
+        path: &Path,
+        settings: ShaderSettings,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (language, code) = shader::source(path)?;
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::PRIMARY,
+            ..Default::default()
+        });
+        let display = NonNull::new(conn.backend().display_ptr() as *mut c_void)
+            .ok_or("The Wayland connection has no display pointer")?;
+        let window = NonNull::new(surface.id().as_ptr() as *mut c_void)
+            .ok_or("The layer surface has no pointer")?;
+        // SAFETY: the connection and the surface outlive the wgpu surface, which `run`
+        // drops first.
+        let surface = unsafe {
+            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::RawHandle {
+                raw_display_handle: RawDisplayHandle::Wayland(WaylandDisplayHandle::new(display)),
+                raw_window_handle: RawWindowHandle::Wayland(WaylandWindowHandle::new(window)),
+            })
+        }?;
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            compatible_surface: Some(&surface),
+            force_fallback_adapter: false,
+        }))
+        .ok_or("No GPU can draw on this output")?;
+        let (device, queue) = block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("wpe shader"),
+                required_features: wgpu::Features::empty(),
+                required_limits:
+                    wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+            },
+            None,
+        ))?;
+
+        let capabilities = surface.get_capabilities(&adapter);
+        // Shadertoy writes its colors as they are, into a framebuffer without sRGB.
+        let format = capabilities
+            .formats
+            .iter()
+            .copied()
+            .find(|format| !format.is_srgb())
+            .or_else(|| capabilities.formats.first().copied())
+            .ok_or("The GPU cannot draw on this output")?;
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: 1,
+            height: 1,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: capabilities.alpha_modes[0],
+            view_formats: Vec::new(),
+            desired_maximum_frame_latency: 2,
+        };
+
+        // Compile errors come back through the error scope instead of a panic.
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("wpe fullscreen"),
+            source: wgpu::ShaderSource::Wgsl(FULLSCREEN_VERTEX.into()),
+        });
+        let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("wpe shader"),
+            source: match language {
+                Language::Glsl => wgpu::ShaderSource::Glsl {
+                    shader: code.into(),
+                    stage: wgpu::naga::ShaderStage::Fragment,
+                    defines: Default::default(),
+                },
+                Language::Wgsl => wgpu::ShaderSource::Wgsl(code.into()),
+            },
+        });
+        let bind_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wpe uniforms"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wpe shader"),
+            bind_group_layouts: &[&bind_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("wpe shader"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &vertex,
+                entry_point: "main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment,
+                entry_point: match language {
+                    Language::Glsl => "main",
+                    Language::Wgsl => "wpe_fragment",
+                },
+                targets: &[Some(format.into())],
+            }),
+            multiview: None,
+        });
+        if let Some(err) = block_on(device.pop_error_scope()) {
+            return Err(format!("{} does not compile: {}", path.display(), err).into());
+        }
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("wpe uniforms"),
+            size: UNIFORM_SIZE as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wpe uniforms"),
+            layout: &bind_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.as_entire_binding(),
+            }],
+        });
+        Ok(Self {
+            settings,
+            surface,
+            device,
+            queue,
+            config,
+            configured: false,
+            pipeline,
+            uniforms,
+            bind_group,
+            time: Duration::ZERO,
+            last_drawn: None,
+            frames: 0,
+        })
+    }
+
+    /// Time between frames at the configured rate.
+    fn interval(&self) -> Duration {
+        Duration::from_secs(1) / self.settings.fps.max(1)
+    }
+
+    /// Stop the clock until the next frame.
+    fn pause(&mut self) {
+        self.last_drawn = None;
+    }
+
+    /// Draw the next frame, `width`x`height` pixels, if its time has come.
+    fn draw(
+        &mut self,
+        (width, height): (u32, u32),
+        pointer: Option<(f64, f64)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = Instant::now();
+        let elapsed = self.last_drawn.map(|last| now - last);
+        if elapsed.is_some_and(|elapsed| elapsed < self.interval()) {
+            return Ok(());
+        }
+        if !self.configured || (self.config.width, self.config.height) != (width, height) {
+            self.config.width = width;
+            self.config.height = height;
+            self.surface.configure(&self.device, &self.config);
+            self.configured = true;
+        }
+        let delta = elapsed.unwrap_or_default() * self.settings.speed_percent / 100;
+        self.time += delta;
+        self.last_drawn = Some(now);
+
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.configured = false;
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::Timeout) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        // Shadertoy measures the pointer from the bottom left.
+        let mouse = pointer.map_or((width as f32 / 2.0, height as f32 / 2.0), |(x, y)| {
+            (x as f32, height as f32 - y as f32)
+        });
+        self.queue.write_buffer(
+            &self.uniforms,
+            0,
+            &uniform_bytes(
+                (width as f32, height as f32),
+                self.time.as_secs_f32(),
+                mouse,
+                delta.as_secs_f32(),
+                self.frames,
+            ),
+        );
+
+        let target = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("wpe shader"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+        frame.present();
+        self.frames = self.frames.wrapping_add(1);
+        Ok(())
+    }
+}
+
+/// The uniform block as the shader preludes lay it out (std140): resolution and
+/// time, then the pointer, then the frame's delta and number, then the local date
+/// as Shadertoy's `iDate` has it (year, month from 0, day, seconds since midnight).
+fn uniform_bytes(
+    resolution: (f32, f32),
+    time: f32,
+    mouse: (f32, f32),
+    delta: f32,
+    frame: i32,
+) -> [u8; UNIFORM_SIZE] {
+    let now = Local::now();
+    let seconds = now.num_seconds_from_midnight() as f32 + now.nanosecond() as f32 / 1e9;
+    let words = [
+        resolution.0.to_ne_bytes(),
+        resolution.1.to_ne_bytes(),
+        1.0f32.to_ne_bytes(),
+        time.to_ne_bytes(),
+        mouse.0.to_ne_bytes(),
+        mouse.1.to_ne_bytes(),
+        0.0f32.to_ne_bytes(),
+        0.0f32.to_ne_bytes(),
+        delta.to_ne_bytes(),
+        frame.to_ne_bytes(),
+        [0; 4],
+        [0; 4],
+        (now.year() as f32).to_ne_bytes(),
+        (now.month0() as f32).to_ne_bytes(),
+        (now.day() as f32).to_ne_bytes(),
+        seconds.to_ne_bytes(),
+    ];
+    let mut bytes = [0; UNIFORM_SIZE];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word);
+    }
+    bytes
+}
+
+/// Send queued requests and handle whatever the compositor sent, without blocking.
+fn pump(
+    event_queue: &mut EventQueue<RenderState>,
+    state: &mut RenderState,
+) -> Result<(), Box<dyn Error>> {
+    event_queue.flush()?;
+    if let Some(guard) = event_queue.prepare_read() {
+        match guard.read() {
+            Ok(_) => {}
+            Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    event_queue.dispatch_pending(state)?;
+    Ok(())
+}
+
+/// A libmpv instance with a software render context.
+struct Player {
+    handle: *mut ffi::mpv_handle,
+    render: *mut ffi::mpv_render_context,
+    /// The thread reading libmpv's events, joined before the player is destroyed.
+    events: Option<JoinHandle<()>>,
+    /// Set once the player is being dropped, so the event thread stops.
+    closing: Arc<AtomicBool>,
+}
+
+impl Player {
+    fn new(options: &str, slideshow: Option<u64>) -> Result<Self, Box<dyn Error>> {
+        // SAFETY: mpv_create has no preconditions; a null return is checked.
+        let handle = unsafe { ffi::mpv_create() };
+        if handle.is_null() {
+            return Err("libmpv could not create a player".into());
+        }
+        let mut player = Self {
+            handle,
+            render: ptr::null_mut(),
+            events: None,
+            closing: Arc::new(AtomicBool::new(false)),
+        };
+        player.set_option("vo", "libmpv")?;
+        for arg in options.split_whitespace() {
+            let (name, value) = split_option(arg);
+            if let Err(err) = player.set_option(name, value) {
+                warn!("Ignoring mpv option {}: {}", arg, err);
+            }
+        }
+        if let Some(seconds) = slideshow {
+            player.set_option("image-display-duration", &seconds.to_string())?;
+        }
+        // SAFETY: `handle` is a live player that has not been initialized yet.
+        check(unsafe { ffi::mpv_initialize(handle) })?;
+
+        let mut params = [
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_API_TYPE,
+                data: c"sw".as_ptr() as *mut c_void,
+            },
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_INVALID,
+                data: ptr::null_mut(),
+            },
+        ];
+        // SAFETY: `params` is terminated by MPV_RENDER_PARAM_INVALID and outlives the call.
+        check(unsafe {
+            ffi::mpv_render_context_create(&mut player.render, handle, params.as_mut_ptr())
+        })?;
+        // SAFETY: the callback only touches statics and never calls back into libmpv.
+        unsafe {
+            ffi::mpv_render_context_set_update_callback(
+                player.render,
+                Some(on_update),
+                ptr::null_mut(),
+            );
+        }
+        player.events = Some(player.drain_events());
+        Ok(player)
+    }
+
+    fn set_option(&self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        let name = CString::new(name)?;
+        let value = CString::new(value)?;
+        // SAFETY: both strings are NUL-terminated and live across the call.
+        check(unsafe { ffi::mpv_set_option_string(self.handle, name.as_ptr(), value.as_ptr()) })
+    }
+
+    fn load(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let command = CString::new("loadfile")?;
+        let path = CString::new(path.to_string_lossy().into_owned())?;
+        let mut args = [command.as_ptr(), path.as_ptr(), ptr::null()];
+        // SAFETY: `args` is a NULL-terminated array of live C strings.
+        check(unsafe { ffi::mpv_command(self.handle, args.as_mut_ptr()) })
+    }
+
+    /// Read libmpv's events on a thread of their own, exiting when it shuts down
+    /// (e.g. after `quit` over the IPC socket). A shutdown begun by dropping the
+    /// player only ends the thread.
+    fn drain_events(&self) -> JoinHandle<()> {
+        let handle = self.handle as usize;
+        let closing = Arc::clone(&self.closing);
+        thread::spawn(move || {
+            let handle = handle as *mut ffi::mpv_handle;
+            loop {
+                // SAFETY: Drop joins this thread before destroying the handle, and
+                // libmpv allows waiting for events from another thread.
+                let event = unsafe { &*ffi::mpv_wait_event(handle, -1.0) };
+                if closing.load(Ordering::Acquire) {
+                    return;
+                }
+                if event.event_id == ffi::MPV_EVENT_SHUTDOWN {
+                    process::exit(0);
+                }
+            }
+        })
+    }
+
+    /// Whether playback is paused, e.g. over the IPC socket.
+    fn paused(&self) -> bool {
+        let mut flag: c_int = 0;
+        // SAFETY: MPV_FORMAT_FLAG writes a single int into `flag`, which outlives the call.
+        let status = unsafe {
+            ffi::mpv_get_property(
+                self.handle,
+                c"pause".as_ptr(),
+                ffi::MPV_FORMAT_FLAG,
+                &mut flag as *mut c_int as *mut c_void,
+            )
+        };
+        status >= 0 && flag != 0
+    }
+
+    /// Index of the playlist entry up, or -1 before there is one.
+    fn playlist_pos(&self) -> i64 {
+        let mut position: i64 = -1;
+        // SAFETY: MPV_FORMAT_INT64 writes a single i64 into `position`, which outlives
+        // the call.
+        let status = unsafe {
+            ffi::mpv_get_property(
+                self.handle,
+                c"playlist-pos".as_ptr(),
+                ffi::MPV_FORMAT_INT64,
+                &mut position as *mut i64 as *mut c_void,
+            )
+        };
+        if status >= 0 { position } else { -1 }
+    }
+
+    fn has_new_frame(&self) -> bool {
+        // SAFETY: `render` is a live render context.
+        unsafe { ffi::mpv_render_context_update(self.render) & ffi::MPV_RENDER_UPDATE_FRAME != 0 }
+    }
+
+    /// Render the current frame into `canvas`, `width`x`height` pixels of bgr0.
+    fn render(&self, canvas: &mut [u8], width: u32, height: u32) -> Result<(), Box<dyn Error>> {
+        let mut size = [width as c_int, height as c_int];
+        let mut stride = width as usize * 4;
+        if canvas.len() < stride * height as usize {
+            return Err("render buffer is smaller than the surface".into());
+        }
+        let mut params = [
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_SW_SIZE,
+                data: size.as_mut_ptr() as *mut c_void,
+            },
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_SW_FORMAT,
+                data: PIXEL_FORMAT.as_ptr() as *mut c_void,
+            },
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_SW_STRIDE,
+                data: &mut stride as *mut usize as *mut c_void,
+            },
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_SW_POINTER,
+                data: canvas.as_mut_ptr() as *mut c_void,
+            },
+            ffi::mpv_render_param {
+                kind: ffi::MPV_RENDER_PARAM_INVALID,
+                data: ptr::null_mut(),
+            },
+        ];
+        // SAFETY: `canvas` holds `height` rows of `stride` bytes, and every parameter
+        // outlives the call.
+        check(unsafe { ffi::mpv_render_context_render(self.render, params.as_mut_ptr()) })
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.closing.store(true, Ordering::Release);
+        if let Some(events) = self.events.take() {
+            // SAFETY: the handle is still alive; mpv_wakeup may be called from any thread.
+            unsafe { ffi::mpv_wakeup(self.handle) };
+            let _ = events.join();
+        }
+        // SAFETY: nothing else uses the handle now the event thread is gone, and the
+        // render context has to go before the player it belongs to.
+        unsafe {
+            if !self.render.is_null() {
+                ffi::mpv_render_context_free(self.render);
+            }
+            ffi::mpv_terminate_destroy(self.handle);
+        }
+    }
+}
+
+/// Turn a libmpv status code into an error carrying its message.
+fn check(status: c_int) -> Result<(), Box<dyn Error>> {
+    if status >= 0 {
+        return Ok(());
+    }
+    // SAFETY: mpv_error_string returns a static string for any code.
+    let message = unsafe { CStr::from_ptr(ffi::mpv_error_string(status)) };
+    Err(message.to_string_lossy().into_owned().into())
+}
+
+/// Split one of mpvpaper's `-o` words such as `--hwdec=auto-safe` into a name and
+/// value; flags like `--no-audio` get an empty value, which libmpv reads as a switch.
+fn split_option(arg: &str) -> (&str, &str) {
+    let arg = arg.trim_start_matches("--");
+    arg.split_once('=').unwrap_or((arg, ""))
+}
+
+/// The layer surface and the buffers drawn into it.
+struct RenderState {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    seat_state: SeatState,
+    shm: Shm,
+    layer: Option<LayerSurface>,
+    pool: Option<SlotPool>,
+    size: Option<(u32, u32)>,
+    /// The surface was (re)configured and has no frame at its size yet.
+    needs_draw: bool,
+    closed: bool,
+    /// For time-lapse sets and transitions, a copy of the last frame drawn outside a
+    /// transition, to change from.
+    last_frame: Option<Vec<u8>>,
+    transition: Option<Transition>,
+    /// Only taken when `follow_pointer` is set.
+    pointer: Option<wl_pointer::WlPointer>,
+    /// Where the pointer is over the surface, while it is.
+    pointer_at: Option<(f64, f64)>,
+    /// Whether to ask the seat for the pointer at all: parallax and shaders use it.
+    follow_pointer: bool,
+    parallax: Option<Parallax>,
+    /// A shader frame was committed and the compositor has not asked for the next.
+    frame_pending: bool,
+}
+
+impl RenderState {
+    /// Where a parallax view should look from: the pointer, from -1 to 1 across each
+    /// axis of the surface, or straight on once it left.
+    fn pointer_target(&self) -> (f32, f32) {
+        match (self.pointer_at, self.size) {
+            (Some((x, y)), Some((width, height))) => (
+                (x / f64::from(width) * 2.0 - 1.0).clamp(-1.0, 1.0) as f32,
+                (y / f64::from(height) * 2.0 - 1.0).clamp(-1.0, 1.0) as f32,
+            ),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// A transition from the last frame drawn, once there is one.
+    fn change_from_last(&self, effect: TransitionEffect, duration: Duration) -> Option<Transition> {
+        self.last_frame
+            .clone()
+            .filter(|frame| !frame.is_empty())
+            .map(|from| Transition {
+                from,
+                effect,
+                duration,
+                started: None,
+            })
+    }
+
+    fn draw(&mut self, player: &Player) -> Result<(), Box<dyn Error>> {
+        let (Some(layer), Some((width, height))) = (&self.layer, self.size) else {
+            return Ok(());
+        };
+        if self.pool.is_none() {
+            self.pool = Some(SlotPool::new((width * height * 4) as usize, &self.shm)?);
+        }
+        let Some(pool) = &mut self.pool else {
+            return Ok(());
+        };
+        let stride = width as i32 * 4;
+        let (buffer, canvas) = pool.create_buffer(
+            width as i32,
+            height as i32,
+            stride,
+            wl_shm::Format::Xrgb8888,
+        )?;
+        match &mut self.parallax {
+            Some(parallax) => parallax.render(canvas, width, height),
+            None => player.render(canvas, width, height)?,
+        }
+        if let Some(transition) = self.transition.take()
+            && transition.from.len() == canvas.len()
+        {
+            match transition.progress() {
+                // Keep the old image up until the new one is ready.
+                None => {
+                    canvas.copy_from_slice(&transition.from);
+                    self.transition = Some(transition);
+                }
+                Some(progress) if progress < 1.0 => {
+                    match transition.effect {
+                        TransitionEffect::Slide => slide(canvas, &transition.from, width, progress),
+                        _ => blend(canvas, &transition.from, progress),
+                    }
+                    self.transition = Some(transition);
+                }
+                Some(_) => {}
+            }
+        }
+        if self.transition.is_none()
+            && let Some(last_frame) = &mut self.last_frame
+        {
+            last_frame.clear();
+            last_frame.extend_from_slice(canvas);
+        }
+
+        layer
+            .wl_surface()
+            .damage_buffer(0, 0, width as i32, height as i32);
+        buffer.attach_to(layer.wl_surface())?;
+        layer.commit();
+        self.needs_draw = false;
+        Ok(())
+    }
+}
+
+impl CompositorHandler for RenderState {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_factor: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+        self.frame_pending = false;
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for RenderState {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl LayerShellHandler for RenderState {
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
+        // The compositor drops the surface when its output goes away.
+        self.closed = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let (width, height) = configure.new_size;
+        if width > 0 && height > 0 && self.size != Some((width, height)) {
+            self.size = Some((width, height));
+            // The old pool is sized for the old surface.
+            self.pool = None;
+        }
+        self.needs_draw = true;
+    }
+}
+
+impl ShmHandler for RenderState {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl SeatHandler for RenderState {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {}
+
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer && self.follow_pointer && self.pointer.is_none() {
+            match self.seat_state.get_pointer(qh, &seat) {
+                Ok(pointer) => self.pointer = Some(pointer),
+                Err(err) => warn!("Could not follow the pointer: {}", err),
+            }
+        }
+    }
+
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer
+            && let Some(pointer) = self.pointer.take()
+        {
+            pointer.release();
+            self.pointer_at = None;
+        }
+    }
+
+    fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {
+    }
+}
+
+impl PointerHandler for RenderState {
+    fn pointer_frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _pointer: &wl_pointer::WlPointer,
+        events: &[PointerEvent],
+    ) {
+        for event in events {
+            match event.kind {
+                PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
+                    self.pointer_at = Some(event.position);
+                }
+                PointerEventKind::Leave { .. } => self.pointer_at = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+delegate_compositor!(RenderState);
+delegate_output!(RenderState);
+delegate_seat!(RenderState);
+delegate_pointer!(RenderState);
+delegate_shm!(RenderState);
+delegate_layer!(RenderState);
+delegate_registry!(RenderState);
+
+impl ProvidesRegistryState for RenderState {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState, SeatState];
+}
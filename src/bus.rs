@@ -0,0 +1,163 @@
+//! `io.melechtna.wpe` on the session bus, for bars, scripts, and other apps.
+//!
+//! `wpe daemon` claims the name and serves `/io/melechtna/wpe`. Setting a wallpaper
+//! goes through the daemon's own socket like `wpe daemon set` does; skipping and
+//! pausing go straight to the players over mpv IPC, like `wpe next` and
+//! `wpe pause`. Every [`WATCH_INTERVAL`] the daemon asks its players what they
+//! show, and `WallpaperChanged` goes out for each output whose file changed.
+//!
+//! ```text
+//! busctl --user call io.melechtna.wpe /io/melechtna/wpe io.melechtna.wpe Next s DP-1
+//! ```
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use tracing::warn;
+use zbus::{blocking::Connection, fdo, interface, object_server::SignalEmitter};
+
+use crate::{
+    control::{self, Action},
+    daemon::{self, Request},
+    ipc,
+};
+
+pub const BUS_NAME: &str = "io.melechtna.wpe";
+const OBJECT_PATH: &str = "/io/melechtna/wpe";
+/// How often the players are asked which file they show.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Keeps the name on the bus, and tells listeners what the outputs show.
+pub struct Bus {
+    connection: Connection,
+    /// Each output's file at the last look.
+    shown: HashMap<String, PathBuf>,
+    checked: Instant,
+}
+
+/// Claim [`BUS_NAME`] and serve the control interface.
+pub fn serve() -> Result<Bus, Box<dyn Error>> {
+    let connection = zbus::blocking::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Control)?
+        .build()?;
+    Ok(Bus {
+        connection,
+        shown: HashMap::new(),
+        checked: Instant::now(),
+    })
+}
+
+impl Bus {
+    /// Send `WallpaperChanged` for each of `monitors` whose file changed since the last
+    /// look, once [`WATCH_INTERVAL`] has passed.
+    pub fn watch(&mut self, monitors: Vec<String>) {
+        if self.checked.elapsed() < WATCH_INTERVAL {
+            return;
+        }
+        self.checked = Instant::now();
+        let mut shown = HashMap::new();
+        for monitor in monitors {
+            let Some(path) = ipc::get_property(&monitor, "path")
+                .ok()
+                .and_then(|value| value.as_str().map(PathBuf::from))
+            else {
+                continue;
+            };
+            if self.shown.get(&monitor) != Some(&path) {
+                self.emit_changed(&monitor, &path);
+            }
+            shown.insert(monitor, path);
+        }
+        self.shown = shown;
+    }
+
+    fn emit_changed(&self, monitor: &str, path: &Path) {
+        let result = self
+            .connection
+            .object_server()
+            .interface::<_, Control>(OBJECT_PATH)
+            .and_then(|control| {
+                zbus::block_on(Control::wallpaper_changed(
+                    control.signal_emitter(),
+                    monitor,
+                    &path.to_string_lossy(),
+                ))
+            });
+        if let Err(err) = result {
+            warn!("Could not announce the wallpaper on {}: {}", monitor, err);
+        }
+    }
+}
+
+struct Control;
+
+#[interface(name = "io.melechtna.wpe")]
+impl Control {
+    /// Make `monitor` play `path` (a file, folder, or URL), saved into the config.
+    fn set_wallpaper(&self, monitor: String, path: String) -> fdo::Result<()> {
+        let reply = request(Request::Set {
+            monitor,
+            path: PathBuf::from(path),
+        })?;
+        match reply.failed.first() {
+            Some((monitor, err)) => Err(fdo::Error::Failed(format!("{monitor}: {err}"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Skip to the next file of `monitor`'s folder.
+    fn next(&self, monitor: String) -> fdo::Result<()> {
+        Action::Next.run(&monitor).map_err(fdo::Error::Failed)
+    }
+
+    /// Go back to the file `monitor` showed before.
+    fn previous(&self, monitor: String) -> fdo::Result<()> {
+        Action::Previous.run(&monitor).map_err(fdo::Error::Failed)
+    }
+
+    /// Tag the file on `monitor` as a favorite.
+    fn favorite(&self, monitor: String) -> fdo::Result<()> {
+        Action::Favorite.run(&monitor).map_err(fdo::Error::Failed)
+    }
+
+    /// Pause every wallpaper.
+    fn pause(&self) -> fdo::Result<()> {
+        control::act_on_all(Action::Pause).map_err(fdo::Error::Failed)
+    }
+
+    /// Resume every wallpaper.
+    fn resume(&self) -> fdo::Result<()> {
+        control::act_on_all(Action::Resume).map_err(fdo::Error::Failed)
+    }
+
+    /// The running wallpapers: output, pid, state, and source.
+    fn status(&self) -> fdo::Result<Vec<(String, u32, String, String)>> {
+        Ok(request(Request::Status)?
+            .running
+            .into_iter()
+            .map(|running| (running.monitor, running.pid, running.state, running.source))
+            .collect())
+    }
+
+    /// `monitor` started showing `path`.
+    #[zbus(signal)]
+    async fn wallpaper_changed(
+        emitter: &SignalEmitter<'_>,
+        monitor: &str,
+        path: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Hand `request` to the daemon over its socket.
+fn request(request: Request) -> fdo::Result<daemon::Reply> {
+    let reply = daemon::send(&request).map_err(|err| fdo::Error::Failed(err.to_string()))?;
+    match reply.error {
+        Some(err) => Err(fdo::Error::Failed(err)),
+        None => Ok(reply),
+    }
+}
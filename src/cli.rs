@@ -1,4 +1,8 @@
-use clap::Parser;
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::{Parser, Subcommand};
+
+use crate::{config::ConfigFormat, output::OutputMode, service::ServiceMode, worker::WorkerTask};
 
 /// CLI switches for launching wallpapers or the GUI.
 #[derive(Parser, Debug)]
@@ -7,4 +11,276 @@ pub struct Args {
     /// Launch configured wallpapers using ~/.config/wpe/config.toml.
     #[arg(short = 'c', long = "config", help = "Launch configured wallpapers")]
     pub use_config: bool,
+
+    /// Seconds to keep waiting for configured monitors that are not connected yet, and
+    /// for the filesystems wallpaper paths live on to mount.
+    #[arg(
+        long = "wait-timeout",
+        value_name = "SECONDS",
+        default_value_t = 10,
+        help = "How long -c waits for configured monitors and mounts to appear"
+    )]
+    pub wait_timeout: u64,
+
+    /// Print nothing on stdout; rely on the exit code.
+    #[arg(short, long, global = true, conflicts_with = "porcelain")]
+    pub quiet: bool,
+
+    /// Print stable, tab-separated records for scripts instead of prose.
+    #[arg(long, global = true)]
+    pub porcelain: bool,
+
+    /// Lock the config as `locked = true` does: the GUI only starts and stops
+    /// wallpapers, and the daemon refuses to change them.
+    #[arg(long, global = true)]
+    pub kiosk: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Args {
+    pub fn output_mode(&self) -> OutputMode {
+        if self.porcelain {
+            OutputMode::Porcelain
+        } else if self.quiet {
+            OutputMode::Quiet
+        } else {
+            OutputMode::Human
+        }
+    }
+}
+
+/// Maintenance subcommands that run instead of the GUI or launcher.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List running wallpapers, then installed dependency versions and any
+    /// compatibility problems.
+    Status {
+        /// Print the wallpapers, tools, and warnings as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// List connected outputs with their mode and the wallpaper on each.
+    Monitors {
+        /// Print the outputs as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Skip to the next file of a folder wallpaper.
+    Next(Target),
+    /// Go back to the file a wallpaper showed before this one.
+    Previous(Target),
+    /// Tag the file a wallpaper shows now as a favorite.
+    Favorite(Target),
+    /// Pause a running wallpaper.
+    Pause(Target),
+    /// Resume a paused wallpaper.
+    Resume(Target),
+    /// Pause every wallpaper, or resume them all if none is playing; for a keybinding.
+    Toggle {
+        /// Skip the badge flashed on screen.
+        #[arg(long)]
+        no_osd: bool,
+    },
+    /// Check the compositor, tools, config, and directories wallpapers depend on.
+    Doctor {
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Sample running wallpapers and estimate their CPU/GPU cost.
+    PowerReport {
+        /// Length of the sampling window in seconds.
+        #[arg(long, value_name = "SECONDS", default_value_t = 10)]
+        seconds: u64,
+    },
+    /// Compare hwdec and scaling settings for a file and recommend the cheapest.
+    Bench {
+        file: PathBuf,
+        /// Output to play on (default: the first one).
+        #[arg(long)]
+        monitor: Option<String>,
+        /// Length of each measurement in seconds.
+        #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+        seconds: u64,
+        /// Save the recommendation into the entries that play this file.
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Inspect or rewrite the config file.
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Pull `[[sync]]` collections from their remotes.
+    Sync {
+        /// Only this collection (default: all of them).
+        name: Option<String>,
+        /// Skip collections whose interval has not elapsed, for use from a timer.
+        #[arg(long)]
+        due: bool,
+    },
+    /// Tag wallpaper files and folders for `source = { tags = [...] }` entries.
+    Tag {
+        #[command(subcommand)]
+        action: TagCommand,
+    },
+    /// Import Wallpaper Engine video projects from Steam's workshop folder.
+    Import {
+        /// A project folder, or a folder of them (default: Steam's Wallpaper Engine
+        /// workshop folder).
+        path: Option<PathBuf>,
+        /// Make this output's entry play the import.
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+    /// Show which wallpaper files were on screen most, and which never were.
+    Stats {
+        /// How many of the most shown files to list.
+        #[arg(long, value_name = "COUNT", default_value_t = 10)]
+        top: usize,
+    },
+    /// Save the config as a named profile, or switch to one.
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommand,
+    },
+    /// Start wpe with the graphical session through a systemd user unit.
+    InstallService {
+        /// Run the daemon, or `wpe -c` once.
+        #[arg(long, value_enum, default_value_t = ServiceMode::Daemon)]
+        mode: ServiceMode,
+        /// Also write an XDG autostart entry that starts the unit, for sessions that
+        /// never reach graphical-session.target.
+        #[arg(long)]
+        autostart: bool,
+        /// Start the unit right away as well.
+        #[arg(long)]
+        now: bool,
+    },
+    /// Stop and remove what `install-service` wrote.
+    UninstallService,
+    /// Run the daemon that owns every wallpaper, or send it a command.
+    Daemon {
+        /// Also answer HTTP requests on this address, e.g. 127.0.0.1:9843.
+        #[arg(long, value_name = "ADDRESS")]
+        http: Option<SocketAddr>,
+        #[command(subcommand)]
+        action: Option<DaemonCommand>,
+    },
+    /// Play a wallpaper on one output with the built-in libmpv backend. Started by
+    /// wpe itself with the same arguments mpvpaper takes.
+    #[cfg(feature = "builtin")]
+    #[command(hide = true)]
+    Render {
+        /// mpv options, e.g. "--hwdec=auto-safe --loop-file=inf".
+        #[arg(short = 'o', value_name = "OPTIONS")]
+        options: Option<String>,
+        /// Seconds each file of a playlist stays on screen.
+        #[arg(long, value_name = "SECONDS")]
+        slideshow: Option<u64>,
+        /// Show the folder at `path` as a time-lapse set, fading over this many seconds.
+        #[arg(long, value_name = "FADE_SECONDS")]
+        timelapse: Option<u64>,
+        /// Hour of the day the set's first image comes up at.
+        #[arg(long, value_name = "HOUR", default_value_t = 0)]
+        timelapse_start: u32,
+        /// Shift the image at `path` with the pointer by this depth map.
+        #[arg(long, value_name = "DEPTH_MAP")]
+        parallax: Option<PathBuf>,
+        /// How far the nearest parts of a parallax image move, in percent of the width.
+        #[arg(long, value_name = "PERCENT", default_value_t = 2)]
+        parallax_shift: u32,
+        /// Draw the shader at `path` on the GPU, at most this many frames a second.
+        #[arg(long, value_name = "FPS")]
+        shader: Option<u32>,
+        /// How fast a shader's clock runs, in percent of real time.
+        #[arg(long, value_name = "PERCENT", default_value_t = 100)]
+        shader_speed: u32,
+        /// How a playlist moves from one file to the next.
+        #[arg(long, value_enum, value_name = "EFFECT")]
+        transition: Option<crate::transition::TransitionEffect>,
+        /// How long a transition takes, in milliseconds.
+        #[arg(long, value_name = "MS", default_value_t = 800)]
+        transition_ms: u64,
+        monitor: String,
+        path: PathBuf,
+    },
+    /// Read one untrusted media file for the GUI or daemon; started by wpe itself.
+    #[command(hide = true)]
+    Worker {
+        #[arg(value_enum)]
+        task: WorkerTask,
+        path: PathBuf,
+    },
+}
+
+/// The output a playback command acts on.
+#[derive(clap::Args, Debug)]
+pub struct Target {
+    /// Output name, e.g. DP-1.
+    #[arg(required_unless_present = "all")]
+    pub monitor: Option<String>,
+    /// Every running wallpaper.
+    #[arg(long, conflicts_with = "monitor")]
+    pub all: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DaemonCommand {
+    /// Start configured wallpapers that are not running yet.
+    Start,
+    /// Stop one output's wallpaper, or all of them.
+    Stop { monitor: Option<String> },
+    /// Re-read the config and restart the wallpapers whose entry changed.
+    Reload,
+    /// Make an output's entry play this file, folder, or URL, and restart it.
+    Set { monitor: String, path: PathBuf },
+    /// List the wallpapers the daemon runs.
+    Status,
+    /// Stop every wallpaper and shut the daemon down.
+    Quit,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Rewrite the active config in another format (toml, json, ron).
+    Convert {
+        #[arg(value_enum)]
+        to: ConfigFormat,
+        /// Replace a config that already exists in that format.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print a JSON Schema of the config file for editor completion and checks.
+    Schema,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommand {
+    /// Save the active config as ~/.config/wpe/profiles/NAME.
+    Save { name: String },
+    /// Make a saved profile the active config and apply it to running wallpapers.
+    Load { name: String },
+    /// List the saved profiles.
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TagCommand {
+    /// Add tags to a file or folder.
+    Add {
+        path: PathBuf,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a file or folder.
+    Remove {
+        path: PathBuf,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// List tagged paths, optionally only those carrying `tag`.
+    List { tag: Option<String> },
 }
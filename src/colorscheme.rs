@@ -0,0 +1,121 @@
+//! Color schemes that follow the wallpaper.
+//!
+//! With `[colorscheme] enabled = true`, whoever keeps the players running (the GUI or
+//! `wpe daemon`) notices a new file on screen when it asks the players what they show
+//! for the usage records (see [`crate::usage`]). It then captures a frame, writes the
+//! frame's dominant colors to `$XDG_CACHE_HOME/wpe/colors.json` and `colors` (one
+//! `#rrggbb` per line, most common first), and hands the frame to pywal or matugen
+//! when `tool` names one, so terminals and bars can follow the wallpaper. Players
+//! without IPC (the still-image backends) are not followed.
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{fallback, palette, state};
+
+/// Colors written, as many as pywal's scheme has.
+const PALETTE_SIZE: usize = 16;
+
+/// `tool`: what the frame is handed to after the colors are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchemeTool {
+    /// Only write the colors.
+    #[default]
+    None,
+    /// `wal -i FRAME -n -q`, leaving the wallpaper to wpe.
+    Pywal,
+    /// `matugen image FRAME`.
+    Matugen,
+}
+
+/// `[colorscheme]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ColorSchemeSettings {
+    pub enabled: bool,
+    /// Output whose wallpaper the scheme follows; unset follows whichever changed last.
+    pub monitor: Option<String>,
+    pub tool: SchemeTool,
+}
+
+impl ColorSchemeSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Of the monitors whose file just changed (from [`crate::usage::Tracker::record`]),
+    /// the one the scheme should follow now.
+    pub fn pick(&self, changed: Vec<(String, PathBuf)>) -> Option<(String, PathBuf)> {
+        if !self.enabled {
+            return None;
+        }
+        changed.into_iter().rev().find(|(monitor, _)| {
+            self.monitor
+                .as_deref()
+                .is_none_or(|followed| followed == monitor)
+        })
+    }
+}
+
+/// Write the colors of what `monitor` shows now, `file`, and run the configured tool.
+/// Blocks on the player's IPC socket and the tool.
+pub fn update(
+    settings: &ColorSchemeSettings,
+    monitor: &str,
+    file: &Path,
+) -> Result<(), Box<dyn Error>> {
+    fallback::capture(monitor)?;
+    let frame = fallback::cached_frame(monitor)
+        .ok_or_else(|| format!("No frame of {monitor}'s wallpaper was captured"))?;
+    let colors: Vec<String> = palette::extract(&frame, PALETTE_SIZE)?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let dir = state::cache_dir()?;
+    let scheme = json!({
+        "monitor": monitor,
+        "wallpaper": file,
+        "frame": frame,
+        "colors": colors,
+    });
+    fs::write(
+        dir.join("colors.json"),
+        serde_json::to_string_pretty(&scheme)?,
+    )?;
+    fs::write(dir.join("colors"), colors.join("\n") + "\n")?;
+    info!("Wrote the color scheme of {} ({})", monitor, file.display());
+
+    let mut command = match settings.tool {
+        SchemeTool::None => return Ok(()),
+        SchemeTool::Pywal => {
+            let mut command = Command::new("wal");
+            command.arg("-i").arg(&frame).args(["-n", "-q"]);
+            command
+        }
+        SchemeTool::Matugen => {
+            let mut command = Command::new("matugen");
+            command.arg("image").arg(&frame);
+            command
+        }
+    };
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|err| format!("Could not run {program}: {err}"))?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}").into());
+    }
+    Ok(())
+}
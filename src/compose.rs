@@ -0,0 +1,66 @@
+//! Combine several sources into one wallpaper inside mpv.
+//!
+//! mpvpaper draws a single full-output player per monitor, so grids and
+//! picture-in-picture are composited inside that player: extra sources are loaded
+//! with `external-file` and a `lavfi-complex` graph combines their video tracks into
+//! `[vo]`. The options go into an include file next to the IPC socket, which keeps
+//! paths with spaces intact and is reachable from the sandbox.
+
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::{config::MediaKind, grid::Grid, ipc, pip::Pip};
+
+/// Used when the output's mode is unknown; the result is scaled to the window anyway.
+const FALLBACK_SIZE: (u32, u32) = (1920, 1080);
+
+/// Write the include file combining `grid` and `pip` for `monitor`, returning its path.
+pub fn write_include(
+    monitor: &str,
+    grid: Option<&Grid>,
+    pip: Option<&Pip>,
+    output_size: Option<(u32, u32)>,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let size = output_size.unwrap_or(FALLBACK_SIZE);
+    let mut content = String::from("# Written by wpe; regenerated on every launch.\n");
+
+    // Video tracks are numbered in load order: the main file, then each external file.
+    let mut graph = String::new();
+    let mut tracks = 1;
+    let base = match grid {
+        Some(grid) => {
+            for tile in grid.tiles.iter().skip(1) {
+                content.push_str(&option("external-file", &tile.path().to_string_lossy()));
+            }
+            tracks = grid.tiles.len();
+            let output = if pip.is_some() { "base" } else { "vo" };
+            graph.push_str(&grid.filter_graph(size, output));
+            "base"
+        }
+        None => "vid1",
+    };
+    if let Some(pip) = pip {
+        content.push_str(&option("external-file", &pip.source));
+        if !graph.is_empty() {
+            graph.push(';');
+        }
+        graph.push_str(&pip.filter_graph(base, tracks + 1, size.0));
+    }
+
+    if grid.is_some_and(|grid| {
+        grid.tiles
+            .iter()
+            .all(|tile| matches!(tile, MediaKind::Image(_)))
+    }) {
+        content.push_str(&option("image-display-duration", "inf"));
+    }
+    content.push_str(&option("lavfi-complex", &graph));
+
+    let path = ipc::socket_dir()?.join(format!("compose-{}.conf", ipc::file_safe(monitor)));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// One config-file line, using mpv's `%len%` quoting so any value survives.
+fn option(name: &str, value: &str) -> String {
+    format!("{name}=%{}%{value}\n", value.len())
+}
@@ -1,47 +1,101 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
     error::Error,
-    fs,
+    fmt, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
     path::{Path, PathBuf},
-    time::Duration,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime},
 };
 
+use chrono::NaiveDate;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::{borrow::Cow, env};
+use tracing::{info, warn};
 
-use crate::monitors::Monitor;
+use crate::{
+    battery::PowerSettings,
+    colorscheme::ColorSchemeSettings,
+    error::{Source, WpeError},
+    fallback::{self, FallbackSource},
+    fullscreen::FullscreenSettings,
+    grid::Grid,
+    idle::IdleSettings,
+    loops::LoopPoints,
+    monitors::{self, Monitor},
+    motion::MotionSettings,
+    notify::NotificationSettings,
+    parallax::ParallaxSettings,
+    pip::Pip,
+    procedural::ProceduralSettings,
+    seasons::{self, SeasonRule},
+    shader::ShaderSettings,
+    sources::{self, Mix, Tagged, WallpaperSource},
+    state,
+    sync::SyncCollection,
+    thermal::ThermalSettings,
+    timelapse::{self, TimelapseSettings},
+    transition::TransitionSettings,
+    tweaks::{LiveTweakSettings, Picture},
+    variants,
+    watchdog::WatchdogSettings,
+    web::WebSettings,
+};
 
 const CONFIG_HEADER: &str = "\
 # ///////////////////////////////////////////////
 # This config powers WallPaper Engine (wpe).
 # Each display starts with [[wallpapers]] and is
 # auto-populated either by the GUI or by
-# running wpe -c on first run. monitor is
-# the output we're targeting. path is the
-# image, video, or folder. scale controls how
-# mpvpaper scales the source: fit fills the
-# monitor, stretch preserves aspect ratio, and
-# original uses the source resolution. Set enabled
-# to false to leave a display unconfigured without
-# clearing the path. order is for folders:
-# sequential (A-Z) or random.
-# interval_seconds is the amount of time (in
-# seconds) before folder content swaps to the
-# next image or video.
+# running wpe -c on first run. monitor is the
+# output we're targeting. path is the image,
+# video, folder, playlist, or stream URL.
+# scale controls how the source is scaled: fit,
+# stretch, original, fill, center, or tile.
+# Set enabled to false to leave a display
+# unconfigured without clearing the path.
+# order is for folders: sequential (A-Z),
+# random, or least-shown. interval_seconds is
+# how long each image of a folder is shown.
+# Every other setting is described in the
+# README; wpe config schema prints them all as
+# a JSON Schema for editor completion.
 # ///////////////////////////////////////////////
 ";
 
 pub const PLACEHOLDER_PATH: &str = "your/image/or/folder/here";
 
+/// `--kiosk` was given, which locks the config like `locked = true`.
+static KIOSK: AtomicBool = AtomicBool::new(false);
+
+/// Whether `path` is the placeholder a new entry ships with (or blank), i.e. no
+/// wallpaper has been chosen yet. Resolving it would point somewhere under $HOME.
+pub fn is_placeholder(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    let path = path.trim().trim_start_matches('~').trim_start_matches('/');
+    path.is_empty() || path == PLACEHOLDER_PATH
+}
+
 /// Scaling choices exposed to both CLI and config file.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ScaleMode {
     /// Non-uniform scaling to fill the entire output.
+    #[default]
     Fit,
     /// Uniform scaling that preserves aspect ratio (letterboxed/pillarboxed).
     Stretch,
     /// No scaling (render at the source centered as is).
     Original,
+    /// Uniform scaling that covers the output, cropping what sticks out.
+    Fill,
+    /// Never scaled, centered; a source larger than the output is cropped.
+    Center,
+    /// A single image repeated across the output at its own size, for small patterns.
+    Tile,
 }
 
 #[derive(Debug, Clone)]
@@ -49,14 +103,34 @@ pub enum MediaKind {
     Image(PathBuf),
     Folder(PathBuf),
     Video(PathBuf),
+    /// An `.m3u`/`.pls` file mpv plays through like a folder.
+    Playlist(PathBuf),
+    /// A URL mpv opens itself.
+    Stream(String),
+    /// Several sources (`path = [...]`) merged into one playlist.
+    Mix(Vec<MediaKind>),
 }
 
 impl MediaKind {
+    /// What to pass to mpvpaper; a stream's URL as it is.
     pub fn path(&self) -> &Path {
         match self {
-            MediaKind::Image(path) | MediaKind::Folder(path) | MediaKind::Video(path) => path,
+            MediaKind::Image(path)
+            | MediaKind::Folder(path)
+            | MediaKind::Video(path)
+            | MediaKind::Playlist(path) => path,
+            MediaKind::Stream(url) => Path::new(url),
+            MediaKind::Mix(items) => items.first().map_or(Path::new(""), MediaKind::path),
         }
     }
+
+    /// Whether mpv steps through several items, so the slideshow settings apply.
+    pub fn is_playlist(&self) -> bool {
+        matches!(
+            self,
+            MediaKind::Folder(_) | MediaKind::Playlist(_) | MediaKind::Mix(_)
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -64,190 +138,1908 @@ pub struct RuntimeConfig {
     pub monitor: Option<String>,
     pub media: MediaKind,
     pub slideshow: SlideshowSettings,
+    /// Which files of a folder `media` play.
+    pub folder_filter: FolderFilter,
     pub scale: ScaleMode,
+    pub audio: bool,
+    pub audio_device: Option<String>,
+    /// Volume from 0 to 100, when `audio` is on.
+    pub volume: u8,
+    /// The entry's own backend, over the top-level `backend`.
+    pub backend: Option<Backend>,
+    pub mpvpaper: MpvpaperFlags,
+    /// A-B loop applied to single videos, from the GUI's loop tool.
+    pub loop_points: Option<LoopPoints>,
+    pub tuning: MpvTuning,
+    pub picture: Picture,
+    pub margins: Margins,
+    /// Mode of the output being drawn on, when known; margins and grids need it.
+    pub output_size: Option<(u32, u32)>,
+    /// Refresh rate of that output in Hz, for `display_sync`.
+    pub refresh_rate: Option<u32>,
+    /// Tiles composited into one frame, for grid entries.
+    pub grid: Option<Grid>,
+    /// Small second source drawn in a corner.
+    pub pip: Option<Pip>,
+    /// Folder images shown by time of day, for time-lapse sets.
+    pub timelapse: Option<TimelapseSettings>,
+    /// Depth map the image shifts by as the pointer moves.
+    pub parallax: Option<ParallaxSettings>,
+    /// Frame rate and speed of a shader drawn on the GPU.
+    pub shader: Option<ShaderSettings>,
+    /// How a playlist moves on to its next file; unset to cut.
+    pub transition: Option<TransitionSettings>,
+    /// Showing the monitor's last-known-good frame because the source is missing.
+    pub from_cache: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// mpvpaper's own switches, passed as typed flags instead of through `-o`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MpvpaperFlags {
+    /// Fork mpvpaper into the background (`--fork`).
+    pub fork: bool,
+    /// Pause playback while the wallpaper is hidden (`--auto-pause`). On by default;
+    /// skipped when the installed mpvpaper is too old to support it.
+    pub auto_pause: bool,
+    /// Stop playback while the wallpaper is hidden (`--auto-stop`).
+    pub auto_stop: bool,
+    /// Advance folder playlists on the interval timer (`--slideshow`); when false,
+    /// each item plays to its end before mpv moves on.
+    pub slideshow: bool,
+}
+
+impl Default for MpvpaperFlags {
+    fn default() -> Self {
+        Self {
+            fork: false,
+            auto_pause: true,
+            auto_stop: false,
+            slideshow: true,
+        }
+    }
+}
+
+impl MpvpaperFlags {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// mpv decode and scaling options for one entry, as recommended by `wpe bench`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MpvTuning {
+    /// `--hwdec` value such as `auto-safe`, `vaapi`, `nvdec`, or `none`; unset takes
+    /// the top-level `hwdec`, then `auto-safe`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hwdec: Option<String>,
+    /// mpv `--profile`, e.g. `fast` for cheaper scaling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Pace videos to the output's refresh rate (`--video-sync=display-resample`) and
+    /// nudge the speed so a loop lasts a whole number of refreshes.
+    #[serde(skip_serializing_if = "is_false")]
+    pub display_sync: bool,
+    /// Largest video height to play at; taller videos are scaled down right after
+    /// decoding. Unset uses the output's height, `0` turns the cap off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+}
+
+impl MpvTuning {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Pixels along each edge of the output the wallpaper leaves uncovered, so it does
+/// not render behind a transparent bar or dock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Margins {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl Margins {
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Clamp so at least a quarter of the output stays covered on each axis.
+    pub fn clamped(self, width: u32, height: u32) -> Self {
+        let max_x = width * 3 / 4;
+        let max_y = height * 3 / 4;
+        let left = self.left.min(max_x);
+        let top = self.top.min(max_y);
+        Self {
+            top,
+            right: self.right.min(max_x - left),
+            bottom: self.bottom.min(max_y - top),
+            left,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum SlideshowOrder {
+    #[default]
     Sequential,
     Random,
+    /// Random, drawing files that were shown least often first.
+    LeastShown,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct SlideshowSettings {
     pub order: SlideshowOrder,
+    /// How long each image is shown.
     pub interval: Duration,
+    /// Percent `interval` may vary by.
+    pub jitter: u8,
+    /// The longest a video plays before the slideshow moves on; `None` plays it to the end.
+    pub video_limit: Option<Duration>,
 }
 
-impl RuntimeConfig {
-    /// Build runtime settings from ~/.config/wpe/config.toml
-    pub fn from_entry(index: usize) -> Result<Self, Box<dyn Error>> {
-        let mut profile = load_or_create_profile()?;
-        if profile.wallpapers.is_empty() {
-            profile.wallpapers.push(WallpaperEntry::default());
-            save_profile(&profile)?;
+/// Which files of a folder play: `recursive`, `include`, and `exclude` on the entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderFilter {
+    /// Descend into subfolders (as mpv does).
+    pub recursive: bool,
+    /// Globs, relative to the folder, of which a file must match one; empty takes all.
+    pub include: Vec<String>,
+    /// Globs of files left out even when included, e.g. `**/thumbs/**`.
+    pub exclude: Vec<String>,
+    /// Only files tagged as favorites (see [`crate::history`]).
+    pub favorites_only: bool,
+}
+
+impl Default for FolderFilter {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            favorites_only: false,
         }
+    }
+}
 
-        let entry = profile
-            .wallpapers
-            .get(index)
-            .ok_or_else(|| format!("No wallpaper entry found at index {}", index))?;
+impl FolderFilter {
+    /// Whether the folder plays the way mpv would list it anyway.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 
-        let path = entry
-            .path
-            .as_ref()
-            .ok_or_else(|| "Configured entry is missing a file or folder path".to_string())?;
+    /// Compile the globs, failing on the first malformed one.
+    pub fn matcher(&self) -> Result<FolderMatcher, WpeError> {
+        let build =
+            |patterns: &[String]| -> Result<GlobSet, WpeError> {
+                let mut set = GlobSetBuilder::new();
+                for pattern in patterns {
+                    set.add(Glob::new(pattern).map_err(|err| {
+                        WpeError::config(None, "Bad folder glob").with_source(err)
+                    })?);
+                }
+                set.build()
+                    .map_err(|err| WpeError::config(None, "Bad folder globs").with_source(err))
+            };
+        Ok(FolderMatcher {
+            include: build(&self.include)?,
+            exclude: build(&self.exclude)?,
+        })
+    }
+}
 
-        let resolved_path = normalize_entry_path(path);
-        let media = detect_media_kind(&resolved_path)?;
+/// A compiled [`FolderFilter`].
+pub struct FolderMatcher {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl FolderMatcher {
+    /// Whether the file at `relative` (to the folder) plays.
+    pub fn matches(&self, relative: &Path) -> bool {
+        (self.include.is_empty() || self.include.is_match(relative))
+            && !self.exclude.is_match(relative)
+    }
+}
+
+impl SlideshowSettings {
+    /// The interval for one player: `interval` moved by up to `jitter` percent either way,
+    /// so players started together drift apart instead of changing on the same second.
+    pub fn draw_interval(&self) -> Duration {
+        if self.jitter == 0 {
+            return self.interval;
+        }
+        let spread = f64::from(self.jitter.min(MAX_INTERVAL_JITTER)) / 100.0;
+        let factor = 1.0 + spread * (fastrand::f64() * 2.0 - 1.0);
+        self.interval.mul_f64(factor).max(Duration::from_secs(1))
+    }
+}
+
+impl RuntimeConfig {
+    /// Build runtime settings from an entry that is already in memory.
+    pub fn from_profile_entry(entry: &WallpaperProfileEntry) -> Result<Self, WpeError> {
+        Self::resolve_entry(entry)
+            .map_err(|err| WpeError::config(entry.configured_path().map(Path::to_path_buf), err))
+    }
+
+    fn resolve_entry(entry: &WallpaperProfileEntry) -> Result<Self, Box<dyn Error>> {
+        if let Some(grid) = &entry.grid {
+            let grid = grid.resolve()?;
+            let mut runtime = Self::from_profile_entry(&WallpaperProfileEntry {
+                path: Some(grid.tiles[0].path().to_path_buf()),
+                source: None,
+                grid: None,
+                timelapse: None,
+                parallax: None,
+                shader: None,
+                procedural: None,
+                ..entry.clone()
+            })?;
+            // Loop points belong to a single video, not to the composited grid.
+            runtime.loop_points = None;
+            runtime.grid = Some(grid);
+            return Ok(runtime);
+        }
+        if let Some(procedural) = &entry.procedural {
+            let (path, shader) = procedural.materialize()?;
+            return Self::from_profile_entry(&WallpaperProfileEntry {
+                path: Some(path),
+                extra_paths: Vec::new(),
+                source: None,
+                timelapse: None,
+                parallax: None,
+                shader: Some(shader),
+                procedural: None,
+                ..entry.clone()
+            });
+        }
+
+        let tags = match &entry.source {
+            Some(source) if source.seasonal => {
+                source.resolve_tags(&load_settings()?.seasons, seasons::today())
+            }
+            Some(source) => source.resolve_tags(&[], seasons::today()),
+            None => None,
+        };
+        let mut from_cache = false;
+        let media = match (tags, entry.mix()) {
+            (Some(tags), _) => Tagged(tags).resolve()?,
+            (None, Some(mix)) => mix.resolve()?,
+            (None, None) => {
+                let path = entry.configured_path().ok_or_else(|| {
+                    format!(
+                        "No wallpaper chosen for {} yet; set its path to an image, video, or folder",
+                        entry.monitor.as_deref().unwrap_or("this entry")
+                    )
+                })?;
+                match sources::for_path(path) {
+                    Some(source) => source.resolve()?,
+                    None => match entry.monitor.as_deref().and_then(fallback::cached_frame) {
+                        Some(frame) => {
+                            warn!(
+                                "{} is unavailable; showing the last cached frame instead.",
+                                normalize_entry_path(path).display()
+                            );
+                            from_cache = true;
+                            MediaKind::Image(frame)
+                        }
+                        None => sources::resolve_path(path)?,
+                    },
+                }
+            }
+        };
+        let loop_points = match &media {
+            MediaKind::Video(path) => state::load_state().loops.get(path).copied(),
+            _ => None,
+        };
+        if matches!(media, MediaKind::Folder(_) | MediaKind::Mix(_)) {
+            entry.folder_filter.matcher()?;
+        }
+        // A missing source already fell back to a cached still, which plays as it is.
+        let timelapse = match (&media, entry.timelapse) {
+            (MediaKind::Folder(folder), Some(settings)) => {
+                timelapse::frames(folder)?;
+                Some(settings)
+            }
+            (_, Some(_)) if !from_cache => {
+                return Err("A timelapse entry's path has to be a folder of images".into());
+            }
+            _ => None,
+        };
+        let parallax = match (&media, &entry.parallax) {
+            (MediaKind::Image(image), Some(settings)) if !from_cache => {
+                let settings = ParallaxSettings {
+                    depth_map: normalize_entry_path(&settings.depth_map),
+                    ..settings.clone()
+                };
+                settings.validate(image)?;
+                Some(settings)
+            }
+            (_, Some(_)) if !from_cache => {
+                return Err("A parallax entry's path has to be an image".into());
+            }
+            _ => None,
+        };
+        let shader = match (&media, entry.shader) {
+            (MediaKind::Image(file), Some(settings)) if !from_cache => {
+                settings.validate(file)?;
+                Some(settings)
+            }
+            (_, Some(_)) if !from_cache => {
+                return Err("A shader entry's path has to be a .glsl, .frag, or .wgsl file".into());
+            }
+            _ => None,
+        };
+        let transition = entry.transition.filter(|transition| {
+            transition.is_active() && media.is_playlist() && timelapse.is_none()
+        });
         let slideshow = SlideshowSettings {
             order: entry.order,
             interval: Duration::from_secs(entry.interval_seconds.max(1)),
+            jitter: entry.interval_jitter,
+            video_limit: entry
+                .video_seconds
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs),
         };
 
         Ok(RuntimeConfig {
             monitor: entry.monitor.clone(),
             media,
             slideshow,
+            folder_filter: entry.folder_filter.clone(),
             scale: entry.scale,
+            audio: entry.audio,
+            audio_device: entry.audio_device.clone(),
+            volume: entry.volume,
+            backend: entry.backend,
+            mpvpaper: entry.mpvpaper,
+            tuning: entry.tuning.clone(),
+            picture: entry.picture,
+            margins: entry.margins,
+            output_size: None,
+            refresh_rate: None,
+            grid: None,
+            pip: entry.pip.as_ref().and_then(PipSettings::resolve),
+            timelapse,
+            parallax,
+            shader,
+            transition,
+            loop_points,
+            from_cache,
         })
     }
 }
 
-/// Inspect a path and convert it into a MediaKind for renderer usage.
-fn detect_media_kind(path: &Path) -> Result<MediaKind, Box<dyn Error>> {
-    let metadata = fs::metadata(path)
-        .map_err(|err| format!("Unable to access {}: {}", path.display(), err))?;
-    if metadata.is_dir() {
-        return Ok(MediaKind::Folder(path.to_path_buf()));
+impl RuntimeConfig {
+    /// Remember the output's mode, which margins and display sync are measured against.
+    pub fn set_output(&mut self, monitor: &Monitor) {
+        self.output_size = Some((monitor.width, monitor.height));
+        self.refresh_rate = Some(monitor.refresh_rate).filter(|hz| *hz > 0);
     }
 
-    if metadata.is_file() {
-        if is_probably_video(path) {
-            return Ok(MediaKind::Video(path.to_path_buf()));
+    /// Swap a folder of encodes of one wallpaper for the variant that best fits `monitor`.
+    pub fn select_variant(&mut self, monitor: &Monitor, settings: &VariantSettings) {
+        let MediaKind::Folder(folder) = &self.media else {
+            return;
+        };
+        if let Some(best) = variants::select_variant(folder, monitor, settings)
+            && let Ok(media) = sources::resolve_path(&best)
+        {
+            info!(
+                "Using {} for {} ({}x{} @ {}Hz)",
+                best.display(),
+                monitor.name,
+                monitor.width,
+                monitor.height,
+                monitor.refresh_rate
+            );
+            self.media = media;
         }
-        return Ok(MediaKind::Image(path.to_path_buf()));
     }
 
-    Err(format!("{} is neither a file nor a folder", path.display()).into())
+    /// Show `frame` alone instead of the entry's media, muted and without extras.
+    pub fn show_still(&mut self, frame: PathBuf) {
+        self.media = MediaKind::Image(frame);
+        self.audio = false;
+        self.loop_points = None;
+        self.grid = None;
+        self.pip = None;
+        self.timelapse = None;
+        self.parallax = None;
+        self.shader = None;
+        self.transition = None;
+    }
+}
+
+/// Top-level config file layout written/read by the GUI/CLI.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct Profile {
+    // Plain keys have to come before the tables in TOML.
+    /// What draws the wallpapers unless an entry picks its own.
+    #[serde(default, skip_serializing_if = "Backend::is_default")]
+    backend: Backend,
+    /// Kiosk mode: wallpapers can be started and stopped, but the GUI and the daemon
+    /// change nothing in the config.
+    #[serde(default, skip_serializing_if = "is_false")]
+    locked: bool,
+    /// Hardware decoding for entries without `tuning.hwdec`; unset is `auto-safe`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hwdec: Option<String>,
+    /// One `[[wallpapers]]` entry per monitor, or per group of monitors.
+    #[serde(default)]
+    wallpapers: Vec<WallpaperEntry>,
+    #[serde(default, skip_serializing_if = "SecuritySettings::is_default")]
+    security: SecuritySettings,
+    #[serde(default, skip_serializing_if = "ProcessSettings::is_default")]
+    process: ProcessSettings,
+    #[serde(default, skip_serializing_if = "VariantSettings::is_default")]
+    variants: VariantSettings,
+    #[serde(default, skip_serializing_if = "AudioSettings::is_default")]
+    audio: AudioSettings,
+    /// Date rules for `source = { seasonal = true }` entries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    seasons: Vec<SeasonRule>,
+    /// Remote collections mirrored into local folders.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sync: Vec<SyncCollection>,
+    #[serde(default, skip_serializing_if = "CrashSettings::is_default")]
+    crash_reports: CrashSettings,
+    #[serde(default, skip_serializing_if = "ThermalSettings::is_default")]
+    thermal: ThermalSettings,
+    #[serde(default, skip_serializing_if = "FullscreenSettings::is_default")]
+    fullscreen: FullscreenSettings,
+    #[serde(default, skip_serializing_if = "PowerSettings::is_default")]
+    power: PowerSettings,
+    #[serde(default, skip_serializing_if = "IdleSettings::is_default")]
+    idle: IdleSettings,
+    #[serde(default, skip_serializing_if = "MotionSettings::is_default")]
+    motion: MotionSettings,
+    #[serde(default, skip_serializing_if = "LiveTweakSettings::is_default")]
+    live_tweaks: LiveTweakSettings,
+    #[serde(default, skip_serializing_if = "WatchdogSettings::is_default")]
+    watchdog: WatchdogSettings,
+    #[serde(default, skip_serializing_if = "NotificationSettings::is_default")]
+    notifications: NotificationSettings,
+    #[serde(default, skip_serializing_if = "ColorSchemeSettings::is_default")]
+    colorscheme: ColorSchemeSettings,
+    #[serde(default, skip_serializing_if = "WebSettings::is_default")]
+    web: WebSettings,
+    #[serde(default, skip_serializing_if = "GuiSettings::is_default")]
+    gui: GuiSettings,
+    #[serde(default, skip_serializing_if = "Preferences::is_default")]
+    settings: Preferences,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            locked: false,
+            hwdec: None,
+            wallpapers: vec![WallpaperEntry::default()],
+            security: SecuritySettings::default(),
+            process: ProcessSettings::default(),
+            variants: VariantSettings::default(),
+            audio: AudioSettings::default(),
+            seasons: Vec::new(),
+            sync: Vec::new(),
+            crash_reports: CrashSettings::default(),
+            thermal: ThermalSettings::default(),
+            fullscreen: FullscreenSettings::default(),
+            power: PowerSettings::default(),
+            idle: IdleSettings::default(),
+            motion: MotionSettings::default(),
+            live_tweaks: LiveTweakSettings::default(),
+            watchdog: WatchdogSettings::default(),
+            notifications: NotificationSettings::default(),
+            colorscheme: ColorSchemeSettings::default(),
+            web: WebSettings::default(),
+            gui: GuiSettings::default(),
+            settings: Preferences::default(),
+        }
+    }
+}
+
+/// Global settings that apply to every wallpaper rather than a single monitor.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    pub backend: Backend,
+    /// Set by `locked = true` or `--kiosk`: only starting and stopping is allowed.
+    pub locked: bool,
+    /// Top-level `hwdec`, the `--hwdec` for entries that don't set their own.
+    pub hwdec: Option<String>,
+    pub security: SecuritySettings,
+    pub process: ProcessSettings,
+    pub variants: VariantSettings,
+    pub audio: AudioSettings,
+    /// Date rules for `source = { seasonal = true }` entries.
+    pub seasons: Vec<SeasonRule>,
+    /// Remote collections mirrored into local folders.
+    pub sync: Vec<SyncCollection>,
+    pub crash_reports: CrashSettings,
+    pub thermal: ThermalSettings,
+    pub fullscreen: FullscreenSettings,
+    pub power: PowerSettings,
+    pub idle: IdleSettings,
+    pub motion: MotionSettings,
+    pub live_tweaks: LiveTweakSettings,
+    pub watchdog: WatchdogSettings,
+    pub notifications: NotificationSettings,
+    pub colorscheme: ColorSchemeSettings,
+    pub web: WebSettings,
+    pub gui: GuiSettings,
+    pub preferences: Preferences,
+}
+
+/// What draws the wallpapers: the external mpvpaper, libmpv inside wpe itself, one
+/// of the still-image setters, or a web renderer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    #[default]
+    Mpvpaper,
+    /// `wpe render`, only available when wpe is built with the `builtin` feature.
+    Builtin,
+    /// swaybg, for still images without mpv.
+    Swaybg,
+    /// swww, for still images through a running `swww-daemon`.
+    Swww,
+    /// The `[web] command`, for HTML/WebGL pages.
+    Web,
+}
+
+impl Backend {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `[variants]` table: picking between encodes of the same wallpaper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct VariantSettings {
+    /// Collapse a folder of encodes (`name-1080p60`, `name-4k30`) to the best match for the output.
+    pub enabled: bool,
+    /// Text between the wallpaper name and its resolution/framerate tag.
+    pub separator: String,
+}
+
+impl Default for VariantSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            separator: "-".into(),
+        }
+    }
+}
+
+impl VariantSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `[audio]` table: behaviour shared by every wallpaper with `audio = true`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Lower wallpaper audio while another application is playing.
+    pub duck: bool,
+    /// Volume (0-100) wallpapers drop to while ducked.
+    pub duck_volume: u8,
+    /// Let several wallpapers play sound at once; otherwise only the first one does.
+    pub allow_multiple: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            duck: true,
+            duck_volume: 30,
+            allow_multiple: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `[process]` table: scheduling applied to every spawned player.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ProcessSettings {
+    /// Niceness passed to `nice -n` (-20 to 19; higher is lower priority).
+    pub nice: Option<i32>,
+    /// I/O scheduling class passed to `ionice -c`.
+    pub ionice: Option<IoClass>,
+    /// Priority within the I/O class (0-7), passed to `ionice -n`.
+    pub ionice_level: Option<u8>,
+    /// CPU list for `taskset -c`, e.g. "0-3" or "8,9,10,11" to pin decoding to efficiency cores.
+    pub cpu_affinity: Option<String>,
+}
+
+impl ProcessSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Wrapper commands (taskset, nice, ionice) that exec into the player with these settings.
+    pub fn wrapper_args(&self) -> Vec<String> {
+        let mut argv = Vec::new();
+        if let Some(cpus) = self.cpu_affinity.as_deref().map(str::trim)
+            && !cpus.is_empty()
+        {
+            argv.extend(["taskset".into(), "-c".into(), cpus.to_string()]);
+        }
+        if let Some(nice) = self.nice {
+            argv.extend(["nice".into(), "-n".into(), nice.clamp(-20, 19).to_string()]);
+        }
+        if let Some(class) = self.ionice {
+            argv.extend(["ionice".into(), "-c".into(), class.code().to_string()]);
+            if let Some(level) = self.ionice_level
+                && class != IoClass::Idle
+            {
+                argv.extend(["-n".into(), level.min(7).to_string()]);
+            }
+        }
+        argv
+    }
+}
+
+/// I/O scheduling classes understood by `ionice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoClass {
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+impl IoClass {
+    fn code(self) -> u8 {
+        match self {
+            IoClass::Realtime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        }
+    }
+}
+
+/// `[security]` table: how much of the system spawned players may see.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SecuritySettings {
+    /// Run mpvpaper inside bubblewrap, limited to its media and the Wayland socket.
+    pub sandbox: bool,
+}
+
+impl SecuritySettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// `[gui]` table: colors of the settings window and the monitor badges.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GuiSettings {
+    /// Where the accent was kept before `[settings]`; only read when that has none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accent: Option<HexColor>,
+    /// Take the accent and background from the wallpaper on the selected monitor.
+    #[serde(skip_serializing_if = "is_false")]
+    pub from_wallpaper: bool,
+    /// Draw the window larger, for fingers on tablets and convertibles.
+    #[serde(skip_serializing_if = "is_false")]
+    pub touch: bool,
+    /// Show an icon in the tray, and hide the window into it when closed.
+    #[serde(skip_serializing_if = "is_false")]
+    pub tray: bool,
+    /// What each monitor badge says, e.g. `"{name} {width}x{height}@{hz}"` or
+    /// `"{wallpaper}"`; the output name when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub badge_label: Option<String>,
+    /// Overrides used while the desktop prefers a light theme.
+    #[serde(skip_serializing_if = "PaletteOverride::is_empty")]
+    pub light: PaletteOverride,
+    /// Overrides used while the desktop prefers a dark theme.
+    #[serde(skip_serializing_if = "PaletteOverride::is_empty")]
+    pub dark: PaletteOverride,
+    /// Colors of individual outputs' tabs and badges, keyed by output name, so each
+    /// badge is easy to match to its tab.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, HexColor>,
+}
+
+impl GuiSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Any part of the GUI palette; unset colors keep the theme's own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PaletteOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<HexColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub danger: Option<HexColor>,
+}
+
+impl PaletteOverride {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// An RGB color written as `"#RRGGBB"` (or `"#RGB"`) in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub [u8; 3]);
+
+impl HexColor {
+    pub fn parse(text: &str) -> Option<Self> {
+        let digits = text.trim().strip_prefix('#').unwrap_or(text.trim());
+        let expanded: String = match digits.len() {
+            3 => digits.chars().flat_map(|ch| [ch, ch]).collect(),
+            6 => digits.to_string(),
+            _ => return None,
+        };
+        let channel = |index: usize| u8::from_str_radix(expanded.get(index..index + 2)?, 16).ok();
+        Some(Self([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+impl JsonSchema for HexColor {
+    fn schema_name() -> Cow<'static, str> {
+        "HexColor".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "An RGB color, \"#RRGGBB\" or \"#RGB\".",
+            "pattern": "^#?([0-9A-Fa-f]{3}|[0-9A-Fa-f]{6})$"
+        })
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{r:02X}{g:02X}{b:02X}")
+    }
+}
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text).ok_or_else(|| format!("{text:?} is not a #RRGGBB color"))
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(color: HexColor) -> Self {
+        color.to_string()
+    }
+}
+
+/// `[settings]` table: preferences set on the GUI's Settings page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Preferences {
+    /// Show the monitor badges when the GUI starts.
+    #[serde(skip_serializing_if = "is_true")]
+    pub overlay: bool,
+    /// Scale mode of entries made for monitors seen for the first time.
+    #[serde(skip_serializing_if = "is_default_scale")]
+    pub scale: ScaleMode,
+    /// Slideshow interval of entries made for monitors seen for the first time.
+    #[serde(skip_serializing_if = "is_default_interval")]
+    pub interval_seconds: u64,
+    /// Light or dark GUI whatever the desktop prefers.
+    #[serde(skip_serializing_if = "ThemeOverride::is_auto")]
+    pub theme: ThemeOverride,
+    /// Buttons, tabs, and the monitor badges; purple when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent: Option<HexColor>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            overlay: true,
+            scale: ScaleMode::default(),
+            interval_seconds: DEFAULT_INTERVAL_SECS,
+            theme: ThemeOverride::Auto,
+            accent: None,
+        }
+    }
+}
+
+impl Preferences {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// A blank entry for `monitor`, with the preferred scale and interval.
+    pub fn new_entry(&self, monitor: &str) -> WallpaperProfileEntry {
+        WallpaperProfileEntry {
+            monitor: Some(monitor.to_string()),
+            scale: self.scale,
+            interval_seconds: self.interval_seconds.max(1),
+            ..WallpaperProfileEntry::default()
+        }
+    }
+}
+
+fn is_default_scale(scale: &ScaleMode) -> bool {
+    *scale == ScaleMode::default()
+}
+
+fn is_default_interval(seconds: &u64) -> bool {
+    *seconds == DEFAULT_INTERVAL_SECS
+}
+
+/// `theme` under `[settings]`: whether the GUI follows the desktop's light or dark
+/// preference.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeOverride {
+    /// Follow the desktop.
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl ThemeOverride {
+    fn is_auto(&self) -> bool {
+        *self == ThemeOverride::Auto
+    }
+}
+
+/// Write the `[gui]` and `[settings]` tables, leaving the rest of the config as it is.
+pub fn save_gui_settings(gui: &GuiSettings, preferences: &Preferences) -> Result<(), WpeError> {
+    let mut profile = load_or_create_profile()?;
+    profile.gui = gui.clone();
+    profile.settings = preferences.clone();
+    save_profile(&profile)
+}
+
+/// `[crash_reports]` table: local reports written when wpe panics.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CrashSettings {
+    /// Save a report to the state directory on a crash; off unless opted in.
+    pub enabled: bool,
+}
+
+impl CrashSettings {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Lock the config for the rest of the process, as `locked = true` does (`--kiosk`).
+pub fn set_kiosk(kiosk: bool) {
+    KIOSK.store(kiosk, Ordering::Relaxed);
+}
+
+/// Read the global settings from the config file.
+pub fn load_settings() -> Result<Settings, WpeError> {
+    let profile = load_or_create_profile()?;
+    let mut gui = profile.gui;
+    let mut preferences = profile.settings;
+    // Saving the settings moves an accent left under `[gui]` to `[settings]`.
+    preferences.accent = preferences.accent.or(gui.accent.take());
+    Ok(Settings {
+        backend: profile.backend,
+        locked: profile.locked || KIOSK.load(Ordering::Relaxed),
+        hwdec: profile.hwdec,
+        security: profile.security,
+        process: profile.process,
+        variants: profile.variants,
+        audio: profile.audio,
+        seasons: profile.seasons,
+        sync: profile.sync,
+        crash_reports: profile.crash_reports,
+        thermal: profile.thermal,
+        fullscreen: profile.fullscreen,
+        power: profile.power,
+        idle: profile.idle,
+        motion: profile.motion,
+        live_tweaks: profile.live_tweaks,
+        watchdog: profile.watchdog,
+        notifications: profile.notifications,
+        colorscheme: profile.colorscheme,
+        web: profile.web,
+        gui,
+        preferences,
+    })
+}
+
+/// `path` as written: one source, or a list merged into one playlist.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum EntryPaths {
+    One(PathBuf),
+    Many(Vec<PathBuf>),
+}
+
+impl EntryPaths {
+    /// `path`, then `extra`: a single source stays a plain string in the file.
+    fn new(path: Option<PathBuf>, extra: Vec<PathBuf>) -> Option<Self> {
+        match (path, extra.is_empty()) {
+            (path, true) => path.map(EntryPaths::One),
+            (path, false) => Some(EntryPaths::Many(path.into_iter().chain(extra).collect())),
+        }
+    }
+
+    /// The first source and the rest.
+    fn split(self) -> (Option<PathBuf>, Vec<PathBuf>) {
+        match self {
+            EntryPaths::One(path) => (Some(path), Vec::new()),
+            EntryPaths::Many(mut paths) if !paths.is_empty() => {
+                let first = paths.remove(0);
+                (Some(first), paths)
+            }
+            EntryPaths::Many(_) => (None, Vec::new()),
+        }
+    }
+}
+
+/// Per-monitor wallpaper entry persisted to the config file.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct WallpaperEntry {
+    /// Output name such as `DP-1`, as listed by `wpe monitors`.
+    monitor: Option<String>,
+    /// An image, video, folder, playlist, or stream URL, or a list of them played
+    /// one after another.
+    path: Option<EntryPaths>,
+    /// Launch this entry with Start and `wpe -c`.
+    #[serde(default = "default_enabled_false")]
+    enabled: bool,
+    #[serde(default)]
+    scale: ScaleMode,
+    /// Order a folder or playlist plays in.
+    #[serde(default)]
+    order: SlideshowOrder,
+    /// Seconds each image of a folder or playlist stays up.
+    #[serde(default = "default_interval_secs")]
+    interval_seconds: u64,
+    /// Percent the interval may vary by, up to 50.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    interval_jitter: u8,
+    /// Cut a folder's videos short after this many seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    video_seconds: Option<u64>,
+    /// Fade or slide between the files of a folder or playlist instead of cutting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transition: Option<TransitionSettings>,
+    /// Play the files in a folder's subfolders too.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    recursive: bool,
+    /// Globs a folder's files must match, relative to the folder.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Globs of a folder's files to leave out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    /// Play only the folder's files marked with `wpe favorite`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    favorites_only: bool,
+    /// Play the wallpaper's sound.
+    #[serde(default, skip_serializing_if = "is_false")]
+    audio: bool,
+    /// PipeWire sink (`node.name`) to play through; the default output when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio_device: Option<String>,
+    /// How loud the sound plays, from 0 to 100.
+    #[serde(default = "default_volume", skip_serializing_if = "is_full_volume")]
+    volume: u8,
+    /// What draws this entry, over the top-level `backend`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backend: Option<Backend>,
+    /// Draw the wallpaper from tags or seasons instead of `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<EntrySource>,
+    #[serde(default, skip_serializing_if = "MpvpaperFlags::is_default")]
+    mpvpaper: MpvpaperFlags,
+    #[serde(default, skip_serializing_if = "MpvTuning::is_default")]
+    tuning: MpvTuning,
+    #[serde(default, skip_serializing_if = "Picture::is_default")]
+    picture: Picture,
+    #[serde(default, skip_serializing_if = "Margins::is_zero")]
+    margins: Margins,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grid: Option<GridSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pip: Option<PipSettings>,
+    /// Show the folder in `path` as a time-lapse set of images across the day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timelapse: Option<TimelapseSettings>,
+    /// Shift the image in `path` with the pointer, by a depth map.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parallax: Option<ParallaxSettings>,
+    /// Draw the shader file in `path` on the GPU.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shader: Option<ShaderSettings>,
+    /// Draw one of wpe's built-in wallpapers instead of `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    procedural: Option<ProceduralSettings>,
+    /// Paths or `#RRGGBB` colors tried in order when the wallpaper cannot be shown.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fallbacks: Vec<FallbackSource>,
+    /// Outputs sharing this entry instead of the single `monitor`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    monitors: Vec<String>,
+    /// Per-output differences from a shared entry, keyed by output name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    overrides: BTreeMap<String, MonitorOverride>,
+    /// Share this entry with every connected output that has no entry of its own,
+    /// instead of `monitor` or `monitors`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    mirror: bool,
+}
+
+impl Default for WallpaperEntry {
+    fn default() -> Self {
+        Self {
+            monitor: None,
+            path: Some(EntryPaths::One(PathBuf::from(PLACEHOLDER_PATH))),
+            enabled: false,
+            scale: ScaleMode::Fit,
+            order: SlideshowOrder::Sequential,
+            interval_seconds: DEFAULT_INTERVAL_SECS,
+            interval_jitter: 0,
+            video_seconds: None,
+            transition: None,
+            recursive: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            favorites_only: false,
+            audio: false,
+            audio_device: None,
+            volume: MAX_VOLUME,
+            backend: None,
+            source: None,
+            mpvpaper: MpvpaperFlags::default(),
+            tuning: MpvTuning::default(),
+            picture: Picture::default(),
+            margins: Margins::default(),
+            grid: None,
+            pip: None,
+            timelapse: None,
+            parallax: None,
+            shader: None,
+            procedural: None,
+            fallbacks: Vec::new(),
+            monitors: Vec::new(),
+            overrides: BTreeMap::new(),
+            mirror: false,
+        }
+    }
+}
+
+pub const DEFAULT_INTERVAL_SECS: u64 = 300;
+
+/// Largest `interval_jitter` honoured, in percent.
+pub const MAX_INTERVAL_JITTER: u8 = 50;
+
+/// Loudest `volume`, which is also the default.
+pub const MAX_VOLUME: u8 = 100;
+
+fn default_interval_secs() -> u64 {
+    DEFAULT_INTERVAL_SECS
+}
+
+fn default_volume() -> u8 {
+    MAX_VOLUME
+}
+
+fn is_full_volume(value: &u8) -> bool {
+    *value >= MAX_VOLUME
+}
+
+fn default_enabled_false() -> bool {
+    false
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
+/// Simplified entry structure exposed to the GUI layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WallpaperProfileEntry {
+    pub monitor: Option<String>,
+    pub path: Option<PathBuf>,
+    /// Further sources played after `path` as one playlist (`path = [...]` in the file).
+    pub extra_paths: Vec<PathBuf>,
+    pub enabled: bool,
+    pub scale: ScaleMode,
+    pub order: SlideshowOrder,
+    pub interval_seconds: u64,
+    /// Percent the slideshow interval may vary by, so outputs don't all change at once.
+    pub interval_jitter: u8,
+    /// The longest a folder's videos play; `None` plays each to its end.
+    pub video_seconds: Option<u64>,
+    /// Fade or slide between files instead of cutting.
+    pub transition: Option<TransitionSettings>,
+    /// Which files of a folder `path` play.
+    pub folder_filter: FolderFilter,
+    pub audio: bool,
+    /// PipeWire sink (`node.name`) to play through; `None` uses the default output.
+    pub audio_device: Option<String>,
+    /// Volume from 0 to 100.
+    pub volume: u8,
+    /// What draws this entry, when not the top-level `backend`.
+    pub backend: Option<Backend>,
+    /// Draw the wallpaper from tags instead of the fixed `path`.
+    pub source: Option<EntrySource>,
+    pub mpvpaper: MpvpaperFlags,
+    pub tuning: MpvTuning,
+    /// Equalizer and zoom, also saved here from live tweaks.
+    pub picture: Picture,
+    /// Edges left uncovered, e.g. behind a transparent bar.
+    pub margins: Margins,
+    /// Tile several sources on the output instead of playing `path`.
+    pub grid: Option<GridSettings>,
+    /// Second source drawn in a corner over the wallpaper.
+    pub pip: Option<PipSettings>,
+    /// Show the folder as images spread across the day.
+    pub timelapse: Option<TimelapseSettings>,
+    /// Shift the image with the pointer, by a depth map.
+    pub parallax: Option<ParallaxSettings>,
+    /// Draw the shader file on the GPU.
+    pub shader: Option<ShaderSettings>,
+    /// Draw a built-in wallpaper instead of `path`.
+    pub procedural: Option<ProceduralSettings>,
+    /// Shown in turn when the wallpaper cannot be.
+    pub fallbacks: Vec<FallbackSource>,
+    /// Set when the entry is one output of a shared `monitors = [...]` entry.
+    pub shared: Option<SharedEntry>,
+}
+
+/// Where a per-output entry came from when one `[[wallpapers]]` entry drives several outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedEntry {
+    /// Every output of the shared entry, in config order.
+    pub monitors: Vec<String>,
+    /// The shared entry's own values, before this output's overrides.
+    pub inherited: MonitorOverride,
+    /// The entry is `mirror = true`: `monitors` are the outputs connected at load.
+    pub mirror: bool,
+}
+
+/// Settings one output of a shared entry may set differently (`[wallpapers.overrides.<output>]`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MonitorOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<ScaleMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<SlideshowOrder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margins: Option<Margins>,
+}
+
+impl MonitorOverride {
+    /// Every overridable value of `entry`.
+    fn capture(entry: &WallpaperProfileEntry) -> Self {
+        Self {
+            enabled: Some(entry.enabled),
+            scale: Some(entry.scale),
+            order: Some(entry.order),
+            interval_seconds: Some(entry.interval_seconds),
+            margins: Some(entry.margins),
+        }
+    }
+
+    /// Set the values present here on `entry`.
+    pub fn apply(&self, entry: &mut WallpaperProfileEntry) {
+        if let Some(enabled) = self.enabled {
+            entry.enabled = enabled;
+        }
+        if let Some(scale) = self.scale {
+            entry.scale = scale;
+        }
+        if let Some(order) = self.order {
+            entry.order = order;
+        }
+        if let Some(interval) = self.interval_seconds {
+            entry.interval_seconds = interval.max(1);
+        }
+        if let Some(margins) = self.margins {
+            entry.margins = margins;
+        }
+    }
+
+    /// Values of `entry` that differ from `self`, which holds the shared values.
+    fn differences(&self, entry: &WallpaperProfileEntry) -> Self {
+        let own = Self::capture(entry);
+        Self {
+            enabled: own.enabled.filter(|_| own.enabled != self.enabled),
+            scale: own.scale.filter(|_| own.scale != self.scale),
+            order: own.order.filter(|_| own.order != self.order),
+            interval_seconds: own
+                .interval_seconds
+                .filter(|_| own.interval_seconds != self.interval_seconds),
+            margins: own.margins.filter(|_| own.margins != self.margins),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Config names of the settings present, e.g. for showing what an output overrides.
+    pub fn fields(&self) -> Vec<&'static str> {
+        [
+            ("enabled", self.enabled.is_some()),
+            ("scale", self.scale.is_some()),
+            ("order", self.order.is_some()),
+            ("interval", self.interval_seconds.is_some()),
+            ("margins", self.margins.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+}
+
+impl SharedEntry {
+    /// What `entry`, one output of this shared entry, sets differently.
+    pub fn overrides(&self, entry: &WallpaperProfileEntry) -> MonitorOverride {
+        self.inherited.differences(entry)
+    }
+
+    /// `entry` with its overrides dropped and no output, for comparing members.
+    fn shared_part(&self, entry: &WallpaperProfileEntry) -> WallpaperProfileEntry {
+        let mut shared = WallpaperProfileEntry {
+            monitor: None,
+            shared: None,
+            ..entry.clone()
+        };
+        self.inherited.apply(&mut shared);
+        shared
+    }
+}
+
+impl WallpaperProfileEntry {
+    /// Whether the entry has something to play: a path, a tag source, a grid, or a
+    /// built-in wallpaper.
+    pub fn has_source(&self) -> bool {
+        !self.configured_paths().is_empty()
+            || self.source.as_ref().is_some_and(EntrySource::is_active)
+            || self.grid.is_some()
+            || self.procedural.is_some()
+    }
+
+    /// The entry's path, unless it is missing or still the placeholder.
+    pub fn configured_path(&self) -> Option<&Path> {
+        self.path.as_deref().filter(|path| !is_placeholder(path))
+    }
+
+    /// Every source the entry lists: `path` and then `extra_paths`, placeholders left out.
+    pub fn configured_paths(&self) -> Vec<&Path> {
+        self.path
+            .iter()
+            .chain(&self.extra_paths)
+            .map(PathBuf::as_path)
+            .filter(|path| !is_placeholder(path))
+            .collect()
+    }
+
+    /// The entry's sources as one [`Mix`], when it lists more than `path`.
+    pub fn mix(&self) -> Option<Mix> {
+        (!self.extra_paths.is_empty()).then(|| {
+            Mix(self
+                .configured_paths()
+                .into_iter()
+                .map(Path::to_path_buf)
+                .collect())
+        })
+    }
+
+    /// The entry showing its `index`th fallback instead of its own source.
+    pub fn fallback_entry(&self, index: usize) -> Result<Self, WpeError> {
+        let fallback = self
+            .fallbacks
+            .get(index)
+            .ok_or_else(|| WpeError::config(None, "The entry has no more fallbacks"))?;
+        let (path, scale) = match fallback {
+            FallbackSource::Path(path) => (path.clone(), self.scale),
+            // The color image is tiny, so it has to fill the output regardless of `scale`.
+            FallbackSource::Color(color) => (
+                fallback::solid_color(*color).map_err(|err| WpeError::config(None, err))?,
+                ScaleMode::Fit,
+            ),
+        };
+        Ok(Self {
+            path: Some(path),
+            extra_paths: Vec::new(),
+            scale,
+            source: None,
+            grid: None,
+            timelapse: None,
+            parallax: None,
+            shader: None,
+            procedural: None,
+            fallbacks: Vec::new(),
+            ..self.clone()
+        })
+    }
+
+    /// Nothing has been chosen to play yet; such entries are skipped rather than failing.
+    pub fn is_unconfigured(&self) -> bool {
+        !self.has_source()
+    }
+
+    /// Shows a single still image, so swapping it for a frame saves nothing.
+    pub fn is_still_image(&self) -> bool {
+        self.grid.is_none()
+            && self.pip.is_none()
+            && self.timelapse.is_none()
+            && self.parallax.is_none()
+            && self.shader.is_none()
+            && self.procedural.is_none()
+            && self.source.is_none()
+            && self.extra_paths.is_empty()
+            && self.path.as_ref().is_some_and(|path| {
+                let path = normalize_entry_path(path);
+                path.is_file() && !is_probably_video(&path)
+            })
+    }
+}
+
+/// Several sources tiled on one output, e.g. scenes side by side on an ultrawide.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct GridSettings {
+    /// Two to four videos or images, left to right and top to bottom.
+    pub sources: Vec<PathBuf>,
+    #[serde(default)]
+    pub layout: GridLayout,
+}
+
+/// How grid tiles are arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridLayout {
+    /// Side by side.
+    #[default]
+    Row,
+    /// Stacked top to bottom.
+    Column,
+    /// Two by two; needs exactly four sources.
+    Square,
+}
+
+impl GridSettings {
+    /// Check the sources and work out what each one is.
+    fn resolve(&self) -> Result<Grid, Box<dyn Error>> {
+        if !(2..=4).contains(&self.sources.len()) {
+            return Err(
+                format!("A grid needs 2 to 4 sources, found {}", self.sources.len()).into(),
+            );
+        }
+        if self.layout == GridLayout::Square && self.sources.len() != 4 {
+            return Err("A square grid needs exactly 4 sources".into());
+        }
+        let tiles = self
+            .sources
+            .iter()
+            .map(|source| match sources::resolve_path(source)? {
+                tile @ (MediaKind::Image(_) | MediaKind::Video(_)) => Ok(tile),
+                other => Err(format!(
+                    "Grid sources must be local files ({})",
+                    other.path().display()
+                )
+                .into()),
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        Ok(Grid {
+            tiles,
+            layout: self.layout,
+        })
+    }
+}
+
+/// A second source drawn small in one corner, e.g. a stream of a fish tank.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PipSettings {
+    /// A video or image path, or a stream URL.
+    pub source: String,
+    #[serde(default)]
+    pub corner: PipCorner,
+    /// Width as a percentage of the output, from 5 to 50.
+    #[serde(default = "default_pip_size")]
+    pub size_percent: u32,
+    /// Gap to the output's edges in pixels.
+    #[serde(default = "default_pip_margin")]
+    pub margin: u32,
+}
+
+/// Which corner the picture-in-picture sits in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum PipCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+fn default_pip_size() -> u32 {
+    25
+}
+
+fn default_pip_margin() -> u32 {
+    32
+}
+
+impl PipSettings {
+    /// The overlay to draw, or `None` (with a warning) when a local source is unusable.
+    fn resolve(&self) -> Option<Pip> {
+        let (source, is_image) = if self.source.contains("://") {
+            (self.source.clone(), false)
+        } else {
+            let path = normalize_entry_path(Path::new(&self.source));
+            if !path.is_file() {
+                warn!(
+                    "Picture-in-picture source {} is not a file; skipping it.",
+                    path.display()
+                );
+                return None;
+            }
+            let is_image = !is_probably_video(&path);
+            (path.to_string_lossy().into_owned(), is_image)
+        };
+        Some(Pip {
+            source,
+            is_image,
+            corner: self.corner,
+            size_percent: self.size_percent.clamp(5, 50),
+            margin: self.margin,
+        })
+    }
 }
 
-/// Top-level config file layout written/read by the GUI/CLI.
-#[derive(Debug, Serialize, Deserialize)]
-struct Profile {
+/// Where an entry draws its media from when it is not a fixed path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EntrySource {
+    /// Pick a random file or folder carrying all of these tags (see `wpe tag`).
     #[serde(default)]
-    wallpapers: Vec<WallpaperEntry>,
+    pub tags: Vec<String>,
+    /// Use the tags of the first matching `[[seasons]]` rule, falling back to `tags`/`path`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub seasonal: bool,
 }
 
-impl Default for Profile {
-    fn default() -> Self {
-        Self {
-            wallpapers: vec![WallpaperEntry::default()],
-        }
+impl EntrySource {
+    pub fn has_tags(&self) -> bool {
+        self.tags.iter().any(|tag| !tag.trim().is_empty())
     }
-}
 
-/// Per-monitor wallpaper entry persisted to the config file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct WallpaperEntry {
-    monitor: Option<String>,
-    path: Option<PathBuf>,
-    #[serde(default = "default_enabled_false")]
-    enabled: bool,
-    #[serde(default)]
-    scale: ScaleMode,
-    #[serde(default)]
-    order: SlideshowOrder,
-    #[serde(default = "default_interval_secs")]
-    interval_seconds: u64,
+    /// Whether this source replaces the entry's `path`.
+    pub fn is_active(&self) -> bool {
+        self.has_tags() || self.seasonal
+    }
+
+    /// Tags to draw from on `date`, or `None` to play the entry's `path`.
+    pub fn resolve_tags(&self, rules: &[SeasonRule], date: NaiveDate) -> Option<Vec<String>> {
+        if self.seasonal
+            && let Some(tags) = seasons::active_tags(rules, date)
+        {
+            return Some(tags.to_vec());
+        }
+        self.has_tags().then(|| self.tags.clone())
+    }
 }
 
-impl Default for WallpaperEntry {
+impl Default for WallpaperProfileEntry {
     fn default() -> Self {
         Self {
             monitor: None,
             path: Some(PathBuf::from(PLACEHOLDER_PATH)),
+            extra_paths: Vec::new(),
             enabled: false,
             scale: ScaleMode::Fit,
             order: SlideshowOrder::Sequential,
             interval_seconds: DEFAULT_INTERVAL_SECS,
+            interval_jitter: 0,
+            video_seconds: None,
+            transition: None,
+            folder_filter: FolderFilter::default(),
+            audio: false,
+            audio_device: None,
+            volume: MAX_VOLUME,
+            backend: None,
+            source: None,
+            mpvpaper: MpvpaperFlags::default(),
+            tuning: MpvTuning::default(),
+            picture: Picture::default(),
+            margins: Margins::default(),
+            grid: None,
+            pip: None,
+            timelapse: None,
+            parallax: None,
+            shader: None,
+            procedural: None,
+            fallbacks: Vec::new(),
+            shared: None,
         }
     }
 }
 
-pub const DEFAULT_INTERVAL_SECS: u64 = 300;
-
-fn default_interval_secs() -> u64 {
-    DEFAULT_INTERVAL_SECS
+impl From<WallpaperEntry> for WallpaperProfileEntry {
+    fn from(entry: WallpaperEntry) -> Self {
+        let (path, extra_paths) = entry.path.map(EntryPaths::split).unwrap_or_default();
+        Self {
+            monitor: entry.monitor,
+            path,
+            extra_paths,
+            enabled: entry.enabled,
+            scale: entry.scale,
+            order: entry.order,
+            interval_seconds: entry.interval_seconds.max(1),
+            interval_jitter: entry.interval_jitter.min(MAX_INTERVAL_JITTER),
+            video_seconds: entry.video_seconds,
+            transition: entry.transition,
+            folder_filter: FolderFilter {
+                recursive: entry.recursive,
+                include: entry.include,
+                exclude: entry.exclude,
+                favorites_only: entry.favorites_only,
+            },
+            audio: entry.audio,
+            audio_device: entry.audio_device,
+            volume: entry.volume.min(MAX_VOLUME),
+            backend: entry.backend,
+            source: entry.source,
+            mpvpaper: entry.mpvpaper,
+            tuning: entry.tuning,
+            picture: entry.picture,
+            margins: entry.margins,
+            grid: entry.grid,
+            pip: entry.pip,
+            timelapse: entry.timelapse,
+            parallax: entry.parallax,
+            shader: entry.shader,
+            procedural: entry.procedural,
+            fallbacks: entry.fallbacks,
+            shared: None,
+        }
+    }
 }
 
-fn default_enabled_false() -> bool {
-    false
+impl From<&WallpaperProfileEntry> for WallpaperEntry {
+    fn from(entry: &WallpaperProfileEntry) -> Self {
+        Self {
+            monitor: entry.monitor.clone(),
+            path: EntryPaths::new(entry.path.clone(), entry.extra_paths.clone()),
+            enabled: entry.enabled,
+            scale: entry.scale,
+            order: entry.order,
+            interval_seconds: entry.interval_seconds.max(1),
+            interval_jitter: entry.interval_jitter.min(MAX_INTERVAL_JITTER),
+            video_seconds: entry.video_seconds,
+            transition: entry.transition,
+            recursive: entry.folder_filter.recursive,
+            include: entry.folder_filter.include.clone(),
+            exclude: entry.folder_filter.exclude.clone(),
+            favorites_only: entry.folder_filter.favorites_only,
+            audio: entry.audio,
+            audio_device: entry.audio_device.clone(),
+            volume: entry.volume.min(MAX_VOLUME),
+            backend: entry.backend,
+            source: entry.source.clone(),
+            mpvpaper: entry.mpvpaper,
+            tuning: entry.tuning.clone(),
+            picture: entry.picture,
+            margins: entry.margins,
+            grid: entry.grid.clone(),
+            pip: entry.pip.clone(),
+            timelapse: entry.timelapse,
+            parallax: entry.parallax.clone(),
+            shader: entry.shader,
+            procedural: entry.procedural.clone(),
+            fallbacks: entry.fallbacks.clone(),
+            monitors: Vec::new(),
+            overrides: BTreeMap::new(),
+            mirror: false,
+        }
+    }
 }
 
-/// Simplified entry structure exposed to the GUI layer.
-#[derive(Debug, Clone)]
-pub struct WallpaperProfileEntry {
-    pub monitor: Option<String>,
-    pub path: Option<PathBuf>,
-    pub enabled: bool,
-    pub scale: ScaleMode,
-    pub order: SlideshowOrder,
-    pub interval_seconds: u64,
+/// One entry per output, with shared entries expanded and their overrides applied.
+/// A mirrored entry is shared by `mirrored`.
+fn expand_entry(mut entry: WallpaperEntry, mirrored: &[String]) -> Vec<WallpaperProfileEntry> {
+    let mirror = std::mem::take(&mut entry.mirror);
+    if mirror {
+        entry.monitor = None;
+        // Outputs with overrides keep them while disconnected.
+        entry.monitors = mirrored.to_vec();
+        for monitor in entry.overrides.keys() {
+            if !entry.monitors.contains(monitor) {
+                entry.monitors.push(monitor.clone());
+            }
+        }
+    } else if entry.monitors.is_empty() {
+        return vec![entry.into()];
+    }
+    let monitors = std::mem::take(&mut entry.monitors);
+    let overrides = std::mem::take(&mut entry.overrides);
+    let base = WallpaperProfileEntry::from(entry);
+    let shared = SharedEntry {
+        inherited: MonitorOverride::capture(&base),
+        monitors: monitors.clone(),
+        mirror,
+    };
+    if monitors.is_empty() {
+        // Nothing to mirror onto yet; an unassigned entry keeps it in the config.
+        return vec![WallpaperProfileEntry {
+            shared: Some(shared),
+            ..base
+        }];
+    }
+    monitors
+        .into_iter()
+        .map(|monitor| {
+            let mut entry = base.clone();
+            if let Some(overrides) = overrides.get(&monitor) {
+                overrides.apply(&mut entry);
+            }
+            entry.monitor = Some(monitor);
+            entry.shared = Some(shared.clone());
+            entry
+        })
+        .collect()
 }
 
-impl Default for WallpaperProfileEntry {
-    fn default() -> Self {
-        Self {
-            monitor: None,
-            path: Some(PathBuf::from(PLACEHOLDER_PATH)),
-            enabled: false,
-            scale: ScaleMode::Fit,
-            order: SlideshowOrder::Sequential,
-            interval_seconds: DEFAULT_INTERVAL_SECS,
+/// Fold outputs of a shared entry back into one entry with overrides. An output whose
+/// other settings were changed on their own becomes an entry of its own.
+fn collapse_entries(entries: &[WallpaperProfileEntry]) -> Vec<WallpaperEntry> {
+    let mut written = vec![false; entries.len()];
+    let mut collapsed = Vec::new();
+    // The first group of a mirrored entry stays mirrored; outputs that left it are
+    // written on their own.
+    let mut mirrored = false;
+    for (index, entry) in entries.iter().enumerate() {
+        if written[index] {
+            continue;
+        }
+        let Some(shared) = &entry.shared else {
+            written[index] = true;
+            collapsed.push(entry.into());
+            continue;
+        };
+
+        let base = shared.shared_part(entry);
+        let mut monitors = Vec::new();
+        let mut overrides = BTreeMap::new();
+        for (other_index, other) in entries.iter().enumerate().skip(index) {
+            let member = !written[other_index]
+                && other.shared.as_ref().is_some_and(|other_shared| {
+                    other_shared.monitors == shared.monitors && other_shared.mirror == shared.mirror
+                })
+                && shared.shared_part(other) == base;
+            let Some(monitor) = other.monitor.clone().filter(|_| member) else {
+                continue;
+            };
+            written[other_index] = true;
+            let differences = shared.overrides(other);
+            if !differences.is_empty() {
+                overrides.insert(monitor.clone(), differences);
+            }
+            monitors.push(monitor);
+        }
+
+        if shared.mirror && !mirrored {
+            mirrored = true;
+            written[index] = true;
+            collapsed.push(WallpaperEntry {
+                overrides,
+                mirror: true,
+                ..(&base).into()
+            });
+        } else if monitors.len() < 2 {
+            written[index] = true;
+            collapsed.push(entry.into());
+        } else {
+            collapsed.push(WallpaperEntry {
+                monitors,
+                overrides,
+                ..(&base).into()
+            });
         }
     }
+    collapsed
 }
 
-pub fn load_wallpaper_entries() -> Result<Vec<WallpaperProfileEntry>, Box<dyn Error>> {
+pub fn load_wallpaper_entries() -> Result<Vec<WallpaperProfileEntry>, WpeError> {
     let profile = load_or_create_profile()?;
+    let mirrored = mirror_targets(&profile.wallpapers);
     let entries = profile
         .wallpapers
         .into_iter()
-        .map(|entry| WallpaperProfileEntry {
-            monitor: entry.monitor,
-            path: entry.path,
-            enabled: entry.enabled,
-            scale: entry.scale,
-            order: entry.order,
-            interval_seconds: entry.interval_seconds.max(1),
-        })
+        .flat_map(|entry| expand_entry(entry, &mirrored))
         .collect();
     Ok(entries)
 }
 
-pub fn save_wallpaper_entries(entries: &[WallpaperProfileEntry]) -> Result<(), Box<dyn Error>> {
-    let profile = Profile {
-        wallpapers: entries
-            .iter()
-            .map(|entry| WallpaperEntry {
-                monitor: entry.monitor.clone(),
-                path: entry.path.clone(),
-                enabled: entry.enabled,
-                scale: entry.scale,
-                order: entry.order,
-                interval_seconds: entry.interval_seconds.max(1),
-            })
-            .collect(),
+/// `entry` as one `mirror = true` entry played by each of `monitors`.
+pub fn mirror_entry(
+    entry: &WallpaperProfileEntry,
+    monitors: &[String],
+) -> Vec<WallpaperProfileEntry> {
+    let base = WallpaperProfileEntry {
+        monitor: None,
+        shared: None,
+        ..entry.clone()
+    };
+    let shared = SharedEntry {
+        inherited: MonitorOverride::capture(&base),
+        monitors: monitors.to_vec(),
+        mirror: true,
+    };
+    monitors
+        .iter()
+        .map(|monitor| WallpaperProfileEntry {
+            monitor: Some(monitor.clone()),
+            shared: Some(shared.clone()),
+            ..base.clone()
+        })
+        .collect()
+}
+
+/// Entries for those of `monitors` that `entries` has none for, copied from the
+/// mirrored entry among them if there is one, so outputs plugged in after the config
+/// was read join it.
+pub fn mirror_onto(
+    entries: &[WallpaperProfileEntry],
+    monitors: &[String],
+) -> Vec<WallpaperProfileEntry> {
+    let Some((mirrored, shared)) = entries.iter().find_map(|entry| {
+        entry
+            .shared
+            .as_ref()
+            .filter(|shared| shared.mirror)
+            .map(|shared| (entry, shared))
+    }) else {
+        return Vec::new();
     };
+    let base = shared.shared_part(mirrored);
+    monitors
+        .iter()
+        .filter(|monitor| {
+            !entries
+                .iter()
+                .any(|entry| entry.monitor.as_deref() == Some(monitor.as_str()))
+        })
+        .map(|monitor| WallpaperProfileEntry {
+            monitor: Some(monitor.clone()),
+            shared: Some(shared.clone()),
+            ..base.clone()
+        })
+        .collect()
+}
+
+/// Connected outputs no entry names, for `mirror = true` entries to play on. The
+/// compositor is only asked when some entry mirrors.
+fn mirror_targets(wallpapers: &[WallpaperEntry]) -> Vec<String> {
+    if !wallpapers.iter().any(|entry| entry.mirror) {
+        return Vec::new();
+    }
+    let named: BTreeSet<&str> = wallpapers
+        .iter()
+        .filter(|entry| !entry.mirror)
+        .flat_map(|entry| entry.monitor.iter().chain(&entry.monitors))
+        .map(String::as_str)
+        .collect();
+    match monitors::list_monitors() {
+        Ok(connected) => connected
+            .into_iter()
+            .map(|monitor| monitor.name)
+            .filter(|name| !named.contains(name.as_str()))
+            .collect(),
+        Err(err) => {
+            warn!("Could not list monitors for a mirrored entry: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_wallpaper_entries(entries: &[WallpaperProfileEntry]) -> Result<(), WpeError> {
+    // Keep the global sections intact; only the wallpaper list is rewritten.
+    let mut profile = load_or_create_profile()?;
+    profile.wallpapers = collapse_entries(entries);
     save_profile(&profile)
 }
 
+/// The entry of `monitor`, else the unassigned placeholder of a fresh config, else a
+/// new one, assigned to the monitor.
+pub fn entry_for_monitor<'a>(
+    entries: &'a mut Vec<WallpaperProfileEntry>,
+    monitor: &str,
+) -> &'a mut WallpaperProfileEntry {
+    let index = entries
+        .iter()
+        .position(|entry| entry.monitor.as_deref() == Some(monitor))
+        .or_else(|| {
+            entries
+                .iter()
+                .position(|entry| entry.monitor.is_none() && entry.is_unconfigured())
+        });
+    let index = match index {
+        Some(index) => {
+            entries[index].monitor = Some(monitor.to_string());
+            index
+        }
+        None => {
+            let preferences = load_settings()
+                .map(|settings| settings.preferences)
+                .unwrap_or_default();
+            entries.push(preferences.new_entry(monitor));
+            entries.len() - 1
+        }
+    };
+    &mut entries[index]
+}
+
+/// Make `monitor`'s entry play `path` alone, instead of tags, a mix, or a grid.
+pub fn set_monitor_path(monitor: &str, path: PathBuf) -> Result<(), WpeError> {
+    let mut entries = load_wallpaper_entries()?;
+    let entry = entry_for_monitor(&mut entries, monitor);
+    entry.enabled = true;
+    entry.path = Some(path);
+    entry.extra_paths.clear();
+    entry.source = None;
+    entry.grid = None;
+    entry.procedural = None;
+    save_wallpaper_entries(&entries)
+}
+
 /// Ensure the config file exists with one entry per monitor, returning entries and creation flag.
 pub fn ensure_profile_for_monitors(
     monitors: &[Monitor],
-) -> Result<(Vec<WallpaperProfileEntry>, bool, PathBuf), Box<dyn Error>> {
+) -> Result<(Vec<WallpaperProfileEntry>, bool, PathBuf), WpeError> {
     let path = config_file_path()?;
     if path.exists() {
         let entries = load_wallpaper_entries()?;
@@ -265,33 +2057,293 @@ pub fn ensure_profile_for_monitors(
             .map(|monitor| WallpaperProfileEntry {
                 monitor: Some(monitor.name.clone()),
                 path: Some(PathBuf::from(PLACEHOLDER_PATH)),
+                extra_paths: Vec::new(),
                 enabled: false,
                 scale: ScaleMode::Fit,
                 order: SlideshowOrder::Sequential,
                 interval_seconds: DEFAULT_INTERVAL_SECS,
+                interval_jitter: 0,
+                video_seconds: None,
+                transition: None,
+                folder_filter: FolderFilter::default(),
+                audio: false,
+                audio_device: None,
+                volume: MAX_VOLUME,
+                backend: None,
+                source: None,
+                mpvpaper: MpvpaperFlags::default(),
+                tuning: MpvTuning::default(),
+                picture: Picture::default(),
+                margins: Margins::default(),
+                grid: None,
+                pip: None,
+                timelapse: None,
+                parallax: None,
+                shader: None,
+                procedural: None,
+                fallbacks: Vec::new(),
+                shared: None,
             })
             .collect()
     };
 
     save_wallpaper_entries(&entries)?;
-    Ok((entries, true, path))
+    // A read-only config directory means nothing was actually written.
+    let created = path.exists();
+    Ok((entries, created, path))
+}
+
+/// On-disk formats the profile can be stored in, picked by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Ron,
+}
+
+impl ConfigFormat {
+    /// Lookup order when several config files exist side by side.
+    const ALL: [ConfigFormat; 3] = [ConfigFormat::Toml, ConfigFormat::Json, ConfigFormat::Ron];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::Ron => "ron",
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == ext)
+    }
+
+    fn parse(self, data: &str) -> Result<Profile, Source> {
+        Ok(match self {
+            ConfigFormat::Toml => toml::from_str(data)?,
+            ConfigFormat::Json => serde_json::from_str(data)?,
+            ConfigFormat::Ron => ron::from_str(data)?,
+        })
+    }
+
+    fn render(self, profile: &Profile) -> Result<String, Source> {
+        Ok(match self {
+            ConfigFormat::Toml => {
+                let data = toml::to_string_pretty(profile)?;
+                let mut content = String::new();
+                content.push_str(CONFIG_HEADER);
+                if !CONFIG_HEADER.ends_with('\n') {
+                    content.push('\n');
+                }
+                content.push_str(&data);
+                content
+            }
+            ConfigFormat::Json => {
+                let mut content = serde_json::to_string_pretty(profile)?;
+                content.push('\n');
+                content
+            }
+            ConfigFormat::Ron => {
+                let mut content =
+                    ron::ser::to_string_pretty(profile, ron::ser::PrettyConfig::default())?;
+                content.push('\n');
+                content
+            }
+        })
+    }
 }
 
-/// Resolve ~/.config/wpe/config.toml or create it alongside the directory.
-fn config_file_path() -> Result<PathBuf, Box<dyn Error>> {
+/// Resolve ~/.config/wpe, creating the directory if needed.
+fn config_dir() -> Result<PathBuf, WpeError> {
     let base = if let Ok(custom) = env::var("XDG_CONFIG_HOME") {
         PathBuf::from(custom)
     } else {
-        let home = env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+        let home = env::var("HOME")
+            .map_err(|_| WpeError::config(None, "HOME environment variable not set"))?;
         PathBuf::from(home).join(".config")
     };
     let dir = base.join("wpe");
-    fs::create_dir_all(&dir)?;
-    Ok(dir.join("config.toml"))
+    fs::create_dir_all(&dir).map_err(|err| io_error(&dir, "create", err))?;
+    Ok(dir)
+}
+
+/// `err` from trying to `action` (read, write, ...) `path`, as a config error.
+fn io_error(path: &Path, action: &str, err: io::Error) -> WpeError {
+    WpeError::config(
+        Some(path.to_path_buf()),
+        format!("Unable to {action} {}", path.display()),
+    )
+    .with_source(err)
+}
+
+/// Resolve the active config file: the first existing config.{toml,json,ron},
+/// falling back to config.toml for new setups.
+pub fn config_file_path() -> Result<PathBuf, WpeError> {
+    let dir = config_dir()?;
+    let existing = ConfigFormat::ALL
+        .into_iter()
+        .map(|format| dir.join(format!("config.{}", format.extension())))
+        .find(|path| path.exists());
+    Ok(existing.unwrap_or_else(|| dir.join("config.toml")))
+}
+
+/// Rewrite the active config in another format, moving the old file aside as `.bak`.
+/// An existing file in the new format is only replaced with `force`. Returns the old
+/// and new paths.
+pub fn convert_config(to: ConfigFormat, force: bool) -> Result<(PathBuf, PathBuf), WpeError> {
+    let from = config_file_path()?;
+    let profile = load_or_create_profile()?;
+    let target = from.with_extension(to.extension());
+    if target == from {
+        return Err(WpeError::config(
+            Some(from.clone()),
+            format!("{} is already in {} format", from.display(), to.extension()),
+        ));
+    }
+
+    if target.exists() && !force {
+        return Err(WpeError::config(
+            Some(target.clone()),
+            format!(
+                "{} already exists; pass --force to replace it",
+                target.display()
+            ),
+        ));
+    }
+    check_unlocked(&profile, &from)?;
+    check_unlocked(&profile, &target)?;
+
+    // Written directly rather than through save_profile_to_path, which leaves
+    // read-only files alone: the old file is only moved aside once this worked.
+    let content = render_profile(&profile, &target)?;
+    fs::write(&target, content).map_err(|err| io_error(&target, "write", err))?;
+    let mut backup = from.clone().into_os_string();
+    backup.push(".bak");
+    fs::rename(&from, &backup).map_err(|err| io_error(&from, "move aside", err))?;
+    Ok((from, target))
+}
+
+/// JSON Schema of the config file, generated from the types it is read into, for
+/// editors to complete and check hand-edited configs with.
+pub fn config_schema() -> Result<String, WpeError> {
+    let mut schema = schemars::schema_for!(Profile);
+    schema.insert("title".into(), "wpe config".into());
+    serde_json::to_string_pretty(&schema)
+        .map_err(|err| WpeError::config(None, "Unable to write the schema").with_source(err))
+}
+
+/// Where named profiles live, e.g. `~/.config/wpe/profiles/work.toml`.
+pub fn profiles_dir() -> Result<PathBuf, WpeError> {
+    Ok(config_dir()?.join("profiles"))
+}
+
+/// Names of the saved profiles, sorted.
+pub fn list_profiles() -> Result<Vec<String>, WpeError> {
+    let dir = profiles_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .map_err(|err| io_error(&dir, "list", err))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| ConfigFormat::from_path(path).is_some())
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// The saved file for profile `name`, in whichever format it was written.
+fn named_profile_path(name: &str) -> Result<Option<PathBuf>, WpeError> {
+    let dir = profiles_dir()?;
+    Ok(ConfigFormat::ALL
+        .into_iter()
+        .map(|format| dir.join(format!("{name}.{}", format.extension())))
+        .find(|path| path.exists()))
+}
+
+fn check_profile_name(name: &str) -> Result<(), WpeError> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        return Err(WpeError::config(
+            None,
+            format!("{name:?} is not a usable profile name"),
+        ));
+    }
+    Ok(())
+}
+
+/// Save the active config as profile `name`, replacing an earlier save of it.
+pub fn save_named_profile(name: &str) -> Result<PathBuf, WpeError> {
+    check_profile_name(name)?;
+    let profile = load_or_create_profile()?;
+    let format = ConfigFormat::from_path(&config_file_path()?).unwrap_or(ConfigFormat::Toml);
+    let dir = profiles_dir()?;
+    fs::create_dir_all(&dir).map_err(|err| io_error(&dir, "create", err))?;
+    // An earlier save in another format would shadow this one on load.
+    if let Some(previous) = named_profile_path(name)? {
+        fs::remove_file(&previous).map_err(|err| io_error(&previous, "remove", err))?;
+    }
+    let path = dir.join(format!("{name}.{}", format.extension()));
+    save_profile_to_path(&profile, &path)?;
+    Ok(path)
+}
+
+/// Make profile `name` the active config. The config it replaces is kept as `.bak`.
+pub fn load_named_profile(name: &str) -> Result<PathBuf, WpeError> {
+    check_profile_name(name)?;
+    let source = named_profile_path(name)?.ok_or_else(|| {
+        WpeError::config(
+            None,
+            format!(
+                "No profile named {name} in {}",
+                profiles_dir()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default()
+            ),
+        )
+    })?;
+    let data = fs::read_to_string(&source).map_err(|err| io_error(&source, "read", err))?;
+    let profile = ConfigFormat::from_path(&source)
+        .unwrap_or(ConfigFormat::Toml)
+        .parse(&data)
+        .map_err(|err| {
+            WpeError::config(
+                Some(source.clone()),
+                format!("Failed to parse {}", source.display()),
+            )
+            .with_source(err)
+        })?;
+
+    let target = config_file_path()?;
+    if is_read_only(&target) {
+        return Err(WpeError::config(
+            Some(target.clone()),
+            format!(
+                "{} is read-only; profiles cannot replace it",
+                target.display()
+            ),
+        ));
+    }
+    check_unlocked(&profile, &target)?;
+    if target.exists() {
+        let mut backup = target.clone().into_os_string();
+        backup.push(".bak");
+        fs::copy(&target, &backup).map_err(|err| io_error(&target, "back up", err))?;
+    }
+    save_profile_to_path(&profile, &target)?;
+    Ok(source)
 }
 
 /// Read the TOML profile from disk (creating a default file if missing).
-fn load_or_create_profile() -> Result<Profile, Box<dyn Error>> {
+fn load_or_create_profile() -> Result<Profile, WpeError> {
     let path = config_file_path()?;
     if !path.exists() {
         let profile = Profile::default();
@@ -299,28 +2351,136 @@ fn load_or_create_profile() -> Result<Profile, Box<dyn Error>> {
         return Ok(profile);
     }
 
-    let data = fs::read_to_string(&path)?;
-    let profile: Profile = toml::from_str(&data)?;
-    Ok(profile)
+    let data = fs::read_to_string(&path).map_err(|err| io_error(&path, "read", err))?;
+    let format = ConfigFormat::from_path(&path).unwrap_or(ConfigFormat::Toml);
+    format.parse(&data).map_err(|err| {
+        WpeError::config(
+            Some(path.clone()),
+            format!("Failed to parse {}", path.display()),
+        )
+        .with_source(err)
+    })
 }
 
-fn save_profile(profile: &Profile) -> Result<(), Box<dyn Error>> {
+fn save_profile(profile: &Profile) -> Result<(), WpeError> {
     let path = config_file_path()?;
     save_profile_to_path(profile, &path)
 }
 
-fn save_profile_to_path(profile: &Profile, path: &Path) -> Result<(), Box<dyn Error>> {
-    let data = toml::to_string_pretty(profile)?;
-    let mut content = String::new();
-    content.push_str(CONFIG_HEADER);
-    if !CONFIG_HEADER.ends_with('\n') {
-        content.push('\n');
+/// The profile in the format `path`'s extension asks for, TOML by default.
+fn render_profile(profile: &Profile, path: &Path) -> Result<String, WpeError> {
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Toml);
+    format.render(profile).map_err(|err| {
+        WpeError::config(
+            Some(path.to_path_buf()),
+            format!("Unable to render {}", path.display()),
+        )
+        .with_source(err)
+    })
+}
+
+/// Refuse to write over `path` when the config there is locked, when `profile` (what
+/// would replace it) is, or under `--kiosk`.
+fn check_unlocked(profile: &Profile, path: &Path) -> Result<(), WpeError> {
+    if path.exists() && (profile.locked || KIOSK.load(Ordering::Relaxed) || locked_on_disk(path)) {
+        return Err(WpeError::config(
+            Some(path.to_path_buf()),
+            format!(
+                "{} is locked; it cannot be changed from wpe",
+                path.display()
+            ),
+        ));
     }
-    content.push_str(&data);
-    fs::write(path, content)?;
     Ok(())
 }
 
+/// Whether the config at `path` sets `locked = true`. A file that cannot be read or
+/// parsed counts as unlocked; reading the config reports that on its own.
+fn locked_on_disk(path: &Path) -> bool {
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Toml);
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| format.parse(&data).ok())
+        .is_some_and(|profile| profile.locked)
+}
+
+/// Write the profile unless the config is read-only (e.g. managed by home-manager),
+/// in which case the write is skipped with a warning. A locked config, on disk or in
+/// `profile` (or any, under `--kiosk`), is never written over.
+fn save_profile_to_path(profile: &Profile, path: &Path) -> Result<(), WpeError> {
+    check_unlocked(profile, path)?;
+    if is_read_only(path) {
+        warn!("{} is read-only; leaving it untouched.", path.display());
+        return Ok(());
+    }
+
+    let content = render_profile(profile, path)?;
+    match fs::write(path, content) {
+        Ok(()) => Ok(()),
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+            ) =>
+        {
+            warn!(
+                "Cannot write {} ({}); leaving it untouched.",
+                path.display(),
+                err
+            );
+            Ok(())
+        }
+        Err(err) => Err(io_error(path, "write", err)),
+    }
+}
+
+/// Identifies one version of the config file, to notice edits made elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigStamp {
+    pub modified: SystemTime,
+    hash: u64,
+}
+
+impl ConfigStamp {
+    /// Whether both stamps saw the same contents, even if the file was touched since.
+    pub fn same_content(&self, other: &ConfigStamp) -> bool {
+        self.hash == other.hash
+    }
+}
+
+/// Stamp of the active config file as it is now, or `None` if it cannot be read.
+pub fn config_stamp() -> Option<ConfigStamp> {
+    let path = config_file_path().ok()?;
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let mut hasher = DefaultHasher::new();
+    fs::read(&path).ok()?.hash(&mut hasher);
+    Some(ConfigStamp {
+        modified,
+        hash: hasher.finish(),
+    })
+}
+
+/// When the active config file was last written; cheaper than `config_stamp`.
+pub fn config_modified() -> Option<SystemTime> {
+    fs::metadata(config_file_path().ok()?)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Whether the active config file exists but cannot be written to.
+pub fn config_is_read_only() -> bool {
+    config_file_path()
+        .map(|path| is_read_only(&path))
+        .unwrap_or(false)
+}
+
+fn is_read_only(path: &Path) -> bool {
+    // Symlinks into a store (Nix, Guix) resolve to read-only files.
+    fs::metadata(path)
+        .map(|meta| meta.permissions().readonly())
+        .unwrap_or(false)
+}
+
 /// Convert a GUI text field into a PathBuf, expanding leading ~ and env vars.
 pub fn parse_user_path(input: &str) -> Option<PathBuf> {
     let trimmed = input.trim();
@@ -425,19 +2585,7 @@ fn canonicalize_best_effort(path: PathBuf) -> PathBuf {
     fs::canonicalize(&path).unwrap_or(path)
 }
 
-impl Default for ScaleMode {
-    fn default() -> Self {
-        ScaleMode::Fit
-    }
-}
-
-impl Default for SlideshowOrder {
-    fn default() -> Self {
-        SlideshowOrder::Sequential
-    }
-}
-
-fn is_probably_video(path: &Path) -> bool {
+pub fn is_probably_video(path: &Path) -> bool {
     const VIDEO_EXTENSIONS: &[&str] = &[
         "mp4", "mkv", "webm", "mov", "avi", "flv", "wmv", "m4v", "mpg", "mpeg", "ogv", "ts",
         "m2ts", "mxf", "3gp", "m4p",
@@ -451,3 +2599,217 @@ fn is_probably_video(path: &Path) -> bool {
         })
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    /// A fresh file path under the temp dir for one test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("wpe-config-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("a scratch dir");
+        dir.join("config.toml")
+    }
+
+    #[test]
+    fn a_locked_config_is_not_replaced_by_an_unlocked_one() {
+        let path = scratch("locked");
+        let locked = Profile {
+            locked: true,
+            ..Profile::default()
+        };
+        fs::write(&path, ConfigFormat::Toml.render(&locked).unwrap()).unwrap();
+
+        let err = save_profile_to_path(&Profile::default(), &path).unwrap_err();
+        assert!(err.to_string().contains("is locked"));
+        assert!(locked_on_disk(&path));
+    }
+
+    #[test]
+    fn an_unlocked_config_is_written() {
+        let path = scratch("unlocked");
+        fs::write(
+            &path,
+            ConfigFormat::Toml.render(&Profile::default()).unwrap(),
+        )
+        .unwrap();
+        let profile = Profile {
+            hwdec: Some("vaapi".into()),
+            ..Profile::default()
+        };
+
+        save_profile_to_path(&profile, &path).unwrap();
+        let written = ConfigFormat::Toml
+            .parse(&fs::read_to_string(&path).unwrap())
+            .unwrap();
+        assert_eq!(written.hwdec.as_deref(), Some("vaapi"));
+    }
+
+    /// The per-output entries `text` loads as, with `mirrored` the outputs a
+    /// `mirror = true` entry plays on.
+    fn load(text: &str, mirrored: &[&str]) -> Vec<WallpaperProfileEntry> {
+        let mirrored: Vec<String> = mirrored.iter().map(|name| name.to_string()).collect();
+        ConfigFormat::Toml
+            .parse(text)
+            .unwrap()
+            .wallpapers
+            .into_iter()
+            .flat_map(|entry| expand_entry(entry, &mirrored))
+            .collect()
+    }
+
+    /// `entries` written out the way a save writes them, as the file's text.
+    fn save(entries: &[WallpaperProfileEntry]) -> String {
+        let profile = Profile {
+            wallpapers: collapse_entries(entries),
+            ..Profile::default()
+        };
+        ConfigFormat::Toml.render(&profile).unwrap()
+    }
+
+    /// What each output plays, leaving out how the file groups them.
+    fn played(entries: &[WallpaperProfileEntry]) -> Vec<WallpaperProfileEntry> {
+        let mut played: Vec<WallpaperProfileEntry> = entries
+            .iter()
+            .map(|entry| WallpaperProfileEntry {
+                shared: None,
+                ..entry.clone()
+            })
+            .collect();
+        played.sort_by(|a, b| a.monitor.cmp(&b.monitor));
+        played
+    }
+
+    const SHARED: &str = r#"
+[[wallpapers]]
+monitors = ["DP-1", "DP-2", "HDMI-A-1"]
+path = "/walls/space"
+enabled = true
+
+[wallpapers.overrides.DP-2]
+scale = "fill"
+"#;
+
+    #[test]
+    fn a_shared_entry_with_overrides_survives_a_save() {
+        let loaded = load(SHARED, &[]);
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[1].scale, ScaleMode::Fill);
+
+        let saved = save(&loaded);
+        let collapsed = ConfigFormat::Toml.parse(&saved).unwrap().wallpapers;
+        assert_eq!(collapsed.len(), 1);
+        assert_eq!(collapsed[0].overrides.len(), 1);
+        assert_eq!(load(&saved, &[]), loaded);
+    }
+
+    #[test]
+    fn a_member_with_its_own_source_is_split_out() {
+        let mut loaded = load(SHARED, &[]);
+        loaded[2].path = Some(PathBuf::from("/walls/forest"));
+
+        let saved = save(&loaded);
+        let collapsed = ConfigFormat::Toml.parse(&saved).unwrap().wallpapers;
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(collapsed[0].monitors, ["DP-1", "DP-2"]);
+        assert_eq!(collapsed[1].monitor.as_deref(), Some("HDMI-A-1"));
+        assert_eq!(played(&load(&saved, &[])), played(&loaded));
+    }
+
+    #[test]
+    fn a_group_of_one_is_written_as_a_plain_entry() {
+        let loaded = load(
+            r#"
+[[wallpapers]]
+monitors = ["DP-1"]
+path = "/walls/space"
+
+[wallpapers.overrides.DP-1]
+scale = "fill"
+"#,
+            &[],
+        );
+        assert_eq!(loaded.len(), 1);
+
+        let saved = save(&loaded);
+        let collapsed = ConfigFormat::Toml.parse(&saved).unwrap().wallpapers;
+        assert_eq!(collapsed.len(), 1);
+        assert_eq!(collapsed[0].monitor.as_deref(), Some("DP-1"));
+        assert!(collapsed[0].monitors.is_empty());
+        assert_eq!(played(&load(&saved, &[])), played(&loaded));
+    }
+
+    const MIRRORED: &str = r#"
+[[wallpapers]]
+mirror = true
+path = "/walls/space"
+
+[wallpapers.overrides.HDMI-A-1]
+scale = "fill"
+"#;
+
+    #[test]
+    fn only_the_first_mirrored_group_stays_mirrored() {
+        let mut loaded = load(MIRRORED, &["DP-1", "DP-2"]);
+        assert_eq!(loaded.len(), 3);
+        loaded[1].path = Some(PathBuf::from("/walls/forest"));
+
+        let saved = save(&loaded);
+        let collapsed = ConfigFormat::Toml.parse(&saved).unwrap().wallpapers;
+        assert_eq!(collapsed.len(), 2);
+        assert!(collapsed[0].mirror);
+        assert!(!collapsed[1].mirror);
+        assert_eq!(collapsed[1].monitor.as_deref(), Some("DP-2"));
+        // DP-2 has an entry of its own now, so the mirror no longer covers it.
+        assert_eq!(played(&load(&saved, &["DP-1"])), played(&loaded));
+    }
+
+    #[test]
+    fn overridden_outputs_stay_while_disconnected() {
+        let loaded = load(MIRRORED, &["DP-1"]);
+        let monitors: Vec<_> = loaded
+            .iter()
+            .map(|entry| entry.monitor.as_deref())
+            .collect();
+        assert_eq!(monitors, [Some("DP-1"), Some("HDMI-A-1")]);
+        assert_eq!(loaded[1].scale, ScaleMode::Fill);
+
+        let saved = save(&loaded);
+        let collapsed = ConfigFormat::Toml.parse(&saved).unwrap().wallpapers;
+        assert_eq!(collapsed.len(), 1);
+        assert!(collapsed[0].mirror);
+        assert!(collapsed[0].overrides.contains_key("HDMI-A-1"));
+        assert_eq!(load(&saved, &["DP-1"]), loaded);
+    }
+
+    #[test]
+    fn outputs_plugged_in_later_join_the_mirror() {
+        let loaded = load(MIRRORED, &["DP-1"]);
+        let connected = ["DP-1".to_string(), "DP-2".to_string()];
+
+        let joined = mirror_onto(&loaded, &connected);
+        assert_eq!(joined.len(), 1);
+        assert_eq!(joined[0].monitor.as_deref(), Some("DP-2"));
+        assert_eq!(joined[0].path, loaded[0].path);
+        assert_eq!(joined[0].scale, ScaleMode::Fit);
+        assert_eq!(joined[0].shared, loaded[0].shared);
+
+        let mut entries = loaded;
+        entries.extend(joined);
+        let collapsed = ConfigFormat::Toml
+            .parse(&save(&entries))
+            .unwrap()
+            .wallpapers;
+        assert_eq!(collapsed.len(), 1);
+        assert!(collapsed[0].mirror);
+    }
+
+    #[test]
+    fn nothing_joins_without_a_mirror() {
+        let loaded = load(SHARED, &[]);
+        assert!(mirror_onto(&loaded, &["DP-3".to_string()]).is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+//! Reload the daemon when the config file is edited by hand.
+//!
+//! The config directory is watched through inotify rather than the file itself,
+//! since most editors save by writing a new file and renaming it over the old one.
+//! Saves come in bursts of events, so one reload is asked for once the file has
+//! been quiet for [`SETTLE_TIME`]. The daemon's reload compares every entry with
+//! what runs and restarts only the outputs whose entry changed.
+
+use std::{error::Error, sync::mpsc, time::Duration};
+
+use futures::channel::mpsc::UnboundedSender;
+use notify::{EventKind, RecursiveMode, Watcher};
+use tracing::{info, warn};
+
+use crate::config;
+
+/// How long the config has to stay untouched before it is read again.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Send `()` after each change to the active config file, until the receiver is gone.
+pub fn watch(tx: UnboundedSender<()>) {
+    if let Err(err) = watch_config(&tx) {
+        warn!("Not watching the config for changes: {}", err);
+    }
+}
+
+fn watch_config(tx: &UnboundedSender<()>) -> Result<(), Box<dyn Error>> {
+    let path = config::config_file_path()?;
+    let dir = path.parent().ok_or("The config file has no directory")?;
+    let (events_tx, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(events_tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    info!("Watching {} for changes", dir.display());
+
+    for event in &events {
+        let Ok(event) = event else {
+            continue;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            continue;
+        }
+        // Converting the config to another format changes which file is active.
+        let Ok(active) = config::config_file_path() else {
+            continue;
+        };
+        if !event.paths.contains(&active) {
+            continue;
+        }
+        while events.recv_timeout(SETTLE_TIME).is_ok() {}
+        if tx.unbounded_send(()).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,362 @@
+//! `wpe status/monitors/next/pause/resume/toggle`: talk to the players that are
+//! already running.
+//!
+//! Players are found in /proc like `wpe power-report` does, and driven over the
+//! mpv IPC socket each one opens, so this works for wallpapers started by
+//! `wpe -c`, the GUI, or the daemon alike. `status` and `monitors` can print JSON
+//! instead, for waybar modules and scripts. `toggle` is meant for a keybinding, so it
+//! also flashes the new state on screen.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config,
+    deps::Dependencies,
+    gui, history, ipc,
+    monitors::{self, Monitor},
+    output::{self, CliError, Failure, OutputMode},
+    power::{self, Instance},
+};
+
+/// How long `wpe toggle` shows its badge.
+const TOGGLE_BADGE: Duration = Duration::from_millis(1200);
+
+/// One output in `wpe monitors --json`.
+#[derive(Debug, Serialize)]
+struct MonitorStatus<'a> {
+    name: &'a str,
+    description: &'a str,
+    width: u32,
+    height: u32,
+    refresh_rate: u32,
+    /// `playing`, `paused`, `unreachable`, or `stopped` when nothing runs on it.
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wallpaper: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<u32>,
+}
+
+/// One running wallpaper in `wpe status --json`, with its output's mode when connected.
+#[derive(Debug, Serialize)]
+struct WallpaperStatus<'a> {
+    monitor: &'a str,
+    pid: u32,
+    state: &'static str,
+    wallpaper: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_rate: Option<u32>,
+}
+
+/// What to do to a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Next,
+    Pause,
+    Resume,
+    /// Go back to the file shown before.
+    Previous,
+    /// Tag the file on screen as a favorite.
+    Favorite,
+}
+
+impl Action {
+    /// Do it to the player on `monitor`.
+    pub fn run(self, monitor: &str) -> Result<(), String> {
+        let result = match self {
+            Action::Next => ipc::command(monitor, &[json!("playlist-next")]).map(|_| ()),
+            Action::Pause => ipc::set_property(monitor, "pause", json!(true)),
+            Action::Resume => ipc::set_property(monitor, "pause", json!(false)),
+            Action::Previous => {
+                return history::go_back(monitor)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string());
+            }
+            Action::Favorite => {
+                return history::favorite(monitor)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string());
+            }
+        };
+        result.map_err(|err| match self {
+            // mpv refuses playlist-next on the last (or only) entry.
+            Action::Next if ipc::is_listening(monitor) => "nothing to skip to".to_string(),
+            _ => err.to_string(),
+        })
+    }
+
+    /// What was done, for the status line.
+    pub fn done(self) -> &'static str {
+        match self {
+            Action::Next => "Skipped to the next wallpaper",
+            Action::Pause => "Paused the wallpaper",
+            Action::Resume => "Resumed the wallpaper",
+            Action::Previous => "Went back to the previous wallpaper",
+            Action::Favorite => "Added the wallpaper to the favorites",
+        }
+    }
+
+    fn record(self) -> &'static str {
+        match self {
+            Action::Next => "next",
+            Action::Pause => "paused",
+            Action::Resume => "resumed",
+            Action::Previous => "previous",
+            Action::Favorite => "favorite",
+        }
+    }
+}
+
+/// Print every running wallpaper with its state and current file.
+pub fn status() -> Result<(), CliError> {
+    let instances = running();
+    if instances.is_empty() {
+        output::say("No wallpapers are running.");
+    }
+    for instance in &instances {
+        let (state, path) = playback(instance);
+        output::say(format!(
+            "{}: {} {} (pid {})",
+            instance.monitor, state, path, instance.pid
+        ));
+        output::record(
+            "wallpaper",
+            &[&instance.monitor, &instance.pid.to_string(), state, &path],
+        );
+    }
+    Ok(())
+}
+
+/// Print the running wallpapers and the tools found as one JSON object.
+pub fn status_json(deps: &Dependencies) -> Result<(), CliError> {
+    let outputs = monitors::list_monitors().unwrap_or_default();
+    let instances = running();
+    let wallpapers: Vec<WallpaperStatus> = instances
+        .iter()
+        .map(|instance| {
+            let (state, wallpaper) = playback(instance);
+            let output = outputs
+                .iter()
+                .find(|output| output.name == instance.monitor);
+            WallpaperStatus {
+                monitor: &instance.monitor,
+                pid: instance.pid,
+                state,
+                wallpaper,
+                width: output.map(|output| output.width),
+                height: output.map(|output| output.height),
+                refresh_rate: output.map(|output| output.refresh_rate),
+            }
+        })
+        .collect();
+    let tools: Vec<Value> = deps
+        .tools()
+        .into_iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "installed": tool.installed,
+                "version": tool.version,
+            })
+        })
+        .collect();
+    print_json(&json!({
+        "wallpapers": wallpapers,
+        "tools": tools,
+        "warnings": deps.issues(),
+    }))
+}
+
+/// List the connected outputs with their mode and what plays on them, as JSON with
+/// `json`.
+pub fn monitors(json: bool) -> Result<(), CliError> {
+    let outputs = monitors::list_monitors()?;
+    let instances = running();
+    let statuses: Vec<MonitorStatus> = outputs
+        .iter()
+        .map(|output| monitor_status(output, &instances))
+        .collect();
+    if json {
+        return print_json(&json!(statuses));
+    }
+    if statuses.is_empty() {
+        output::say("No outputs are connected.");
+    }
+    for status in &statuses {
+        let playing = match &status.wallpaper {
+            Some(wallpaper) => format!("{} {}", status.state, wallpaper),
+            None => status.state.to_string(),
+        };
+        output::say(format!(
+            "{}: {}x{} @ {}Hz, {} ({})",
+            status.name,
+            status.width,
+            status.height,
+            status.refresh_rate,
+            playing,
+            status.description
+        ));
+        output::record(
+            "monitor",
+            &[
+                status.name,
+                &format!("{}x{}", status.width, status.height),
+                &status.refresh_rate.to_string(),
+                status.state,
+                status.wallpaper.as_deref().unwrap_or("-"),
+            ],
+        );
+    }
+    Ok(())
+}
+
+fn monitor_status<'a>(output: &'a Monitor, instances: &[Instance]) -> MonitorStatus<'a> {
+    let instance = instances
+        .iter()
+        .find(|instance| instance.monitor == output.name);
+    let (state, wallpaper) = match instance.map(playback) {
+        Some((state, wallpaper)) => (state, Some(wallpaper)),
+        None => ("stopped", None),
+    };
+    MonitorStatus {
+        name: &output.name,
+        description: &output.description,
+        width: output.width,
+        height: output.height,
+        refresh_rate: output.refresh_rate,
+        state,
+        wallpaper,
+        pid: instance.map(|instance| instance.pid),
+    }
+}
+
+/// Print `value` on stdout unless `--quiet`, like `wpe doctor --json`.
+fn print_json(value: &Value) -> Result<(), CliError> {
+    let text =
+        serde_json::to_string_pretty(value).map_err(|err| CliError::new(Failure::General, err))?;
+    if output::mode() != OutputMode::Quiet {
+        println!("{text}");
+    }
+    Ok(())
+}
+
+/// Apply `action` to the player on `monitor`, or to every player when `None`.
+pub fn act(action: Action, monitor: Option<&str>) -> Result<(), CliError> {
+    let instances = running();
+    let targets: Vec<&Instance> = match monitor {
+        Some(monitor) => {
+            let instance = instances
+                .iter()
+                .find(|instance| instance.monitor == monitor)
+                .ok_or_else(|| {
+                    CliError::new(
+                        Failure::General,
+                        format!("No wallpaper is running on {monitor}"),
+                    )
+                })?;
+            vec![instance]
+        }
+        None => instances.iter().collect(),
+    };
+    if targets.is_empty() {
+        return Err(CliError::new(Failure::General, "No wallpapers are running"));
+    }
+
+    let mut failures = Vec::new();
+    for instance in targets {
+        match action.run(&instance.monitor) {
+            Ok(()) => {
+                output::say(format!("{} on {}.", action.done(), instance.monitor));
+                output::record(action.record(), &[&instance.monitor]);
+            }
+            Err(err) => {
+                output::say(format!("{}: {}", instance.monitor, err));
+                output::record("failed", &[&instance.monitor, &err]);
+                failures.push(instance.monitor.clone());
+            }
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::new(
+            Failure::General,
+            format!("Could not control the player on {}", failures.join(", ")),
+        ))
+    }
+}
+
+/// Pause every wallpaper if any of them is playing, or resume them all, then flash
+/// which it was on every output unless `osd` is off.
+pub fn toggle(osd: bool) -> Result<(), CliError> {
+    let (action, badge) = if any_playing() {
+        (Action::Pause, "Wallpapers paused")
+    } else {
+        (Action::Resume, "Wallpapers playing")
+    };
+    act(action, None)?;
+    if osd {
+        let accent = config::load_settings()
+            .ok()
+            .and_then(|settings| settings.preferences.accent);
+        gui::flash(accent, badge, TOGGLE_BADGE);
+    }
+    Ok(())
+}
+
+/// Whether any running player is unpaused.
+pub fn any_playing() -> bool {
+    running().iter().any(|instance| {
+        ipc::get_property(&instance.monitor, "pause").is_ok_and(|paused| paused == json!(false))
+    })
+}
+
+/// Apply `action` to every running player, quietly, with each failure in the error.
+pub fn act_on_all(action: Action) -> Result<(), String> {
+    let failures: Vec<String> = running()
+        .into_iter()
+        .filter_map(|instance| {
+            action
+                .run(&instance.monitor)
+                .err()
+                .map(|err| format!("{}: {}", instance.monitor, err))
+        })
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+/// The running players, in output order.
+pub fn running() -> Vec<Instance> {
+    let mut instances = power::find_instances();
+    instances.sort_by(|a, b| a.monitor.cmp(&b.monitor));
+    instances
+}
+
+/// `playing`/`paused` and the file on screen, or `unreachable` and mpvpaper's argument
+/// when the player has no IPC socket (e.g. started by hand).
+fn playback(instance: &Instance) -> (&'static str, String) {
+    let Ok(paused) = ipc::get_property(&instance.monitor, "pause") else {
+        return ("unreachable", instance.source.clone());
+    };
+    let state = if paused.as_bool().unwrap_or(false) {
+        "paused"
+    } else {
+        "playing"
+    };
+    let path = ipc::get_property(&instance.monitor, "path")
+        .ok()
+        .and_then(|path| path.as_str().map(str::to_string))
+        .unwrap_or_else(|| instance.source.clone());
+    (state, path)
+}
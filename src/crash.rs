@@ -0,0 +1,254 @@
+//! Opt-in local crash reports.
+//!
+//! With `[crash_reports] enabled = true`, a panic writes a plain-text report to
+//! `$XDG_STATE_HOME/wpe/crashes/`: the panic message, a backtrace, the last log
+//! lines, and a summary of the config with paths, tags, and URLs left out. Nothing
+//! is sent anywhere; the GUI points at new reports on its next launch so they can be
+//! attached to a bug report by hand.
+
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    env,
+    error::Error,
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    thread,
+};
+
+use chrono::Local;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::{
+    config::{self, WallpaperProfileEntry},
+    state::{self, State},
+};
+
+/// Log lines kept in memory for the next report.
+const LOG_LINES: usize = 200;
+
+static LOG_TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Log writer that goes to stderr and keeps the most recent lines for crash reports.
+pub struct LogWriter;
+
+impl<'a> MakeWriter<'a> for LogWriter {
+    type Writer = TailWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        TailWriter
+    }
+}
+
+pub struct TailWriter;
+
+impl Write for TailWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut tail = LOG_TAIL.lock().unwrap_or_else(PoisonError::into_inner);
+        for line in String::from_utf8_lossy(buf).lines() {
+            if tail.len() == LOG_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line.to_string());
+        }
+        drop(tail);
+        io::stderr().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// Write a report for every panic when crash reports are enabled, after the usual message.
+pub fn install_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if !enabled() {
+            return;
+        }
+        match write_report(info) {
+            Ok(path) => eprintln!("wpe: crash report saved to {}", path.display()),
+            Err(err) => eprintln!("wpe: could not save a crash report: {err}"),
+        }
+    }));
+}
+
+/// Reports are opt-in; reading the setting must not create a config on the way.
+fn enabled() -> bool {
+    config::config_file_path().is_ok_and(|path| path.exists())
+        && config::load_settings().is_ok_and(|settings| settings.crash_reports.enabled)
+}
+
+pub fn reports_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let dir = state::state_dir()?.join("crashes");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Reports written since the newest one `state` has recorded as seen, oldest first.
+pub fn unseen_reports(state: &State) -> Vec<PathBuf> {
+    let Ok(entries) = reports_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with("crash-")
+                        && state
+                            .last_crash_seen
+                            .as_deref()
+                            .is_none_or(|seen| name > seen)
+                })
+        })
+        .collect();
+    reports.sort();
+    reports
+}
+
+/// Remember `reports` as seen so they are only pointed out once.
+pub fn mark_seen(state: &mut State, reports: &[PathBuf]) {
+    if let Some(name) = reports
+        .last()
+        .and_then(|path| path.file_name())
+        .and_then(|name| name.to_str())
+    {
+        state.last_crash_seen = Some(name.to_string());
+    }
+}
+
+fn write_report(info: &PanicHookInfo<'_>) -> Result<PathBuf, Box<dyn Error>> {
+    let now = Local::now();
+    let path = reports_dir()?.join(format!(
+        "crash-{}-{}.txt",
+        now.format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|text| text.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".into());
+    let mut report = String::new();
+    let _ = writeln!(report, "wpe {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "time: {}", now.to_rfc3339());
+    let _ = writeln!(
+        report,
+        "thread: {}",
+        thread::current().name().unwrap_or("unnamed")
+    );
+    let _ = writeln!(report, "panic: {}", message);
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "location: {}:{}", location.file(), location.line());
+    }
+    let _ = writeln!(report, "system: {} {}", env::consts::OS, env::consts::ARCH);
+
+    let _ = writeln!(report, "\n-- config --");
+    report.push_str(&config_summary());
+    let _ = writeln!(report, "\n-- backtrace --\n{}", Backtrace::force_capture());
+    let _ = writeln!(report, "-- last log lines --");
+    let tail = LOG_TAIL.lock().unwrap_or_else(PoisonError::into_inner);
+    for line in tail.iter() {
+        let _ = writeln!(report, "{}", line);
+    }
+    drop(tail);
+
+    fs::write(&path, anonymize(&report))?;
+    Ok(path)
+}
+
+/// The shape of the config without paths, tags, or URLs.
+fn config_summary() -> String {
+    let mut summary = String::new();
+    let (Ok(settings), Ok(entries)) = (config::load_settings(), config::load_wallpaper_entries())
+    else {
+        summary.push_str("(config could not be read)\n");
+        return summary;
+    };
+    let _ = writeln!(
+        summary,
+        "sandbox: {}, variants: {}, ducking: {}, seasons: {}, sync collections: {}",
+        settings.security.sandbox,
+        settings.variants.enabled,
+        settings.audio.duck,
+        settings.seasons.len(),
+        settings.sync.len()
+    );
+    for (index, entry) in entries.iter().enumerate() {
+        let _ = writeln!(summary, "entry {}: {}", index, describe_entry(entry));
+    }
+    summary
+}
+
+fn describe_entry(entry: &WallpaperProfileEntry) -> String {
+    let source = if let Some(grid) = &entry.grid {
+        format!("grid of {} ({:?})", grid.sources.len(), grid.layout)
+    } else if entry
+        .source
+        .as_ref()
+        .is_some_and(|source| source.is_active())
+    {
+        "tags".into()
+    } else {
+        match entry.path.as_deref() {
+            Some(_) if !entry.extra_paths.is_empty() => {
+                format!("mix of {}", entry.extra_paths.len() + 1)
+            }
+            Some(path) => describe_path(path),
+            None => "none".into(),
+        }
+    };
+    let mut description = format!(
+        "monitor={} enabled={} source={} scale={:?} audio={}",
+        entry.monitor.as_deref().unwrap_or("-"),
+        entry.enabled,
+        source,
+        entry.scale,
+        entry.audio
+    );
+    if entry.pip.is_some() {
+        description.push_str(" pip");
+    }
+    if !entry.margins.is_zero() {
+        description.push_str(" margins");
+    }
+    if let Some(hwdec) = &entry.tuning.hwdec {
+        let _ = write!(description, " hwdec={hwdec}");
+    }
+    description
+}
+
+/// A path reduced to its kind and extension.
+fn describe_path(path: &Path) -> String {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("file (.{})", ext.to_lowercase()),
+        None => "folder".into(),
+    }
+}
+
+/// Replace the home directory and user name, which log lines and messages may carry.
+fn anonymize(report: &str) -> String {
+    let mut report = report.to_string();
+    if let Ok(home) = env::var("HOME")
+        && home.len() > 1
+    {
+        report = report.replace(&home, "~");
+    }
+    if let Ok(user) = env::var("USER")
+        && user.len() > 2
+    {
+        report = report.replace(&user, "<user>");
+    }
+    report
+}
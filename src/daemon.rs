@@ -0,0 +1,757 @@
+//! `wpe daemon`: one long-running owner for every mpvpaper.
+//!
+//! The daemon listens on `$XDG_RUNTIME_DIR/wpe.sock` (the state directory without a
+//! runtime dir). A client connects, writes one JSON request line such as
+//! `{"command":"reload"}`, and reads one JSON reply line. `wpe -c`, the GUI, and
+//! `wpe daemon <command>` send their start/stop/reload requests here while a
+//! daemon runs, so the players have a single parent instead of being found again
+//! with `pkill`. The daemon also serves [`crate::bus`] on the session bus, and with
+//! `--http`, the [`crate::http`] API. Edits to the config file reload it on their own
+//! (see [`crate::config_watch`]), outputs plugged in get a wallpaper, and every
+//! wallpaper comes back after the compositor restarts. A locked config
+//! (`locked = true` or `--kiosk`) turns `set` requests away, from whichever of these
+//! they come.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    env,
+    error::Error,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::SocketAddr,
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    audio, backend,
+    battery::{self, BatteryPolicy},
+    bus,
+    colorscheme::{self, ColorSchemeSettings},
+    config::{self, Settings, WallpaperProfileEntry},
+    config_watch, fallback,
+    fullscreen::{self, Pauser},
+    history, http,
+    idle::{self, IdleEvent},
+    launcher::Instance,
+    monitors::{self, Monitor, MonitorEvent},
+    notify::StartupSummary,
+    output::{self, CliError, Failure},
+    runtime, state, tweaks,
+    usage::{self, Tracker},
+    watchdog::Watchdog,
+};
+
+/// How often the daemon looks for players that exited while no request came in.
+const REAP_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a client gets to send its request line.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Clients give up on a daemon that does not answer; starts wait for playback.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a client asks the daemon to do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    /// Launch configured wallpapers that are not running yet.
+    Start,
+    /// Stop one output's wallpaper, or all of them.
+    Stop { monitor: Option<String> },
+    /// Re-read the config and restart only the wallpapers whose entry changed.
+    Reload,
+    /// Make `monitor`'s entry play `path` alone, save the config, and restart it.
+    Set { monitor: String, path: PathBuf },
+    /// List the running wallpapers.
+    Status,
+    /// Stop every wallpaper and exit.
+    Quit,
+}
+
+/// The daemon's answer to one request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reply {
+    /// Set when the request could not be carried out at all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Outputs whose wallpaper was (re)started by this request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub started: Vec<String>,
+    /// Outputs whose wallpaper was stopped by this request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stopped: Vec<String>,
+    /// Outputs that failed to start, with the reason.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<(String, String)>,
+    /// Configured outputs left out, with why (not connected, no wallpaper chosen).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<(String, String)>,
+    /// Outputs whose player crashed and is waiting to be restarted, or was given up on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crashed: Vec<(String, String)>,
+    /// Everything running once the request was handled.
+    #[serde(default)]
+    pub running: Vec<RunningInfo>,
+}
+
+/// One wallpaper the daemon owns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningInfo {
+    pub monitor: String,
+    pub pid: u32,
+    /// Where the player is in its lifecycle, e.g. `running`.
+    #[serde(default)]
+    pub state: String,
+    /// The configured path, or a description of the tag or grid source.
+    pub source: String,
+}
+
+/// Where the control socket lives.
+pub fn socket_path() -> Result<PathBuf, Box<dyn Error>> {
+    if let Ok(runtime) = env::var("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(runtime).join("wpe.sock"));
+    }
+    Ok(state::state_dir()?.join("wpe.sock"))
+}
+
+/// Whether a daemon is accepting requests.
+pub fn is_running() -> bool {
+    socket_path().is_ok_and(|path| UnixStream::connect(path).is_ok())
+}
+
+/// Send `request` to the running daemon and wait for its reply.
+pub fn send(request: &Request) -> Result<Reply, Box<dyn Error>> {
+    let path = socket_path()?;
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|err| format!("No wpe daemon is listening on {}: {}", path.display(), err))?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    writeln!(stream, "{}", serde_json::to_string(request)?)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    if line.is_empty() {
+        return Err("The wpe daemon closed the connection without answering".into());
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Print what the daemon did, failing when it refused the request or a wallpaper did
+/// not start.
+pub fn report(reply: &Reply) -> Result<(), CliError> {
+    if let Some(err) = &reply.error {
+        return Err(CliError::new(Failure::General, err));
+    }
+    for monitor in &reply.stopped {
+        output::say(format!("Stopped the wallpaper on {monitor}."));
+        output::record("stopped", &[monitor]);
+    }
+    for monitor in &reply.started {
+        output::say(format!("Started the wallpaper on {monitor}."));
+        output::record("started", &[monitor]);
+    }
+    for (monitor, err) in &reply.failed {
+        output::say(format!("{monitor}: failed: {err}"));
+        output::record("failed", &[monitor, err]);
+    }
+    for (monitor, reason) in &reply.skipped {
+        output::say(format!("{monitor}: skipped: {reason}"));
+        output::record("skipped", &[monitor, reason]);
+    }
+    for (monitor, note) in &reply.crashed {
+        output::say(format!("{monitor}: {note}"));
+        output::record("crashed", &[monitor, note]);
+    }
+    for running in &reply.running {
+        output::say(format!(
+            "{}: {} (pid {}, {})",
+            running.monitor, running.source, running.pid, running.state
+        ));
+        output::record(
+            "instance",
+            &[
+                &running.monitor,
+                &running.pid.to_string(),
+                &running.source,
+                &running.state,
+            ],
+        );
+    }
+    if !reply.failed.is_empty() {
+        return Err(CliError::new(
+            Failure::General,
+            format!("{} wallpaper(s) did not start playing", reply.failed.len()),
+        ));
+    }
+    Ok(())
+}
+
+/// A wallpaper player owned by the daemon.
+struct Player {
+    instance: Instance,
+    /// Launched while on battery with `on_battery = "static-frame"` or with reduced
+    /// motion, so showing a still frame if one was cached.
+    still: bool,
+}
+
+#[derive(Default)]
+struct Daemon {
+    players: HashMap<String, Player>,
+    /// Outputs a fullscreen window covers.
+    covered_windows: BTreeSet<String>,
+    on_battery: bool,
+    battery_policy: BatteryPolicy,
+    /// `[motion]` or the desktop asked for still wallpapers.
+    reduced_motion: bool,
+    colorscheme: ColorSchemeSettings,
+    session_idle: bool,
+    /// Outputs the compositor powered down.
+    screens_off: BTreeSet<String>,
+    pauser: Pauser,
+    usage: Tracker,
+    watchdog: Watchdog,
+}
+
+/// Run the daemon until a `quit` request arrives: launch the configured wallpapers,
+/// then serve requests.
+pub fn run(http: Option<SocketAddr>) -> Result<(), Box<dyn Error>> {
+    let path = socket_path()?;
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!("A wpe daemon is already listening on {}", path.display()).into());
+    }
+    // Nobody answered, so the file is left over from a daemon that did not exit cleanly.
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .map_err(|err| format!("Unable to listen on {}: {}", path.display(), err))?;
+    listener.set_nonblocking(true)?;
+    info!("wpe daemon listening on {}", path.display());
+
+    // `[fullscreen]`, `[power]`, `[motion]`, `[idle]`, and `[colorscheme]` are read
+    // once (the desktop's reduced motion preference too); restart the daemon to change
+    // them.
+    let settings = config::load_settings().unwrap_or_default();
+    let (window_tx, mut covered) = futures::channel::mpsc::unbounded();
+    if settings.fullscreen.pause {
+        let fullscreen_settings = settings.fullscreen;
+        runtime::spawn_watcher(move || fullscreen::watch(fullscreen_settings, window_tx));
+    }
+    let (battery_tx, mut power_source) = futures::channel::mpsc::unbounded();
+    let mut daemon = Daemon {
+        battery_policy: settings.power.on_battery,
+        reduced_motion: settings.motion.reduced(),
+        colorscheme: settings.colorscheme.clone(),
+        ..Daemon::default()
+    };
+    if daemon.battery_policy != BatteryPolicy::Continue {
+        daemon.on_battery = battery::on_battery();
+        runtime::spawn_watcher(move || battery::watch(battery_tx));
+    }
+    let (config_tx, mut config_changes) = futures::channel::mpsc::unbounded();
+    runtime::spawn_watcher(move || config_watch::watch(config_tx));
+    let (idle_tx, mut idle_events) = futures::channel::mpsc::unbounded();
+    if settings.idle.is_active() {
+        let idle_settings = settings.idle;
+        runtime::spawn_watcher(move || idle::watch(idle_settings, idle_tx));
+    }
+
+    let (outputs_tx, mut output_events) = futures::channel::mpsc::unbounded();
+    runtime::spawn_watcher(move || monitors::watch_monitors_unbounded(outputs_tx));
+
+    // Players left by `wpe -c` or an earlier daemon would end up stacked under ours.
+    let _ = backend::stop_all();
+    let reply = daemon.handle(Request::Start);
+    for (monitor, err) in &reply.failed {
+        warn!("Could not start the wallpaper on {}: {}", monitor, err);
+    }
+    let mut outputs: Vec<String> = monitors::list_monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|monitor| monitor.name)
+        .collect();
+    StartupSummary {
+        monitors: outputs.len(),
+        launched: reply.started.len(),
+        skipped: reply
+            .skipped
+            .iter()
+            .cloned()
+            .chain(
+                reply
+                    .failed
+                    .iter()
+                    .map(|(monitor, err)| (monitor.clone(), format!("failed: {err}"))),
+            )
+            .collect(),
+    }
+    .report(&settings.notifications);
+    // Other apps can reach the daemon over D-Bus too, but it works without a session bus.
+    let mut bus = bus::serve()
+        .map_err(|err| warn!("Not serving {} on the session bus: {}", bus::BUS_NAME, err))
+        .ok();
+    if let Some(address) = http {
+        http::serve(address)?;
+    }
+
+    let mut reconnecting = false;
+    // Requests are answered as soon as they connect; without one, the players are
+    // checked every REAP_INTERVAL.
+    runtime::get().block_on(async {
+        let listener = tokio::net::UnixListener::from_std(listener)?;
+        loop {
+            // Only the newest updates matter; older ones are already out of date.
+            while let Ok(outputs) = covered.try_recv() {
+                daemon.covered_windows = outputs;
+            }
+            while let Ok(on_battery) = power_source.try_recv() {
+                daemon.on_battery = on_battery;
+            }
+            while let Ok(event) = idle_events.try_recv() {
+                match event {
+                    IdleEvent::Idle(idle) => daemon.session_idle = idle,
+                    IdleEvent::ScreensOff(outputs) => daemon.screens_off = outputs,
+                }
+            }
+            let mut plugged: Option<Vec<String>> = None;
+            while let Ok(event) = output_events.try_recv() {
+                match event {
+                    MonitorEvent::Outputs(monitors) => {
+                        plugged = Some(monitors.into_iter().map(|monitor| monitor.name).collect());
+                    }
+                    MonitorEvent::ConnectionLost(err) => {
+                        // The players go down with the compositor; once it is back,
+                        // its first snapshot brings every wallpaper back.
+                        warn!("Lost the compositor ({}); waiting for it to return", err);
+                        reconnecting = true;
+                        outputs.clear();
+                        plugged = None;
+                    }
+                }
+            }
+            if let Some(names) = plugged.filter(|names| *names != outputs) {
+                if std::mem::take(&mut reconnecting) {
+                    // Whatever survived belongs to the old session; start afresh
+                    // rather than wait for the watchdog.
+                    info!("The compositor is back; restarting the wallpapers");
+                    let monitors: Vec<String> = daemon.players.keys().cloned().collect();
+                    for monitor in monitors {
+                        daemon.stop(&monitor);
+                    }
+                } else {
+                    // New outputs get a wallpaper, and `mirror = true` entries take them in.
+                    info!("The outputs changed; starting wallpapers on new ones");
+                }
+                outputs = names;
+                let reply = daemon.handle(Request::Start);
+                for (monitor, err) in &reply.failed {
+                    warn!("Could not start the wallpaper on {}: {}", monitor, err);
+                }
+            }
+            let mut edited = false;
+            while config_changes.try_recv().is_ok() {
+                edited = true;
+            }
+            if edited {
+                info!("The config changed; reloading");
+                let reply = daemon.handle(Request::Reload);
+                if let Some(err) = &reply.error {
+                    warn!("Could not reload the config: {}", err);
+                }
+                for (monitor, err) in &reply.failed {
+                    warn!("Could not start the wallpaper on {}: {}", monitor, err);
+                }
+            }
+            daemon.sync_stills();
+            daemon.sync_pauses();
+            daemon.track_usage();
+            daemon.restart_crashed();
+            if let Some(bus) = &mut bus {
+                bus.watch(daemon.players.keys().cloned().collect());
+            }
+
+            let accepted = tokio::time::timeout(REAP_INTERVAL, listener.accept()).await;
+            match accepted.map(|result| result.and_then(|(stream, _)| stream.into_std())) {
+                Ok(Ok(stream)) => {
+                    if daemon.serve(stream) {
+                        return Ok::<(), Box<dyn Error>>(());
+                    }
+                }
+                Ok(Err(err)) => warn!("Failed to accept a daemon connection: {}", err),
+                Err(_) => daemon.reap(),
+            }
+        }
+    })?;
+
+    if let Some(collected) = daemon.usage.flush(true) {
+        usage::save(collected);
+    }
+    let _ = fs::remove_file(&path);
+    info!("wpe daemon exiting");
+    Ok(())
+}
+
+impl Daemon {
+    /// Answer one connection, returning whether the daemon should exit.
+    fn serve(&mut self, stream: UnixStream) -> bool {
+        // Accepted streams start out non-blocking, but a client gets a normal blocking one.
+        let _ = stream.set_nonblocking(false);
+        // A client that never sends its request must not stall every other one.
+        let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+        let mut line = String::new();
+        let request = BufReader::new(&stream)
+            .read_line(&mut line)
+            .map_err(|err| err.to_string())
+            .and_then(|_| serde_json::from_str::<Request>(&line).map_err(|err| err.to_string()));
+        let quit = request == Ok(Request::Quit);
+        let reply = match request {
+            Ok(request) => self.handle(request),
+            Err(err) => Reply {
+                error: Some(format!("Invalid request: {err}")),
+                ..Reply::default()
+            },
+        };
+        if let Ok(data) = serde_json::to_string(&reply) {
+            let _ = writeln!(&stream, "{data}");
+        }
+        quit
+    }
+
+    /// Pause the players under fullscreen windows or on powered-down outputs, or all of
+    /// them while idle or on battery with `on_battery = "pause"`, and resume the rest.
+    fn sync_pauses(&mut self) {
+        let mut held = self.covered_windows.clone();
+        if self.on_battery && self.battery_policy == BatteryPolicy::Pause {
+            held.extend(self.players.keys().cloned());
+        }
+        held.extend(idle::held(
+            self.session_idle,
+            &self.screens_off,
+            self.players.keys(),
+        ));
+        let running = self
+            .players
+            .iter()
+            .map(|(monitor, player)| (monitor.as_str(), player.instance.child.id()));
+        self.pauser.update(&held, running);
+    }
+
+    fn wants_stills(&self) -> bool {
+        self.reduced_motion
+            || (self.on_battery && self.battery_policy == BatteryPolicy::StaticFrame)
+    }
+
+    /// Restart players as still frames on battery with `on_battery = "static-frame"`,
+    /// and from their source again on AC power. With reduced motion they stay still.
+    fn sync_stills(&mut self) {
+        let wants_stills = self.wants_stills();
+        let switching: Vec<String> = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.still != wants_stills)
+            .filter(|(_, player)| !player.instance.entry.is_still_image())
+            .map(|(monitor, _)| monitor.clone())
+            .collect();
+        if switching.is_empty() {
+            return;
+        }
+        self.remember_tweaks(&switching);
+        let settings = config::load_settings().unwrap_or_default();
+        let outputs = monitors::list_monitors().unwrap_or_default();
+        for monitor in switching {
+            // Capture what is on screen now so the still matches the wallpaper.
+            if wants_stills && let Err(err) = fallback::capture(&monitor) {
+                warn!("Could not capture a still of {}: {}", monitor, err);
+            }
+            let Some(entry) = self
+                .players
+                .get(&monitor)
+                .map(|player| player.instance.entry.clone())
+            else {
+                continue;
+            };
+            self.stop(&monitor);
+            let output = outputs.iter().find(|output| output.name == monitor);
+            if let Err(err) = self.launch(entry, output, &settings) {
+                warn!("Could not restart the wallpaper on {}: {}", monitor, err);
+            }
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> Reply {
+        self.reap();
+        let mut reply = Reply::default();
+        match request {
+            Request::Start => self.apply(&mut reply, false),
+            Request::Reload => self.apply(&mut reply, true),
+            Request::Set { .. }
+                if config::load_settings().is_ok_and(|settings| settings.locked) =>
+            {
+                reply.error =
+                    Some("The config is locked; wallpapers can only be started and stopped".into());
+            }
+            Request::Set { monitor, path } => {
+                // Whatever was tweaked live goes into the entry before it is rewritten.
+                self.remember_tweaks(std::slice::from_ref(&monitor));
+                match config::set_monitor_path(&monitor, path) {
+                    Ok(()) => self.apply(&mut reply, true),
+                    Err(err) => reply.error = Some(format!("Failed to save the config: {err}")),
+                }
+            }
+            Request::Stop {
+                monitor: Some(monitor),
+            } => {
+                self.remember_tweaks(std::slice::from_ref(&monitor));
+                if self.stop(&monitor) {
+                    reply.stopped.push(monitor);
+                } else {
+                    reply.error = Some(format!("No wallpaper is running on {monitor}"));
+                }
+            }
+            Request::Stop { monitor: None } | Request::Quit => {
+                let monitors: Vec<String> = self.players.keys().cloned().collect();
+                self.remember_tweaks(&monitors);
+                for monitor in monitors {
+                    self.stop(&monitor);
+                    reply.stopped.push(monitor);
+                }
+            }
+            Request::Status => {}
+        }
+        reply.running = self.running();
+        reply.crashed = self.watchdog.troubles();
+        reply
+    }
+
+    /// Bring the players in line with the config. Without `restart_changed`, only
+    /// outputs with nothing running are started.
+    fn apply(&mut self, reply: &mut Reply, restart_changed: bool) {
+        // Crashed wallpapers still configured are started below like any other.
+        self.watchdog.clear();
+        let running: Vec<String> = self.players.keys().cloned().collect();
+        self.remember_tweaks(&running);
+        let mut entries = match config::load_wallpaper_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                reply.error = Some(format!("Failed to load the config: {err}"));
+                return;
+            }
+        };
+        let settings = match config::load_settings() {
+            Ok(settings) => settings,
+            Err(err) => {
+                reply.error = Some(format!("Failed to load the config: {err}"));
+                return;
+            }
+        };
+        audio::limit_audible(&mut entries, &settings.audio);
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.enabled && entry.is_unconfigured())
+        {
+            if let Some(monitor) = &entry.monitor {
+                reply
+                    .skipped
+                    .push((monitor.clone(), "no wallpaper chosen yet".into()));
+            }
+        }
+        let desired: Vec<WallpaperProfileEntry> = entries
+            .into_iter()
+            .filter(|entry| entry.enabled && entry.has_source() && entry.monitor.is_some())
+            .collect();
+
+        if restart_changed {
+            let stale: Vec<String> = self
+                .players
+                .iter()
+                .filter(|(monitor, player)| {
+                    !desired.iter().any(|entry| {
+                        entry.monitor.as_deref() == Some(monitor.as_str())
+                            && *entry == player.instance.entry
+                    })
+                })
+                .map(|(monitor, _)| monitor.clone())
+                .collect();
+            for monitor in stale {
+                self.stop(&monitor);
+                reply.stopped.push(monitor);
+            }
+        }
+
+        let outputs = monitors::list_monitors().unwrap_or_default();
+        for entry in desired {
+            let Some(monitor) = entry.monitor.clone() else {
+                continue;
+            };
+            if self.players.contains_key(&monitor) {
+                continue;
+            }
+            let output = outputs.iter().find(|output| output.name == monitor);
+            if output.is_none() && !outputs.is_empty() {
+                // Not connected; the next start or reload picks it up.
+                reply
+                    .skipped
+                    .push((monitor, "monitor not connected".into()));
+                continue;
+            }
+            match self.launch(entry, output, &settings) {
+                Ok(()) => reply.started.push(monitor),
+                Err(err) => reply.failed.push((monitor, err)),
+            }
+        }
+    }
+
+    /// Start a player for `entry` and wait until it plays; a still frame stands in
+    /// for moving wallpapers while [`Daemon::wants_stills`].
+    fn launch(
+        &mut self,
+        entry: WallpaperProfileEntry,
+        output: Option<&Monitor>,
+        settings: &Settings,
+    ) -> Result<(), String> {
+        let monitor = entry.monitor.clone().unwrap_or_default();
+        let still = if self.wants_stills() && !entry.is_still_image() {
+            fallback::cached_frame(&monitor)
+        } else {
+            None
+        };
+        let mut instance = Instance::spawn(&entry, output, settings, still)?;
+        if let Err(err) = instance.confirm(|_| {}) {
+            instance.stop();
+            return Err(err);
+        }
+        if instance.stand_in().is_none()
+            && let Err(err) = fallback::capture(&monitor)
+        {
+            warn!("Could not cache a frame for {}: {}", monitor, err);
+        }
+        info!("Daemon started the wallpaper on {}", monitor);
+        let still = self.wants_stills();
+        self.players.insert(monitor, Player { instance, still });
+        Ok(())
+    }
+
+    /// Save the live picture tweaks of `monitors` (with `[live_tweaks] remember`), in
+    /// the players' entries too so a reload does not see them as changed.
+    fn remember_tweaks(&mut self, monitors: &[String]) {
+        for (monitor, picture) in tweaks::remember(monitors.iter().map(String::as_str)) {
+            if let Some(player) = self.players.get_mut(&monitor) {
+                player.instance.entry.picture = picture;
+            }
+        }
+    }
+
+    /// Stop the player on `monitor`, or call off its restart, returning whether either
+    /// was pending.
+    fn stop(&mut self, monitor: &str) -> bool {
+        let restarting = self.watchdog.forget(monitor);
+        let Some(player) = self.players.remove(monitor) else {
+            return restarting;
+        };
+        player.instance.stop();
+        true
+    }
+
+    /// Forget players that exited on their own; `--fork`ed launchers exit right away.
+    /// Count what the players show towards the usage records, and follow a new file
+    /// with the color scheme.
+    fn track_usage(&mut self) {
+        if self.usage.is_due() {
+            let monitors = self.players.keys().cloned().collect();
+            let changed = self.usage.record(usage::sample(monitors));
+            history::record(&changed);
+            if let Some((monitor, file)) = self.colorscheme.pick(changed) {
+                let settings = self.colorscheme.clone();
+                runtime::get().spawn_blocking(move || {
+                    if let Err(err) = colorscheme::update(&settings, &monitor, &file) {
+                        warn!("Could not update the color scheme: {}", err);
+                    }
+                });
+            }
+        }
+        if let Some(collected) = self.usage.flush(false) {
+            usage::save(collected);
+        }
+    }
+
+    fn reap(&mut self) {
+        let exited: Vec<String> = self
+            .players
+            .iter_mut()
+            .filter(|(_, player)| player.instance.has_exited())
+            .map(|(monitor, _)| monitor.clone())
+            .collect();
+        if exited.is_empty() {
+            return;
+        }
+        let settings = config::load_settings().unwrap_or_default().watchdog;
+        for monitor in exited {
+            if let Some(player) = self.players.remove(&monitor) {
+                self.watchdog
+                    .exited(&monitor, &player.instance.entry, &settings);
+            }
+        }
+    }
+
+    /// Bring back the players whose restart the watchdog says is due.
+    fn restart_crashed(&mut self) {
+        let due = self.watchdog.due();
+        if due.is_empty() {
+            return;
+        }
+        let settings = config::load_settings().unwrap_or_default();
+        let outputs = monitors::list_monitors().unwrap_or_default();
+        for (monitor, entry) in due {
+            if self.players.contains_key(&monitor) {
+                self.watchdog.forget(&monitor);
+                continue;
+            }
+            let output = outputs.iter().find(|output| output.name == monitor);
+            if output.is_none() && !outputs.is_empty() {
+                // Unplugged; the next start or reload brings it back.
+                self.watchdog.forget(&monitor);
+                continue;
+            }
+            match self.launch(entry.clone(), output, &settings) {
+                Ok(()) => self.watchdog.restarted(&monitor),
+                Err(err) => {
+                    warn!("Could not restart the wallpaper on {}: {}", monitor, err);
+                    self.watchdog.exited(&monitor, &entry, &settings.watchdog);
+                }
+            }
+        }
+    }
+
+    fn running(&self) -> Vec<RunningInfo> {
+        let mut running: Vec<RunningInfo> = self
+            .players
+            .iter()
+            .map(|(monitor, player)| RunningInfo {
+                monitor: monitor.clone(),
+                pid: player.instance.child.id(),
+                state: player.instance.state().to_string(),
+                source: describe_source(&player.instance.entry),
+            })
+            .collect();
+        running.sort_by(|a, b| a.monitor.cmp(&b.monitor));
+        running
+    }
+}
+
+fn describe_source(entry: &WallpaperProfileEntry) -> String {
+    if let Some(grid) = &entry.grid {
+        return format!("grid of {}", grid.sources.len());
+    }
+    if let Some(source) = entry.source.as_ref().filter(|source| source.is_active()) {
+        return if source.seasonal {
+            "seasonal tags".into()
+        } else {
+            format!("tags {}", source.tags.join(", "))
+        };
+    }
+    match entry.configured_paths().as_slice() {
+        [] => String::new(),
+        [path] => path.display().to_string(),
+        paths => format!("mix of {}", paths.len()),
+    }
+}
@@ -0,0 +1,168 @@
+//! Probing of the external binaries wpe drives (mpvpaper, mpv, swaybg, swww, bwrap,
+//! ffmpeg).
+//!
+//! Each tool is run once per process to record whether it is installed and which
+//! version it reports (mpvpaper reports none, so its help is read instead), so
+//! optional features can be gated instead of handing an older mpvpaper flags it
+//! would reject.
+
+use std::{
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+/// What we learned about one external binary.
+#[derive(Debug, Clone)]
+pub struct Tool {
+    pub name: &'static str,
+    pub installed: bool,
+    pub version: Option<String>,
+    /// Combined stdout/stderr of the probe, used for flag detection.
+    output: String,
+}
+
+/// Optional behaviour that depends on what the installed tools understand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// mpvpaper's `--auto-pause`/`--auto-stop` occlusion handling.
+    OcclusionPause,
+}
+
+impl Feature {
+    fn describe(self) -> &'static str {
+        match self {
+            Feature::OcclusionPause => "auto-pause/auto-stop while hidden",
+        }
+    }
+}
+
+/// Snapshot of every external dependency.
+#[derive(Debug, Clone)]
+pub struct Dependencies {
+    pub mpvpaper: Tool,
+    pub mpv: Tool,
+    /// swaybg, only needed for `backend = "swaybg"`.
+    pub swaybg: Tool,
+    /// swww, only needed for `backend = "swww"`.
+    pub swww: Tool,
+    /// bubblewrap, only needed when `[security] sandbox` is on.
+    pub bwrap: Tool,
+    /// ffmpeg, only needed for loop point detection.
+    pub ffmpeg: Tool,
+}
+
+impl Dependencies {
+    /// Whether the installed tools can provide `feature`.
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::OcclusionPause => {
+                self.mpvpaper.output.contains("--auto-pause")
+                    && self.mpvpaper.output.contains("--auto-stop")
+            }
+        }
+    }
+
+    /// Human-readable problems worth showing to the user.
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if !self.mpvpaper.installed {
+            issues.push(if self.swaybg.installed || self.swww.installed {
+                "mpvpaper was not found in PATH; only still images (swaybg, swww) can be shown."
+                    .into()
+            } else {
+                "mpvpaper was not found in PATH; wallpapers cannot start.".into()
+            });
+            return issues;
+        }
+        if !self.mpv.installed {
+            issues.push("mpv was not found in PATH; mpvpaper may fail to play media.".into());
+        }
+        for feature in [Feature::OcclusionPause] {
+            if !self.supports(feature) {
+                issues.push(format!(
+                    "Your mpvpaper is too old for {}.",
+                    feature.describe()
+                ));
+            }
+        }
+        issues
+    }
+
+    /// Every probed tool, in display order.
+    pub fn tools(&self) -> [&Tool; 6] {
+        [
+            &self.mpvpaper,
+            &self.mpv,
+            &self.swaybg,
+            &self.swww,
+            &self.bwrap,
+            &self.ffmpeg,
+        ]
+    }
+
+    /// One line per tool for status output.
+    pub fn summary(&self) -> Vec<String> {
+        self.tools()
+            .into_iter()
+            .map(|tool| match (tool.installed, &tool.version) {
+                (false, _) => format!("{}: not installed", tool.name),
+                (true, Some(version)) => format!("{}: {}", tool.name, version),
+                (true, None) => format!("{}: installed (version unknown)", tool.name),
+            })
+            .collect()
+    }
+}
+
+/// Probe the tools once and reuse the result for the rest of the process.
+pub fn probe() -> &'static Dependencies {
+    static DEPENDENCIES: OnceLock<Dependencies> = OnceLock::new();
+    DEPENDENCIES.get_or_init(|| Dependencies {
+        // mpvpaper has no version flag; its help is only read for the flags it takes.
+        mpvpaper: Tool {
+            version: None,
+            ..probe_tool("mpvpaper", "--help")
+        },
+        mpv: probe_tool("mpv", "--version"),
+        swaybg: probe_tool("swaybg", "--version"),
+        swww: probe_tool("swww", "--version"),
+        bwrap: probe_tool("bwrap", "--version"),
+        ffmpeg: probe_tool("ffmpeg", "-version"),
+    })
+}
+
+fn probe_tool(name: &'static str, arg: &str) -> Tool {
+    match Command::new(name).arg(arg).stdin(Stdio::null()).output() {
+        Ok(result) => {
+            let mut output = String::from_utf8_lossy(&result.stdout).into_owned();
+            output.push_str(&String::from_utf8_lossy(&result.stderr));
+            Tool {
+                name,
+                installed: true,
+                version: parse_version(&output),
+                output,
+            }
+        }
+        Err(_) => Tool {
+            name,
+            installed: false,
+            version: None,
+            output: String::new(),
+        },
+    }
+}
+
+/// Pick the first token that looks like a version (`v0.38.0`, `1.7`, ...).
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|token| token.trim_matches(|ch: char| ch == ',' || ch == '(' || ch == ')'))
+        .find(|token| {
+            let digits = token.strip_prefix('v').unwrap_or(token);
+            digits.contains('.')
+                && digits.starts_with(|ch: char| ch.is_ascii_digit())
+                && digits
+                    .chars()
+                    .all(|ch| ch.is_ascii_digit() || ch == '.' || ch == '-' || ch == '+')
+        })
+        .map(str::to_string)
+}
@@ -0,0 +1,424 @@
+//! `wpe doctor`: check the environment wallpapers depend on.
+//!
+//! Every check runs even when an earlier one fails, so a single report shows
+//! everything that needs fixing. Failures make the command exit non-zero; warnings
+//! point at things that only matter for some setups.
+
+use std::{
+    collections::HashSet,
+    env,
+    error::Error,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::{self, RuntimeConfig},
+    deps, fallback, ipc, monitors,
+    output::{self, CliError, Failure, OutputMode},
+    power, state,
+};
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        }
+    }
+
+    /// ANSI color for the label in terminal output.
+    fn color(self) -> &'static str {
+        match self {
+            Status::Ok => "32",
+            Status::Warn => "33",
+            Status::Fail => "31",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    ok: bool,
+    checks: &'a [Check],
+}
+
+/// Run every check and print the report, as JSON with `json`.
+pub fn run(json: bool) -> Result<(), CliError> {
+    let checks = checks();
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+
+    if json {
+        let report = Report {
+            ok: failed == 0,
+            checks: &checks,
+        };
+        let text = serde_json::to_string_pretty(&report)
+            .map_err(|err| CliError::new(Failure::General, err))?;
+        if output::mode() != OutputMode::Quiet {
+            println!("{text}");
+        }
+    } else {
+        let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+        for check in &checks {
+            let label = if color {
+                format!(
+                    "\x1b[{}m{:<4}\x1b[0m",
+                    check.status.color(),
+                    check.status.label()
+                )
+            } else {
+                format!("{:<4}", check.status.label())
+            };
+            output::say(format!("{} {:<14} {}", label, check.name, check.detail));
+            output::record("check", &[check.name, check.status.label(), &check.detail]);
+        }
+    }
+
+    if failed > 0 {
+        return Err(CliError::new(
+            Failure::General,
+            format!("{failed} check(s) failed"),
+        ));
+    }
+    Ok(())
+}
+
+/// Every check, in the order they are reported.
+pub fn checks() -> Vec<Check> {
+    let mut checks = vec![wayland_socket()];
+    // Without a compositor the protocol checks can only repeat the socket failure.
+    if checks[0].status != Status::Fail {
+        checks.push(layer_shell());
+        checks.push(window_list());
+        checks.push(idle_notify());
+        checks.push(outputs());
+    }
+    checks.push(portal());
+    checks.extend(tools());
+    checks.push(config_file());
+    checks.extend(writable_dirs());
+    checks.push(players());
+    checks
+}
+
+fn wayland_socket() -> Check {
+    const NAME: &str = "wayland";
+    let Some(display) = env::var_os("WAYLAND_DISPLAY") else {
+        return Check::new(NAME, Status::Fail, "WAYLAND_DISPLAY is not set");
+    };
+    let path = Path::new(&display);
+    let socket = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match env::var_os("XDG_RUNTIME_DIR") {
+            Some(runtime) => PathBuf::from(runtime).join(path),
+            None => return Check::new(NAME, Status::Fail, "XDG_RUNTIME_DIR is not set"),
+        }
+    };
+    if socket.exists() {
+        Check::new(NAME, Status::Ok, socket.display().to_string())
+    } else {
+        Check::new(
+            NAME,
+            Status::Fail,
+            format!("{} does not exist", socket.display()),
+        )
+    }
+}
+
+fn layer_shell() -> Check {
+    const NAME: &str = "layer-shell";
+    match monitors::has_global("zwlr_layer_shell_v1") {
+        Ok(true) => Check::new(NAME, Status::Ok, "zwlr_layer_shell_v1 is available"),
+        Ok(false) => Check::new(
+            NAME,
+            Status::Fail,
+            "The compositor does not offer wlr-layer-shell; mpvpaper cannot draw wallpapers",
+        ),
+        Err(err) => Check::new(NAME, Status::Fail, format!("Cannot connect: {err}")),
+    }
+}
+
+fn window_list() -> Check {
+    const NAME: &str = "window list";
+    match monitors::has_global("zwlr_foreign_toplevel_manager_v1") {
+        Ok(true) => Check::new(
+            NAME,
+            Status::Ok,
+            "zwlr_foreign_toplevel_manager_v1 is available",
+        ),
+        Ok(false) => Check::new(
+            NAME,
+            Status::Warn,
+            "The compositor does not list windows; wallpapers under fullscreen windows keep playing",
+        ),
+        Err(err) => Check::new(NAME, Status::Fail, format!("Cannot connect: {err}")),
+    }
+}
+
+fn idle_notify() -> Check {
+    const NAME: &str = "idle";
+    match monitors::has_global("ext_idle_notifier_v1") {
+        Ok(true) => Check::new(NAME, Status::Ok, "ext_idle_notifier_v1 is available"),
+        Ok(false) => Check::new(
+            NAME,
+            Status::Warn,
+            "The compositor does not report idleness; [idle] timeout_seconds has no effect",
+        ),
+        Err(err) => Check::new(NAME, Status::Fail, format!("Cannot connect: {err}")),
+    }
+}
+
+fn outputs() -> Check {
+    const NAME: &str = "outputs";
+    match monitors::list_monitors() {
+        Ok(monitors) if monitors.is_empty() => {
+            Check::new(NAME, Status::Fail, "No outputs detected")
+        }
+        Ok(monitors) => {
+            let names: Vec<_> = monitors
+                .iter()
+                .map(|monitor| format!("{} ({}x{})", monitor.name, monitor.width, monitor.height))
+                .collect();
+            Check::new(NAME, Status::Ok, names.join(", "))
+        }
+        Err(err) => Check::new(NAME, Status::Fail, format!("Cannot list outputs: {err}")),
+    }
+}
+
+fn portal() -> Check {
+    const NAME: &str = "portal";
+    let owned = zbus::blocking::Connection::session().and_then(|connection| {
+        let proxy = zbus::blocking::fdo::DBusProxy::new(&connection)?;
+        Ok(proxy.name_has_owner("org.freedesktop.portal.Desktop".try_into()?)?)
+    });
+    match owned {
+        Ok(true) => Check::new(NAME, Status::Ok, "xdg-desktop-portal is running"),
+        Ok(false) => Check::new(
+            NAME,
+            Status::Warn,
+            "xdg-desktop-portal is not running; the GUI's file picker and theme detection need it",
+        ),
+        Err(err) => Check::new(
+            NAME,
+            Status::Warn,
+            format!("No D-Bus session bus ({err}); the GUI's file picker needs one"),
+        ),
+    }
+}
+
+fn tools() -> Vec<Check> {
+    let deps = deps::probe();
+    let mut checks: Vec<Check> = deps
+        .tools()
+        .into_iter()
+        .map(|tool| {
+            // Only mpvpaper is essential; the rest back optional features.
+            let missing = match tool.name {
+                "mpvpaper" => Status::Fail,
+                _ => Status::Warn,
+            };
+            match (tool.installed, &tool.version) {
+                (false, _) => Check::new(tool.name, missing, "not found in PATH"),
+                (true, Some(version)) => Check::new(tool.name, Status::Ok, version.clone()),
+                (true, None) => Check::new(tool.name, Status::Ok, "installed (version unknown)"),
+            }
+        })
+        .collect();
+    if deps.mpvpaper.installed {
+        checks.extend(
+            deps.issues()
+                .into_iter()
+                .map(|issue| Check::new("features", Status::Warn, issue)),
+        );
+    }
+    checks
+}
+
+fn config_file() -> Check {
+    const NAME: &str = "config";
+    let path = match config::config_file_path() {
+        Ok(path) => path,
+        Err(err) => return Check::new(NAME, Status::Fail, err.to_string()),
+    };
+    if !path.exists() {
+        return Check::new(
+            NAME,
+            Status::Warn,
+            format!(
+                "{} does not exist yet; run wpe to create it",
+                path.display()
+            ),
+        );
+    }
+    if let Err(err) = config::load_settings() {
+        return Check::new(NAME, Status::Fail, err.to_string());
+    }
+    let entries = match config::load_wallpaper_entries() {
+        Ok(entries) => entries,
+        Err(err) => return Check::new(NAME, Status::Fail, err.to_string()),
+    };
+
+    let problems: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.enabled && !entry.is_unconfigured())
+        .filter_map(|entry| {
+            let err = RuntimeConfig::from_profile_entry(entry).err()?;
+            Some(format!(
+                "{}: {}",
+                entry.monitor.as_deref().unwrap_or("unassigned entry"),
+                err
+            ))
+        })
+        .collect();
+    let unconfigured: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.enabled && entry.is_unconfigured())
+        .map(|entry| entry.monitor.as_deref().unwrap_or("unassigned entry"))
+        .collect();
+    if problems.is_empty() && !unconfigured.is_empty() {
+        Check::new(
+            NAME,
+            Status::Warn,
+            format!(
+                "no wallpaper chosen yet for {}; they are skipped",
+                unconfigured.join(", ")
+            ),
+        )
+    } else if problems.is_empty() {
+        let enabled = entries.iter().filter(|entry| entry.enabled).count();
+        Check::new(
+            NAME,
+            Status::Ok,
+            format!("{} ({} enabled entries)", path.display(), enabled),
+        )
+    } else {
+        Check::new(NAME, Status::Fail, problems.join("; "))
+    }
+}
+
+fn writable_dirs() -> Vec<Check> {
+    let mut checks = vec![
+        dir_check("state-dir", state::state_dir()),
+        dir_check("runtime-dir", ipc::socket_dir().map_err(Into::into)),
+        dir_check("cache-dir", fallback::cache_dir()),
+    ];
+    // A read-only config is supported (e.g. home-manager), so it is only a warning.
+    if config::config_is_read_only() {
+        checks.push(Check::new(
+            "config-write",
+            Status::Warn,
+            "The config file is read-only; the GUI cannot save changes",
+        ));
+    }
+    checks
+}
+
+fn dir_check(name: &'static str, dir: Result<PathBuf, Box<dyn Error>>) -> Check {
+    let dir = match dir {
+        Ok(dir) => dir,
+        Err(err) => return Check::new(name, Status::Fail, err.to_string()),
+    };
+    let probe = dir.join(".wpe-doctor");
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Check::new(name, Status::Ok, dir.display().to_string())
+        }
+        Err(err) => Check::new(
+            name,
+            Status::Fail,
+            format!("{} is not writable: {}", dir.display(), err),
+        ),
+    }
+}
+
+/// Players on outputs that are gone, and sockets no player listens on.
+fn players() -> Check {
+    const NAME: &str = "players";
+    let instances = power::find_instances();
+    let connected: Option<HashSet<String>> = monitors::list_monitors()
+        .ok()
+        .map(|monitors| monitors.into_iter().map(|monitor| monitor.name).collect());
+
+    let mut problems = Vec::new();
+    for instance in &instances {
+        if connected
+            .as_ref()
+            .is_some_and(|connected| !connected.contains(&instance.monitor))
+        {
+            problems.push(format!(
+                "pid {} plays on {}, which is not connected",
+                instance.pid, instance.monitor
+            ));
+        } else if !ipc::is_listening(&instance.monitor) {
+            problems.push(format!(
+                "pid {} on {} does not answer IPC",
+                instance.pid, instance.monitor
+            ));
+        }
+    }
+
+    let running: HashSet<String> = instances
+        .iter()
+        .map(|instance| ipc::file_safe(&instance.monitor))
+        .collect();
+    if let Ok(dir) = ipc::socket_dir()
+        && let Ok(entries) = fs::read_dir(dir)
+    {
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(monitor) = name
+                .strip_prefix("mpv-")
+                .and_then(|rest| rest.strip_suffix(".sock"))
+                && !running.contains(monitor)
+            {
+                problems.push(format!("stale socket {}", entry.path().display()));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Check::new(
+            NAME,
+            Status::Ok,
+            format!("{} running, none stale", instances.len()),
+        )
+    } else {
+        Check::new(NAME, Status::Warn, problems.join("; "))
+    }
+}
@@ -0,0 +1,239 @@
+//! What kind of thing went wrong, for callers that react to more than the message.
+//!
+//! The public functions of the config, monitor, player, and IPC modules return a
+//! [`WpeError`] naming the kind: the config, the compositor, the player backend, or a
+//! player's IPC socket. Where an io, toml, or serde error is behind it, that error is
+//! kept as the [`source`](Error::source) and its text is appended to the message, so
+//! printing the error alone still says what failed. A kind survives being boxed again
+//! further up; [`WpeError::find`] recovers it, which is how the CLI picks its exit
+//! code and the GUI its advice.
+
+use std::{error::Error, fmt, path::PathBuf};
+
+use thiserror::Error;
+
+use crate::ipc;
+
+/// The underlying error behind a [`WpeError`].
+pub type Source = Box<dyn Error + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum WpeError {
+    /// The config cannot be read, parsed, or written, or an entry cannot play.
+    #[error("{message}")]
+    Config {
+        /// The file involved, when known.
+        path: Option<PathBuf>,
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+    /// The Wayland compositor cannot be reached or lacks something wpe needs.
+    #[error("{message}")]
+    Wayland {
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+    /// mpvpaper failed to start or to come up.
+    #[error("{message}")]
+    Backend {
+        monitor: Option<String>,
+        /// mpvpaper (or a tool wrapped around it) is not installed.
+        missing: bool,
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+    /// A player's IPC socket did not answer as expected.
+    #[error("{message}")]
+    Ipc {
+        monitor: String,
+        message: String,
+        #[source]
+        source: Option<Source>,
+    },
+}
+
+impl WpeError {
+    pub fn config(path: Option<PathBuf>, err: impl fmt::Display) -> Self {
+        WpeError::Config {
+            path,
+            message: err.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn wayland(err: impl fmt::Display) -> Self {
+        WpeError::Wayland {
+            message: err.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn backend(monitor: Option<&str>, err: impl fmt::Display) -> Self {
+        WpeError::Backend {
+            monitor: monitor.map(str::to_string),
+            missing: false,
+            message: err.to_string(),
+            source: None,
+        }
+    }
+
+    /// mpvpaper, or a wrapper in front of it, is not installed.
+    pub fn backend_missing(monitor: Option<&str>, err: impl fmt::Display) -> Self {
+        WpeError::Backend {
+            monitor: monitor.map(str::to_string),
+            missing: true,
+            message: err.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn ipc(monitor: &str, err: impl fmt::Display) -> Self {
+        WpeError::Ipc {
+            monitor: monitor.to_string(),
+            message: err.to_string(),
+            source: None,
+        }
+    }
+
+    /// Append `detail` to the message, keeping the kind.
+    pub fn with_detail(mut self, detail: impl fmt::Display) -> Self {
+        let (WpeError::Config { message, .. }
+        | WpeError::Wayland { message, .. }
+        | WpeError::Backend { message, .. }
+        | WpeError::Ipc { message, .. }) = &mut self;
+        *message = format!("{message}: {detail}");
+        self
+    }
+
+    /// Keep `err` as the source, appending its text to the message.
+    pub fn with_source(self, err: impl Into<Source>) -> Self {
+        let err = err.into();
+        let mut this = self.with_detail(&err);
+        let (WpeError::Config { source, .. }
+        | WpeError::Wayland { source, .. }
+        | WpeError::Backend { source, .. }
+        | WpeError::Ipc { source, .. }) = &mut this;
+        *source = Some(err);
+        this
+    }
+
+    /// What the user can do about it.
+    pub fn hint(&self) -> String {
+        match self {
+            WpeError::Config { path: Some(path), .. } => format!(
+                "Fix {}; `wpe doctor` checks every entry.",
+                path.display()
+            ),
+            WpeError::Config { path: None, .. } => {
+                "Fix the config file; `wpe doctor` checks every entry.".to_string()
+            }
+            WpeError::Wayland { .. } => {
+                "wpe needs a running Wayland compositor; check that WAYLAND_DISPLAY is set."
+                    .to_string()
+            }
+            WpeError::Backend { missing: true, .. } => {
+                "Install mpvpaper (and any [process] or [security] tools) and make sure it is in PATH."
+                    .to_string()
+            }
+            WpeError::Backend {
+                monitor: Some(monitor),
+                ..
+            } => format!(
+                "The player's own output is in $XDG_RUNTIME_DIR/wpe/mpvpaper-{}.log.",
+                ipc::file_safe(monitor)
+            ),
+            WpeError::Backend { monitor: None, .. } => {
+                "The player's own output is in $XDG_RUNTIME_DIR/wpe/.".to_string()
+            }
+            WpeError::Ipc { monitor, .. } => format!(
+                "The wallpaper on {monitor} may have exited; `wpe status` lists the ones running."
+            ),
+        }
+    }
+
+    /// The kind tagged on `err` or anything it wraps.
+    pub fn find<'a>(err: &'a (dyn Error + 'static)) -> Option<&'a WpeError> {
+        let mut current = Some(err);
+        while let Some(err) = current {
+            if let Some(found) = err.downcast_ref::<WpeError>() {
+                return Some(found);
+            }
+            current = err.source();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn with_source_keeps_the_kind_and_the_error() {
+        let err = WpeError::config(
+            Some(PathBuf::from("config.toml")),
+            "Unable to read config.toml",
+        )
+        .with_source(io::Error::from(io::ErrorKind::NotFound));
+        assert!(err.to_string().starts_with("Unable to read config.toml: "));
+        match &err {
+            WpeError::Config {
+                path: Some(path),
+                source: Some(source),
+                ..
+            } => {
+                assert_eq!(path, &PathBuf::from("config.toml"));
+                let io = source.downcast_ref::<io::Error>().expect("an io::Error");
+                assert_eq!(io.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("expected a config error, got {other:?}"),
+        }
+        assert!(err.source().is_some_and(|source| source.is::<io::Error>()));
+    }
+
+    #[test]
+    fn parse_errors_are_kept_as_the_source() {
+        let parse = toml::from_str::<toml::Table>("monitor = ").unwrap_err();
+        let err = WpeError::config(None, "Failed to parse config.toml").with_source(parse);
+        assert!(matches!(
+            &err,
+            WpeError::Config { source: Some(source), .. } if source.is::<toml::de::Error>()
+        ));
+    }
+
+    #[test]
+    fn constructors_pick_the_variant() {
+        assert!(matches!(
+            WpeError::wayland("no compositor"),
+            WpeError::Wayland { source: None, .. }
+        ));
+        assert!(matches!(
+            WpeError::backend(Some("DP-1"), "exited"),
+            WpeError::Backend { monitor: Some(monitor), missing: false, .. } if monitor == "DP-1"
+        ));
+        assert!(matches!(
+            WpeError::backend_missing(None, "mpvpaper: not found"),
+            WpeError::Backend { missing: true, .. }
+        ));
+        assert!(matches!(
+            WpeError::ipc("HDMI-A-1", "closed").with_detail("again"),
+            WpeError::Ipc { monitor, message, .. } if monitor == "HDMI-A-1" && message == "closed: again"
+        ));
+    }
+
+    #[test]
+    fn find_looks_through_boxes() {
+        let boxed: Box<dyn Error> = WpeError::wayland("no compositor").into();
+        assert!(matches!(
+            WpeError::find(boxed.as_ref()),
+            Some(WpeError::Wayland { .. })
+        ));
+        let plain: Box<dyn Error> = "something else".into();
+        assert!(WpeError::find(plain.as_ref()).is_none());
+    }
+}
@@ -0,0 +1,123 @@
+//! Last-known-good frames for sources that go missing.
+//!
+//! Once a wallpaper is playing, a screenshot of it is kept per output in
+//! `$XDG_CACHE_HOME/wpe/last-good/`. When an entry's path lives on a network share
+//! or external drive that is not mounted at launch, that frame is shown instead of
+//! failing, and the GUI swaps back to the real source once the path reappears.
+//!
+//! An entry can also list `fallbacks`: further paths or `#RRGGBB` colors tried in
+//! order when its source does not resolve or its player fails to come up, so a
+//! broken path leaves a plainer wallpaper rather than a blank output.
+
+use std::{
+    borrow::Cow,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{Rgb, RgbImage};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{config::HexColor, ipc, state};
+
+/// Side of the square image a solid color fallback is drawn from.
+const SOLID_SIZE: u32 = 16;
+
+/// One step of an entry's `fallbacks`: `"#RRGGBB"` for a solid color, anything
+/// else for a path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FallbackSource {
+    Path(PathBuf),
+    Color(HexColor),
+}
+
+impl TryFrom<String> for FallbackSource {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        if text.starts_with('#') {
+            HexColor::parse(&text)
+                .map(FallbackSource::Color)
+                .ok_or_else(|| format!("{text} is not a #RRGGBB color"))
+        } else {
+            Ok(FallbackSource::Path(PathBuf::from(text)))
+        }
+    }
+}
+
+impl From<FallbackSource> for String {
+    fn from(source: FallbackSource) -> Self {
+        match source {
+            FallbackSource::Path(path) => path.to_string_lossy().into_owned(),
+            FallbackSource::Color(color) => color.to_string(),
+        }
+    }
+}
+
+impl JsonSchema for FallbackSource {
+    fn schema_name() -> Cow<'static, str> {
+        "FallbackSource".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A path to try, or a \"#RRGGBB\" solid color."
+        })
+    }
+}
+
+/// An image filled with `color`, written to the cache the first time it is asked for.
+pub fn solid_color(color: HexColor) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = state::cache_dir()?.join("solid");
+    fs::create_dir_all(&dir)?;
+    let [red, green, blue] = color.0;
+    let path = dir.join(format!("{red:02x}{green:02x}{blue:02x}.png"));
+    if !path.is_file() {
+        RgbImage::from_pixel(SOLID_SIZE, SOLID_SIZE, Rgb(color.0)).save(&path)?;
+    }
+    Ok(path)
+}
+
+/// Directory holding the cached frames, created if needed.
+pub fn cache_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let dir = state::cache_dir()?.join("last-good");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn frame_path(monitor: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(cache_dir()?.join(format!("{}.png", ipc::file_safe(monitor))))
+}
+
+/// The cached frame for `monitor`, if one was ever captured.
+pub fn cached_frame(monitor: &str) -> Option<PathBuf> {
+    frame_path(monitor).ok().filter(|path| path.is_file())
+}
+
+/// Whether `path` is missing but `monitor` has a cached frame to stand in for it.
+pub fn can_stand_in(monitor: Option<&str>, path: &Path) -> bool {
+    !path.exists() && monitor.and_then(cached_frame).is_some()
+}
+
+/// Save the frame currently shown on `monitor` as its last-known-good image.
+pub fn capture(monitor: &str) -> Result<(), Box<dyn Error>> {
+    let target = frame_path(monitor)?;
+    // mpv picks the image format from the extension, so keep `.png` on the temp file.
+    let partial = target.with_extension("partial.png");
+    ipc::command(
+        monitor,
+        &[
+            json!("screenshot-to-file"),
+            json!(partial.to_string_lossy()),
+            json!("video"),
+        ],
+    )?;
+    // Rename so a half-written screenshot never replaces a good one.
+    fs::rename(&partial, &target)?;
+    Ok(())
+}
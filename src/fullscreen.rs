@@ -0,0 +1,308 @@
+//! Pause wallpapers while a fullscreen window covers their output.
+//!
+//! Windows are followed through `wlr-foreign-toplevel-management`, which wlroots
+//! compositors (Sway, Hyprland, river, labwc, ...) and KDE provide. Each toplevel
+//! reports its states and the outputs it is on; an output counts as covered while a
+//! fullscreen window (or, optionally, a maximized one) that is not minimized is on it.
+//! The player on a covered output is paused over its mpv IPC socket and resumed once
+//! the output is uncovered. mpvpaper's own `--auto-pause` only helps on compositors
+//! that report the wallpaper surface as hidden, which most do not.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    thread,
+};
+
+use futures::channel::mpsc::UnboundedSender;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use smithay_client_toolkit::{
+    output::{OutputHandler, OutputState},
+    reexports::client::{
+        Connection, Dispatch, Proxy, QueueHandle, event_created_child,
+        globals::registry_queue_init, protocol::wl_output::WlOutput,
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+};
+use tracing::{debug, info, warn};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+use crate::{ipc, monitors};
+
+/// `[fullscreen]` table: pause wallpapers hidden behind fullscreen windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct FullscreenSettings {
+    pub pause: bool,
+    /// Maximized windows cover the output too (bars and gaps aside).
+    pub maximized: bool,
+}
+
+impl Default for FullscreenSettings {
+    fn default() -> Self {
+        Self {
+            pause: true,
+            maximized: false,
+        }
+    }
+}
+
+impl FullscreenSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What the compositor last said about one window.
+#[derive(Debug, Default, Clone)]
+struct Toplevel {
+    outputs: Vec<WlOutput>,
+    fullscreen: bool,
+    maximized: bool,
+    minimized: bool,
+}
+
+/// Connection state for the toplevel watcher.
+struct WatchApp {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    /// Toplevels by protocol object id; updated on `done`.
+    toplevels: HashMap<u32, Toplevel>,
+    /// Changes received since the last `done` of each toplevel.
+    pending: HashMap<u32, Toplevel>,
+    finished: bool,
+}
+
+impl WatchApp {
+    /// Names of the outputs covered by a window, with `maximized` deciding whether
+    /// maximized windows count.
+    fn covered(&self, maximized: bool) -> BTreeSet<String> {
+        self.toplevels
+            .values()
+            .filter(|toplevel| !toplevel.minimized)
+            .filter(|toplevel| toplevel.fullscreen || (maximized && toplevel.maximized))
+            .flat_map(|toplevel| &toplevel.outputs)
+            .filter_map(|output| self.output_state.info(output)?.name)
+            .collect()
+    }
+}
+
+impl OutputHandler for WatchApp {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {}
+
+    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: WlOutput) {}
+
+    fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
+        for toplevel in self.toplevels.values_mut() {
+            toplevel.outputs.retain(|known| *known != output);
+        }
+    }
+}
+
+smithay_client_toolkit::delegate_registry!(WatchApp);
+smithay_client_toolkit::delegate_output!(WatchApp);
+
+impl ProvidesRegistryState for WatchApp {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    smithay_client_toolkit::registry_handlers!(OutputState);
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WatchApp {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Event::Finished = event {
+            state.finished = true;
+        }
+    }
+
+    event_created_child!(WatchApp, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ())
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for WatchApp {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::Event;
+
+        let id = handle.id().protocol_id();
+        if let Event::Closed = event {
+            state.toplevels.remove(&id);
+            state.pending.remove(&id);
+            handle.destroy();
+            return;
+        }
+        if let Event::Done = event {
+            if let Some(pending) = state.pending.remove(&id) {
+                state.toplevels.insert(id, pending);
+            }
+            return;
+        }
+
+        let current = state.toplevels.get(&id).cloned().unwrap_or_default();
+        let pending = state.pending.entry(id).or_insert(current);
+        match event {
+            Event::OutputEnter { output } if !pending.outputs.contains(&output) => {
+                pending.outputs.push(output);
+            }
+            Event::OutputLeave { output } => pending.outputs.retain(|known| *known != output),
+            Event::State { state: raw } => {
+                let states: Vec<u32> = raw
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect();
+                let has =
+                    |flag: zwlr_foreign_toplevel_handle_v1::State| states.contains(&(flag as u32));
+                pending.maximized = has(zwlr_foreign_toplevel_handle_v1::State::Maximized);
+                pending.minimized = has(zwlr_foreign_toplevel_handle_v1::State::Minimized);
+                pending.fullscreen = has(zwlr_foreign_toplevel_handle_v1::State::Fullscreen);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Follow windows and send the set of covered outputs whenever it changes.
+///
+/// Returns when the receiver is gone or the compositor does not offer the protocol;
+/// a lost connection is retried with the same backoff as the output watcher.
+pub fn watch(settings: FullscreenSettings, tx: UnboundedSender<BTreeSet<String>>) {
+    let mut attempt = 0;
+    loop {
+        match watch_session(settings, &tx, &mut attempt) {
+            Ok(()) => return,
+            Err(err) => {
+                warn!(
+                    "Lost the Wayland connection while watching windows: {}",
+                    err
+                );
+                // Nothing can be seen as covered until the watcher is back.
+                if tx.unbounded_send(BTreeSet::new()).is_err() {
+                    return;
+                }
+                thread::sleep(monitors::reconnect_delay(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn watch_session(
+    settings: FullscreenSettings,
+    tx: &UnboundedSender<BTreeSet<String>>,
+    attempt: &mut u32,
+) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init::<WatchApp>(&conn)?;
+    let qh = event_queue.handle();
+    let mut app = WatchApp {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+        toplevels: HashMap::new(),
+        pending: HashMap::new(),
+        finished: false,
+    };
+    if globals
+        .bind::<ZwlrForeignToplevelManagerV1, _, _>(&qh, 1..=3, ())
+        .is_err()
+    {
+        info!(
+            "The compositor does not list windows; wallpapers under fullscreen windows keep playing."
+        );
+        return Ok(());
+    }
+    *attempt = 0;
+
+    let mut last = BTreeSet::new();
+    loop {
+        event_queue.blocking_dispatch(&mut app)?;
+        if app.finished {
+            return Err("the compositor stopped listing windows".into());
+        }
+        let covered = app.covered(settings.maximized);
+        if covered != last {
+            if tx.unbounded_send(covered.clone()).is_err() {
+                return Ok(());
+            }
+            last = covered;
+        }
+    }
+}
+
+/// Pauses players while their output is held (covered by a fullscreen window, or on
+/// battery with `on_battery = "pause"`) and resumes them afterwards, leaving alone
+/// players it did not pause itself.
+#[derive(Debug, Default)]
+pub struct Pauser {
+    /// Held players by output: the pid seen, and whether this pauser paused it
+    /// (`false` when it was already paused, e.g. by `wpe pause`).
+    paused: BTreeMap<String, (u32, bool)>,
+}
+
+impl Pauser {
+    /// Pause the `running` players (output and pid) on `held` outputs and resume the
+    /// rest. Call again after players restart; a new player starts unpaused.
+    pub fn update<'a>(
+        &mut self,
+        held: &BTreeSet<String>,
+        running: impl IntoIterator<Item = (&'a str, u32)>,
+    ) {
+        let running: BTreeMap<&str, u32> = running.into_iter().collect();
+        // A restarted player starts unpaused, and a stopped one needs nothing.
+        self.paused
+            .retain(|monitor, (pid, _)| running.get(monitor.as_str()) == Some(pid));
+
+        for (monitor, pid) in running {
+            let covered = held.contains(monitor);
+            if covered == self.paused.contains_key(monitor) {
+                continue;
+            }
+            if !covered && self.paused.get(monitor).is_some_and(|(_, ours)| !ours) {
+                self.paused.remove(monitor);
+                continue;
+            }
+            if covered
+                && ipc::get_property(monitor, "pause").is_ok_and(|paused| paused == json!(true))
+            {
+                self.paused.insert(monitor.to_string(), (pid, false));
+                continue;
+            }
+            match ipc::set_property(monitor, "pause", json!(covered)) {
+                Ok(()) if covered => {
+                    info!("Pausing the wallpaper on {}", monitor);
+                    self.paused.insert(monitor.to_string(), (pid, true));
+                }
+                Ok(()) => {
+                    info!("Resuming the wallpaper on {}", monitor);
+                    self.paused.remove(monitor);
+                }
+                // Not listening yet (still launching); the next sync tries again.
+                Err(err) => debug!("Could not pause or resume {}: {}", monitor, err),
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Tile several videos or images on one output.
+//!
+//! The first source is the main file and the others are external files (see
+//! `compose`); the graph scales and crops every tile to its cell and stacks them.
+
+use std::fmt::Write as _;
+
+use crate::config::{GridLayout, MediaKind};
+
+/// A grid entry with its sources resolved, ready to launch.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    /// Tiles in order: left to right, top to bottom.
+    pub tiles: Vec<MediaKind>,
+    pub layout: GridLayout,
+}
+
+impl Grid {
+    /// Width and height of each tile on an output of `size`.
+    fn tile_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let count = self.tiles.len().max(1) as u32;
+        // Even sizes keep chroma-subsampled formats happy.
+        let even = |value: u32| (value / 2 * 2).max(2);
+        match self.layout {
+            GridLayout::Row => (even(width / count), even(height)),
+            GridLayout::Column => (even(width), even(height / count)),
+            GridLayout::Square => (even(width / 2), even(height / 2)),
+        }
+    }
+
+    /// Graph stacking video tracks 1..=tiles into `[output]`.
+    pub fn filter_graph(&self, size: (u32, u32), output: &str) -> String {
+        let (tile_width, tile_height) = self.tile_size(size);
+        let mut graph = String::new();
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let track = index + 1;
+            // An image is a single frame; repeat it so the stack never runs dry.
+            let repeat = match tile {
+                MediaKind::Image(_) => "loop=loop=-1:size=1,",
+                _ => "",
+            };
+            let _ = write!(
+                graph,
+                "[vid{track}]{repeat}scale={tile_width}:{tile_height}:\
+                 force_original_aspect_ratio=increase,crop={tile_width}:{tile_height},\
+                 setsar=1[t{track}];"
+            );
+        }
+        for track in 1..=self.tiles.len() {
+            let _ = write!(graph, "[t{track}]");
+        }
+        let count = self.tiles.len();
+        match self.layout {
+            GridLayout::Row => {
+                let _ = write!(graph, "hstack=inputs={count}[{output}]");
+            }
+            GridLayout::Column => {
+                let _ = write!(graph, "vstack=inputs={count}[{output}]");
+            }
+            GridLayout::Square => {
+                let _ = write!(
+                    graph,
+                    "xstack=inputs=4:layout=0_0|w0_0|0_h0|w0_h0[{output}]"
+                );
+            }
+        }
+        graph
+    }
+}
@@ -1,49 +1,123 @@
 use std::{
-    fs,
-    process::{Command, Stdio},
-    time::Duration,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use iced::{
-    Color, Element, Length, Subscription, Task, Theme, alignment, application, time,
-    widget::{Column, Row, button, container, scrollable, text},
+    Color, Element, Event, Length, Size, Subscription, Task, Theme, alignment, event, keyboard,
+    time,
+    widget::{
+        Column, Row, button, container, image, mouse_area, pick_list, scrollable, text, text_input,
+    },
     window,
 };
 
+use chrono::NaiveDate;
+use tracing::{info, warn};
+
 use crate::{
-    config::{self, WallpaperProfileEntry},
+    audio::{self, AudioSink, Ducker},
+    backend,
+    battery::BatteryPolicy,
+    colorscheme::{self, ColorSchemeSettings},
+    config::{
+        self, AudioSettings, ConfigStamp, GuiSettings, Preferences, Settings, ThemeOverride,
+        WallpaperProfileEntry,
+    },
+    control::{self, Action},
+    crash, daemon, fallback,
+    fullscreen::{FullscreenSettings, Pauser},
+    history,
+    idle::{self, IdleEvent, IdleSettings},
+    launcher::{Instance, LaunchState, StandIn},
     monitors::Monitor,
+    mounts,
+    mpvpaper::LaunchStage,
+    palette::ThemeColors,
+    queue, seasons,
+    sources::{self, Tagged, WallpaperSource},
+    state::{self, State, WindowGeometry},
+    sync::{self, SyncCollection},
+    tags,
+    thermal::{self, ThermalSettings, Throttle},
+    tray::TrayEvent,
+    tweaks,
+    usage::{self, Tracker},
+    watchdog::{Verdict, Watchdog},
 };
 
 use super::{
+    apply,
+    commands::{self, CommandPalette, PaletteCommand},
+    crossfade::Curtain,
+    drops::{Dropped, Drops},
     editor::{MonitorEditor, MonitorTab},
+    gallery::{Gallery, PREVIEWS_IN_FLIGHT},
+    gestures::{Gesture, Gestures},
     helpers::{
-        PathSelection, detect_theme_preference, load_entries, load_monitors, monitor_events,
-        select_wallpaper_source, spawn_wallpaper,
+        PathSelection, battery_events, cache_frame, cover_outputs, covered_outputs, daemon_request,
+        detect_theme_preference, idle_events, load_audio_sinks, load_entries, load_monitors,
+        load_thumbnail, monitor_events, mount_sources, probe_dependencies, probe_sources,
+        pull_collection, scan_folder, scan_loop_points, select_wallpaper_source, tray_events,
+        update_ducking, wait_for_stage, wallpaper_colors,
     },
     message::Message,
-    overlay,
-    style::{load_folder_icon, purple_button_style},
+    overlay::{self, Overlay},
+    settings::SettingsPage,
+    style::{
+        DEFAULT_ACCENT, accent_button_style, build_theme, load_folder_icon, muted_button_style,
+        output_button_style,
+    },
     types::ThemePreference,
 };
 
+/// How often `[gui] from_wallpaper` looks at the wallpaper again, for slideshows.
+const WALLPAPER_COLORS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often `[thermal]` reads the temperature sensors.
+const THERMAL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the shuffle position of random folders is saved, in case of a power cut.
+const QUEUE_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long Preview plays the unsaved settings before putting the old wallpapers back.
+const PREVIEW_DURATION: Duration = Duration::from_secs(10);
+
+/// How long to wait before retrying a `[[sync]]` pull that failed.
+const SYNC_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// How much larger `[gui] touch` draws the window, so every control is easier to hit.
+const TOUCH_SCALE: f64 = 1.3;
+
 pub fn launch() -> Result<(), Box<dyn std::error::Error>> {
-    overlay::spawn_overlay();
-    application("WallPaper Engine", GuiApp::update, GuiApp::view)
-        .window(window::Settings {
-            platform_specific: window::settings::PlatformSpecific {
-                application_id: "io.melechtna.wpe".into(),
-                ..Default::default()
-            },
-            ..window::Settings::default()
-        })
+    // A daemon rather than an application, so the GUI can outlive its window in the tray.
+    iced::daemon("WallPaper Engine", GuiApp::update, GuiApp::view)
         .subscription(|state| state.subscription())
-        .theme(|state| state.theme())
-        .window_size((860.0, 620.0))
+        .theme(|state, _window| state.theme())
+        .scale_factor(|state, _window| state.scale_factor())
         .run_with(GuiApp::init)
         .map_err(|err| err.into())
 }
 
+/// Open the settings window at the size it last had.
+fn open_window(state: &State) -> (window::Id, Task<Message>) {
+    let size = state
+        .window
+        .map(|geometry| Size::new(geometry.width, geometry.height))
+        .unwrap_or(Size::new(860.0, 620.0));
+    let (id, open) = window::open(window::Settings {
+        size,
+        platform_specific: window::settings::PlatformSpecific {
+            application_id: "io.melechtna.wpe".into(),
+            ..Default::default()
+        },
+        ..window::Settings::default()
+    });
+    (id, open.discard())
+}
+
 /// Aggregated GUI state and child-process tracking.
 pub(crate) struct GuiApp {
     monitors: Vec<Monitor>,
@@ -51,19 +125,133 @@ pub(crate) struct GuiApp {
     tabs: Vec<MonitorTab>,
     active_tab: usize,
     status: Option<StatusBanner>,
-    wallpaper_running: bool,
+    dependency_warnings: Vec<String>,
+    /// Crash reports written since the last launch.
+    crash_reports: Vec<PathBuf>,
+    running: HashMap<String, Instance>,
+    /// Frames of replaced wallpapers held up until their successors play.
+    curtains: HashMap<String, Curtain>,
+    resume_after_reconnect: bool,
     system_theme: ThemePreference,
+    /// `[gui]` colors, with unsaved picker changes already applied.
+    gui_settings: GuiSettings,
+    settings_page: SettingsPage,
+    /// `[settings]`, as edited on the Settings page.
+    preferences: Preferences,
+    /// GUI colors taken from the selected monitor's wallpaper.
+    wallpaper_colors: Option<ThemeColors>,
+    /// When the wallpaper colors were last requested; `None` asks for them on the next tick.
+    colors_requested: Option<Instant>,
     picker_icon: Option<iced::widget::svg::Handle>,
+    /// Previews of the tabs' sources; `None` while one is made or when there is none.
+    thumbnails: HashMap<PathBuf, Option<image::Handle>>,
+    gallery: Option<Gallery>,
+    /// The monitor badges, while shown; they go when the window closes.
+    overlay: Option<Overlay>,
+    palette: CommandPalette,
+    gestures: Gestures,
+    drops: Drops,
+    /// The page is scrolled all the way up, so pulling down refreshes the monitors.
+    scrolled_to_top: bool,
+    state: State,
+    /// `state` as last read from or written to disk, to tell the GUI's own changes
+    /// from those other processes make.
+    saved_state: State,
+    state_dirty: bool,
+    audio_sinks: Vec<AudioSink>,
+    audio_settings: AudioSettings,
+    /// A check for the sources of cached-frame stand-ins is running.
+    probing_sources: bool,
+    /// Taken while a ducking update runs in the background.
+    ducker: Option<Ducker>,
+    launch: Option<Launch>,
+    launch_generation: u64,
+    /// Date the seasonal sources were last evaluated for.
+    season_day: NaiveDate,
+    sync_collections: Vec<SyncCollection>,
+    /// Collections being pulled or backing off after a failure, until the given time.
+    sync_blocked: HashMap<String, Instant>,
+    /// Config file as last loaded or saved, so outside edits are never overwritten.
+    config_stamp: Option<ConfigStamp>,
+    thermal_settings: ThermalSettings,
+    throttle: Throttle,
+    thermal_checked: Option<Instant>,
+    /// Monitors whose still frame is being captured before the swap.
+    stills_pending: HashSet<String>,
+    /// Monitors whose player, started outside a Start, is being waited on.
+    confirming: HashSet<String>,
+    queue_saved: Instant,
+    usage: Tracker,
+    colorscheme: ColorSchemeSettings,
+    /// Crashed players waiting to be restarted.
+    watchdog: Watchdog,
+    preview: Option<Preview>,
+    /// Saved profile names, for the switcher.
+    profiles: Vec<String>,
+    /// Name typed for Save profile.
+    profile_name: String,
+    fullscreen_settings: FullscreenSettings,
+    /// Outputs a fullscreen window covers.
+    covered_windows: BTreeSet<String>,
+    battery_policy: BatteryPolicy,
+    on_battery: bool,
+    /// `[motion]` or the desktop asked for still wallpapers and no fades.
+    reduced_motion: bool,
+    idle_settings: IdleSettings,
+    session_idle: bool,
+    /// Outputs the compositor powered down.
+    screens_off: BTreeSet<String>,
+    /// Pauses wallpapers under fullscreen windows, on battery, or while idle.
+    pauser: Pauser,
+    /// `locked = true` or `--kiosk`: only Start and Stop are offered.
+    locked: bool,
+    /// The settings window, unless it was closed into the tray.
+    window: Option<window::Id>,
+    /// Whether the tray took the icon, so closing the window hides it instead of quitting.
+    in_tray: bool,
+}
+
+/// Unsaved settings on screen for a while, and what to put back afterwards.
+struct Preview {
+    until: Instant,
+    /// Entries that were playing before the preview.
+    previous: Vec<WallpaperProfileEntry>,
+}
+
+/// A Start in progress, launching the queued entries one at a time.
+struct Launch {
+    generation: u64,
+    pending: VecDeque<WallpaperProfileEntry>,
+    /// Monitor whose wallpaper has been spawned but is not playing yet.
+    current: Option<String>,
+    total: usize,
+    unchanged: usize,
+    started: usize,
+    failures: Vec<String>,
+    /// Monitors showing a cached frame because their source is missing.
+    cached: Vec<String>,
+    settings: Settings,
 }
 
 impl GuiApp {
     pub fn init() -> (Self, Task<Message>) {
-        let commands = vec![
+        let mut commands = vec![
             Task::perform(load_monitors(), Message::MonitorsLoaded),
             Task::perform(load_entries(), Message::EntriesLoaded),
             Task::perform(detect_theme_preference(), Message::ThemeDetected),
+            Task::perform(probe_dependencies(), Message::DependenciesProbed),
+            Task::perform(load_audio_sinks(), Message::AudioSinksLoaded),
         ];
 
+        let settings = config::load_settings().unwrap_or_default();
+        let mut state = state::load_state();
+        let saved_state = state.clone();
+        let crash_reports = crash::unseen_reports(&state);
+        crash::mark_seen(&mut state, &crash_reports);
+        let profile_name = state.active_profile.clone().unwrap_or_default();
+        let (window, open) = open_window(&state);
+        commands.push(open);
+
         (
             Self {
                 monitors: Vec::new(),
@@ -71,9 +259,65 @@ impl GuiApp {
                 tabs: Vec::new(),
                 active_tab: 0,
                 status: Some(StatusBanner::info("Gathering monitors...")),
-                wallpaper_running: false,
+                dependency_warnings: Vec::new(),
+                state_dirty: !crash_reports.is_empty(),
+                crash_reports,
+                running: HashMap::new(),
+                curtains: HashMap::new(),
+                resume_after_reconnect: false,
                 system_theme: ThemePreference::Dark,
+                settings_page: SettingsPage::new(&settings.preferences),
+                preferences: settings.preferences.clone(),
+                gui_settings: settings.gui,
+                wallpaper_colors: None,
+                colors_requested: None,
                 picker_icon: load_folder_icon(),
+                thumbnails: HashMap::new(),
+                gallery: None,
+                overlay: settings
+                    .preferences
+                    .overlay
+                    .then(|| overlay::spawn_overlay(&settings.gui, settings.preferences.accent)),
+                palette: CommandPalette::default(),
+                gestures: Gestures::default(),
+                drops: Drops::default(),
+                scrolled_to_top: true,
+                state,
+                saved_state,
+                audio_sinks: Vec::new(),
+                audio_settings: AudioSettings::default(),
+                probing_sources: false,
+                ducker: Some(Ducker::default()),
+                launch: None,
+                launch_generation: 0,
+                season_day: seasons::today(),
+                sync_collections: settings.sync,
+                sync_blocked: HashMap::new(),
+                config_stamp: config::config_stamp(),
+                thermal_settings: settings.thermal,
+                fullscreen_settings: settings.fullscreen,
+                covered_windows: BTreeSet::new(),
+                battery_policy: settings.power.on_battery,
+                on_battery: false,
+                reduced_motion: settings.motion.reduced(),
+                idle_settings: settings.idle,
+                session_idle: false,
+                screens_off: BTreeSet::new(),
+                pauser: Pauser::default(),
+                throttle: Throttle::default(),
+                thermal_checked: None,
+                stills_pending: HashSet::new(),
+                confirming: HashSet::new(),
+                queue_saved: Instant::now(),
+                usage: Tracker::default(),
+                colorscheme: settings.colorscheme.clone(),
+                watchdog: Watchdog::default(),
+                preview: None,
+                profiles: config::list_profiles().unwrap_or_default(),
+                profile_name,
+                locked: settings.locked,
+                window: Some(window),
+                in_tray: false,
             },
             Task::batch(commands),
         )
@@ -110,21 +354,109 @@ impl GuiApp {
             Message::ThemeDetected(theme) => {
                 self.system_theme = theme;
             }
+            Message::DependenciesProbed(warnings) => {
+                self.dependency_warnings = warnings;
+            }
+            Message::AudioSinksLoaded(sinks) => {
+                self.audio_sinks = sinks;
+            }
             Message::MonitorsUpdated(monitors) => {
+                // The watcher's first snapshot repeats what startup already listed.
+                if monitors == self.monitors && !self.resume_after_reconnect {
+                    return Task::none();
+                }
                 self.reconcile_monitors(monitors);
-                if self.wallpaper_running {
+                if self.resume_after_reconnect {
+                    // The old mpvpaper instances died with the previous compositor session.
+                    self.resume_after_reconnect = false;
+                    self.running.clear();
+                    return self.start_wallpaper();
+                } else if self.wallpaper_running() {
                     let _ = self.stop_wallpaper();
-                    let _ = self.start_wallpaper();
+                    return self.start_wallpaper();
                 }
             }
+            Message::CompositorLost(err) => {
+                self.resume_after_reconnect |= self.wallpaper_running();
+                self.status = Some(StatusBanner::error(format!(
+                    "Lost the compositor connection ({}); reconnecting...",
+                    err
+                )));
+            }
             Message::SelectTab(index) => {
                 if index < self.tabs.len() {
                     self.active_tab = index;
+                    self.colors_requested = None;
+                }
+            }
+            Message::TabHovered(index, inside) => self.drops.hover_tab(index, inside),
+            Message::FileDragged(_) if self.locked => {}
+            Message::FileDragged(event) => {
+                if let Some(dropped) = self.drops.update(event, self.active_tab) {
+                    return self.drop_source(dropped);
+                }
+            }
+            Message::Touched(event) => match self.gestures.update(event, self.scrolled_to_top) {
+                Some(Gesture::NextTab) if self.active_tab + 1 < self.tabs.len() => {
+                    return self.update(Message::SelectTab(self.active_tab + 1));
+                }
+                Some(Gesture::PreviousTab) if self.active_tab > 0 => {
+                    return self.update(Message::SelectTab(self.active_tab - 1));
+                }
+                Some(Gesture::RefreshMonitors) => return self.update(Message::RefreshMonitors),
+                _ => {}
+            },
+            Message::RefreshMonitors => {
+                self.status = Some(StatusBanner::info("Looking for monitors..."));
+                return Task::perform(load_monitors(), Message::MonitorsLoaded);
+            }
+            Message::PlayerAction(action, monitor) => {
+                self.status = Some(match action.run(&monitor) {
+                    Ok(()) => StatusBanner::success(format!("{} on {}.", action.done(), monitor)),
+                    Err(err) => StatusBanner::error(format!("{}: {}", monitor, err)),
+                });
+            }
+            Message::BadgesToggled => {
+                // Dropping the handle takes the badges down.
+                self.overlay = match self.overlay.take() {
+                    Some(_) => None,
+                    None => Some(overlay::spawn_overlay(
+                        &self.gui_settings,
+                        self.preferences.accent,
+                    )),
+                };
+            }
+            Message::PaletteToggled => {
+                self.palette.toggle();
+                if self.palette.open {
+                    return text_input::focus(commands::input_id());
+                }
+            }
+            Message::PaletteClosed => self.palette.close(),
+            Message::PaletteQueryChanged(query) => self.palette.set_query(query),
+            Message::PaletteMoved(step) => {
+                if self.palette.open {
+                    let commands = self.palette_commands();
+                    self.palette.move_selection(step, &commands);
+                }
+            }
+            Message::PaletteSubmitted => {
+                if let Some(message) = self.palette.chosen(&self.palette_commands()) {
+                    self.palette.close();
+                    return self.update(message);
                 }
             }
+            Message::PaletteChosen(message) => {
+                self.palette.close();
+                return self.update(*message);
+            }
+            Message::Scrolled(offset) => {
+                self.scrolled_to_top = offset <= 0.0;
+            }
             Message::PathChanged(index, value) => {
                 if let Some(tab) = self.tabs.get_mut(index) {
                     tab.editor.set_path_text(value);
+                    tab.editor.sync_path_tags(&self.state);
                 }
             }
             Message::BrowsePressed(index, kind) => {
@@ -140,8 +472,124 @@ impl GuiApp {
                 Ok(Some(path)) => {
                     if let Some(tab) = self.tabs.get_mut(index) {
                         tab.editor.set_path_buf(path);
+                        tab.editor.sync_path_tags(&self.state);
                         self.status = Some(StatusBanner::success("Updated source path."));
                     }
+                    return self.request_thumbnails();
+                }
+                Ok(None) => {
+                    self.status = Some(StatusBanner::info("Selection canceled."));
+                }
+                Err(err) => {
+                    self.status = Some(StatusBanner::error(err));
+                }
+            },
+            Message::ThumbnailLoaded(source, result) => {
+                let preview = match result {
+                    Ok(thumbnail) => Some(image::Handle::from_path(thumbnail)),
+                    Err(err) => {
+                        warn!("No preview of {}: {}", source.display(), err);
+                        None
+                    }
+                };
+                let in_gallery = self
+                    .gallery
+                    .as_ref()
+                    .is_some_and(|gallery| gallery.contains(&source));
+                self.thumbnails.insert(source, preview);
+                if in_gallery {
+                    return self.request_gallery_previews(1);
+                }
+            }
+            Message::GalleryOpened(index) => {
+                let Some((folder, chosen)) = self
+                    .tabs
+                    .get(index)
+                    .and_then(|tab| tab.editor.gallery_source())
+                else {
+                    return Task::none();
+                };
+                self.gallery = Some(Gallery::new(index, folder.clone(), chosen));
+                return Task::perform(scan_folder(folder), |(folder, result)| {
+                    Message::GalleryScanned(folder, result)
+                });
+            }
+            Message::GalleryScanned(folder, result) => {
+                if let Some(gallery) = self
+                    .gallery
+                    .as_mut()
+                    .filter(|gallery| gallery.folder == folder)
+                {
+                    gallery.scanned(result);
+                    return self.request_gallery_previews(PREVIEWS_IN_FLIGHT);
+                }
+            }
+            Message::GalleryToggled(index) => {
+                if let Some(gallery) = &mut self.gallery {
+                    gallery.toggle(index);
+                }
+            }
+            Message::GalleryMoved(index, step) => {
+                if let Some(gallery) = &mut self.gallery {
+                    gallery.move_item(index, step);
+                }
+            }
+            Message::GalleryAllToggled(selected) => {
+                if let Some(gallery) = &mut self.gallery {
+                    gallery.select_all(selected);
+                }
+            }
+            Message::GalleryApplied => {
+                let Some(gallery) = self.gallery.take() else {
+                    return Task::none();
+                };
+                let selection = gallery.selection();
+                if selection.is_empty() {
+                    self.status = Some(StatusBanner::error("Select at least one file to play."));
+                    self.gallery = Some(gallery);
+                    return Task::none();
+                }
+                if let Some(tab) = self.tabs.get_mut(gallery.tab) {
+                    tab.editor.set_sources(if gallery.is_whole_folder() {
+                        vec![gallery.folder.clone()]
+                    } else {
+                        selection
+                    });
+                    tab.editor.sync_path_tags(&self.state);
+                    self.status = Some(StatusBanner::success(
+                        "Updated the sources from the gallery.",
+                    ));
+                }
+                return self.request_thumbnails();
+            }
+            Message::GalleryClosed => {
+                self.gallery = None;
+            }
+            Message::ExtraSourceChanged(index, slot, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_extra_path_text(slot, value);
+                }
+            }
+            Message::ExtraSourceRemoved(index, slot) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.remove_extra_path(slot);
+                }
+            }
+            Message::AddSourcePressed(index, kind) => {
+                self.status = Some(StatusBanner::info(match kind {
+                    PathSelection::File => "Select an image/video to add…",
+                    PathSelection::Folder => "Select a folder to add…",
+                }));
+                return Task::perform(select_wallpaper_source(kind), move |result| {
+                    Message::ExtraSourcePicked(index, result)
+                });
+            }
+            Message::ExtraSourcePicked(index, result) => match result {
+                Ok(Some(path)) => {
+                    if let Some(tab) = self.tabs.get_mut(index) {
+                        tab.editor.add_extra_path(path);
+                        self.status = Some(StatusBanner::success("Added a source."));
+                    }
                 }
                 Ok(None) => {
                     self.status = Some(StatusBanner::info("Selection canceled."));
@@ -170,64 +618,655 @@ impl GuiApp {
                     tab.editor.set_interval(value);
                 }
             }
-            Message::StartPressed => {
-                if self.wallpaper_running {
-                    if let Err(err) = self.stop_wallpaper() {
-                        self.status = Some(StatusBanner::error(err));
-                        return Task::none();
+            Message::PathTagsChanged(index, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_path_tags_text(value);
+                }
+            }
+            Message::SaveTagsPressed(index) => {
+                if let Some(tab) = self.tabs.get_mut(index)
+                    && let Some(path) = tab.editor.tag_path()
+                {
+                    tags::set_tags(&mut self.state, &path, tab.editor.path_tags());
+                    tab.editor.sync_path_tags(&self.state);
+                    self.state_dirty = true;
+                    self.flush_state();
+                    self.status = Some(StatusBanner::success(format!(
+                        "Saved tags for {}.",
+                        path.display()
+                    )));
+                }
+            }
+            Message::SourceTagsChanged(index, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_source_tags(value);
+                }
+            }
+            Message::SeasonalToggled(index, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_seasonal(value);
+                }
+            }
+            Message::MarginChanged(index, edge, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_margin_text(edge, value);
+                }
+            }
+            Message::MarginsDragged(index, margins) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_margins(margins);
+                }
+            }
+            Message::AudioToggled(index, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_audio(value);
+                }
+                if value && !self.audio_settings.allow_multiple {
+                    // Only one wallpaper plays sound unless [audio] allow_multiple is set.
+                    let mut muted = Vec::new();
+                    for (other, tab) in self.tabs.iter_mut().enumerate() {
+                        if other != index && tab.editor.audio() {
+                            tab.editor.set_audio(false);
+                            muted.push(tab.monitor.name.clone());
+                        }
+                    }
+                    if !muted.is_empty() {
+                        self.status = Some(StatusBanner::info(format!(
+                            "Turned sound off on {}; set allow_multiple under [audio] to play several at once.",
+                            muted.join(", ")
+                        )));
                     }
                 }
-                let _ = self.start_wallpaper();
+                if value {
+                    // Sinks may have changed since startup (headsets, HDMI audio).
+                    return Task::perform(load_audio_sinks(), Message::AudioSinksLoaded);
+                }
+            }
+            Message::AudioDeviceSelected(index, name) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_audio_device(name);
+                }
+            }
+            Message::VolumeChanged(index, value) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.set_volume(value);
+                }
+            }
+            Message::FindLoopPressed(index) => {
+                let Some(path) = self.tabs.get(index).and_then(|tab| tab.editor.video_path())
+                else {
+                    return Task::none();
+                };
+                self.status = Some(StatusBanner::info("Scanning the video for loop points..."));
+                return Task::perform(scan_loop_points(path), move |result| {
+                    Message::LoopFound(index, result)
+                });
+            }
+            Message::LoopFound(index, result) => match result {
+                Ok((path, points)) => {
+                    self.state.loops.insert(path, points);
+                    self.status = Some(StatusBanner::success(format!(
+                        "Looping {:.1}s – {:.1}s.",
+                        points.start, points.end
+                    )));
+                    return self.apply_loop_change(index);
+                }
+                Err(err) => {
+                    self.status = Some(StatusBanner::error(err));
+                }
+            },
+            Message::ClearLoopPressed(index) => {
+                if let Some(path) = self.tabs.get(index).and_then(|tab| tab.editor.video_path()) {
+                    self.state.loops.remove(&path);
+                    self.status = Some(StatusBanner::info("Loop points cleared."));
+                    return self.apply_loop_change(index);
+                }
+            }
+            Message::StartPressed => {
+                return self.start_wallpaper();
+            }
+            Message::SavePressed => {
+                return self.save_wallpaper();
+            }
+            Message::ApplyPressed => {
+                return self.apply_wallpaper();
+            }
+            Message::PreviewPressed => {
+                if let Err(err) = self.preview_all() {
+                    self.status = Some(StatusBanner::error(err));
+                }
+            }
+            Message::CancelLaunchPressed => {
+                self.cancel_launch();
+            }
+            Message::ProfileSelected(name) => return self.switch_profile(name),
+            Message::ProfileNameChanged(name) => self.profile_name = name,
+            Message::SaveProfilePressed => self.save_profile(),
+            Message::StageFinished(monitor, stage, result) => {
+                return self.stage_finished(monitor, stage, result);
+            }
+            Message::LaunchStageFinished(generation, monitor, stage, result) => {
+                return self.launch_stage_finished(generation, monitor, stage, result);
             }
             Message::StopPressed => {
+                self.launch = None;
+                self.preview = None;
                 if let Err(err) = self.stop_wallpaper() {
                     self.status = Some(StatusBanner::error(err));
                 }
             }
+            Message::SettingsToggled => {
+                self.settings_page.open = !self.settings_page.open;
+            }
+            Message::OverlayAtLaunchToggled(enabled) => {
+                self.preferences.overlay = enabled;
+            }
+            Message::DefaultScaleChanged(scale) => {
+                self.preferences.scale = scale;
+            }
+            Message::DefaultIntervalChanged(value) => {
+                if let Some(seconds) = self.settings_page.set_interval_text(value) {
+                    self.preferences.interval_seconds = seconds;
+                }
+            }
+            Message::ThemeOverrideChanged(theme) => {
+                self.preferences.theme = theme;
+            }
+            Message::FromWallpaperToggled(enabled) => {
+                self.gui_settings.from_wallpaper = enabled;
+                self.colors_requested = None;
+            }
+            Message::TouchModeToggled(enabled) => {
+                self.gui_settings.touch = enabled;
+            }
+            Message::WallpaperColors(result) => match result {
+                Ok(colors) => self.wallpaper_colors = Some(colors),
+                Err(err) => info!("Keeping the current GUI colors: {}", err),
+            },
+            Message::AccentTextChanged(value) => {
+                if let Some(color) = self.settings_page.appearance.set_hex_text(value) {
+                    self.preferences.accent = Some(color);
+                }
+            }
+            Message::AccentChannelChanged(channel, value) => {
+                self.preferences.accent =
+                    Some(self.settings_page.appearance.set_channel(channel, value));
+            }
+            Message::AccentPicked(color) => {
+                self.settings_page.appearance.set_color(color);
+                self.preferences.accent = Some(color);
+            }
+            Message::SettingsSaved => {
+                if !self.settings_page.interval_is_valid() {
+                    self.status = Some(StatusBanner::error(
+                        "Fix the slideshow interval before saving the settings.",
+                    ));
+                    return Task::none();
+                }
+                self.preferences.accent = Some(self.settings_page.appearance.color())
+                    .filter(|color| *color != DEFAULT_ACCENT);
+                // Keep the file's palette overrides; only the toggles are edited here.
+                let mut gui = config::load_settings()
+                    .map(|settings| settings.gui)
+                    .unwrap_or_default();
+                gui.from_wallpaper = self.gui_settings.from_wallpaper;
+                gui.touch = self.gui_settings.touch;
+                match config::save_gui_settings(&gui, &self.preferences) {
+                    Ok(()) => {
+                        self.config_stamp = config::config_stamp();
+                        self.gui_settings = gui;
+                        self.status = Some(StatusBanner::success("Saved the settings."));
+                    }
+                    Err(err) => {
+                        self.status = Some(StatusBanner::error(format!(
+                            "Failed to save the settings: {}",
+                            err
+                        )));
+                    }
+                }
+            }
+            Message::WindowClosed(id) => {
+                if self.window == Some(id) {
+                    self.window = None;
+                    if !self.in_tray {
+                        return iced::exit();
+                    }
+                    info!("Closed the window; wpe stays in the tray");
+                }
+            }
+            Message::Tray(event) => return self.tray_event(event),
+            Message::WindowResized(size) => {
+                self.state.window = Some(WindowGeometry {
+                    width: size.width,
+                    height: size.height,
+                });
+                self.state_dirty = true;
+            }
+            Message::Covered(generation, curtains) => {
+                return self.covered(generation, curtains);
+            }
+            Message::SourcesProbed(restored) => {
+                self.sources_probed(restored);
+            }
+            Message::DuckingUpdated(ducker) => {
+                self.ducker = Some(ducker);
+            }
             Message::Tick => {
+                if self
+                    .preview
+                    .as_ref()
+                    .is_some_and(|preview| Instant::now() >= preview.until)
+                {
+                    self.end_preview();
+                }
                 self.poll_wallpaper();
+                self.restart_crashed();
+                // Players restarted since the last tick start unpaused.
+                self.sync_pauses();
+                self.check_temperature();
+                self.flush_state();
+                return Task::batch([
+                    self.restore_sources(),
+                    self.update_ducking(),
+                    self.check_config_changes(),
+                    self.refresh_wallpaper_colors(),
+                    self.request_thumbnails(),
+                    self.refresh_seasons(),
+                    self.start_due_syncs(),
+                    self.sync_stills(),
+                    self.confirm_launches(),
+                    self.save_queues(),
+                    self.track_usage(),
+                ]);
+            }
+            Message::SourcesMounted(generation, missing) => {
+                if self
+                    .launch
+                    .as_ref()
+                    .is_some_and(|launch| launch.generation == generation)
+                {
+                    for path in &missing {
+                        warn!("{} did not become available.", path.display());
+                    }
+                    return self.launch_next();
+                }
+            }
+            Message::ConfigChanged(result) => match result {
+                Ok(entries) => self.merge_external_entries(entries),
+                Err(err) => {
+                    self.status = Some(StatusBanner::error(format!(
+                        "The config file changed but could not be read: {}",
+                        err
+                    )));
+                }
+            },
+            Message::ConflictResolved(index, choice) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.resolve_conflict(choice);
+                    tab.editor.sync_path_tags(&self.state);
+                }
+            }
+            Message::OverridesReset(index) => {
+                if let Some(tab) = self.tabs.get_mut(index) {
+                    tab.editor.reset_overrides();
+                }
+            }
+            Message::MirrorToggled(index, mirror) => self.set_mirror(index, mirror),
+            Message::CopySettingsTo(index, target) => {
+                let Some(tab) = self.tabs.get(index) else {
+                    return Task::none();
+                };
+                let source = tab.editor.to_entry(&tab.monitor.name);
+                let from = tab.monitor.name.clone();
+                if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.monitor.name == target) {
+                    tab.editor.copy_settings(&source);
+                    tab.editor.sync_path_tags(&self.state);
+                    self.status = Some(StatusBanner::info(format!(
+                        "Copied the settings of {from} to {target}; press Save or Start to keep them."
+                    )));
+                }
+            }
+            Message::FrameCached(monitor, result) => match result {
+                Ok(()) => self.colors_requested = None,
+                Err(err) => warn!("Could not cache a frame for {}: {}", monitor, err),
+            },
+            Message::StillCaptured(monitor, result) => {
+                self.stills_pending.remove(&monitor);
+                if let Err(err) = result {
+                    warn!("Could not capture a still for {}: {}", monitor, err);
+                }
+                if self.wants_stills() {
+                    self.show_still(&monitor);
+                }
+            }
+            Message::CoveredOutputs(covered) => {
+                self.covered_windows = covered;
+                self.sync_pauses();
+            }
+            Message::Idle(event) => {
+                match event {
+                    IdleEvent::Idle(idle) => self.session_idle = idle,
+                    IdleEvent::ScreensOff(outputs) => self.screens_off = outputs,
+                }
+                self.sync_pauses();
+            }
+            Message::PowerSourceChanged(on_battery) => {
+                self.on_battery = on_battery;
+                let message = match (on_battery, self.battery_policy) {
+                    (true, BatteryPolicy::Pause) => Some("On battery; wallpapers are paused."),
+                    (true, BatteryPolicy::StaticFrame) => Some("On battery; showing still frames."),
+                    (false, BatteryPolicy::Pause | BatteryPolicy::StaticFrame) => {
+                        Some("On AC power; resuming wallpapers.")
+                    }
+                    _ => None,
+                };
+                if let Some(message) = message {
+                    self.status = Some(StatusBanner::info(message));
+                }
+                self.sync_pauses();
+                return self.sync_stills();
             }
+            Message::DaemonReplied(result) => {
+                self.status = Some(match result {
+                    Ok(reply) => daemon_status(&reply),
+                    Err(err) => StatusBanner::error(format!("The wpe daemon failed: {}", err)),
+                });
+            }
+            Message::SyncFinished(name, result) => match result {
+                Ok(()) => {
+                    self.sync_blocked.remove(&name);
+                    if let Some(collection) = self.sync_collections.iter().find(|c| c.name == name)
+                    {
+                        sync::mark_synced(collection, &mut self.state);
+                        self.state_dirty = true;
+                    }
+                    self.status = Some(StatusBanner::success(format!("Synced {}.", name)));
+                }
+                Err(err) => {
+                    self.sync_blocked
+                        .insert(name, Instant::now() + SYNC_RETRY_DELAY);
+                    self.status = Some(StatusBanner::error(err));
+                }
+            },
         }
 
         Task::none()
     }
 
-    fn view(&self) -> Element<'_, Message> {
+    fn view(&self, _window: window::Id) -> Element<'_, Message> {
         let mut content = Column::new().spacing(16).padding(24);
 
+        if self.palette.open {
+            content = content.push(self.palette.view(&self.palette_commands()));
+        }
+
         if let Some(banner) = &self.status {
             content = content.push(self.status_banner(banner));
         }
 
+        for warning in &self.dependency_warnings {
+            content = content.push(self.status_banner(&StatusBanner::error(warning.clone())));
+        }
+
+        if let Some(report) = self.crash_reports.last() {
+            let earlier = match self.crash_reports.len() {
+                1 => String::new(),
+                count => format!(" ({} more next to it)", count - 1),
+            };
+            content = content.push(self.status_banner(&StatusBanner::error(format!(
+                "wpe crashed during an earlier run. The report is at {}{}; please attach it to a bug report.",
+                report.display(),
+                earlier
+            ))));
+        }
+
         if self.tabs.is_empty() {
             content = content.push(text("Waiting for monitors..."));
         } else {
-            content = content.push(self.tab_bar()).push(self.active_editor_view());
+            content = content.push(self.tab_bar());
+            content = match &self.gallery {
+                _ if self.locked => content.push(self.locked_view()),
+                Some(gallery) if gallery.tab == self.active_tab => {
+                    content.push(gallery.view(&self.thumbnails))
+                }
+                _ => content.push(self.active_editor_view()),
+            };
         }
 
         content = content.push(self.action_row());
+        if self.launch.is_none() && !self.locked {
+            content = content.push(self.profile_row());
+        }
+        if self.settings_page.open {
+            content = content.push(
+                self.settings_page
+                    .view(&self.gui_settings, &self.preferences),
+            );
+        }
 
-        container(scrollable(content).height(Length::Fill)).into()
+        container(
+            scrollable(content)
+                .height(Length::Fill)
+                .on_scroll(|viewport| Message::Scrolled(viewport.absolute_offset().y)),
+        )
+        .into()
     }
 
     fn theme(&self) -> Theme {
-        match self.system_theme {
-            ThemePreference::Light => Theme::Light,
-            ThemePreference::Dark => Theme::Dark,
+        let wallpaper = self
+            .wallpaper_colors
+            .as_ref()
+            .filter(|_| self.gui_settings.from_wallpaper);
+        let preference = match self.preferences.theme {
+            ThemeOverride::Auto => self.system_theme,
+            ThemeOverride::Light => ThemePreference::Light,
+            ThemeOverride::Dark => ThemePreference::Dark,
+        };
+        build_theme(
+            preference,
+            &self.gui_settings,
+            self.preferences.accent,
+            wallpaper,
+        )
+    }
+
+    /// Everything the command palette offers right now.
+    fn palette_commands(&self) -> Vec<PaletteCommand> {
+        if self.locked {
+            let mut commands = vec![
+                PaletteCommand::new("Start wallpapers", Message::StartPressed),
+                PaletteCommand::new("Stop wallpapers", Message::StopPressed),
+            ];
+            commands.extend(self.tabs.iter().enumerate().map(|(index, tab)| {
+                PaletteCommand::new(
+                    format!("Show monitor {}", tab.monitor.name),
+                    Message::SelectTab(index),
+                )
+            }));
+            return commands;
+        }
+        let mut commands = vec![
+            PaletteCommand::new("Start wallpapers", Message::StartPressed),
+            PaletteCommand::new("Stop wallpapers", Message::StopPressed),
+            PaletteCommand::new("Save settings", Message::SavePressed),
+            PaletteCommand::new(
+                "Apply settings to running wallpapers",
+                Message::ApplyPressed,
+            ),
+            PaletteCommand::new("Preview unsaved settings", Message::PreviewPressed),
+            PaletteCommand::new(
+                if self.settings_page.open {
+                    "Close settings"
+                } else {
+                    "Open settings"
+                },
+                Message::SettingsToggled,
+            ),
+            PaletteCommand::new(
+                if self.overlay.is_some() {
+                    "Hide monitor badges"
+                } else {
+                    "Identify monitors (show badges)"
+                },
+                Message::BadgesToggled,
+            ),
+            PaletteCommand::new("Refresh monitors", Message::RefreshMonitors),
+        ];
+        let mut running: Vec<&String> = self.running.keys().collect();
+        running.sort();
+        commands.extend(running.into_iter().flat_map(|monitor| {
+            [
+                ("Next wallpaper", Action::Next),
+                ("Previous wallpaper", Action::Previous),
+                ("Favorite the wallpaper", Action::Favorite),
+            ]
+            .map(|(label, action)| {
+                PaletteCommand::new(
+                    format!("{label} on {monitor}"),
+                    Message::PlayerAction(action, monitor.clone()),
+                )
+            })
+        }));
+        commands.extend(self.tabs.iter().enumerate().map(|(index, tab)| {
+            PaletteCommand::new(
+                format!("Edit monitor {}", tab.monitor.name),
+                Message::SelectTab(index),
+            )
+        }));
+        commands.extend(self.profiles.iter().map(|name| {
+            PaletteCommand::new(
+                format!("Switch to profile {name}"),
+                Message::ProfileSelected(name.clone()),
+            )
+        }));
+        commands
+    }
+
+    fn scale_factor(&self) -> f64 {
+        if self.gui_settings.touch {
+            TOUCH_SCALE
+        } else {
+            1.0
         }
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(vec![
+        let mut subscriptions = vec![
             time::every(Duration::from_secs(1)).map(|_| Message::Tick),
+            window::resize_events().map(|(_, size)| Message::WindowResized(size)),
+            window::close_events().map(Message::WindowClosed),
             monitor_events(),
-        ])
+            event::listen_with(|event, _status, _window| match event {
+                Event::Touch(touch) => Some(Message::Touched(touch)),
+                Event::Window(
+                    event @ (window::Event::FileHovered(_)
+                    | window::Event::FileDropped(_)
+                    | window::Event::FilesHoveredLeft),
+                ) => Some(Message::FileDragged(event)),
+                Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                    shortcut(key.as_ref(), modifiers)
+                }
+                _ => None,
+            }),
+        ];
+        if self.fullscreen_settings.pause {
+            subscriptions.push(covered_outputs(self.fullscreen_settings));
+        }
+        if self.battery_policy != BatteryPolicy::Continue {
+            subscriptions.push(battery_events());
+        }
+        if self.idle_settings.is_active() {
+            subscriptions.push(idle_events(self.idle_settings));
+        }
+        if self.gui_settings.tray {
+            subscriptions.push(tray_events());
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    /// Act on a click on the tray icon or its menu.
+    fn tray_event(&mut self, event: TrayEvent) -> Task<Message> {
+        match event {
+            TrayEvent::Shown => self.in_tray = true,
+            TrayEvent::Unavailable(err) => {
+                self.in_tray = false;
+                self.status = Some(StatusBanner::error(format!(
+                    "No tray icon, so closing the window quits wpe: {err}"
+                )));
+            }
+            TrayEvent::Open => {
+                return match self.window {
+                    Some(id) => window::gain_focus(id),
+                    None => {
+                        let (id, open) = open_window(&self.state);
+                        self.window = Some(id);
+                        open
+                    }
+                };
+            }
+            TrayEvent::Next => {
+                self.status = Some(match control::act_on_all(Action::Next) {
+                    Ok(()) => StatusBanner::success("Skipped to the next wallpaper everywhere."),
+                    Err(err) => StatusBanner::error(format!("Could not skip: {err}")),
+                });
+            }
+            TrayEvent::TogglePause => {
+                let action = if control::any_playing() {
+                    Action::Pause
+                } else {
+                    Action::Resume
+                };
+                self.status = Some(match control::act_on_all(action) {
+                    Ok(()) => StatusBanner::success(format!("{} everywhere.", action.done())),
+                    Err(err) => StatusBanner::error(err),
+                });
+            }
+            TrayEvent::Quit => return iced::exit(),
+        }
+        Task::none()
+    }
+
+    /// Pause the wallpapers under fullscreen windows or on powered-down outputs, or all
+    /// of them while idle or on battery with `on_battery = "pause"`, and resume the rest.
+    fn sync_pauses(&mut self) {
+        let mut held = if self.fullscreen_settings.pause {
+            self.covered_windows.clone()
+        } else {
+            BTreeSet::new()
+        };
+        if self.on_battery && self.battery_policy == BatteryPolicy::Pause {
+            held.extend(self.running.keys().cloned());
+        }
+        held.extend(idle::held(
+            self.session_idle,
+            &self.screens_off,
+            self.running.keys(),
+        ));
+        let running = self
+            .running
+            .iter()
+            .map(|(monitor, running)| (monitor.as_str(), running.child.id()));
+        self.pauser.update(&held, running);
     }
 
     /// Reconcile current tabs/entries against a fresh monitor list.
     fn reconcile_monitors(&mut self, new_monitors: Vec<Monitor>) {
         self.monitors = new_monitors.clone();
 
+        // Outputs plugged in since the config was read join a `mirror = true` entry.
+        let names: Vec<String> = new_monitors
+            .iter()
+            .map(|monitor| monitor.name.clone())
+            .collect();
+        let mut known = self.saved_entries.clone();
+        known.extend(
+            self.tabs
+                .iter()
+                .map(|tab| tab.editor.to_entry(&tab.monitor.name)),
+        );
+        let joined = config::mirror_onto(&known, &names);
+        self.saved_entries.extend(joined);
+
         // Saved entries from disk (for monitors not currently connected).
         let mut remaining_saved = self.saved_entries.clone();
         // Single fallback for entries without an assigned monitor (applied once).
@@ -286,8 +1325,7 @@ impl GuiApp {
             }
 
             // Otherwise create a new blank entry for this monitor.
-            let mut entry = WallpaperProfileEntry::default();
-            entry.monitor = Some(monitor.name.clone());
+            let entry = self.preferences.new_entry(&monitor.name);
             rebuilt_tabs.push(MonitorTab {
                 monitor,
                 editor: MonitorEditor::new(Some(entry)),
@@ -300,6 +1338,9 @@ impl GuiApp {
         }
         self.saved_entries = remaining_saved;
         self.tabs = rebuilt_tabs;
+        for tab in &mut self.tabs {
+            tab.editor.sync_path_tags(&self.state);
+        }
 
         if self.tabs.is_empty() {
             self.status = Some(StatusBanner::error(
@@ -316,16 +1357,24 @@ impl GuiApp {
         let mut bar = Row::new().spacing(12).push(text("Monitors:").size(18));
 
         for (index, tab) in self.tabs.iter().enumerate() {
-            let mut label = format!("{}", tab.monitor.name);
+            let mut label = tab.monitor.name.clone();
             if tab.editor.is_dirty() {
                 label.push_str(" *");
             }
 
-            let button = button(text(label).size(16))
-                .padding([8, 16])
-                .style(purple_button_style());
+            let color = self.gui_settings.outputs.get(&tab.monitor.name).copied();
+            let button = button(text(label).size(16)).padding([8, 16]);
+            let button = if tab.editor.is_unconfigured() {
+                button.style(muted_button_style(color))
+            } else {
+                button.style(output_button_style(color))
+            };
 
-            bar = bar.push(button.on_press(Message::SelectTab(index)));
+            bar = bar.push(
+                mouse_area(button.on_press(Message::SelectTab(index)))
+                    .on_enter(Message::TabHovered(index, true))
+                    .on_exit(Message::TabHovered(index, false)),
+            );
         }
 
         bar.into()
@@ -333,7 +1382,28 @@ impl GuiApp {
 
     fn active_editor_view(&self) -> Element<'_, Message> {
         if let Some(tab) = self.tabs.get(self.active_tab) {
-            tab.view(self.active_tab, self.picker_icon.as_ref())
+            let loop_points = tab
+                .editor
+                .video_path()
+                .and_then(|path| self.state.loops.get(&path).copied());
+            let thumbnail = tab
+                .editor
+                .preview_source()
+                .and_then(|source| self.thumbnails.get(&source)?.as_ref());
+            let others = self
+                .tabs
+                .iter()
+                .map(|other| other.monitor.name.clone())
+                .filter(|name| *name != tab.monitor.name)
+                .collect();
+            tab.view(
+                self.active_tab,
+                self.picker_icon.as_ref(),
+                thumbnail,
+                loop_points,
+                &self.audio_sinks,
+                others,
+            )
         } else {
             Column::new()
                 .push(text("Select a monitor to configure."))
@@ -341,112 +1411,1341 @@ impl GuiApp {
         }
     }
 
+    /// What the active tab plays, in place of its editor while the config is locked.
+    fn locked_view(&self) -> Element<'_, Message> {
+        let playing = self
+            .saved_entries
+            .iter()
+            .find(|entry| {
+                self.tabs
+                    .get(self.active_tab)
+                    .is_some_and(|tab| entry.monitor.as_deref() == Some(&tab.monitor.name))
+            })
+            .and_then(WallpaperProfileEntry::configured_path)
+            .map_or("No wallpaper configured.".to_string(), |path| {
+                path.display().to_string()
+            });
+        Column::new()
+            .spacing(8)
+            .push(text(playing))
+            .push(text(
+                "The config is locked; wallpapers can be started and stopped, not changed.",
+            ))
+            .into()
+    }
+
     fn action_row(&self) -> Element<'_, Message> {
+        if self.launch.is_some() {
+            let cancel_button = button(text("Cancel"))
+                .on_press(Message::CancelLaunchPressed)
+                .style(accent_button_style())
+                .padding([8, 20]);
+            return Row::new()
+                .spacing(16)
+                .align_y(alignment::Vertical::Center)
+                .push(cancel_button)
+                .into();
+        }
+
         let start_button = button(text("Start"))
             .on_press(Message::StartPressed)
-            .style(purple_button_style())
+            .style(accent_button_style())
             .padding([8, 20]);
 
         let stop_button = button(text("Stop"))
             .on_press(Message::StopPressed)
-            .style(purple_button_style())
+            .style(accent_button_style())
             .padding([8, 20]);
 
+        let save_button = button(text("Save"))
+            .on_press(Message::SavePressed)
+            .style(accent_button_style())
+            .padding([8, 20]);
+
+        let apply_button = button(text("Apply"))
+            .on_press(Message::ApplyPressed)
+            .style(accent_button_style())
+            .padding([8, 20]);
+
+        let preview_button = button(text("Preview"))
+            .on_press(Message::PreviewPressed)
+            .style(accent_button_style())
+            .padding([8, 20]);
+
+        if self.locked {
+            return Row::new()
+                .spacing(16)
+                .align_y(alignment::Vertical::Center)
+                .push(start_button)
+                .push(stop_button)
+                .into();
+        }
+
         Row::new()
             .spacing(16)
             .align_y(alignment::Vertical::Center)
             .push(start_button)
             .push(stop_button)
+            .push(save_button)
+            .push(apply_button)
+            .push(preview_button)
+            .push(
+                button(text("Settings"))
+                    .on_press(Message::SettingsToggled)
+                    .style(accent_button_style())
+                    .padding([8, 20]),
+            )
             .into()
     }
 
+    fn profile_row(&self) -> Element<'_, Message> {
+        let selected = self
+            .state
+            .active_profile
+            .clone()
+            .filter(|name| self.profiles.contains(name));
+        let name = self.profile_name.trim();
+        let save_button = button(text("Save profile"))
+            .on_press_maybe((!name.is_empty()).then_some(Message::SaveProfilePressed))
+            .style(accent_button_style())
+            .padding([8, 20]);
+        Row::new()
+            .spacing(16)
+            .align_y(alignment::Vertical::Center)
+            .push(text("Profile"))
+            .push(
+                pick_list(self.profiles.clone(), selected, Message::ProfileSelected)
+                    .placeholder("Switch to…"),
+            )
+            .push(
+                text_input("Name", &self.profile_name)
+                    .on_input(Message::ProfileNameChanged)
+                    .on_submit(Message::SaveProfilePressed)
+                    .width(Length::Fixed(180.0)),
+            )
+            .push(save_button)
+            .into()
+    }
+
+    /// Save the tabs as they are, then keep that config as the named profile.
+    fn save_profile(&mut self) {
+        let name = self.profile_name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let saved = self
+            .persist_entries()
+            .and_then(|_| config::save_named_profile(&name).map_err(|err| err.to_string()));
+        match saved {
+            Ok(path) => {
+                self.config_stamp = config::config_stamp();
+                self.profiles = config::list_profiles().unwrap_or_default();
+                self.state.active_profile = Some(name.clone());
+                self.state_dirty = true;
+                self.status = Some(StatusBanner::success(format!(
+                    "Saved profile {} to {}.",
+                    name,
+                    path.display()
+                )));
+            }
+            Err(err) => {
+                self.status = Some(StatusBanner::error(format!(
+                    "Could not save profile {}: {}",
+                    name, err
+                )));
+            }
+        }
+    }
+
+    /// Make `name` the active config, replacing the tabs' edits, and restart the
+    /// wallpapers that are playing so they follow it.
+    fn switch_profile(&mut self, name: String) -> Task<Message> {
+        if let Err(err) = config::load_named_profile(&name) {
+            self.status = Some(StatusBanner::error(format!(
+                "Could not load profile {}: {}",
+                name, err
+            )));
+            return Task::none();
+        }
+        self.saved_entries = match config::load_wallpaper_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.status = Some(StatusBanner::error(format!(
+                    "Profile {} could not be read: {}",
+                    name, err
+                )));
+                return Task::none();
+            }
+        };
+        self.config_stamp = config::config_stamp();
+        self.tabs.clear();
+        self.reconcile_monitors(self.monitors.clone());
+        self.profile_name = name.clone();
+        self.state.active_profile = Some(name.clone());
+        self.state_dirty = true;
+
+        if self.wallpaper_running() || daemon::is_running() {
+            return self.start_wallpaper();
+        }
+        self.status = Some(StatusBanner::info(format!(
+            "Loaded profile {}; press Start to play it.",
+            name
+        )));
+        Task::none()
+    }
+
     fn status_banner(&self, banner: &StatusBanner) -> Element<'_, Message> {
         let color = banner.style();
         let content = banner.text.clone();
         text(content)
-            .style(move |_| iced::widget::text::Style {
-                color: Some(color),
-                ..Default::default()
-            })
+            .style(move |_| iced::widget::text::Style { color: Some(color) })
             .into()
     }
 
-    /// Persist current UI state, validate, and start wallpapers.
-    fn start_wallpaper(&mut self) -> Result<(), ()> {
-        match self.persist_entries() {
-            Ok(entries) => match self.validate_entries(&entries) {
-                Ok(valid_entries) if valid_entries == 0 => {
-                    self.status = Some(StatusBanner::error(
-                        "Enable at least one monitor and choose a valid path before starting.",
-                    ));
-                    Err(())
+    /// Saving over an edit made since the last load would lose it, so when the file
+    /// changed, merge it into the tabs instead and ask for `button` to be pressed again.
+    fn merge_outside_edit(&mut self, button: &str) -> Option<Task<Message>> {
+        let stamp = config::config_stamp();
+        if let (Some(loaded), Some(current)) = (self.config_stamp, stamp)
+            && !loaded.same_content(&current)
+        {
+            self.config_stamp = stamp;
+            self.status = Some(StatusBanner::error(format!(
+                "The config file changed since it was loaded; review the merged changes and press {button} again."
+            )));
+            return Some(Task::perform(load_entries(), Message::ConfigChanged));
+        }
+        None
+    }
+
+    /// Write the tabs to the config, leaving the wallpapers as they are.
+    fn save_wallpaper(&mut self) -> Task<Message> {
+        if let Some(task) = self.merge_outside_edit("Save") {
+            return task;
+        }
+        self.status = Some(match self.persist_entries() {
+            Ok(_) if config::config_is_read_only() => {
+                StatusBanner::error("Config is read-only, so these settings were not saved.")
+            }
+            // The daemon reloads the file on its own once it is written.
+            Ok(_) if daemon::is_running() => {
+                StatusBanner::success("Saved; the wpe daemon is applying the config.")
+            }
+            Ok(_) if self.wallpaper_running() => StatusBanner::success(
+                "Saved. Press Apply to restart the monitors whose settings changed.",
+            ),
+            Ok(_) => StatusBanner::success("Saved. Press Start to play the wallpapers."),
+            Err(err) => StatusBanner::error(err),
+        });
+        Task::none()
+    }
+
+    /// Save, then restart only the running wallpapers whose entries changed. Monitors
+    /// that are not playing stay off; Start launches those.
+    fn apply_wallpaper(&mut self) -> Task<Message> {
+        if self.running.is_empty() && !daemon::is_running() {
+            return self.save_wallpaper();
+        }
+        self.launch_entries(true)
+    }
+
+    /// Persist current UI state, validate, and (re)start only the wallpapers that changed.
+    fn start_wallpaper(&mut self) -> Task<Message> {
+        self.launch_entries(false)
+    }
+
+    /// Persist current UI state, validate, and (re)start the wallpapers that changed;
+    /// with `running_only`, monitors without a running wallpaper are left off.
+    ///
+    /// Entries are launched one at a time through `LaunchStageFinished` messages so the
+    /// banner can show progress and Cancel can stop the rest.
+    fn launch_entries(&mut self, running_only: bool) -> Task<Message> {
+        self.launch = None;
+        self.watchdog.clear();
+        // Start keeps whatever is on screen now, so a running preview is not reverted.
+        self.preview = None;
+
+        if let Some(task) = self.merge_outside_edit(if running_only { "Apply" } else { "Start" }) {
+            return task;
+        }
+
+        self.remember_tweaks();
+        let mut entries = match self.persist_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.status = Some(StatusBanner::error(err));
+                return Task::none();
+            }
+        };
+
+        match self.validate_entries(&entries) {
+            Ok(0) => {
+                let unconfigured: Vec<&str> = entries
+                    .iter()
+                    .filter(|entry| entry.enabled && entry.is_unconfigured())
+                    .filter_map(|entry| entry.monitor.as_deref())
+                    .collect();
+                self.status = Some(StatusBanner::error(if unconfigured.is_empty() {
+                    "Enable at least one monitor and choose a valid path before starting."
+                        .to_string()
+                } else {
+                    format!(
+                        "Choose an image, video, or folder for {} before starting.",
+                        unconfigured.join(", ")
+                    )
+                }));
+                return Task::none();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                self.status = Some(StatusBanner::error(err));
+                return Task::none();
+            }
+        }
+
+        // The daemon owns the players; it diffs against the saved config itself.
+        if daemon::is_running() {
+            self.status = Some(StatusBanner::info(
+                "Asking the wpe daemon to apply the config...",
+            ));
+            return Task::perform(
+                daemon_request(daemon::Request::Reload),
+                Message::DaemonReplied,
+            );
+        }
+
+        // Anything running that we did not launch ourselves cannot be diffed, so clear it first.
+        if self.running.is_empty() {
+            let _ = backend::stop_all();
+        }
+
+        let settings = config::load_settings().unwrap_or_default();
+        audio::limit_audible(&mut entries, &settings.audio);
+        let desired: Vec<WallpaperProfileEntry> = entries
+            .into_iter()
+            .filter(|entry| entry.enabled && entry.has_source())
+            .filter(|entry| {
+                entry
+                    .monitor
+                    .as_ref()
+                    .is_some_and(|monitor| !running_only || self.running.contains_key(monitor))
+            })
+            .collect();
+        let plan = apply::plan_apply(&self.running, &desired);
+
+        self.lift_curtains();
+        let mut covering = Vec::new();
+        for monitor in &plan.stop {
+            let restarting = plan
+                .start
+                .iter()
+                .any(|entry| entry.monitor.as_deref() == Some(monitor.as_str()));
+            // Its frame is held up first, in a task; it stops in `Message::Covered`.
+            if restarting
+                && self
+                    .running
+                    .get(monitor)
+                    .is_some_and(|running| running.backend().has_ipc())
+            {
+                covering.push(monitor.clone());
+            } else if let Some(running) = self.running.remove(monitor) {
+                running.stop();
+            }
+        }
+
+        self.audio_settings = settings.audio.clone();
+        self.sync_collections = settings.sync.clone();
+        self.thermal_settings = settings.thermal.clone();
+        self.fullscreen_settings = settings.fullscreen;
+        if !self.fullscreen_settings.pause {
+            self.covered_windows.clear();
+        }
+        self.battery_policy = settings.power.on_battery;
+        self.reduced_motion = settings.motion.reduced();
+        self.colorscheme = settings.colorscheme.clone();
+        if settings.idle != self.idle_settings {
+            self.idle_settings = settings.idle;
+            self.session_idle = false;
+            self.screens_off.clear();
+        }
+        self.launch_generation += 1;
+        self.launch = Some(Launch {
+            generation: self.launch_generation,
+            total: plan.start.len(),
+            unchanged: plan.unchanged.len(),
+            pending: plan.start.into(),
+            current: None,
+            started: 0,
+            failures: Vec::new(),
+            cached: Vec::new(),
+            settings,
+        });
+
+        if !covering.is_empty() {
+            let generation = self.launch_generation;
+            return Task::perform(cover_outputs(covering), move |curtains| {
+                Message::Covered(generation, curtains)
+            });
+        }
+        self.mount_and_launch()
+    }
+
+    /// Hold up the frames of the players `curtains` covered and stop them, then go on
+    /// with the launch that is restarting them.
+    fn covered(
+        &mut self,
+        generation: u64,
+        curtains: Vec<(String, Option<Arc<Curtain>>)>,
+    ) -> Task<Message> {
+        // A newer launch planned against these players itself, and a canceled one
+        // leaves them playing; either way the curtains come down with this message.
+        if !self
+            .launch
+            .as_ref()
+            .is_some_and(|launch| launch.generation == generation)
+        {
+            return Task::none();
+        }
+        for (monitor, curtain) in curtains {
+            if let Some(running) = self.running.remove(&monitor) {
+                running.stop();
+            }
+            if let Some(curtain) = curtain.and_then(Arc::into_inner) {
+                self.curtains.insert(monitor, curtain);
+            }
+        }
+        self.mount_and_launch()
+    }
+
+    /// Mount the sources of the launch's entries that need it, then start them.
+    fn mount_and_launch(&mut self) -> Task<Message> {
+        let unmounted: Vec<PathBuf> = self
+            .launch
+            .iter()
+            .flat_map(|launch| &launch.pending)
+            .filter_map(WallpaperProfileEntry::configured_path)
+            .map(config::normalize_entry_path)
+            .filter(|path| mounts::is_mountable(path))
+            .collect();
+        if !unmounted.is_empty() {
+            self.status = Some(StatusBanner::info(format!(
+                "Waiting for {} wallpaper source(s) to mount…",
+                unmounted.len()
+            )));
+            let generation = self.launch_generation;
+            return Task::perform(mount_sources(unmounted), move |missing| {
+                Message::SourcesMounted(generation, missing)
+            });
+        }
+        self.launch_next()
+    }
+
+    /// Spawn the next queued entry, or finish the launch when the queue is empty.
+    fn launch_next(&mut self) -> Task<Message> {
+        let Some(launch) = self.launch.as_mut() else {
+            return Task::none();
+        };
+
+        while let Some(entry) = launch.pending.pop_front() {
+            let monitor = entry.monitor.clone().unwrap_or_default();
+            let position = launch.total - launch.pending.len();
+            let connected = self.monitors.iter().find(|m| m.name == monitor);
+            match Instance::spawn(&entry, connected, &launch.settings, None) {
+                Ok(running) => {
+                    if running.stand_in() == Some(StandIn::CachedFrame) {
+                        launch.cached.push(monitor.clone());
+                    }
+                    let pid = running.pid();
+                    let backend = running.backend();
+                    self.running.insert(monitor.clone(), running);
+                    launch.current = Some(monitor.clone());
+                    self.status = Some(StatusBanner::info(format!(
+                        "[{}/{}] {}: {}…",
+                        position,
+                        launch.total,
+                        monitor,
+                        LaunchStage::WaitingForSurface
+                    )));
+                    let generation = launch.generation;
+                    let stage = LaunchStage::WaitingForSurface;
+                    return Task::perform(
+                        wait_for_stage(backend, monitor.clone(), pid, stage),
+                        move |result| {
+                            Message::LaunchStageFinished(generation, monitor.clone(), stage, result)
+                        },
+                    );
                 }
-                Ok(valid_entries) => match spawn_wallpaper() {
-                    Ok(()) => {
-                        self.wallpaper_running = true;
-                        self.status = Some(StatusBanner::success(format!(
-                            "Wallpaper started for {} configured entry(ies).",
-                            valid_entries
-                        )));
-                        Ok(())
+                Err(err) => {
+                    launch.failures.push(err);
+                    if let Some(curtain) = self.curtains.remove(&monitor) {
+                        curtain.lift();
                     }
-                    Err(err) => {
-                        self.status = Some(StatusBanner::error(format!(
-                            "Failed to launch wallpaper: {}",
-                            err
-                        )));
-                        Err(())
+                }
+            }
+        }
+
+        let Some(launch) = self.launch.take() else {
+            return Task::none();
+        };
+        self.lift_curtains();
+        if !launch.failures.is_empty() {
+            self.status = Some(StatusBanner::error(format!(
+                "Failed to launch wallpaper: {}",
+                launch.failures.join("; ")
+            )));
+            return Task::none();
+        }
+
+        let mut summary = if launch.total == 0 {
+            "No changes to apply; wallpapers left running.".to_string()
+        } else {
+            format!(
+                "Restarted {} entry(ies), left {} untouched.",
+                launch.total, launch.unchanged
+            )
+        };
+        if config::config_is_read_only() {
+            summary.push_str(" Config is read-only, so these settings were not saved.");
+        }
+        if !launch.cached.is_empty() {
+            summary.push_str(&format!(
+                " Source unavailable for {}; showing the last cached frame until it returns.",
+                launch.cached.join(", ")
+            ));
+            self.status = Some(StatusBanner::error(summary));
+            return Task::none();
+        }
+        self.status = Some(StatusBanner::success(summary));
+        Task::none()
+    }
+
+    /// Advance the launch after `monitor` finished waiting on `stage`.
+    fn launch_stage_finished(
+        &mut self,
+        generation: u64,
+        monitor: String,
+        stage: LaunchStage,
+        result: Result<(), String>,
+    ) -> Task<Message> {
+        let Some(launch) = self
+            .launch
+            .as_mut()
+            .filter(|launch| launch.generation == generation)
+        else {
+            // Canceled or superseded by a newer Start.
+            return Task::none();
+        };
+        let position = launch.total - launch.pending.len();
+
+        if let Err(err) = result {
+            launch.current = None;
+            launch.failures.push(err);
+            // Don't keep a player around that never got to the screen.
+            if let Some(running) = self.running.remove(&monitor) {
+                running.stop();
+            }
+            if let Some(curtain) = self.curtains.remove(&monitor) {
+                curtain.lift();
+            }
+            return self.launch_next();
+        }
+
+        let next = self.running.get_mut(&monitor).and_then(|running| {
+            running.finish(stage);
+            running.pending_stage()
+        });
+        match next {
+            Some(next) => {
+                self.status = Some(StatusBanner::info(format!(
+                    "[{}/{}] {}: {}…",
+                    position, launch.total, monitor, next
+                )));
+                let Some(running) = self.running.get(&monitor) else {
+                    return Task::none();
+                };
+                Task::perform(
+                    wait_for_stage(running.backend(), monitor.clone(), running.pid(), next),
+                    move |result| {
+                        Message::LaunchStageFinished(generation, monitor.clone(), next, result)
+                    },
+                )
+            }
+            None => {
+                launch.current = None;
+                launch.started += 1;
+                if let Some(curtain) = self.curtains.remove(&monitor) {
+                    curtain.lift();
+                }
+                self.status = Some(StatusBanner::info(format!(
+                    "[{}/{}] {}: {}",
+                    position,
+                    launch.total,
+                    monitor,
+                    LaunchStage::Playing
+                )));
+                let cache = match self.running.get(&monitor) {
+                    Some(running)
+                        if running.state() == LaunchState::Running
+                            && running.backend().has_ipc() =>
+                    {
+                        Task::perform(cache_frame(monitor.clone()), move |result| {
+                            Message::FrameCached(monitor.clone(), result)
+                        })
                     }
-                },
+                    _ => Task::none(),
+                };
+                Task::batch([cache, self.launch_next()])
+            }
+        }
+    }
+
+    /// Wait on the players a preview, restore, or still swap started, so they reach
+    /// Running (or Degraded) like the ones Start launches.
+    fn confirm_launches(&mut self) -> Task<Message> {
+        let current = self
+            .launch
+            .as_ref()
+            .and_then(|launch| launch.current.as_deref());
+        let mut tasks = Vec::new();
+        for (monitor, running) in &self.running {
+            let Some(stage) = running.pending_stage() else {
+                continue;
+            };
+            if current == Some(monitor.as_str()) || !self.confirming.insert(monitor.clone()) {
+                continue;
+            }
+            let monitor = monitor.clone();
+            tasks.push(Task::perform(
+                wait_for_stage(running.backend(), monitor.clone(), running.pid(), stage),
+                move |result| Message::StageFinished(monitor.clone(), stage, result),
+            ));
+        }
+        Task::batch(tasks)
+    }
+
+    fn stage_finished(
+        &mut self,
+        monitor: String,
+        stage: LaunchStage,
+        result: Result<(), String>,
+    ) -> Task<Message> {
+        self.confirming.remove(&monitor);
+        let Some(running) = self
+            .running
+            .get_mut(&monitor)
+            .filter(|running| running.pending_stage() == Some(stage))
+        else {
+            // Stopped or replaced while we waited.
+            return Task::none();
+        };
+        if let Err(err) = result {
+            if let Some(running) = self.running.remove(&monitor) {
+                running.stop();
+            }
+            self.status = Some(StatusBanner::error(format!(
+                "The wallpaper on {} did not start: {}",
+                monitor, err
+            )));
+            return Task::none();
+        }
+        running.finish(stage);
+        match running.state() {
+            LaunchState::Launching { .. } => self.confirm_launches(),
+            LaunchState::Running if running.backend().has_ipc() => {
+                Task::perform(cache_frame(monitor.clone()), move |result| {
+                    Message::FrameCached(monitor.clone(), result)
+                })
+            }
+            LaunchState::Running | LaunchState::Stopped | LaunchState::Degraded(_) => Task::none(),
+        }
+    }
+
+    /// Fade out every frame still held up; with reduced motion they come down at once.
+    fn lift_curtains(&mut self) {
+        for (_, curtain) in self.curtains.drain() {
+            if self.reduced_motion {
+                drop(curtain);
+            } else {
+                curtain.lift();
+            }
+        }
+    }
+
+    /// Abandon the rest of a launch, stopping the wallpaper that was still coming up.
+    fn cancel_launch(&mut self) {
+        let Some(launch) = self.launch.take() else {
+            return;
+        };
+        if let Some(monitor) = &launch.current
+            && let Some(running) = self.running.remove(monitor)
+        {
+            running.stop();
+        }
+        self.lift_curtains();
+        self.status = Some(StatusBanner::info(format!(
+            "Launch canceled; {} of {} wallpaper(s) started.",
+            launch.started, launch.total
+        )));
+    }
+
+    fn stop_wallpaper(&mut self) -> Result<(), String> {
+        if daemon::is_running() {
+            let reply = daemon::send(&daemon::Request::Stop { monitor: None })
+                .map_err(|err| err.to_string())?;
+            self.status = Some(daemon_status(&reply));
+            return Ok(());
+        }
+
+        self.remember_tweaks();
+        self.curtains.clear();
+        let tracked = !self.running.is_empty() || !self.watchdog.troubles().is_empty();
+        self.watchdog.clear();
+        for (_, running) in self.running.drain() {
+            running.stop();
+        }
+
+        // Also catch instances started outside this GUI session (e.g. by `wpe -c`).
+        let killed = backend::stop_all().map_err(|err| err.to_string())?;
+        if tracked || killed {
+            self.status = Some(StatusBanner::info("Wallpaper stopped."));
+            Ok(())
+        } else {
+            Err("No running mpvpaper process found.".into())
+        }
+    }
+
+    /// Save picture tweaks made live on the running players (with
+    /// `[live_tweaks] remember`) and take them into the tabs.
+    fn remember_tweaks(&mut self) {
+        if self.locked {
+            return;
+        }
+        let current = matches!(
+            (self.config_stamp, config::config_stamp()),
+            (Some(loaded), Some(stamp)) if loaded.same_content(&stamp)
+        );
+        let monitors: Vec<String> = self.running.keys().cloned().collect();
+        let saved = tweaks::remember(monitors.iter().map(String::as_str));
+        if saved.is_empty() {
+            return;
+        }
+        for (monitor, picture) in saved {
+            if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.monitor.name == monitor) {
+                tab.editor.keep_picture(picture);
+            }
+        }
+        // Our own write is not an outside edit to merge.
+        if current {
+            self.config_stamp = config::config_stamp();
+        }
+    }
+
+    /// Play every tab's unsaved settings for [`PREVIEW_DURATION`] without saving them,
+    /// then put back what was playing before.
+    fn preview_all(&mut self) -> Result<(), String> {
+        if daemon::is_running() {
+            return Err(
+                "Preview is not available while the wpe daemon runs the wallpapers.".into(),
+            );
+        }
+        if let Some(tab) = self
+            .tabs
+            .iter()
+            .find(|tab| tab.editor.interval_error.is_some())
+        {
+            return Err(format!(
+                "Fix the slideshow interval for {}",
+                tab.monitor.name
+            ));
+        }
+        let desired: Vec<WallpaperProfileEntry> = self
+            .tabs
+            .iter()
+            .map(|tab| tab.editor.to_entry(&tab.monitor.name))
+            .filter(|entry| entry.enabled && entry.has_source())
+            .collect();
+        if self.validate_entries(&desired)? == 0 {
+            return Err("Enable at least one monitor with a valid path to preview.".into());
+        }
+
+        self.launch = None;
+        // Previewing again extends the preview but keeps the original restore point.
+        if self.preview.is_none() {
+            let mut previous: Vec<WallpaperProfileEntry> = self
+                .running
+                .values()
+                .map(|running| running.entry.clone())
+                .collect();
+            // Players started outside this GUI (e.g. by `wpe -c`) come back from the config.
+            if previous.is_empty() && backend::stop_all().unwrap_or(false) {
+                previous = self
+                    .saved_entries
+                    .iter()
+                    .filter(|entry| entry.enabled && entry.has_source() && entry.monitor.is_some())
+                    .cloned()
+                    .collect();
+            }
+            self.preview = Some(Preview {
+                until: Instant::now(),
+                previous,
+            });
+        }
+        if let Some(preview) = self.preview.as_mut() {
+            preview.until = Instant::now() + PREVIEW_DURATION;
+        }
+
+        let failures = self.apply_entries(&desired);
+        self.status = Some(if failures.is_empty() {
+            StatusBanner::info(format!(
+                "Previewing unsaved settings for {} seconds…",
+                PREVIEW_DURATION.as_secs()
+            ))
+        } else {
+            StatusBanner::error(format!("Preview failed: {}", failures.join("; ")))
+        });
+        Ok(())
+    }
+
+    /// Put back the wallpapers that played before the preview.
+    fn end_preview(&mut self) {
+        let Some(preview) = self.preview.take() else {
+            return;
+        };
+        let failures = self.apply_entries(&preview.previous);
+        self.status = Some(if failures.is_empty() {
+            StatusBanner::info("Preview over; the previous wallpapers are back.")
+        } else {
+            StatusBanner::error(format!(
+                "Could not restore the previous wallpapers: {}",
+                failures.join("; ")
+            ))
+        });
+    }
+
+    /// Make the running wallpapers match `desired` right away, without the staged
+    /// launch Start uses. Returns the failures.
+    fn apply_entries(&mut self, desired: &[WallpaperProfileEntry]) -> Vec<String> {
+        let plan = apply::plan_apply(&self.running, desired);
+        for monitor in &plan.stop {
+            if let Some(running) = self.running.remove(monitor) {
+                running.stop();
+            }
+        }
+        let settings = config::load_settings().unwrap_or_default();
+        let mut failures = Vec::new();
+        for entry in &plan.start {
+            let monitor = entry.monitor.clone().unwrap_or_default();
+            let connected = self.monitors.iter().find(|m| m.name == monitor);
+            match Instance::spawn(entry, connected, &settings, None) {
+                Ok(running) => {
+                    self.running.insert(monitor, running);
+                }
+                Err(err) => failures.push(format!("{}: {}", monitor, err)),
+            }
+        }
+        failures
+    }
+
+    /// Save changed loop points and restart the monitor's wallpaper if it is playing.
+    fn apply_loop_change(&mut self, index: usize) -> Task<Message> {
+        self.state_dirty = true;
+        self.flush_state();
+
+        let Some(monitor) = self.tabs.get(index).map(|tab| tab.monitor.name.clone()) else {
+            return Task::none();
+        };
+        // Only this monitor restarts, with the entry it plays; unsaved edits in the
+        // other tabs stay unsaved.
+        if self.running.contains_key(&monitor) {
+            let settings = config::load_settings().unwrap_or_default();
+            if let Err(err) = self.respawn(&monitor, &settings) {
+                self.status = Some(StatusBanner::error(format!(
+                    "Failed to restart the wallpaper on {}: {}",
+                    monitor, err
+                )));
+            }
+        }
+        Task::none()
+    }
+
+    /// Restart the player on `monitor` from the entry it is playing, so it picks up
+    /// what changed around it (loop points, a source that is back).
+    fn respawn(&mut self, monitor: &str, settings: &Settings) -> Result<(), String> {
+        let Some(running) = self.running.remove(monitor) else {
+            return Ok(());
+        };
+        let entry = running.entry.clone();
+        running.stop();
+        let connected = self.monitors.iter().find(|m| m.name == monitor);
+        let running = Instance::spawn(&entry, connected, settings, None)?;
+        self.running.insert(monitor.to_string(), running);
+        Ok(())
+    }
+
+    /// Write pending state changes (window size) at most once per tick.
+    fn flush_state(&mut self) {
+        if !self.state_dirty {
+            return;
+        }
+        self.state_dirty = false;
+        // Only what the GUI changed is written over the file, which the CLI and the
+        // daemon also add tags and favorites to.
+        let mut state = state::load_state();
+        state.merge_changes(&self.saved_state, &self.state);
+        if let Err(err) = state::save_state(&state) {
+            warn!("Failed to save GUI state: {}", err);
+            return;
+        }
+        self.saved_state = state.clone();
+        self.state = state;
+    }
+
+    /// At midnight, restart seasonal wallpapers whose `[[seasons]]` rule changed.
+    fn refresh_seasons(&mut self) -> Task<Message> {
+        let today = seasons::today();
+        if self.season_day == today || self.launch.is_some() {
+            return Task::none();
+        }
+        // Without the rules the day stays unchecked, and the next tick tries again.
+        let Ok(settings) = config::load_settings() else {
+            return Task::none();
+        };
+        let yesterday = std::mem::replace(&mut self.season_day, today);
+
+        let changed: Vec<String> = self
+            .running
+            .iter()
+            .filter(|(_, running)| {
+                running.entry.source.as_ref().is_some_and(|source| {
+                    source.seasonal
+                        && source.resolve_tags(&settings.seasons, yesterday)
+                            != source.resolve_tags(&settings.seasons, today)
+                })
+            })
+            .map(|(monitor, _)| monitor.clone())
+            .collect();
+
+        // Only these monitors restart; unsaved edits in the tabs stay unsaved.
+        let failures: Vec<String> = changed
+            .into_iter()
+            .filter_map(|monitor| {
+                self.respawn(&monitor, &settings)
+                    .err()
+                    .map(|err| format!("{monitor}: {err}"))
+            })
+            .collect();
+        if !failures.is_empty() {
+            self.status = Some(StatusBanner::error(format!(
+                "Failed to switch seasonal wallpapers: {}",
+                failures.join("; ")
+            )));
+        }
+        Task::none()
+    }
+
+    /// Swap wallpapers showing a cached frame back to their source once it is mounted again.
+    fn restore_sources(&mut self) -> Task<Message> {
+        if self.launch.is_some() || self.probing_sources {
+            return Task::none();
+        }
+        let missing: Vec<(String, PathBuf)> = self
+            .running
+            .iter()
+            .filter(|(_, running)| running.stand_in() == Some(StandIn::CachedFrame))
+            .filter_map(|(monitor, running)| {
+                let path = config::normalize_entry_path(running.entry.path.as_ref()?);
+                Some((monitor.clone(), path))
+            })
+            .collect();
+        if missing.is_empty() {
+            return Task::none();
+        }
+        // A network share that went away can take a long time to answer a stat.
+        self.probing_sources = true;
+        Task::perform(probe_sources(missing), Message::SourcesProbed)
+    }
+
+    /// Restart the players on `restored`, whose sources are reachable again.
+    fn sources_probed(&mut self, restored: Vec<String>) {
+        self.probing_sources = false;
+        if self.launch.is_some() || restored.is_empty() {
+            return;
+        }
+        let settings = config::load_settings().unwrap_or_default();
+        for monitor in restored {
+            // It may have been stopped or restarted since the probe began.
+            if !self
+                .running
+                .get(&monitor)
+                .is_some_and(|running| running.stand_in() == Some(StandIn::CachedFrame))
+            {
+                continue;
+            }
+            match self.respawn(&monitor, &settings) {
+                Ok(()) => {
+                    info!("Source for {} is back; leaving the cached frame.", monitor);
+                }
                 Err(err) => {
-                    self.status = Some(StatusBanner::error(err));
-                    Err(())
+                    self.status = Some(StatusBanner::error(format!(
+                        "Failed to restore wallpaper for {}: {}",
+                        monitor, err
+                    )));
                 }
-            },
+            }
+        }
+    }
+
+    /// Read the `[thermal]` sensors when due and note whether the machine runs hot.
+    fn check_temperature(&mut self) {
+        if !self.thermal_settings.enabled && !self.throttle.is_hot() {
+            return;
+        }
+        if self.launch.is_some()
+            || self
+                .thermal_checked
+                .is_some_and(|checked| checked.elapsed() < THERMAL_INTERVAL)
+        {
+            return;
+        }
+        self.thermal_checked = Some(Instant::now());
+
+        let reading = thermal::hottest(&self.thermal_settings);
+        if let Some(hot) = self
+            .throttle
+            .update(&self.thermal_settings, reading.as_ref())
+        {
+            let message = match (&reading, hot) {
+                (Some(reading), true) => format!(
+                    "{}; showing still frames until it cools below {:.0}°C.",
+                    reading, self.thermal_settings.resume_below
+                ),
+                _ => "Temperatures are back to normal; resuming wallpapers.".to_string(),
+            };
+            info!("{}", message);
+            self.status = Some(StatusBanner::info(message));
+        }
+    }
+
+    /// Running too hot, with reduced motion, or on battery with
+    /// `on_battery = "static-frame"`.
+    fn wants_stills(&self) -> bool {
+        self.throttle.is_hot()
+            || self.reduced_motion
+            || (self.on_battery && self.battery_policy == BatteryPolicy::StaticFrame)
+    }
+
+    /// Swap moving wallpapers for stills while [`GuiApp::wants_stills`], and back once
+    /// it no longer holds.
+    fn sync_stills(&mut self) -> Task<Message> {
+        if self.launch.is_some() {
+            return Task::none();
+        }
+        if self.wants_stills() {
+            let mut tasks = Vec::new();
+            let moving: Vec<String> = self
+                .running
+                .iter()
+                .filter(|(_, running)| running.stand_in().is_none())
+                .filter(|(_, running)| !running.entry.is_still_image())
+                .map(|(monitor, _)| monitor.clone())
+                .filter(|monitor| !self.stills_pending.contains(monitor))
+                .collect();
+            for monitor in moving {
+                self.stills_pending.insert(monitor.clone());
+                tasks.push(Task::perform(cache_frame(monitor.clone()), move |result| {
+                    Message::StillCaptured(monitor.clone(), result)
+                }));
+            }
+            return Task::batch(tasks);
+        }
+
+        let stills: Vec<String> = self
+            .running
+            .iter()
+            .filter(|(_, running)| running.stand_in() == Some(StandIn::StillFrame))
+            .map(|(monitor, _)| monitor.clone())
+            .collect();
+        if stills.is_empty() {
+            return Task::none();
+        }
+        let settings = config::load_settings().unwrap_or_default();
+        for monitor in stills {
+            let Some(running) = self.running.remove(&monitor) else {
+                continue;
+            };
+            let entry = running.entry.clone();
+            running.stop();
+            let connected = self.monitors.iter().find(|m| m.name == monitor);
+            match Instance::spawn(&entry, connected, &settings, None) {
+                Ok(running) => {
+                    self.running.insert(monitor, running);
+                }
+                Err(err) => {
+                    self.status = Some(StatusBanner::error(format!(
+                        "Failed to resume wallpaper for {}: {}",
+                        monitor, err
+                    )));
+                }
+            }
+        }
+        Task::none()
+    }
+
+    /// Restart `monitor` showing its cached frame instead of its source.
+    fn show_still(&mut self, monitor: &str) {
+        let Some(frame) = fallback::cached_frame(monitor) else {
+            warn!("No still frame of {} to show; leaving it playing.", monitor);
+            return;
+        };
+        let Some(running) = self.running.remove(monitor) else {
+            return;
+        };
+        if running.stand_in() == Some(StandIn::StillFrame) {
+            self.running.insert(monitor.to_string(), running);
+            return;
+        }
+        let entry = running.entry.clone();
+        running.stop();
+        let settings = config::load_settings().unwrap_or_default();
+        let connected = self.monitors.iter().find(|m| m.name == monitor);
+        match Instance::spawn(&entry, connected, &settings, Some(frame)) {
+            Ok(running) => {
+                self.running.insert(monitor.to_string(), running);
+            }
             Err(err) => {
-                self.status = Some(StatusBanner::error(err));
-                Err(())
+                self.status = Some(StatusBanner::error(format!(
+                    "Failed to show a still frame on {}: {}",
+                    monitor, err
+                )));
             }
         }
     }
 
-    fn stop_wallpaper(&mut self) -> Result<(), String> {
-        match Command::new("pkill")
-            .arg("mpvpaper")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
+    /// Make a dropped file or folder the source of its tab, or add it after the source
+    /// when it came with others, and show that tab.
+    fn drop_source(&mut self, dropped: Dropped) -> Task<Message> {
+        let Dropped {
+            path,
+            tab: index,
+            appended,
+        } = dropped;
+        let Some(tab) = self.tabs.get_mut(index) else {
+            return Task::none();
+        };
+        if !path.exists() {
+            self.status = Some(StatusBanner::error(format!(
+                "{} does not exist.",
+                path.display()
+            )));
+            return Task::none();
+        }
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        self.status = Some(StatusBanner::success(if appended {
+            format!("Added {} to {}.", name, tab.monitor.name)
+        } else {
+            format!("Set {}'s source to {}.", tab.monitor.name, name)
+        }));
+        if appended {
+            tab.editor.add_extra_path(path);
+        } else {
+            tab.editor.set_path_buf(path);
+            tab.editor.sync_path_tags(&self.state);
+        }
+        if self
+            .gallery
+            .as_ref()
+            .is_some_and(|gallery| gallery.tab == index)
         {
-            Ok(status) if status.success() => {
-                self.wallpaper_running = false;
-                self.status = Some(StatusBanner::info("Wallpaper stopped."));
-                Ok(())
+            self.gallery = None;
+        }
+        let select = self.update(Message::SelectTab(index));
+        Task::batch([select, self.request_thumbnails()])
+    }
+
+    /// Start making previews of the tabs' sources that have none yet.
+    fn request_thumbnails(&mut self) -> Task<Message> {
+        let mut tasks = Vec::new();
+        for tab in &self.tabs {
+            let Some(source) = tab.editor.preview_source() else {
+                continue;
+            };
+            if self.thumbnails.contains_key(&source) {
+                continue;
             }
-            Ok(_) => {
-                self.wallpaper_running = false;
-                Err("No running mpvpaper process found.".into())
+            self.thumbnails.insert(source.clone(), None);
+            tasks.push(Task::perform(load_thumbnail(source), |(source, result)| {
+                Message::ThumbnailLoaded(source, result)
+            }));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Ask for up to `count` more of the gallery's previews.
+    fn request_gallery_previews(&mut self, count: usize) -> Task<Message> {
+        let Some(gallery) = self.gallery.as_mut() else {
+            return Task::none();
+        };
+        let mut tasks = Vec::new();
+        while tasks.len() < count {
+            let Some(path) = gallery.next_preview() else {
+                break;
+            };
+            if self.thumbnails.contains_key(&path) {
+                continue;
             }
-            Err(err) => Err(format!("Failed to issue pkill: {}", err)),
+            self.thumbnails.insert(path.clone(), None);
+            tasks.push(Task::perform(load_thumbnail(path), |(source, result)| {
+                Message::ThumbnailLoaded(source, result)
+            }));
         }
+        Task::batch(tasks)
     }
 
+    /// Remember how far random folders got, so a crash or power cut keeps the cycle.
+    fn save_queues(&mut self) -> Task<Message> {
+        if self.running.is_empty() || self.queue_saved.elapsed() < QUEUE_SAVE_INTERVAL {
+            return Task::none();
+        }
+        self.queue_saved = Instant::now();
+        Task::future(async { queue::record_all() }).discard()
+    }
+
+    /// Count what the players show towards the usage records, saving them now and then,
+    /// and follow a new file with the color scheme.
+    fn track_usage(&mut self) -> Task<Message> {
+        let mut tasks = Vec::new();
+        if self.usage.is_due() {
+            let monitors = self.running.keys().cloned().collect();
+            let changed = self.usage.record(usage::sample(monitors));
+            history::record(&changed);
+            if let Some((monitor, file)) = self.colorscheme.pick(changed) {
+                let settings = self.colorscheme.clone();
+                tasks.push(
+                    Task::future(async move {
+                        if let Err(err) = colorscheme::update(&settings, &monitor, &file) {
+                            warn!("Could not update the color scheme: {}", err);
+                        }
+                    })
+                    .discard(),
+                );
+            }
+        }
+        if let Some(collected) = self.usage.flush(false) {
+            tasks.push(Task::future(async move { usage::save(collected) }).discard());
+        }
+        Task::batch(tasks)
+    }
+
+    /// Start pulling every `[[sync]]` collection whose interval has passed.
+    fn start_due_syncs(&mut self) -> Task<Message> {
+        let now = Instant::now();
+        self.sync_blocked.retain(|_, until| *until > now);
+
+        let mut tasks = Vec::new();
+        for collection in &self.sync_collections {
+            if self.sync_blocked.contains_key(&collection.name) || !collection.is_due(&self.state) {
+                continue;
+            }
+            // Blocked until the result arrives; a hung transfer is retried after a day.
+            self.sync_blocked.insert(
+                collection.name.clone(),
+                now + Duration::from_secs(24 * 60 * 60),
+            );
+            let name = collection.name.clone();
+            tasks.push(Task::perform(
+                pull_collection(collection.clone()),
+                move |result| Message::SyncFinished(name.clone(), result),
+            ));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Lower audible wallpapers while another application plays sound.
+    fn update_ducking(&mut self) -> Task<Message> {
+        let audible: Vec<String> = self
+            .running
+            .iter()
+            .filter(|(_, running)| running.entry.audio)
+            .map(|(monitor, _)| monitor.clone())
+            .collect();
+        // Nothing to ask PipeWire about; forgetting stopped players needs no task.
+        if audible.is_empty() {
+            if let Some(ducker) = &mut self.ducker {
+                ducker.update(&audible, &self.audio_settings);
+            }
+            return Task::none();
+        }
+        // The last update is still running; the next tick goes again.
+        let Some(ducker) = self.ducker.take() else {
+            return Task::none();
+        };
+        Task::perform(
+            update_ducking(ducker, audible, self.audio_settings.clone()),
+            Message::DuckingUpdated,
+        )
+    }
+
+    fn wallpaper_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    /// Hand instances whose mpvpaper process exited on its own to the watchdog.
     fn poll_wallpaper(&mut self) {
-        if !self.wallpaper_running {
+        let exited: Vec<String> = self
+            .running
+            .iter_mut()
+            .filter(|(_, running)| running.has_exited())
+            .map(|(monitor, _)| monitor.clone())
+            .collect();
+        if exited.is_empty() {
             return;
         }
+        let settings = config::load_settings().unwrap_or_default().watchdog;
+        for monitor in exited {
+            if let Some(running) = self.running.remove(&monitor) {
+                let verdict = self.watchdog.exited(&monitor, &running.entry, &settings);
+                self.status = Some(crash_status(&monitor, &verdict, settings.max_restarts));
+            }
+        }
+    }
 
-        match Command::new("pgrep")
-            .arg("mpvpaper")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-        {
-            Ok(status) if status.success() => {}
-            Ok(_) => {
-                self.wallpaper_running = false;
-                self.status = Some(StatusBanner::info("Wallpaper exited."));
+    /// Restart the crashed players whose wait is over.
+    fn restart_crashed(&mut self) {
+        let due = self.watchdog.due();
+        if due.is_empty() {
+            return;
+        }
+        let settings = config::load_settings().unwrap_or_default();
+        for (monitor, entry) in due {
+            let connected = self.monitors.iter().find(|m| m.name == monitor);
+            if self.running.contains_key(&monitor) || connected.is_none() {
+                // Started again meanwhile, or unplugged.
+                self.watchdog.forget(&monitor);
+                continue;
+            }
+            match Instance::spawn(&entry, connected, &settings, None) {
+                Ok(running) => {
+                    self.running.insert(monitor.clone(), running);
+                    self.watchdog.restarted(&monitor);
+                    self.status = Some(StatusBanner::info(format!(
+                        "Restarted the wallpaper on {}.",
+                        monitor
+                    )));
+                }
+                Err(err) => {
+                    warn!("Could not restart the wallpaper on {}: {}", monitor, err);
+                    let verdict = self.watchdog.exited(&monitor, &entry, &settings.watchdog);
+                    self.status = Some(crash_status(
+                        &monitor,
+                        &verdict,
+                        settings.watchdog.max_restarts,
+                    ));
+                }
             }
-            Err(_) => {}
         }
     }
 
@@ -454,6 +2753,10 @@ impl GuiApp {
         if self.tabs.is_empty() {
             return Err("No monitors available.".into());
         }
+        // Nothing can be edited, so Start plays the config as it is.
+        if self.locked {
+            return Ok(self.saved_entries.clone());
+        }
 
         if let Some(tab) = self
             .tabs
@@ -470,14 +2773,7 @@ impl GuiApp {
         let mut entries = self.saved_entries.clone();
 
         for tab in &self.tabs {
-            let entry = WallpaperProfileEntry {
-                monitor: Some(tab.monitor.name.clone()),
-                path: tab.editor.path_buf(),
-                enabled: tab.editor.enabled(),
-                scale: tab.editor.scale,
-                order: tab.editor.order,
-                interval_seconds: tab.editor.interval_seconds.max(1),
-            };
+            let entry = tab.editor.to_entry(&tab.monitor.name);
 
             if let Some(pos) = entries
                 .iter()
@@ -490,40 +2786,296 @@ impl GuiApp {
         }
 
         config::save_wallpaper_entries(&entries).map_err(|err| err.to_string())?;
-        self.saved_entries = entries.clone();
+        self.config_stamp = config::config_stamp();
         for tab in &mut self.tabs {
-            tab.editor.mark_saved();
+            tab.editor
+                .mark_saved(tab.editor.to_entry(&tab.monitor.name));
         }
+        self.saved_entries = entries.clone();
         Ok(entries)
     }
 
-    /// Ensure every configured path exists before launching wallpapers.
+    /// Make the entry of the tab at `index` the one every monitor plays, or split a
+    /// mirrored entry back into one entry per monitor. Saved right away, since it
+    /// reshapes the config rather than editing one tab.
+    fn set_mirror(&mut self, index: usize, mirror: bool) {
+        let Some(name) = self.tabs.get(index).map(|tab| tab.monitor.name.clone()) else {
+            return;
+        };
+        let mut entries = match self.persist_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.status = Some(StatusBanner::error(err));
+                return;
+            }
+        };
+        if mirror {
+            let Some(source) = entries
+                .iter()
+                .find(|entry| entry.monitor.as_deref() == Some(&name))
+                .cloned()
+            else {
+                return;
+            };
+            // Every connected monitor follows the mirrored entry instead of its own.
+            let names: Vec<String> = self
+                .tabs
+                .iter()
+                .map(|tab| tab.monitor.name.clone())
+                .collect();
+            entries.retain(|entry| {
+                entry.shared.as_ref().is_none_or(|shared| !shared.mirror)
+                    && entry
+                        .monitor
+                        .as_ref()
+                        .is_none_or(|monitor| !names.contains(monitor))
+            });
+            entries.extend(config::mirror_entry(&source, &names));
+        } else {
+            // Each monitor keeps what it showed, as an entry of its own.
+            entries.retain(|entry| {
+                entry.monitor.is_some() || entry.shared.as_ref().is_none_or(|shared| !shared.mirror)
+            });
+            for entry in &mut entries {
+                if entry.shared.as_ref().is_some_and(|shared| shared.mirror) {
+                    entry.shared = None;
+                }
+            }
+        }
+
+        if let Err(err) = config::save_wallpaper_entries(&entries) {
+            self.status = Some(StatusBanner::error(format!(
+                "Could not save the config: {}",
+                err
+            )));
+            return;
+        }
+        self.config_stamp = config::config_stamp();
+        self.saved_entries = match config::load_wallpaper_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.status = Some(StatusBanner::error(format!(
+                    "The config could not be read back: {}",
+                    err
+                )));
+                return;
+            }
+        };
+        self.tabs.clear();
+        self.reconcile_monitors(self.monitors.clone());
+        self.status = Some(StatusBanner::info(if mirror {
+            format!("Every monitor now plays the wallpaper of {name}; press Start to apply.")
+        } else {
+            "Each monitor has an entry of its own again; press Start to apply.".to_string()
+        }));
+    }
+
+    /// Take the GUI colors from the selected monitor's wallpaper when that is enabled.
+    fn refresh_wallpaper_colors(&mut self) -> Task<Message> {
+        if !self.gui_settings.from_wallpaper
+            || self
+                .colors_requested
+                .is_some_and(|at| at.elapsed() < WALLPAPER_COLORS_INTERVAL)
+        {
+            return Task::none();
+        }
+        let Some(tab) = self.tabs.get(self.active_tab) else {
+            return Task::none();
+        };
+        self.colors_requested = Some(Instant::now());
+        Task::perform(
+            wallpaper_colors(tab.monitor.name.clone()),
+            Message::WallpaperColors,
+        )
+    }
+
+    /// Read the config again once its file changes under us.
+    fn check_config_changes(&mut self) -> Task<Message> {
+        let modified = config::config_modified();
+        if modified == self.config_stamp.map(|stamp| stamp.modified) {
+            return Task::none();
+        }
+        let stamp = config::config_stamp();
+        let changed = match (stamp, self.config_stamp) {
+            (Some(stamp), Some(loaded)) => !stamp.same_content(&loaded),
+            (stamp, loaded) => stamp.is_some() != loaded.is_some(),
+        };
+        self.config_stamp = stamp;
+        if !changed {
+            // Touched but not edited.
+            return Task::none();
+        }
+        Task::perform(load_entries(), Message::ConfigChanged)
+    }
+
+    /// Bring entries edited outside the GUI into the tabs without losing unsaved edits.
+    fn merge_external_entries(&mut self, mut entries: Vec<WallpaperProfileEntry>) {
+        let mut conflicts = Vec::new();
+        for tab in &mut self.tabs {
+            let Some(pos) = entries
+                .iter()
+                .position(|e| e.monitor.as_deref() == Some(&tab.monitor.name))
+            else {
+                continue;
+            };
+            let entry = entries.remove(pos);
+            if tab.editor.is_dirty() {
+                tab.editor.merge_external(&tab.monitor.name, entry);
+                if tab.editor.has_conflict() {
+                    conflicts.push(tab.monitor.name.clone());
+                }
+            } else {
+                tab.editor = MonitorEditor::new(Some(entry));
+                tab.editor.sync_path_tags(&self.state);
+            }
+        }
+        // Entries for disconnected monitors are taken from the file as they are.
+        self.saved_entries = entries;
+
+        self.status = Some(if conflicts.is_empty() {
+            StatusBanner::info("Merged changes made to the config file outside the GUI.")
+        } else {
+            StatusBanner::error(format!(
+                "The config file changed while you were editing {}; pick which values to keep.",
+                conflicts.join(", ")
+            ))
+        });
+    }
+
+    /// Ensure every configured path (or tag source) resolves before launching wallpapers.
     fn validate_entries(&self, entries: &[WallpaperProfileEntry]) -> Result<usize, String> {
+        let seasons = config::load_settings()
+            .map(|settings| settings.seasons)
+            .unwrap_or_default();
+        let today = seasons::today();
         let mut valid = 0usize;
         for entry in entries {
-            if !entry.enabled {
+            // Entries still on the placeholder are skipped; Start explains if nothing is left.
+            if !entry.enabled || entry.is_unconfigured() {
                 continue;
             }
 
-            let path = entry.path.as_ref().ok_or_else(|| {
-                format!(
-                    "Enabled entry for {} is missing a file or folder path.",
-                    entry.monitor.as_deref().unwrap_or("an unassigned monitor")
-                )
-            })?;
-
-            let resolved = config::normalize_entry_path(path);
-            match fs::metadata(&resolved) {
-                Ok(_) => valid += 1,
-                Err(_) => {
-                    return Err(format!("Invalid path or file ({})", resolved.display()));
+            if let Some(grid) = &entry.grid {
+                if let Some(missing) = grid
+                    .sources
+                    .iter()
+                    .map(|source| config::normalize_entry_path(source))
+                    .find(|source| !source.exists())
+                {
+                    return Err(format!("Invalid grid source ({})", missing.display()));
                 }
+                valid += 1;
+                continue;
             }
+
+            let tags = entry
+                .source
+                .as_ref()
+                .and_then(|source| source.resolve_tags(&seasons, today));
+            let source: Box<dyn WallpaperSource> = match (tags, entry.mix()) {
+                (Some(tags), _) => Box::new(Tagged(tags)),
+                (None, Some(mix)) => Box::new(mix),
+                (None, None) => {
+                    let path = entry.configured_path().ok_or_else(|| {
+                        format!(
+                            "Enabled entry for {} is missing a file or folder path.",
+                            entry.monitor.as_deref().unwrap_or("an unassigned monitor")
+                        )
+                    })?;
+                    match sources::for_path(path) {
+                        Some(source) => source,
+                        None => {
+                            let resolved = config::normalize_entry_path(path);
+                            // Unmounted share or drive: mounted before launch, or shown from the cache.
+                            if mounts::is_mountable(&resolved)
+                                || fallback::can_stand_in(entry.monitor.as_deref(), &resolved)
+                            {
+                                valid += 1;
+                                continue;
+                            }
+                            return Err(format!("Invalid path or file ({})", resolved.display()));
+                        }
+                    }
+                }
+            };
+            source.validate()?;
+            valid += 1;
         }
         Ok(valid)
     }
 }
 
+/// The message for a window-wide key press: Ctrl+K toggles the command palette, and
+/// Escape and the arrow keys work it while it is open.
+fn shortcut(key: keyboard::Key<&str>, modifiers: keyboard::Modifiers) -> Option<Message> {
+    use keyboard::key::Named;
+    match key {
+        keyboard::Key::Character("k") if modifiers.command() => Some(Message::PaletteToggled),
+        keyboard::Key::Named(Named::Escape) => Some(Message::PaletteClosed),
+        keyboard::Key::Named(Named::ArrowDown) => Some(Message::PaletteMoved(1)),
+        keyboard::Key::Named(Named::ArrowUp) => Some(Message::PaletteMoved(-1)),
+        _ => None,
+    }
+}
+
+/// Summarise what the daemon did for the status banner.
+/// What the banner says when the wallpaper on `monitor` exited on its own.
+fn crash_status(monitor: &str, verdict: &Verdict, max_restarts: u32) -> StatusBanner {
+    match verdict {
+        Verdict::Restart { attempt, after } => StatusBanner::info(format!(
+            "The wallpaper on {} exited; restarting it in {}s (attempt {} of {}).",
+            monitor,
+            after.as_secs(),
+            attempt,
+            max_restarts
+        )),
+        Verdict::GiveUp { restarts: 0 } => {
+            StatusBanner::error(format!("The wallpaper on {} exited.", monitor))
+        }
+        Verdict::GiveUp { restarts } => StatusBanner::error(format!(
+            "The wallpaper on {} kept crashing; gave up after {} restart(s).",
+            monitor, restarts
+        )),
+    }
+}
+
+fn daemon_status(reply: &daemon::Reply) -> StatusBanner {
+    if let Some(err) = &reply.error {
+        return StatusBanner::error(err.clone());
+    }
+    if !reply.failed.is_empty() {
+        let failures: Vec<String> = reply
+            .failed
+            .iter()
+            .map(|(monitor, err)| format!("{}: {}", monitor, err))
+            .collect();
+        return StatusBanner::error(format!(
+            "The daemon could not start: {}",
+            failures.join("; ")
+        ));
+    }
+    if !reply.crashed.is_empty() && reply.started.is_empty() && reply.stopped.is_empty() {
+        let crashed: Vec<String> = reply
+            .crashed
+            .iter()
+            .map(|(monitor, note)| format!("{}: {}", monitor, note))
+            .collect();
+        return StatusBanner::error(crashed.join("; "));
+    }
+    match (reply.started.len(), reply.stopped.len()) {
+        (0, 0) => StatusBanner::info(format!(
+            "Nothing changed; the daemon runs {} wallpaper(s).",
+            reply.running.len()
+        )),
+        (0, stopped) => StatusBanner::info(format!("The daemon stopped {} wallpaper(s).", stopped)),
+        (started, _) => StatusBanner::success(format!(
+            "The daemon started {} wallpaper(s); {} running.",
+            started,
+            reply.running.len()
+        )),
+    }
+}
+
 /// Lightweight helper for showing info/error banners.
 #[derive(Debug, Clone)]
 struct StatusBanner {
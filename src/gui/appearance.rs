@@ -0,0 +1,159 @@
+//! Accent color picker: a hex field, one slider per channel, and a few presets. It is
+//! the Colors part of the Settings page.
+
+use iced::widget::{Column, Row, button, checkbox, container, slider, text, text_input};
+use iced::{Background, Border, Color, Element, Length, alignment, border};
+
+use crate::config::{GuiSettings, HexColor};
+
+use super::{
+    message::Message,
+    style::{DEFAULT_ACCENT, accent_button_style, to_color},
+};
+
+const PRESETS: [HexColor; 8] = [
+    DEFAULT_ACCENT,
+    HexColor([0x1E, 0x5A, 0xA8]),
+    HexColor([0x00, 0x7A, 0x6E]),
+    HexColor([0x2E, 0x7D, 0x32]),
+    HexColor([0xB2, 0x6A, 0x00]),
+    HexColor([0xB7, 0x1C, 0x1C]),
+    HexColor([0xAD, 0x14, 0x57]),
+    HexColor([0x45, 0x4F, 0x5E]),
+];
+
+const CHANNELS: [&str; 3] = ["Red", "Green", "Blue"];
+
+/// State of the accent picker while it is being edited.
+#[derive(Debug)]
+pub(crate) struct AppearancePanel {
+    color: HexColor,
+    /// Hex field as typed, which may not be a valid color yet.
+    hex_text: String,
+}
+
+impl AppearancePanel {
+    pub(crate) fn new(accent: HexColor) -> Self {
+        Self {
+            color: accent,
+            hex_text: accent.to_string(),
+        }
+    }
+
+    pub(crate) fn color(&self) -> HexColor {
+        self.color
+    }
+
+    /// Update from the hex field, returning the color once the text is valid.
+    pub(crate) fn set_hex_text(&mut self, value: String) -> Option<HexColor> {
+        let color = HexColor::parse(&value);
+        self.hex_text = value;
+        if let Some(color) = color {
+            self.color = color;
+        }
+        color
+    }
+
+    pub(crate) fn set_channel(&mut self, channel: usize, value: u8) -> HexColor {
+        self.color.0[channel] = value;
+        self.hex_text = self.color.to_string();
+        self.color
+    }
+
+    pub(crate) fn set_color(&mut self, color: HexColor) {
+        self.color = color;
+        self.hex_text = color.to_string();
+    }
+
+    pub(crate) fn view(&self, gui: &GuiSettings) -> Element<'_, Message> {
+        let valid = HexColor::parse(&self.hex_text).is_some();
+        let mut hex_row = Row::new()
+            .spacing(12)
+            .align_y(alignment::Vertical::Center)
+            .push(text("Accent:").size(16))
+            .push(
+                text_input("#4B006E", &self.hex_text)
+                    .on_input(Message::AccentTextChanged)
+                    .width(Length::Fixed(120.0)),
+            )
+            .push(swatch(self.color, 32.0, None));
+        if !valid {
+            hex_row = hex_row.push(text("Use #RRGGBB").size(14));
+        }
+
+        let mut panel = Column::new()
+            .spacing(12)
+            .push(text("Colors").size(18))
+            .push(
+                checkbox(
+                    "Match the wallpaper on the selected monitor",
+                    gui.from_wallpaper,
+                )
+                .on_toggle(Message::FromWallpaperToggled),
+            )
+            .push(
+                checkbox("Larger controls for touch screens", gui.touch)
+                    .on_toggle(Message::TouchModeToggled),
+            )
+            .push(hex_row);
+        for (channel, label) in CHANNELS.iter().enumerate() {
+            panel = panel.push(
+                Row::new()
+                    .spacing(12)
+                    .align_y(alignment::Vertical::Center)
+                    .push(text(*label).size(14).width(Length::Fixed(48.0)))
+                    .push(
+                        slider(0..=255u8, self.color.0[channel], move |value| {
+                            Message::AccentChannelChanged(channel, value)
+                        })
+                        .width(Length::Fixed(240.0)),
+                    )
+                    .push(text(self.color.0[channel].to_string()).size(14)),
+            );
+        }
+        let presets = PRESETS.iter().fold(Row::new().spacing(8), |row, color| {
+            row.push(swatch(*color, 24.0, Some(Message::AccentPicked(*color))))
+        });
+        panel
+            .push(presets)
+            .push(
+                button(text("Reset"))
+                    .on_press(Message::AccentPicked(DEFAULT_ACCENT))
+                    .style(accent_button_style())
+                    .padding([8, 20]),
+            )
+            .push(
+                text(
+                    "Monitor badges use the saved color the next time the GUI starts. \
+                     Full palettes and light/dark variants are set under [gui] in the config file.",
+                )
+                .size(14),
+            )
+            .into()
+    }
+}
+
+/// A square of `color`, clickable when `on_press` is set.
+fn swatch(color: HexColor, size: f32, on_press: Option<Message>) -> Element<'static, Message> {
+    let fill = to_color(color);
+    let square = container(text(""))
+        .width(Length::Fixed(size))
+        .height(Length::Fixed(size))
+        .style(move |_| container::Style {
+            background: Some(Background::Color(fill)),
+            border: Border {
+                color: Color::from_rgba(1.0, 1.0, 1.0, 0.4),
+                width: 1.0,
+                radius: border::Radius::from(4.0),
+            },
+            ..container::Style::default()
+        });
+    match on_press {
+        Some(message) => button(square)
+            .padding(0)
+            .style(|_, _| button::Style::default())
+            .on_press(message)
+            .into(),
+        None => square.into(),
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use crate::{config::WallpaperProfileEntry, launcher::Instance};
+
+/// Outcome of diffing the running wallpapers against freshly saved entries.
+#[derive(Debug, Default)]
+pub(crate) struct ApplyPlan {
+    /// Monitors whose running instance must be stopped (changed, disabled, or removed).
+    pub stop: Vec<String>,
+    /// Entries that need a fresh mpvpaper instance.
+    pub start: Vec<WallpaperProfileEntry>,
+    /// Monitors left running because nothing about them changed.
+    pub unchanged: Vec<String>,
+}
+
+/// Compare what is running against the desired entries and work out the minimal restarts.
+pub(crate) fn plan_apply(
+    running: &HashMap<String, Instance>,
+    desired: &[WallpaperProfileEntry],
+) -> ApplyPlan {
+    let mut plan = ApplyPlan::default();
+
+    for entry in desired {
+        let Some(monitor) = entry.monitor.as_deref() else {
+            continue;
+        };
+        match running.get(monitor) {
+            Some(current) if current.entry == *entry => plan.unchanged.push(monitor.to_string()),
+            Some(_) => {
+                plan.stop.push(monitor.to_string());
+                plan.start.push(entry.clone());
+            }
+            None => plan.start.push(entry.clone()),
+        }
+    }
+
+    for monitor in running.keys() {
+        let still_wanted = desired
+            .iter()
+            .any(|entry| entry.monitor.as_deref() == Some(monitor.as_str()));
+        if !still_wanted {
+            plan.stop.push(monitor.clone());
+        }
+    }
+
+    plan
+}
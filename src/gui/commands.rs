@@ -0,0 +1,150 @@
+//! Ctrl+K command palette: every action the window offers, found by typing a few
+//! letters of it.
+//!
+//! The commands are built fresh from the app's state each time (one per profile,
+//! running monitor, and tab), and ranked by a fuzzy match: the typed letters must
+//! appear in order, and runs of them and word starts rank higher.
+
+use iced::widget::{Column, button, container, text, text_input};
+use iced::{Element, Length};
+
+use super::{
+    message::Message,
+    style::{accent_button_style, muted_button_style},
+};
+
+/// Most matches listed at once.
+const MAX_SHOWN: usize = 8;
+
+/// One action, as the palette lists it.
+#[derive(Debug, Clone)]
+pub(crate) struct PaletteCommand {
+    pub label: String,
+    pub message: Message,
+}
+
+impl PaletteCommand {
+    pub(crate) fn new(label: impl Into<String>, message: Message) -> Self {
+        Self {
+            label: label.into(),
+            message,
+        }
+    }
+}
+
+/// Whether the palette is up, what was typed, and which match is highlighted.
+#[derive(Debug, Default)]
+pub(crate) struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Open an empty palette, or close it.
+    pub(crate) fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub(crate) fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+
+    /// Move the highlight `step` matches down (or up), wrapping around.
+    pub(crate) fn move_selection(&mut self, step: isize, commands: &[PaletteCommand]) {
+        let count = ranked(&self.query, commands).len().min(MAX_SHOWN);
+        if count > 0 {
+            self.selected = (self.selected as isize + step).rem_euclid(count as isize) as usize;
+        }
+    }
+
+    /// The message of the highlighted match.
+    pub(crate) fn chosen(&self, commands: &[PaletteCommand]) -> Option<Message> {
+        ranked(&self.query, commands)
+            .get(self.selected)
+            .map(|command| command.message.clone())
+    }
+
+    pub(crate) fn view(&self, commands: &[PaletteCommand]) -> Element<'_, Message> {
+        let mut list = Column::new().spacing(4);
+        let matches = ranked(&self.query, commands);
+        if matches.is_empty() {
+            list = list.push(text("No matching command.").size(14));
+        }
+        for (index, command) in matches.into_iter().take(MAX_SHOWN).enumerate() {
+            let item = button(text(command.label.clone()).size(16))
+                .on_press(Message::PaletteChosen(Box::new(command.message.clone())))
+                .width(Length::Fill)
+                .padding([6, 14]);
+            list = list.push(if index == self.selected {
+                item.style(accent_button_style())
+            } else {
+                item.style(muted_button_style(None))
+            });
+        }
+        container(
+            Column::new()
+                .spacing(8)
+                .push(
+                    text_input("Type a command (Esc closes)", &self.query)
+                        .id(input_id())
+                        .on_input(Message::PaletteQueryChanged)
+                        .on_submit(Message::PaletteSubmitted)
+                        .padding(8),
+                )
+                .push(list),
+        )
+        .padding(12)
+        .style(container::rounded_box)
+        .into()
+    }
+}
+
+/// The palette's search field, focused when it opens.
+pub(crate) fn input_id() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+/// `commands` matching `query`, best first; all of them, in order, for an empty query.
+fn ranked<'a>(query: &str, commands: &'a [PaletteCommand]) -> Vec<&'a PaletteCommand> {
+    let mut scored: Vec<(i32, &PaletteCommand)> = commands
+        .iter()
+        .filter_map(|command| Some((score(query, &command.label)?, command)))
+        .collect();
+    // Stable, so equal scores keep the commands' own order.
+    scored.sort_by_key(|(score, _)| -score);
+    scored.into_iter().map(|(_, command)| command).collect()
+}
+
+/// How well `query` matches `label`, or `None` when its letters are not all in
+/// `label` in order. Case and spaces in the query are ignored.
+fn score(query: &str, label: &str) -> Option<i32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query
+        .to_lowercase()
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+    {
+        let found = position + label[position..].iter().position(|ch| *ch == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
@@ -0,0 +1,416 @@
+//! Fade between wallpapers when Start swaps one on a live output.
+//!
+//! mpvpaper cannot hand its surface to the next player, so a restart kills the old
+//! one and the bare desktop flashes until the new one maps. Before stopping it, the
+//! GUI captures the frame on screen and holds it up in a [`Curtain`]: a layer
+//! surface just above the wallpaper showing that frame. Once the new player is
+//! playing underneath, the curtain fades out over [`FADE`].
+
+use std::{
+    error::Error,
+    io::ErrorKind,
+    path::Path,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use image::imageops::{self, FilterType};
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState},
+    delegate_compositor, delegate_layer, delegate_output, delegate_registry, delegate_shm,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    shell::{
+        WaylandSurface,
+        wlr_layer::{
+            Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
+            LayerSurfaceConfigure,
+        },
+    },
+    shm::{Shm, ShmHandler, slot::SlotPool},
+};
+use tracing::warn;
+use wayland_client::{
+    Connection, EventQueue, QueueHandle,
+    backend::WaylandError,
+    globals::registry_queue_init,
+    protocol::{wl_output, wl_shm, wl_surface},
+};
+
+/// How long the old frame takes to fade into the new wallpaper.
+const FADE: Duration = Duration::from_millis(400);
+/// Time between fade steps, and between checks for the signal to fade.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// How long [`Curtain::cover`] waits for the frame to reach the screen.
+const SHOWN_TIMEOUT: Duration = Duration::from_secs(1);
+/// A curtain nobody lifts fades on its own after this, so a lost launch never
+/// leaves a frozen frame behind.
+const MAX_COVER: Duration = Duration::from_secs(20);
+
+/// The frame of an outgoing wallpaper held over its output. Dropping it takes the
+/// frame down at once; [`Curtain::lift`] fades it out.
+#[derive(Debug)]
+pub(crate) struct Curtain {
+    fade: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Curtain {
+    /// Show `frame` over `monitor`, returning once it is on screen (or after
+    /// [`SHOWN_TIMEOUT`], so a slow compositor never holds up the restart).
+    pub(crate) fn cover(monitor: &str, frame: &Path) -> Result<Self, Box<dyn Error>> {
+        let (fade, signal) = mpsc::channel();
+        let (shown, on_screen) = mpsc::channel();
+        let monitor = monitor.to_string();
+        let frame = frame.to_path_buf();
+        let thread = thread::Builder::new()
+            .name("wpe-crossfade".into())
+            .spawn(move || {
+                if let Err(err) = curtain_main(&monitor, &frame, shown, &signal) {
+                    warn!("Crossfade on {} failed: {}", monitor, err);
+                }
+            })?;
+        let curtain = Self {
+            fade: Some(fade),
+            thread: Some(thread),
+        };
+        match on_screen.recv_timeout(SHOWN_TIMEOUT) {
+            Ok(()) | Err(RecvTimeoutError::Timeout) => Ok(curtain),
+            // The thread gave up before drawing anything.
+            Err(RecvTimeoutError::Disconnected) => Err("could not cover the output".into()),
+        }
+    }
+
+    /// Fade the frame out over the wallpaper now playing beneath it.
+    pub(crate) fn lift(mut self) {
+        if let Some(fade) = self.fade.take() {
+            let _ = fade.send(());
+        }
+        // The fade finishes on its own; don't wait for it.
+        self.thread.take();
+    }
+}
+
+impl Drop for Curtain {
+    fn drop(&mut self) {
+        // Hanging up without a fade signal removes the frame right away.
+        self.fade.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Cover `monitor` with `frame` until `signal` says to fade (or hangs up), then
+/// remove the surface.
+fn curtain_main(
+    monitor: &str,
+    frame: &Path,
+    shown: Sender<()>,
+    signal: &Receiver<()>,
+) -> Result<(), Box<dyn Error>> {
+    let image = image::open(frame)?.to_rgba8();
+
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init(&conn)?;
+    let qh = event_queue.handle();
+    let compositor = CompositorState::bind(&globals, &qh)?;
+    let layer_shell = LayerShell::bind(&globals, &qh)?;
+    let mut state = CurtainState {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+        shm: Shm::bind(&globals, &qh)?,
+        layer: None,
+        pool: None,
+        image,
+        pixels: Vec::new(),
+        size: None,
+        needs_draw: false,
+        shown: Some(shown),
+        closed: false,
+    };
+    // One round trip fills in the output names.
+    event_queue.roundtrip(&mut state)?;
+    let output = state
+        .output_state
+        .outputs()
+        .find(|output| {
+            state
+                .output_state
+                .info(output)
+                .is_some_and(|info| info.name.as_deref() == Some(monitor))
+        })
+        .ok_or_else(|| format!("No output named {monitor}"))?;
+
+    let surface = compositor.create_surface(&qh);
+    // Just above the wallpaper's background layer, below every window.
+    let layer = layer_shell.create_layer_surface(
+        &qh,
+        surface,
+        Layer::Bottom,
+        Some("wpe-crossfade"),
+        Some(&output),
+    );
+    layer.set_anchor(Anchor::TOP | Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT);
+    layer.set_size(0, 0);
+    layer.set_exclusive_zone(-1);
+    layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+    layer.commit();
+    state.layer = Some(layer);
+
+    let covered = Instant::now();
+    loop {
+        match signal.recv_timeout(FRAME_INTERVAL) {
+            Ok(()) => break,
+            Err(RecvTimeoutError::Timeout) if covered.elapsed() >= MAX_COVER => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return state.remove(&mut event_queue),
+        }
+        pump(&mut event_queue, &mut state)?;
+        if state.closed {
+            return Ok(());
+        }
+        if state.needs_draw {
+            state.draw(u8::MAX, &qh)?;
+        }
+    }
+
+    let fading = Instant::now();
+    while fading.elapsed() < FADE && !state.closed {
+        let left = 1.0 - fading.elapsed().as_secs_f32() / FADE.as_secs_f32();
+        state.draw((left * f32::from(u8::MAX)) as u8, &qh)?;
+        pump(&mut event_queue, &mut state)?;
+        thread::sleep(FRAME_INTERVAL);
+    }
+    state.remove(&mut event_queue)
+}
+
+/// Send queued requests and handle whatever the compositor sent, without blocking.
+fn pump(
+    event_queue: &mut EventQueue<CurtainState>,
+    state: &mut CurtainState,
+) -> Result<(), Box<dyn Error>> {
+    event_queue.flush()?;
+    if let Some(guard) = event_queue.prepare_read() {
+        match guard.read() {
+            Ok(_) => {}
+            Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    event_queue.dispatch_pending(state)?;
+    Ok(())
+}
+
+/// `image` letterboxed into `width` x `height` the way mpv shows it, as opaque
+/// Argb8888 (blue, green, red, alpha in memory).
+fn fit(image: &image::RgbaImage, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = [0, 0, 0, u8::MAX].repeat((width * height) as usize);
+    let (source_width, source_height) = image.dimensions();
+    if source_width == 0 || source_height == 0 {
+        return pixels;
+    }
+    let scale = f64::min(
+        f64::from(width) / f64::from(source_width),
+        f64::from(height) / f64::from(source_height),
+    );
+    let fitted_width = ((f64::from(source_width) * scale).round() as u32).clamp(1, width);
+    let fitted_height = ((f64::from(source_height) * scale).round() as u32).clamp(1, height);
+    let scaled = imageops::resize(image, fitted_width, fitted_height, FilterType::Triangle);
+    let left = (width - fitted_width) / 2;
+    let top = (height - fitted_height) / 2;
+    for (x, y, pixel) in scaled.enumerate_pixels() {
+        let [r, g, b, _] = pixel.0;
+        let offset = (((top + y) * width + left + x) * 4) as usize;
+        pixels[offset..offset + 4].copy_from_slice(&[b, g, r, u8::MAX]);
+    }
+    pixels
+}
+
+/// The layer surface, the frame it shows, and the buffers drawn into it.
+struct CurtainState {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    shm: Shm,
+    layer: Option<LayerSurface>,
+    pool: Option<SlotPool>,
+    image: image::RgbaImage,
+    /// `image` fitted to `size`.
+    pixels: Vec<u8>,
+    size: Option<(u32, u32)>,
+    /// The surface was (re)configured and has no frame at its size yet.
+    needs_draw: bool,
+    /// Told once the first frame is on screen.
+    shown: Option<Sender<()>>,
+    closed: bool,
+}
+
+impl CurtainState {
+    /// Draw the frame at `alpha` (premultiplied, so every channel scales with it).
+    fn draw(&mut self, alpha: u8, qh: &QueueHandle<Self>) -> Result<(), Box<dyn Error>> {
+        let (Some(layer), Some((width, height))) = (&self.layer, self.size) else {
+            return Ok(());
+        };
+        if self.pool.is_none() {
+            self.pool = Some(SlotPool::new((width * height * 4) as usize, &self.shm)?);
+            self.pixels = fit(&self.image, width, height);
+        }
+        let Some(pool) = &mut self.pool else {
+            return Ok(());
+        };
+        let stride = width as i32 * 4;
+        let (buffer, canvas) = pool.create_buffer(
+            width as i32,
+            height as i32,
+            stride,
+            wl_shm::Format::Argb8888,
+        )?;
+        let alpha = u16::from(alpha);
+        for (out, pixel) in canvas.iter_mut().zip(&self.pixels) {
+            *out = (u16::from(*pixel) * alpha / 255) as u8;
+        }
+
+        let surface = layer.wl_surface();
+        surface.damage_buffer(0, 0, width as i32, height as i32);
+        if self.shown.is_some() {
+            surface.frame(qh, surface.clone());
+        }
+        buffer.attach_to(surface)?;
+        layer.commit();
+        self.needs_draw = false;
+        Ok(())
+    }
+
+    /// Destroy the surface and make sure the compositor hears about it.
+    fn remove(&mut self, event_queue: &mut EventQueue<Self>) -> Result<(), Box<dyn Error>> {
+        self.layer = None;
+        event_queue.flush()?;
+        Ok(())
+    }
+}
+
+impl CompositorHandler for CurtainState {
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_factor: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+        // The compositor asks for the next frame once this one was presented.
+        if let Some(shown) = self.shown.take() {
+            let _ = shown.send(());
+        }
+    }
+
+    fn surface_enter(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _output: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for CurtainState {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn update_output(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl LayerShellHandler for CurtainState {
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
+        // The compositor drops the surface when its output goes away.
+        self.closed = true;
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let (width, height) = configure.new_size;
+        if width > 0 && height > 0 && self.size != Some((width, height)) {
+            self.size = Some((width, height));
+            // The old pool and fitted frame are sized for the old surface.
+            self.pool = None;
+        }
+        self.needs_draw = true;
+    }
+}
+
+impl ShmHandler for CurtainState {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+delegate_compositor!(CurtainState);
+delegate_output!(CurtainState);
+delegate_shm!(CurtainState);
+delegate_layer!(CurtainState);
+delegate_registry!(CurtainState);
+
+impl ProvidesRegistryState for CurtainState {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState];
+}
@@ -0,0 +1,57 @@
+//! Files and folders dragged in from a file manager.
+//!
+//! The window reports each dropped path on its own and without a position, so the
+//! target is the monitor tab the pointer was last over, or else the open tab. The
+//! first path of a drag replaces the source and any further ones are added after it.
+
+use std::path::PathBuf;
+
+use iced::window;
+
+/// A path dropped on the window, and where it goes.
+#[derive(Debug)]
+pub(crate) struct Dropped {
+    pub path: PathBuf,
+    pub tab: usize,
+    /// Another path of the same drag came first.
+    pub appended: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Drops {
+    /// The tab button under the pointer.
+    hovered_tab: Option<usize>,
+    /// Paths dropped since the drag came in.
+    dropped: usize,
+}
+
+impl Drops {
+    /// The pointer entered (`true`) or left the button of `tab`.
+    pub(crate) fn hover_tab(&mut self, tab: usize, inside: bool) {
+        if inside {
+            self.hovered_tab = Some(tab);
+        } else if self.hovered_tab == Some(tab) {
+            self.hovered_tab = None;
+        }
+    }
+
+    /// Follow a drag-and-drop `event`; returns the path it dropped, if any.
+    pub(crate) fn update(&mut self, event: window::Event, active_tab: usize) -> Option<Dropped> {
+        match event {
+            // Every path of a drag is hovered before the first is dropped.
+            window::Event::FileHovered(_) | window::Event::FilesHoveredLeft => {
+                self.dropped = 0;
+                None
+            }
+            window::Event::FileDropped(path) => {
+                self.dropped += 1;
+                Some(Dropped {
+                    path,
+                    tab: self.hovered_tab.unwrap_or(active_tab),
+                    appended: self.dropped > 1,
+                })
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,19 +1,37 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
 
-use iced::widget::{self, Column, Row, button, checkbox, container, svg, text, text_input};
-use iced::{Color, Element, Length, alignment};
+use iced::widget::{
+    self, Column, Row, button, checkbox, container, pick_list, slider, svg, text, text_input,
+};
+use iced::{Color, ContentFit, Element, Length, alignment};
 
 use crate::{
-    config::{self, DEFAULT_INTERVAL_SECS, ScaleMode, SlideshowOrder, WallpaperProfileEntry},
+    audio::AudioSink,
+    config::{
+        self, DEFAULT_INTERVAL_SECS, EntrySource, MAX_VOLUME, Margins, ScaleMode, SharedEntry,
+        SlideshowOrder, WallpaperProfileEntry,
+    },
+    loops::LoopPoints,
     monitors::Monitor,
+    sources,
+    state::State,
+    tags,
+    tweaks::Picture,
 };
 
 use super::{
     helpers::{PathSelection, detect_path_kind, format_interval, parse_interval},
+    margins::{MarginEdge, MarginPreview},
     message::Message,
-    style::{load_file_icon, load_folder_icon, purple_button_style},
+    style::{accent_button_style, load_file_icon, load_folder_icon},
 };
 
+/// Width of the source's preview in the tab.
+const THUMBNAIL_WIDTH: f32 = 240.0;
+
 /// A tab ties monitor metadata with its editable controls.
 pub(crate) struct MonitorTab {
     pub monitor: Monitor,
@@ -25,22 +43,77 @@ pub(crate) struct MonitorTab {
 pub(crate) struct MonitorEditor {
     path_text: String,
     path_kind: PathKind,
+    /// Sources played after the path as one playlist, as typed.
+    extra_path_texts: Vec<String>,
     enabled: bool,
     pub scale: ScaleMode,
     pub order: SlideshowOrder,
     pub interval_seconds: u64,
     interval_text: String,
+    audio: bool,
+    audio_device: Option<String>,
+    volume: u8,
+    /// Tags an entry draws its source from, as typed.
+    source_tags_text: String,
+    /// Draw from the tags of today's `[[seasons]]` rule.
+    seasonal: bool,
+    /// Tags on the current path, edited here and saved to the state file on demand.
+    path_tags_text: String,
+    margins: Margins,
+    /// Margin fields as typed, in `MarginEdge::ALL` order.
+    margin_texts: [String; 4],
     pub interval_error: Option<String>,
     dirty: bool,
+    /// Entry this editor was loaded from, carrying config-only settings the GUI doesn't edit.
+    base: WallpaperProfileEntry,
+    /// Fields the config file changed while they were being edited here.
+    conflict: Option<Conflict>,
+}
+
+/// Unsaved edits that clash with a change made to the config file meanwhile.
+#[derive(Debug)]
+struct Conflict {
+    fields: Vec<&'static str>,
+    /// The edits as they were before the file's changes came in.
+    mine: WallpaperProfileEntry,
+}
+
+/// How to settle unsaved edits that clash with an outside change to the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConflictChoice {
+    /// Keep every edit, overwriting the file's changes on save.
+    KeepMine,
+    /// Drop the edits and show the entry as the file has it.
+    TakeTheirs,
+    /// Take the file's changes except where both sides changed a setting.
+    Merge,
 }
 
 impl MonitorEditor {
     pub(crate) fn new(entry: Option<WallpaperProfileEntry>) -> Self {
+        let base = entry.clone().unwrap_or_default();
+        let audio = base.audio;
+        let audio_device = base.audio_device.clone();
+        let volume = base.volume;
+        let source_tags_text = base
+            .source
+            .as_ref()
+            .map(|source| source.tags.join(", "))
+            .unwrap_or_default();
+        let seasonal = base.source.as_ref().is_some_and(|source| source.seasonal);
+        let margins = base.margins;
+        let extra_path_texts = base
+            .extra_paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
         let (path, scale, order, interval, enabled) = entry
             .map(|entry| {
                 (
+                    // The placeholder shows as an empty field with a hint instead.
                     entry
                         .path
+                        .filter(|p| !config::is_placeholder(p))
                         .map(|p| p.to_string_lossy().into_owned())
                         .unwrap_or_default(),
                     entry.scale,
@@ -63,13 +136,132 @@ impl MonitorEditor {
         Self {
             path_text: path,
             path_kind,
+            extra_path_texts,
             enabled,
             scale,
             order,
             interval_seconds: interval,
             interval_text: format_interval(interval),
             interval_error: None,
+            audio,
+            audio_device,
+            volume,
+            source_tags_text,
+            seasonal,
+            path_tags_text: String::new(),
+            margins,
+            margin_texts: margin_texts(&margins),
             dirty: false,
+            base,
+            conflict: None,
+        }
+    }
+
+    /// Take in `disk`, the entry as the config file now has it after an outside edit.
+    ///
+    /// Fields not edited here follow the file and edits the file didn't touch are kept.
+    /// Fields changed on both sides keep the edit and are reported through the conflict
+    /// notice until `resolve_conflict` is called.
+    pub(crate) fn merge_external(&mut self, monitor: &str, disk: WallpaperProfileEntry) {
+        // Config-only settings come along with the new base.
+        let original = std::mem::replace(&mut self.base, disk.clone());
+        let mine = self.to_entry(monitor);
+        let mut merged = mine.clone();
+        let mut fields = Vec::new();
+        macro_rules! merge {
+            ($($field:ident),*) => {$(
+                if mine.$field == original.$field {
+                    merged.$field = disk.$field.clone();
+                } else if disk.$field != original.$field && disk.$field != mine.$field {
+                    fields.push(stringify!($field));
+                }
+            )*};
+        }
+        merge!(
+            path,
+            extra_paths,
+            enabled,
+            scale,
+            order,
+            interval_seconds,
+            audio,
+            audio_device,
+            volume,
+            source,
+            margins
+        );
+
+        self.load_edits(merged);
+        self.conflict = (!fields.is_empty()).then_some(Conflict { fields, mine });
+    }
+
+    /// Settle a conflict reported by `merge_external`.
+    pub(crate) fn resolve_conflict(&mut self, choice: ConflictChoice) {
+        let Some(conflict) = self.conflict.take() else {
+            return;
+        };
+        match choice {
+            ConflictChoice::KeepMine => self.load_edits(conflict.mine),
+            ConflictChoice::TakeTheirs => self.load_edits(self.base.clone()),
+            ConflictChoice::Merge => {}
+        }
+    }
+
+    /// Take a picture saved from live tweaks as the saved value, keeping other edits.
+    pub(crate) fn keep_picture(&mut self, picture: Picture) {
+        self.base.picture = picture;
+    }
+
+    /// Show `entry` as unsaved edits on top of the current base.
+    fn load_edits(&mut self, entry: WallpaperProfileEntry) {
+        // An interval still being typed is not in the entry yet, so carry it over.
+        let interval_text = self
+            .interval_error
+            .is_some()
+            .then(|| self.interval_text.clone());
+        let base = self.base.clone();
+        let path_tags_text = std::mem::take(&mut self.path_tags_text);
+        *self = Self::new(Some(entry));
+        self.dirty = self.to_entry(base.monitor.as_deref().unwrap_or_default()) != base;
+        self.base = base;
+        self.path_tags_text = path_tags_text;
+        if let Some(text) = interval_text {
+            self.set_interval(text);
+        }
+    }
+
+    /// Build the config entry for `monitor` from the current editor state.
+    pub(crate) fn to_entry(&self, monitor: &str) -> WallpaperProfileEntry {
+        WallpaperProfileEntry {
+            monitor: Some(monitor.to_string()),
+            // An untouched placeholder stays in the file until a real path is chosen.
+            path: self.path_buf().or_else(|| {
+                self.base
+                    .path
+                    .clone()
+                    .filter(|path| config::is_placeholder(path))
+            }),
+            extra_paths: self
+                .extra_path_texts
+                .iter()
+                .filter_map(|text| config::parse_user_path(text))
+                .collect(),
+            enabled: self.enabled,
+            scale: self.scale,
+            order: self.order,
+            interval_seconds: self.interval_seconds.max(1),
+            audio: self.audio,
+            audio_device: self.audio_device.clone(),
+            volume: self.volume,
+            source: Some(EntrySource {
+                tags: tags::parse_tags(&self.source_tags_text)
+                    .into_iter()
+                    .collect(),
+                seasonal: self.seasonal,
+            })
+            .filter(EntrySource::is_active),
+            margins: self.margins,
+            ..self.base.clone()
         }
     }
 
@@ -89,6 +281,85 @@ impl MonitorEditor {
         config::parse_user_path(&self.path_text)
     }
 
+    pub(crate) fn set_extra_path_text(&mut self, slot: usize, value: String) {
+        if let Some(text) = self.extra_path_texts.get_mut(slot) {
+            *text = value;
+            self.dirty = true;
+        }
+    }
+
+    /// Append a source to the list played after the path.
+    pub(crate) fn add_extra_path(&mut self, path: PathBuf) {
+        self.extra_path_texts
+            .push(path.to_string_lossy().into_owned());
+        self.dirty = true;
+    }
+
+    pub(crate) fn remove_extra_path(&mut self, slot: usize) {
+        if slot < self.extra_path_texts.len() {
+            self.extra_path_texts.remove(slot);
+            self.dirty = true;
+        }
+    }
+
+    /// The folder the gallery picks from, and the files already picked from it: a
+    /// folder source picks from itself, and a list of files sharing one folder
+    /// (as the gallery leaves it) from theirs.
+    pub(crate) fn gallery_source(&self) -> Option<(PathBuf, Vec<PathBuf>)> {
+        let first = config::normalize_entry_path(&self.path_buf()?);
+        match self.path_kind {
+            PathKind::Folder if !self.is_mix() => Some((first, Vec::new())),
+            PathKind::File => {
+                let folder = first.parent()?.to_path_buf();
+                let mut files = vec![first];
+                for text in &self.extra_path_texts {
+                    files.push(config::normalize_entry_path(&config::parse_user_path(
+                        text,
+                    )?));
+                }
+                files
+                    .iter()
+                    .all(|file| file.is_file() && file.parent() == Some(folder.as_path()))
+                    .then_some((folder, files))
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace the sources with `sources`, played in that order.
+    pub(crate) fn set_sources(&mut self, sources: Vec<PathBuf>) {
+        let mut sources = sources.into_iter();
+        let Some(first) = sources.next() else {
+            return;
+        };
+        self.extra_path_texts = sources
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        self.set_path_buf(first);
+    }
+
+    /// Several sources are listed, so they play as one playlist.
+    fn is_mix(&self) -> bool {
+        !self.extra_path_texts.is_empty()
+    }
+
+    /// The file or folder whose preview the tab shows: the first source listed.
+    pub(crate) fn preview_source(&self) -> Option<PathBuf> {
+        if !matches!(self.path_kind, PathKind::File | PathKind::Folder) {
+            return None;
+        }
+        Some(config::normalize_entry_path(&self.path_buf()?))
+    }
+
+    /// The source as it will be launched, if it is a single video file.
+    pub(crate) fn video_path(&self) -> Option<PathBuf> {
+        if self.path_kind != PathKind::File || self.is_mix() {
+            return None;
+        }
+        let path = config::normalize_entry_path(&self.path_buf()?);
+        config::is_probably_video(&path).then_some(path)
+    }
+
     pub(crate) fn set_scale(&mut self, scale: ScaleMode) {
         if self.scale != scale {
             self.scale = scale;
@@ -117,18 +388,173 @@ impl MonitorEditor {
         self.dirty = true;
     }
 
-    pub(crate) fn mark_saved(&mut self) {
+    pub(crate) fn set_audio(&mut self, value: bool) {
+        if self.audio != value {
+            self.audio = value;
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn set_volume(&mut self, value: u8) {
+        let value = value.min(MAX_VOLUME);
+        if self.volume != value {
+            self.volume = value;
+            self.dirty = true;
+        }
+    }
+
+    /// Select a sink by name; an empty name means the system default output.
+    pub(crate) fn set_audio_device(&mut self, name: String) {
+        let device = (!name.is_empty()).then_some(name);
+        if self.audio_device != device {
+            self.audio_device = device;
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn set_source_tags(&mut self, value: String) {
+        self.source_tags_text = value;
+        self.dirty = true;
+    }
+
+    pub(crate) fn set_seasonal(&mut self, value: bool) {
+        if self.seasonal != value {
+            self.seasonal = value;
+            self.dirty = true;
+        }
+    }
+
+    /// Update one margin from its text field; anything but digits is ignored.
+    pub(crate) fn set_margin_text(&mut self, edge: MarginEdge, value: String) {
+        let pixels = if value.is_empty() {
+            0
+        } else {
+            match value.parse() {
+                Ok(pixels) => pixels,
+                Err(_) => return,
+            }
+        };
+        edge.set(&mut self.margins, pixels);
+        self.margin_texts[edge as usize] = value;
+        self.dirty = true;
+    }
+
+    /// Replace all margins, e.g. from dragging the preview's handles.
+    pub(crate) fn set_margins(&mut self, margins: Margins) {
+        if self.margins != margins {
+            self.margins = margins;
+            self.margin_texts = margin_texts(&margins);
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn set_path_tags_text(&mut self, value: String) {
+        self.path_tags_text = value;
+    }
+
+    /// The existing file, folder, or playlist tags are attached to.
+    pub(crate) fn tag_path(&self) -> Option<PathBuf> {
+        if matches!(self.path_kind, PathKind::Empty | PathKind::Unknown) {
+            return None;
+        }
+        sources::for_path(&self.path_buf()?)?
+            .local_path()
+            .map(Path::to_path_buf)
+    }
+
+    /// The tags typed for the current path.
+    pub(crate) fn path_tags(&self) -> BTreeSet<String> {
+        tags::parse_tags(&self.path_tags_text)
+    }
+
+    /// Reload the tag field from the tag store after the path changed.
+    pub(crate) fn sync_path_tags(&mut self, state: &State) {
+        self.path_tags_text = self
+            .tag_path()
+            .map(|path| {
+                tags::tags_for(state, &path)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+    }
+
+    /// Take the source, scale, order, and interval of `source`, another tab's entry.
+    pub(crate) fn copy_settings(&mut self, source: &WallpaperProfileEntry) {
+        if let Some(path) = &source.path {
+            self.set_sources(
+                std::iter::once(path.clone())
+                    .chain(source.extra_paths.iter().cloned())
+                    .collect(),
+            );
+        }
+        self.set_scale(source.scale);
+        self.set_order(source.order);
+        if source.interval_seconds != self.interval_seconds || self.interval_error.is_some() {
+            self.set_interval(format_interval(source.interval_seconds));
+        }
+    }
+
+    /// Drop this output's overrides so it follows its shared entry again.
+    pub(crate) fn reset_overrides(&mut self) {
+        let Some(inherited) = self.base.shared.as_ref().map(|shared| shared.inherited) else {
+            return;
+        };
+        if let Some(enabled) = inherited.enabled {
+            self.set_enabled(enabled);
+        }
+        if let Some(scale) = inherited.scale {
+            self.set_scale(scale);
+        }
+        if let Some(order) = inherited.order {
+            self.set_order(order);
+        }
+        if let Some(interval) = inherited.interval_seconds
+            && interval != self.interval_seconds
+        {
+            self.set_interval(format_interval(interval));
+        }
+        if let Some(margins) = inherited.margins {
+            self.set_margins(margins);
+        }
+    }
+
+    pub(crate) fn mark_saved(&mut self, saved: WallpaperProfileEntry) {
+        self.base = saved;
         self.dirty = false;
+        self.conflict = None;
     }
 
     pub(crate) fn is_dirty(&self) -> bool {
         self.dirty
     }
 
+    pub(crate) fn has_conflict(&self) -> bool {
+        self.conflict.is_some()
+    }
+
     pub(crate) fn enabled(&self) -> bool {
         self.enabled
     }
 
+    pub(crate) fn audio(&self) -> bool {
+        self.audio
+    }
+
+    /// Saved as part of a `mirror = true` entry.
+    pub(crate) fn is_mirrored(&self) -> bool {
+        self.base
+            .shared
+            .as_ref()
+            .is_some_and(|shared| shared.mirror)
+    }
+
+    /// No source chosen yet, so Start skips this monitor.
+    pub(crate) fn is_unconfigured(&self) -> bool {
+        self.to_entry("").is_unconfigured()
+    }
+
     pub(crate) fn set_enabled(&mut self, value: bool) {
         if self.enabled != value {
             self.enabled = value;
@@ -143,22 +569,34 @@ pub(crate) enum PathKind {
     Empty,
     File,
     Folder,
+    Playlist,
+    Stream,
     Unknown,
 }
 
 impl PathKind {
     pub(crate) fn description(&self) -> &'static str {
         match self {
-            PathKind::Empty => "No path configured.",
+            PathKind::Empty => "No wallpaper chosen yet; this monitor is skipped on Start.",
             PathKind::File => "Detected: file",
             PathKind::Folder => "Detected: folder",
+            PathKind::Playlist => "Detected: playlist",
+            PathKind::Stream => "Detected: stream (checked when it starts playing)",
             PathKind::Unknown => "Unable to detect path type (will try at runtime)",
         }
     }
 }
 
 impl MonitorTab {
-    pub(crate) fn view(&self, index: usize, icon: Option<&svg::Handle>) -> Element<'_, Message> {
+    pub(crate) fn view(
+        &self,
+        index: usize,
+        icon: Option<&svg::Handle>,
+        thumbnail: Option<&widget::image::Handle>,
+        loop_points: Option<LoopPoints>,
+        sinks: &[AudioSink],
+        others: Vec<String>,
+    ) -> Element<'_, Message> {
         let Monitor {
             name,
             description,
@@ -186,11 +624,79 @@ impl MonitorTab {
                             .on_toggle(move |checked| Message::EnabledToggled(index, checked)),
                     ),
             )
+            .push(
+                Row::new()
+                    .spacing(8)
+                    .align_y(alignment::Vertical::Center)
+                    .push(text("Same wallpaper on every monitor:").size(16))
+                    .push(
+                        checkbox("", self.editor.is_mirrored())
+                            .on_toggle(move |checked| Message::MirrorToggled(index, checked)),
+                    ),
+            )
             .push(self.media_row(index, icon));
+        if !others.is_empty() {
+            body = body.push(
+                Row::new()
+                    .spacing(8)
+                    .align_y(alignment::Vertical::Center)
+                    .push(text("Copy source, scale, order, and interval:").size(16))
+                    .push(
+                        pick_list(others, None::<String>, move |target| {
+                            Message::CopySettingsTo(index, target)
+                        })
+                        .placeholder("Copy to…"),
+                    ),
+            );
+        }
+
+        if let Some(conflict) = &self.editor.conflict {
+            body = body.push(conflict_notice(index, &conflict.fields));
+        }
+        if let Some(shared) = &self.editor.base.shared {
+            body = body.push(self.shared_notice(index, shared));
+        }
+
+        body = body.push(self.extra_sources(index));
+        body = body.push(
+            text(if self.editor.is_mix() {
+                "Plays every source listed as one playlist."
+            } else {
+                self.editor.path_kind.description()
+            })
+            .size(14),
+        );
+        if let Some(thumbnail) = thumbnail {
+            body = body.push(
+                widget::image(thumbnail.clone())
+                    .width(Length::Fixed(THUMBNAIL_WIDTH))
+                    .content_fit(ContentFit::ScaleDown),
+            );
+        }
+        if let Some(grid) = &self.editor.base.grid {
+            body = body.push(
+                text(format!(
+                    "Plays a grid of {} sources from the config file instead of this source.",
+                    grid.sources.len()
+                ))
+                .size(14),
+            );
+        }
+        if self.editor.base.pip.is_some() {
+            body = body.push(
+                text("Shows a picture-in-picture source from the config file in one corner.")
+                    .size(14),
+            );
+        }
+        body = body.push(self.tag_controls(index));
 
-        body = body.push(text(self.editor.path_kind.description()).size(14));
+        if self.editor.video_path().is_some() {
+            body = body.push(loop_controls(index, loop_points));
+        }
 
-        if self.editor.path_kind == PathKind::Folder {
+        if self.editor.is_mix()
+            || matches!(self.editor.path_kind, PathKind::Folder | PathKind::Playlist)
+        {
             body = body
                 .push(folder_controls(index, self.editor.order))
                 .push(interval_row(index, &self.editor.interval_text));
@@ -198,15 +704,183 @@ impl MonitorTab {
                 let warn_color = Color::from_rgb(0.95, 0.56, 0.56);
                 body = body.push(text(err).style(move |_| widget::text::Style {
                     color: Some(warn_color),
-                    ..Default::default()
                 }));
             }
         }
 
         body = body.push(scale_controls(index, self.editor.scale));
+        body = body.push(self.margin_controls(index));
+        body = body.push(audio_controls(
+            index,
+            self.editor.audio,
+            self.editor.audio_device.as_deref(),
+            self.editor.volume,
+            sinks,
+        ));
         container(body).into()
     }
 
+    fn tag_controls(&self, index: usize) -> Element<'_, Message> {
+        let mut column = Column::new().spacing(8);
+        if self.editor.tag_path().is_some() {
+            column = column.push(
+                Row::new()
+                    .spacing(12)
+                    .align_y(alignment::Vertical::Center)
+                    .push(text("Tags"))
+                    .push(
+                        text_input("nature, dark", &self.editor.path_tags_text)
+                            .on_input(move |value| Message::PathTagsChanged(index, value))
+                            .on_submit(Message::SaveTagsPressed(index))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        button(text("Save tags"))
+                            .on_press(Message::SaveTagsPressed(index))
+                            .style(accent_button_style())
+                            .padding([6, 14]),
+                    ),
+            );
+        }
+        column
+            .push(
+                Row::new()
+                    .spacing(12)
+                    .align_y(alignment::Vertical::Center)
+                    .push(text("Draw from tags"))
+                    .push(
+                        text_input(
+                            "leave empty to play the source above",
+                            &self.editor.source_tags_text,
+                        )
+                        .on_input(move |value| Message::SourceTagsChanged(index, value))
+                        .width(Length::Fill),
+                    )
+                    .push(
+                        checkbox("Follow seasons", self.editor.seasonal)
+                            .on_toggle(move |checked| Message::SeasonalToggled(index, checked)),
+                    ),
+            )
+            .into()
+    }
+
+    /// Which monitors share this entry and what this one sets differently.
+    fn shared_notice(&self, index: usize, shared: &SharedEntry) -> Element<'_, Message> {
+        let others: Vec<&str> = shared
+            .monitors
+            .iter()
+            .map(String::as_str)
+            .filter(|monitor| *monitor != self.monitor.name)
+            .collect();
+        let overridden = shared
+            .overrides(&self.editor.to_entry(&self.monitor.name))
+            .fields();
+
+        let shares = if shared.mirror {
+            "Mirrors one config entry onto every monitor".to_string()
+        } else {
+            format!("Shares one config entry with {}", others.join(", "))
+        };
+        let mut column = Column::new()
+            .spacing(8)
+            .push(text(format!("{shares}.")).size(14));
+        column = if overridden.is_empty() {
+            column.push(text("Every setting below is inherited from the shared entry.").size(14))
+        } else {
+            column.push(
+                Row::new()
+                    .spacing(12)
+                    .align_y(alignment::Vertical::Center)
+                    .push(
+                        text(format!(
+                            "Set differently on this monitor: {}.",
+                            overridden.join(", ")
+                        ))
+                        .size(14),
+                    )
+                    .push(
+                        button(text("Use shared settings"))
+                            .on_press(Message::OverridesReset(index))
+                            .style(accent_button_style()),
+                    ),
+            )
+        };
+        column
+            .push(
+                text("Changing the source, tags, or audio gives this monitor an entry of its own.")
+                    .size(14),
+            )
+            .into()
+    }
+
+    /// Sources played after the main one, each removable, and buttons to add more.
+    fn extra_sources(&self, index: usize) -> Element<'_, Message> {
+        let mut column = Column::new().spacing(8);
+        for (slot, path) in self.editor.extra_path_texts.iter().enumerate() {
+            column = column.push(
+                Row::new()
+                    .spacing(12)
+                    .align_y(alignment::Vertical::Center)
+                    .push(text("Then:"))
+                    .push(
+                        text_input("/path/to/image, video, or folder", path)
+                            .on_input(move |value| Message::ExtraSourceChanged(index, slot, value))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        button(text("Remove"))
+                            .on_press(Message::ExtraSourceRemoved(index, slot))
+                            .style(accent_button_style())
+                            .padding([6, 14]),
+                    ),
+            );
+        }
+        let mut buttons = Row::new()
+            .spacing(8)
+            .push(
+                button(text("Add file"))
+                    .on_press(Message::AddSourcePressed(index, PathSelection::File))
+                    .style(accent_button_style())
+                    .padding([6, 14]),
+            )
+            .push(
+                button(text("Add folder"))
+                    .on_press(Message::AddSourcePressed(index, PathSelection::Folder))
+                    .style(accent_button_style())
+                    .padding([6, 14]),
+            );
+        if self.editor.gallery_source().is_some() {
+            buttons = buttons.push(
+                button(text("Gallery…"))
+                    .on_press(Message::GalleryOpened(index))
+                    .style(accent_button_style())
+                    .padding([6, 14]),
+            );
+        }
+        column.push(buttons).into()
+    }
+
+    fn margin_controls(&self, index: usize) -> Element<'_, Message> {
+        let mut fields = Row::new().spacing(12).align_y(alignment::Vertical::Center);
+        for edge in MarginEdge::ALL {
+            fields = fields.push(text(edge.label())).push(
+                text_input("0", &self.editor.margin_texts[edge as usize])
+                    .on_input(move |value| Message::MarginChanged(index, edge, value))
+                    .width(Length::Fixed(64.0)),
+            );
+        }
+        Column::new()
+            .spacing(8)
+            .push(text("Margins (px left uncovered, e.g. behind a bar)"))
+            .push(fields)
+            .push(MarginPreview::new(
+                self.editor.margins,
+                (self.monitor.width, self.monitor.height),
+                move |margins| Message::MarginsDragged(index, margins),
+            ))
+            .into()
+    }
+
     fn media_row(&self, index: usize, folder_icon: Option<&svg::Handle>) -> Element<'_, Message> {
         let file_icon: Element<'_, Message> = load_file_icon()
             .map(|handle| {
@@ -240,19 +914,64 @@ impl MonitorTab {
             .push(
                 button(file_icon)
                     .on_press(Message::BrowsePressed(index, PathSelection::File))
-                    .style(purple_button_style())
+                    .style(accent_button_style())
                     .padding(6),
             )
             .push(
                 button(folder_icon)
                     .on_press(Message::BrowsePressed(index, PathSelection::Folder))
-                    .style(purple_button_style())
+                    .style(accent_button_style())
                     .padding(6),
             )
             .into()
     }
 }
 
+fn conflict_notice(index: usize, fields: &[&str]) -> Element<'static, Message> {
+    let fields = fields
+        .iter()
+        .map(|field| field.replace('_', " "))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Column::new()
+        .spacing(8)
+        .push(
+            text(format!(
+                "The config file changed these settings while you were editing them: {}. \
+                 Merge keeps your value for these and the file's for everything else.",
+                fields
+            ))
+            .size(14)
+            .style(|_| widget::text::Style {
+                color: Some(Color::from_rgb(0.95, 0.7, 0.3)),
+            }),
+        )
+        .push(
+            Row::new()
+                .spacing(8)
+                .push(
+                    button(text("Keep mine"))
+                        .on_press(Message::ConflictResolved(index, ConflictChoice::KeepMine))
+                        .style(accent_button_style()),
+                )
+                .push(
+                    button(text("Take theirs"))
+                        .on_press(Message::ConflictResolved(index, ConflictChoice::TakeTheirs))
+                        .style(accent_button_style()),
+                )
+                .push(
+                    button(text("Merge"))
+                        .on_press(Message::ConflictResolved(index, ConflictChoice::Merge))
+                        .style(accent_button_style()),
+                ),
+        )
+        .into()
+}
+
+fn margin_texts(margins: &Margins) -> [String; 4] {
+    MarginEdge::ALL.map(|edge| edge.get(margins).to_string())
+}
+
 fn folder_controls(index: usize, order: SlideshowOrder) -> Element<'static, Message> {
     let sequential = widget::radio(
         "Sequential",
@@ -267,13 +986,53 @@ fn folder_controls(index: usize, order: SlideshowOrder) -> Element<'static, Mess
         Some(order),
         move |choice| Message::OrderChanged(index, choice),
     );
+
+    let least_shown = widget::radio(
+        "Least shown first",
+        SlideshowOrder::LeastShown,
+        Some(order),
+        move |choice| Message::OrderChanged(index, choice),
+    );
     Column::new()
         .spacing(8)
         .push(text("Folder playback"))
-        .push(Row::new().spacing(12).push(sequential).push(random))
+        .push(
+            Row::new()
+                .spacing(12)
+                .push(sequential)
+                .push(random)
+                .push(least_shown),
+        )
         .into()
 }
 
+fn loop_controls(index: usize, loop_points: Option<LoopPoints>) -> Element<'static, Message> {
+    let mut row = Row::new()
+        .spacing(12)
+        .align_y(alignment::Vertical::Center)
+        .push(text("Loop"))
+        .push(
+            button(text("Find seamless loop"))
+                .on_press(Message::FindLoopPressed(index))
+                .style(accent_button_style())
+                .padding([6, 14]),
+        );
+
+    if let Some(points) = loop_points {
+        row = row
+            .push(text(format!("{:.1}s – {:.1}s", points.start, points.end)).size(14))
+            .push(
+                button(text("Clear"))
+                    .on_press(Message::ClearLoopPressed(index))
+                    .style(accent_button_style())
+                    .padding([6, 14]),
+            );
+    } else {
+        row = row.push(text("Whole file").size(14));
+    }
+    row.into()
+}
+
 fn interval_row<'a>(index: usize, current: &'a str) -> Element<'a, Message> {
     Row::new()
         .spacing(12)
@@ -300,6 +1059,15 @@ fn scale_controls(index: usize, scale: ScaleMode) -> Element<'static, Message> {
     let stretch = widget::radio("Stretch", ScaleMode::Stretch, Some(scale), move |choice| {
         Message::ScaleChanged(index, choice)
     });
+    let fill = widget::radio("Fill", ScaleMode::Fill, Some(scale), move |choice| {
+        Message::ScaleChanged(index, choice)
+    });
+    let center = widget::radio("Center", ScaleMode::Center, Some(scale), move |choice| {
+        Message::ScaleChanged(index, choice)
+    });
+    let tile = widget::radio("Tile", ScaleMode::Tile, Some(scale), move |choice| {
+        Message::ScaleChanged(index, choice)
+    });
 
     Column::new()
         .spacing(8)
@@ -309,7 +1077,61 @@ fn scale_controls(index: usize, scale: ScaleMode) -> Element<'static, Message> {
                 .spacing(12)
                 .push(original)
                 .push(fit)
-                .push(stretch),
+                .push(stretch)
+                .push(fill)
+                .push(center)
+                .push(tile),
         )
         .into()
 }
+
+fn audio_controls(
+    index: usize,
+    audio: bool,
+    device: Option<&str>,
+    volume: u8,
+    sinks: &[AudioSink],
+) -> Element<'static, Message> {
+    let mut row = Row::new()
+        .spacing(12)
+        .align_y(alignment::Vertical::Center)
+        .push(text("Audio"))
+        .push(
+            checkbox("Play sound", audio)
+                .on_toggle(move |checked| Message::AudioToggled(index, checked)),
+        );
+
+    if audio {
+        let mut options = vec![AudioSink::system_default()];
+        options.extend(sinks.iter().cloned());
+        // Keep a configured sink selectable even while it is unplugged.
+        if let Some(name) = device
+            && !options.iter().any(|sink| sink.name == name)
+        {
+            options.push(AudioSink {
+                name: name.to_string(),
+                description: format!("{name} (unavailable)"),
+            });
+        }
+        let selected = options
+            .iter()
+            .find(|sink| sink.name == device.unwrap_or_default())
+            .cloned();
+        row = row.push(text("Output")).push(
+            pick_list(options, selected, move |sink: AudioSink| {
+                Message::AudioDeviceSelected(index, sink.name)
+            })
+            .width(Length::Fixed(260.0)),
+        );
+        row = row
+            .push(text("Volume"))
+            .push(
+                slider(0..=MAX_VOLUME, volume, move |value| {
+                    Message::VolumeChanged(index, value)
+                })
+                .width(Length::Fixed(160.0)),
+            )
+            .push(text(format!("{volume}%")).size(14));
+    }
+    row.into()
+}
@@ -0,0 +1,281 @@
+//! Gallery picker for folder sources: the folder's images and videos as a grid of
+//! previews, to choose which of them play and in what order.
+//!
+//! Using the selection turns the entry into a list of files (`path = [...]`), which
+//! plays in the order shown here; selecting everything in the folder's own order
+//! keeps the folder itself as the source.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use iced::widget::{self, Column, Row, button, checkbox, container, text};
+use iced::{ContentFit, Element, Length, alignment};
+
+use super::{
+    message::Message,
+    style::{accent_button_style, muted_button_style},
+};
+
+/// Previews per row.
+const COLUMNS: usize = 4;
+const CARD_WIDTH: f32 = 180.0;
+const PREVIEW_HEIGHT: f32 = 110.0;
+/// Previews made at the same time, so a large folder does not tie up every worker.
+pub(crate) const PREVIEWS_IN_FLIGHT: usize = 4;
+
+/// One file of the folder.
+#[derive(Debug)]
+struct Item {
+    path: PathBuf,
+    selected: bool,
+}
+
+/// The open gallery, for the tab at `tab`.
+#[derive(Debug)]
+pub(crate) struct Gallery {
+    pub tab: usize,
+    pub folder: PathBuf,
+    /// The folder's files, in the order they will play; `None` while it is scanned.
+    items: Option<Vec<Item>>,
+    /// The folder's files as listed, to tell whether the selection is the whole folder.
+    listing: Vec<PathBuf>,
+    /// Files chosen before the gallery opened, in their order.
+    chosen: Vec<PathBuf>,
+    /// Files whose preview has not been asked for yet.
+    unrequested: VecDeque<PathBuf>,
+    error: Option<String>,
+}
+
+impl Gallery {
+    /// A gallery of `folder` with `chosen` selected; an empty `chosen` selects every file.
+    pub(crate) fn new(tab: usize, folder: PathBuf, chosen: Vec<PathBuf>) -> Self {
+        Self {
+            tab,
+            folder,
+            items: None,
+            listing: Vec::new(),
+            chosen,
+            unrequested: VecDeque::new(),
+            error: None,
+        }
+    }
+
+    /// Fill in the scanned files: the chosen ones first, in their order, then the rest.
+    pub(crate) fn scanned(&mut self, result: Result<Vec<PathBuf>, String>) {
+        let files = match result {
+            Ok(files) => files,
+            Err(err) => {
+                self.error = Some(err);
+                self.items = Some(Vec::new());
+                return;
+            }
+        };
+        let mut items: Vec<Item> = self
+            .chosen
+            .iter()
+            .filter(|path| files.contains(path))
+            .map(|path| Item {
+                path: path.clone(),
+                selected: true,
+            })
+            .collect();
+        let select_all = items.is_empty();
+        items.extend(
+            files
+                .iter()
+                .filter(|path| !self.chosen.contains(path))
+                .map(|path| Item {
+                    path: path.clone(),
+                    selected: select_all,
+                }),
+        );
+        self.unrequested = items.iter().map(|item| item.path.clone()).collect();
+        self.items = Some(items);
+        self.listing = files;
+    }
+
+    /// The next file to make a preview of.
+    pub(crate) fn next_preview(&mut self) -> Option<PathBuf> {
+        self.unrequested.pop_front()
+    }
+
+    pub(crate) fn contains(&self, path: &Path) -> bool {
+        self.items.iter().flatten().any(|item| item.path == path)
+    }
+
+    pub(crate) fn toggle(&mut self, index: usize) {
+        if let Some(item) = self.items.iter_mut().flatten().nth(index) {
+            item.selected = !item.selected;
+        }
+    }
+
+    pub(crate) fn select_all(&mut self, selected: bool) {
+        for item in self.items.iter_mut().flatten() {
+            item.selected = selected;
+        }
+    }
+
+    /// Swap the file at `index` with its neighbour `step` places away.
+    pub(crate) fn move_item(&mut self, index: usize, step: isize) {
+        let Some(items) = &mut self.items else {
+            return;
+        };
+        let Some(target) = index
+            .checked_add_signed(step)
+            .filter(|target| *target < items.len())
+        else {
+            return;
+        };
+        items.swap(index, target);
+    }
+
+    /// The selected files in playing order.
+    pub(crate) fn selection(&self) -> Vec<PathBuf> {
+        self.items
+            .iter()
+            .flatten()
+            .filter(|item| item.selected)
+            .map(|item| item.path.clone())
+            .collect()
+    }
+
+    /// Every file is selected, in the folder's own order, so the folder can stay the source.
+    pub(crate) fn is_whole_folder(&self) -> bool {
+        self.selection() == self.listing
+    }
+
+    pub(crate) fn view<'a>(
+        &'a self,
+        thumbnails: &'a HashMap<PathBuf, Option<widget::image::Handle>>,
+    ) -> Element<'a, Message> {
+        let mut panel = Column::new()
+            .spacing(12)
+            .push(text(format!("Gallery of {}", self.folder.display())).size(22));
+        let Some(items) = &self.items else {
+            return container(panel.push(text("Scanning the folder…").size(14)))
+                .padding(12)
+                .style(container::rounded_box)
+                .into();
+        };
+        if let Some(err) = &self.error {
+            panel = panel.push(text(err).size(14));
+        } else if items.is_empty() {
+            panel = panel.push(text("No images or videos in this folder.").size(14));
+        } else {
+            let selected = items.iter().filter(|item| item.selected).count();
+            panel = panel.push(
+                text(format!(
+                    "{} of {} selected. Selected files play in the order shown; move them with the arrows.",
+                    selected,
+                    items.len()
+                ))
+                .size(14),
+            );
+        }
+
+        let mut position = 0;
+        let mut grid = Column::new().spacing(12);
+        for (row, chunk) in items.chunks(COLUMNS).enumerate() {
+            let mut cards = Row::new().spacing(12);
+            for (column, item) in chunk.iter().enumerate() {
+                let index = row * COLUMNS + column;
+                if item.selected {
+                    position += 1;
+                }
+                let preview = thumbnails.get(&item.path).and_then(Option::as_ref);
+                cards = cards.push(card(
+                    index,
+                    item,
+                    item.selected.then_some(position),
+                    preview,
+                    items.len(),
+                ));
+            }
+            grid = grid.push(cards);
+        }
+
+        let actions = Row::new()
+            .spacing(12)
+            .push(
+                button(text("Use selection"))
+                    .on_press(Message::GalleryApplied)
+                    .style(accent_button_style()),
+            )
+            .push(
+                button(text("Select all"))
+                    .on_press(Message::GalleryAllToggled(true))
+                    .style(muted_button_style(None)),
+            )
+            .push(
+                button(text("Select none"))
+                    .on_press(Message::GalleryAllToggled(false))
+                    .style(muted_button_style(None)),
+            )
+            .push(
+                button(text("Close"))
+                    .on_press(Message::GalleryClosed)
+                    .style(muted_button_style(None)),
+            );
+
+        container(panel.push(grid).push(actions))
+            .padding(12)
+            .style(container::rounded_box)
+            .into()
+    }
+}
+
+/// One file's preview, its checkbox, and the arrows that move it.
+fn card<'a>(
+    index: usize,
+    item: &'a Item,
+    position: Option<usize>,
+    preview: Option<&'a widget::image::Handle>,
+    count: usize,
+) -> Element<'a, Message> {
+    let preview: Element<'a, Message> = match preview {
+        Some(handle) => widget::image(handle.clone())
+            .width(Length::Fixed(CARD_WIDTH))
+            .height(Length::Fixed(PREVIEW_HEIGHT))
+            .content_fit(ContentFit::Contain)
+            .into(),
+        None => container(text("…").size(14))
+            .width(Length::Fixed(CARD_WIDTH))
+            .height(Length::Fixed(PREVIEW_HEIGHT))
+            .align_x(alignment::Horizontal::Center)
+            .align_y(alignment::Vertical::Center)
+            .into(),
+    };
+    let name = item
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let label = match position {
+        Some(position) => format!("{position}. {name}"),
+        None => name,
+    };
+
+    let mut back = button(text("◀").size(14)).style(muted_button_style(None));
+    if index > 0 {
+        back = back.on_press(Message::GalleryMoved(index, -1));
+    }
+    let mut forward = button(text("▶").size(14)).style(muted_button_style(None));
+    if index + 1 < count {
+        forward = forward.on_press(Message::GalleryMoved(index, 1));
+    }
+
+    Column::new()
+        .spacing(6)
+        .width(Length::Fixed(CARD_WIDTH))
+        .push(preview)
+        .push(
+            checkbox(label, item.selected)
+                .on_toggle(move |_| Message::GalleryToggled(index))
+                .size(16)
+                .text_size(13),
+        )
+        .push(Row::new().spacing(6).push(back).push(forward))
+        .into()
+}
@@ -0,0 +1,90 @@
+//! Touch gestures for tablets and convertibles.
+//!
+//! A one-finger swipe sideways moves to the next or previous monitor tab, and
+//! pulling down while the page is scrolled to the top looks for monitors again.
+//! A second finger touching down drops the stroke, so pinches and two-finger
+//! scrolls never count.
+
+use iced::{Point, Vector, touch};
+
+/// How far, in logical pixels, a finger must travel sideways to switch tabs.
+const SWIPE_DISTANCE: f32 = 120.0;
+/// How far a finger must pull down from the top to refresh the monitors.
+const PULL_DISTANCE: f32 = 140.0;
+
+/// What a finished stroke asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Gesture {
+    NextTab,
+    PreviousTab,
+    RefreshMonitors,
+}
+
+/// The finger being followed, where it went down, and whether the page was at the top.
+#[derive(Debug, Clone, Copy)]
+struct Stroke {
+    finger: touch::Finger,
+    start: Point,
+    at_top: bool,
+    /// Another finger touched down meanwhile.
+    cancelled: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Gestures {
+    stroke: Option<Stroke>,
+}
+
+impl Gestures {
+    /// Follow `event`; returns the gesture a stroke made once its finger lifts.
+    /// `at_top` says whether the page is scrolled all the way up.
+    pub(crate) fn update(&mut self, event: touch::Event, at_top: bool) -> Option<Gesture> {
+        match event {
+            touch::Event::FingerPressed { id, position } => {
+                match &mut self.stroke {
+                    Some(stroke) => stroke.cancelled = true,
+                    None => {
+                        self.stroke = Some(Stroke {
+                            finger: id,
+                            start: position,
+                            at_top,
+                            cancelled: false,
+                        })
+                    }
+                }
+                None
+            }
+            touch::Event::FingerLifted { id, position } => {
+                let stroke = self.stroke.filter(|stroke| stroke.finger == id)?;
+                self.stroke = None;
+                if stroke.cancelled {
+                    return None;
+                }
+                classify(position - stroke.start, stroke.at_top)
+            }
+            touch::Event::FingerLost { id, .. } => {
+                if self.stroke.is_some_and(|stroke| stroke.finger == id) {
+                    self.stroke = None;
+                }
+                None
+            }
+            touch::Event::FingerMoved { .. } => None,
+        }
+    }
+}
+
+/// A mostly sideways stroke switches tabs; a mostly downward one from the top refreshes.
+fn classify(delta: Vector, at_top: bool) -> Option<Gesture> {
+    if delta.x.abs() >= SWIPE_DISTANCE && delta.x.abs() > 2.0 * delta.y.abs() {
+        // Content follows the finger, so swiping left brings in the next tab.
+        Some(if delta.x < 0.0 {
+            Gesture::NextTab
+        } else {
+            Gesture::PreviousTab
+        })
+    } else if at_top && delta.y >= PULL_DISTANCE && delta.y > 2.0 * delta.x.abs() {
+        Some(Gesture::RefreshMonitors)
+    } else {
+        None
+    }
+}
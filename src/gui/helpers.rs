@@ -1,19 +1,32 @@
-use std::{
-    env, fs,
-    path::PathBuf,
-    process::{Command, Stdio},
-    thread,
-};
+use std::{env, path::PathBuf, sync::Arc, time::Duration};
 
 use ashpd::desktop::file_chooser::SelectedFiles;
+use tracing::warn;
 use zbus::{Connection, Proxy, zvariant::OwnedValue};
 
 use crate::{
-    config::{self, WallpaperProfileEntry},
-    monitors::{self, Monitor},
+    audio::{self, AudioSink, Ducker},
+    backend::WallpaperBackend,
+    battery,
+    config::{self, AudioSettings, FolderFilter, WallpaperProfileEntry},
+    daemon, deps,
+    error::WpeError,
+    fallback,
+    fullscreen::{self, FullscreenSettings},
+    idle::{self, IdleSettings},
+    ipc,
+    loops::{self, LoopPoints},
+    monitors::{self, Monitor, MonitorEvent},
+    mounts,
+    mpvpaper::{self, LaunchStage},
+    palette::{self, ThemeColors},
+    queue, runtime,
+    sources::{self, SourceKind},
+    sync::{self, SyncCollection},
+    thumbnails, tray,
 };
 
-use super::{editor::PathKind, message::Message, types::ThemePreference};
+use super::{crossfade::Curtain, editor::PathKind, message::Message, types::ThemePreference};
 use futures::stream::{BoxStream, StreamExt};
 use iced::Subscription;
 use iced::advanced::subscription::{self as advanced_subscription, EventStream, Hasher, Recipe};
@@ -25,29 +38,23 @@ pub enum PathSelection {
     Folder,
 }
 
-/// Detect whether the input path points to a file, folder, or nothing.
+/// Detect which kind of source the input names, if any.
 pub(crate) fn detect_path_kind(input: &str) -> PathKind {
     let trimmed = input.trim();
     if trimmed.is_empty() {
         return PathKind::Empty;
     }
 
-    let path = match config::parse_user_path(trimmed) {
-        Some(path) => path,
-        None => return PathKind::Unknown,
-    };
-
-    match fs::metadata(&path) {
-        Ok(metadata) => {
-            if metadata.is_dir() {
-                PathKind::Folder
-            } else if metadata.is_file() {
-                PathKind::File
-            } else {
-                PathKind::Unknown
-            }
-        }
-        Err(_) => PathKind::Unknown,
+    match config::parse_user_path(trimmed)
+        .as_deref()
+        .and_then(sources::for_path)
+        .map(|source| source.kind())
+    {
+        Some(SourceKind::File) => PathKind::File,
+        Some(SourceKind::Folder) => PathKind::Folder,
+        Some(SourceKind::Playlist) => PathKind::Playlist,
+        Some(SourceKind::Stream) => PathKind::Stream,
+        Some(SourceKind::Tags | SourceKind::Mix) | None => PathKind::Unknown,
     }
 }
 
@@ -86,43 +93,164 @@ pub(crate) fn parse_interval(value: &str) -> Result<u64, String> {
     Ok(total.max(1))
 }
 
+/// Probe mpvpaper/mpv and collect any compatibility warnings.
+pub(crate) async fn probe_dependencies() -> Vec<String> {
+    deps::probe().issues()
+}
+
+/// List PipeWire sinks for the audio output dropdown; empty when PipeWire is unavailable.
+pub(crate) async fn load_audio_sinks() -> Vec<AudioSink> {
+    audio::list_sinks().unwrap_or_default()
+}
+
+/// Capture what each of `monitors` shows and hold it up over the output, so the
+/// restart that follows fades in instead of flashing the desktop. Capturing and
+/// waiting for the frame to reach the screen block, so this runs off the UI thread;
+/// an output that could not be covered comes back without a curtain.
+pub(crate) async fn cover_outputs(monitors: Vec<String>) -> Vec<(String, Option<Arc<Curtain>>)> {
+    runtime::blocking(move || {
+        monitors
+            .into_iter()
+            .map(|monitor| {
+                let curtain = cover(&monitor).map(Arc::new);
+                (monitor, curtain)
+            })
+            .collect()
+    })
+    .await
+}
+
+fn cover(monitor: &str) -> Option<Curtain> {
+    if let Err(err) = fallback::capture(monitor) {
+        warn!("No frame of {} to fade from: {}", monitor, err);
+        return None;
+    }
+    let frame = fallback::cached_frame(monitor)?;
+    Curtain::cover(monitor, &frame)
+        .map_err(|err| warn!("Could not fade out the wallpaper on {}: {}", monitor, err))
+        .ok()
+}
+
+/// The monitors in `sources` whose path exists now. Checking a share that went away
+/// can block for a long time, so this runs off the UI thread.
+pub(crate) async fn probe_sources(sources: Vec<(String, PathBuf)>) -> Vec<String> {
+    runtime::blocking(move || {
+        sources
+            .into_iter()
+            .filter(|(_, path)| path.exists())
+            .map(|(monitor, _)| monitor)
+            .collect()
+    })
+    .await
+}
+
+/// Duck or restore the audible wallpapers on `monitors`, handing the ducker back.
+/// Asking PipeWire and the players is blocking, so it runs off the UI thread.
+pub(crate) async fn update_ducking(
+    mut ducker: Ducker,
+    monitors: Vec<String>,
+    settings: AudioSettings,
+) -> Ducker {
+    runtime::blocking(move || {
+        ducker.update(&monitors, &settings);
+        ducker
+    })
+    .await
+}
+
+/// Wait for a freshly spawned wallpaper to finish `stage` (surface map or media load).
+/// The wait polls and sleeps, so it runs on the blocking pool.
+pub(crate) async fn wait_for_stage(
+    backend: &'static dyn WallpaperBackend,
+    monitor: String,
+    pid: Option<u32>,
+    stage: LaunchStage,
+) -> Result<(), String> {
+    runtime::blocking(move || {
+        backend
+            .wait_for_stage(&monitor, pid, stage)
+            .map_err(describe)
+    })
+    .await
+}
+
+/// Once the wallpaper on `monitor` is playing, keep its frame as the last-known-good image.
+pub(crate) async fn cache_frame(monitor: String) -> Result<(), String> {
+    for stage in [LaunchStage::WaitingForSurface, LaunchStage::Loading] {
+        mpvpaper::wait_for_stage(&monitor, None, stage).map_err(describe)?;
+    }
+    fallback::capture(&monitor).map_err(|err| err.to_string())
+}
+
+/// GUI colors from what `monitor` shows, refreshing its cached frame first while it plays.
+pub(crate) async fn wallpaper_colors(monitor: String) -> Result<ThemeColors, String> {
+    // A failed capture still leaves the previous frame to work from.
+    if ipc::is_listening(&monitor)
+        && let Err(err) = fallback::capture(&monitor)
+    {
+        warn!("Could not capture {}'s wallpaper: {}", monitor, err);
+    }
+    let frame = fallback::cached_frame(&monitor)
+        .ok_or_else(|| format!("No frame of {}'s wallpaper yet", monitor))?;
+    let colors = palette::extract(&frame, 8).map_err(|err| err.to_string())?;
+    ThemeColors::from_palette(&colors).ok_or_else(|| "The wallpaper has no colors".to_string())
+}
+
+/// Mount the shares and drives behind `paths`, returning those still missing.
+pub(crate) async fn mount_sources(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    const MOUNT_TIMEOUT: Duration = Duration::from_secs(30);
+    runtime::blocking(move || mounts::ensure_mounted(&paths, MOUNT_TIMEOUT)).await
+}
+
+/// Pull a `[[sync]]` collection from its remote.
+pub(crate) async fn pull_collection(collection: SyncCollection) -> Result<(), String> {
+    runtime::blocking(move || sync::run(&collection).map_err(|err| err.to_string())).await
+}
+
+/// Send a request to the running `wpe daemon`.
+pub(crate) async fn daemon_request(request: daemon::Request) -> Result<daemon::Reply, String> {
+    daemon::send(&request).map_err(|err| err.to_string())
+}
+
+/// Run the (slow) loop point analysis for a video.
+pub(crate) async fn scan_loop_points(path: PathBuf) -> Result<(PathBuf, LoopPoints), String> {
+    loops::find_loop_points(&path)
+        .map(|points| (path, points))
+        .map_err(|err| err.to_string())
+}
+
+/// Make (or find in the cache) the preview of `source`.
+pub(crate) async fn load_thumbnail(source: PathBuf) -> (PathBuf, Result<PathBuf, String>) {
+    let result = thumbnails::thumbnail(&source).map_err(|err| err.to_string());
+    (source, result)
+}
+
+/// The images and videos of `folder` the gallery offers, in playing order.
+pub(crate) async fn scan_folder(folder: PathBuf) -> (PathBuf, Result<Vec<PathBuf>, String>) {
+    let result = queue::list_files(&folder, &FolderFilter::default())
+        .map(|files| {
+            files
+                .into_iter()
+                .filter(|file| thumbnails::is_image(file) || config::is_probably_video(file))
+                .collect()
+        })
+        .map_err(|err| err.to_string());
+    (folder, result)
+}
+
 /// Query wl_output and convert them into our `Monitor` struct.
 pub(crate) async fn load_monitors() -> Result<Vec<Monitor>, String> {
-    monitors::list_monitors().map_err(|err| err.to_string())
+    monitors::list_monitors().map_err(describe)
 }
 
 /// Read the config profile from disk, creating defaults if needed.
 pub(crate) async fn load_entries() -> Result<Vec<WallpaperProfileEntry>, String> {
-    config::load_wallpaper_entries().map_err(|err| err.to_string())
-}
-
-/// Launch the CLI version in the background using `-c`.
-pub(crate) fn spawn_wallpaper() -> Result<(), String> {
-    // Prevent duplicates: kill any running mpvpaper first.
-    let _ = Command::new("pkill")
-        .arg("mpvpaper")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status();
-
-    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
-    let status = Command::new(exe)
-        .arg("-c")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map_err(|err| err.to_string())?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(format!(
-            "wpe -c exited with status {}",
-            status
-                .code()
-                .map(|code| code.to_string())
-                .unwrap_or_else(|| "signal".into())
-        ))
-    }
+    config::load_wallpaper_entries().map_err(describe)
+}
+
+/// An error for the status banner, followed by what the user can do about it.
+fn describe(err: WpeError) -> String {
+    format!("{err}. {}", err.hint())
 }
 
 /// Use xdg-desktop-portal to pick a local file/folder.
@@ -148,7 +276,7 @@ pub(crate) async fn select_wallpaper_source(
                 .map_err(|_| "Only local files or folders are supported.".to_string())
                 .map(Some)
         } else {
-            Err("Only local files or folders are supported.".into())
+            Err("Only local files or folders can be picked; type a stream URL into the path instead.".into())
         }
     } else {
         Ok(None)
@@ -184,10 +312,108 @@ impl Recipe for MonitorEventRecipe {
 
     fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<'static, Message> {
         let (tx, rx) = futures::channel::mpsc::unbounded();
-        thread::spawn(move || {
-            let _ = monitors::watch_monitors_unbounded(tx);
-        });
-        rx.map(Message::MonitorsUpdated).boxed()
+        runtime::spawn_watcher(move || monitors::watch_monitors_unbounded(tx));
+        rx.map(|event| match event {
+            MonitorEvent::Outputs(monitors) => Message::MonitorsUpdated(monitors),
+            MonitorEvent::ConnectionLost(err) => Message::CompositorLost(err),
+        })
+        .boxed()
+    }
+}
+
+/// Subscription that reports which outputs a fullscreen window covers.
+pub(crate) fn covered_outputs(settings: FullscreenSettings) -> Subscription<Message> {
+    advanced_subscription::from_recipe(CoveredOutputsRecipe(settings))
+}
+
+#[derive(Debug, Clone)]
+struct CoveredOutputsRecipe(FullscreenSettings);
+
+impl Recipe for CoveredOutputsRecipe {
+    type Output = Message;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+        "covered-outputs".hash(state);
+        self.0.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<'static, Message> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let settings = self.0;
+        runtime::spawn_watcher(move || fullscreen::watch(settings, tx));
+        rx.map(Message::CoveredOutputs).boxed()
+    }
+}
+
+/// Subscription that reports whether the machine runs from its battery.
+pub(crate) fn battery_events() -> Subscription<Message> {
+    advanced_subscription::from_recipe(BatteryRecipe)
+}
+
+#[derive(Debug, Clone)]
+struct BatteryRecipe;
+
+impl Recipe for BatteryRecipe {
+    type Output = Message;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+        "battery".hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<'static, Message> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        runtime::spawn_watcher(move || battery::watch(tx));
+        rx.map(Message::PowerSourceChanged).boxed()
+    }
+}
+
+/// Subscription that shows the tray icon and reports what is done with it.
+pub(crate) fn tray_events() -> Subscription<Message> {
+    advanced_subscription::from_recipe(TrayRecipe)
+}
+
+#[derive(Debug, Clone)]
+struct TrayRecipe;
+
+impl Recipe for TrayRecipe {
+    type Output = Message;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+        "tray".hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<'static, Message> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        runtime::spawn_watcher(move || tray::watch(tx));
+        rx.map(Message::Tray).boxed()
+    }
+}
+
+/// Subscription that reports idleness and powered-down outputs.
+pub(crate) fn idle_events(settings: IdleSettings) -> Subscription<Message> {
+    advanced_subscription::from_recipe(IdleRecipe(settings))
+}
+
+#[derive(Debug, Clone)]
+struct IdleRecipe(IdleSettings);
+
+impl Recipe for IdleRecipe {
+    type Output = Message;
+
+    fn hash(&self, state: &mut Hasher) {
+        use std::hash::Hash;
+        "idle".hash(state);
+        self.0.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<'static, Message> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let settings = self.0;
+        runtime::spawn_watcher(move || idle::watch(settings, tx));
+        rx.map(Message::Idle).boxed()
     }
 }
 
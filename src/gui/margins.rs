@@ -0,0 +1,330 @@
+//! Preview of an output's margins with handles that can be dragged to resize them.
+
+use iced::advanced::{
+    Clipboard, Layout, Shell, layout, mouse, renderer,
+    widget::{Tree, Widget, tree},
+};
+use iced::{Border, Color, Element, Event, Length, Point, Rectangle, Size, Theme, event};
+
+use crate::config::Margins;
+
+/// Preview width in logical pixels; the height follows the output's aspect ratio.
+const PREVIEW_WIDTH: f32 = 320.0;
+/// How close to an edge, in preview pixels, a press grabs it.
+const GRAB_DISTANCE: f32 = 8.0;
+const HANDLE_LENGTH: f32 = 28.0;
+const HANDLE_THICKNESS: f32 = 4.0;
+
+/// One side of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MarginEdge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl MarginEdge {
+    pub(crate) const ALL: [MarginEdge; 4] = [
+        MarginEdge::Top,
+        MarginEdge::Right,
+        MarginEdge::Bottom,
+        MarginEdge::Left,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            MarginEdge::Top => "Top",
+            MarginEdge::Right => "Right",
+            MarginEdge::Bottom => "Bottom",
+            MarginEdge::Left => "Left",
+        }
+    }
+
+    pub(crate) fn get(self, margins: &Margins) -> u32 {
+        match self {
+            MarginEdge::Top => margins.top,
+            MarginEdge::Right => margins.right,
+            MarginEdge::Bottom => margins.bottom,
+            MarginEdge::Left => margins.left,
+        }
+    }
+
+    pub(crate) fn set(self, margins: &mut Margins, value: u32) {
+        match self {
+            MarginEdge::Top => margins.top = value,
+            MarginEdge::Right => margins.right = value,
+            MarginEdge::Bottom => margins.bottom = value,
+            MarginEdge::Left => margins.left = value,
+        }
+    }
+
+    fn is_horizontal(self) -> bool {
+        matches!(self, MarginEdge::Left | MarginEdge::Right)
+    }
+}
+
+/// Scaled-down output showing the covered area, with a handle on each inner edge.
+pub(crate) struct MarginPreview<'a, Message> {
+    margins: Margins,
+    output: (u32, u32),
+    on_change: Box<dyn Fn(Margins) -> Message + 'a>,
+}
+
+impl<'a, Message> MarginPreview<'a, Message> {
+    pub(crate) fn new(
+        margins: Margins,
+        output: (u32, u32),
+        on_change: impl Fn(Margins) -> Message + 'a,
+    ) -> Self {
+        Self {
+            margins,
+            output: (output.0.max(1), output.1.max(1)),
+            on_change: Box::new(on_change),
+        }
+    }
+
+    fn scale(&self) -> f32 {
+        PREVIEW_WIDTH / self.output.0 as f32
+    }
+
+    /// The part of the preview the wallpaper still covers.
+    fn covered(&self, bounds: Rectangle) -> Rectangle {
+        let scale = self.scale();
+        let margins = self.margins.clamped(self.output.0, self.output.1);
+        let left = margins.left as f32 * scale;
+        let top = margins.top as f32 * scale;
+        Rectangle {
+            x: bounds.x + left,
+            y: bounds.y + top,
+            width: (bounds.width - left - margins.right as f32 * scale).max(1.0),
+            height: (bounds.height - top - margins.bottom as f32 * scale).max(1.0),
+        }
+    }
+
+    /// The edge of the covered area under `position`, if any is within reach.
+    fn edge_at(&self, bounds: Rectangle, position: Point) -> Option<MarginEdge> {
+        if !bounds.contains(position) {
+            return None;
+        }
+        let covered = self.covered(bounds);
+        MarginEdge::ALL
+            .into_iter()
+            .map(|edge| {
+                let distance = match edge {
+                    MarginEdge::Top => (position.y - covered.y).abs(),
+                    MarginEdge::Bottom => (position.y - (covered.y + covered.height)).abs(),
+                    MarginEdge::Left => (position.x - covered.x).abs(),
+                    MarginEdge::Right => (position.x - (covered.x + covered.width)).abs(),
+                };
+                (edge, distance)
+            })
+            .filter(|(_, distance)| *distance <= GRAB_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(edge, _)| edge)
+    }
+
+    /// Margins with `edge` moved to the cursor.
+    fn dragged(&self, bounds: Rectangle, edge: MarginEdge, position: Point) -> Margins {
+        let preview_pixels = match edge {
+            MarginEdge::Top => position.y - bounds.y,
+            MarginEdge::Bottom => bounds.y + bounds.height - position.y,
+            MarginEdge::Left => position.x - bounds.x,
+            MarginEdge::Right => bounds.x + bounds.width - position.x,
+        };
+        let mut margins = self.margins;
+        edge.set(
+            &mut margins,
+            (preview_pixels.max(0.0) / self.scale()).round() as u32,
+        );
+        margins.clamped(self.output.0, self.output.1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct State {
+    dragging: Option<MarginEdge>,
+}
+
+impl<Message> Widget<Message, Theme, iced::Renderer> for MarginPreview<'_, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Shrink, Length::Shrink)
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &iced::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let height = PREVIEW_WIDTH * self.output.1 as f32 / self.output.0 as f32;
+        layout::atomic(
+            limits,
+            Length::Fixed(PREVIEW_WIDTH),
+            Length::Fixed(height.clamp(60.0, 400.0)),
+        )
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &iced::Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(edge) = cursor
+                    .position()
+                    .and_then(|position| self.edge_at(bounds, position))
+                {
+                    state.dragging = Some(edge);
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(edge) = state.dragging {
+                    let margins = self.dragged(bounds, edge, position);
+                    if margins != self.margins {
+                        shell.publish((self.on_change)(margins));
+                    }
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if state.dragging.take().is_some() =>
+            {
+                return event::Status::Captured;
+            }
+            _ => {}
+        }
+        event::Status::Ignored
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut iced::Renderer,
+        theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        use iced::advanced::Renderer as _;
+
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        let palette = theme.extended_palette();
+
+        // Uncovered edges, then the wallpaper area on top.
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: palette.background.strong.color,
+                    width: 1.0,
+                    radius: 4.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            palette.background.weak.color,
+        );
+        let covered = self.covered(bounds);
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: covered,
+                ..renderer::Quad::default()
+            },
+            palette.primary.base.color,
+        );
+
+        let active = state.dragging.or_else(|| {
+            cursor
+                .position()
+                .and_then(|position| self.edge_at(bounds, position))
+        });
+        for edge in MarginEdge::ALL {
+            let handle = match edge {
+                MarginEdge::Top | MarginEdge::Bottom => Rectangle {
+                    x: covered.center_x() - HANDLE_LENGTH / 2.0,
+                    y: if edge == MarginEdge::Top {
+                        covered.y
+                    } else {
+                        covered.y + covered.height - HANDLE_THICKNESS
+                    },
+                    width: HANDLE_LENGTH,
+                    height: HANDLE_THICKNESS,
+                },
+                MarginEdge::Left | MarginEdge::Right => Rectangle {
+                    x: if edge == MarginEdge::Left {
+                        covered.x
+                    } else {
+                        covered.x + covered.width - HANDLE_THICKNESS
+                    },
+                    y: covered.center_y() - HANDLE_LENGTH / 2.0,
+                    width: HANDLE_THICKNESS,
+                    height: HANDLE_LENGTH,
+                },
+            };
+            let color = if active == Some(edge) {
+                Color::WHITE
+            } else {
+                Color::from_rgba(1.0, 1.0, 1.0, 0.6)
+            };
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: handle,
+                    border: Border {
+                        radius: 2.0.into(),
+                        ..Border::default()
+                    },
+                    ..renderer::Quad::default()
+                },
+                color,
+            );
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &iced::Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        let edge = state.dragging.or_else(|| {
+            cursor
+                .position()
+                .and_then(|position| self.edge_at(layout.bounds(), position))
+        });
+        match edge {
+            Some(edge) if edge.is_horizontal() => mouse::Interaction::ResizingHorizontally,
+            Some(_) => mouse::Interaction::ResizingVertically,
+            None => mouse::Interaction::default(),
+        }
+    }
+}
+
+impl<'a, Message: 'a> From<MarginPreview<'a, Message>> for Element<'a, Message> {
+    fn from(preview: MarginPreview<'a, Message>) -> Self {
+        Element::new(preview)
+    }
+}
@@ -1,27 +1,157 @@
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
 
+use iced::{Size, touch, window};
+
+use crate::audio::{AudioSink, Ducker};
 use crate::config::WallpaperProfileEntry;
-use crate::config::{ScaleMode, SlideshowOrder};
+use crate::config::{HexColor, Margins, ScaleMode, SlideshowOrder, ThemeOverride};
+use crate::control::Action;
+use crate::daemon;
+use crate::idle::IdleEvent;
+use crate::loops::LoopPoints;
 use crate::monitors::Monitor;
+use crate::mpvpaper::LaunchStage;
+use crate::palette::ThemeColors;
+use crate::tray::TrayEvent;
 
-use super::{helpers::PathSelection, types::ThemePreference};
+use super::{
+    crossfade::Curtain, editor::ConflictChoice, helpers::PathSelection, margins::MarginEdge,
+    types::ThemePreference,
+};
 
 /// All events the iced state machine reacts to.
 #[derive(Debug, Clone)]
 pub(crate) enum Message {
     MonitorsLoaded(Result<Vec<Monitor>, String>),
     MonitorsUpdated(Vec<Monitor>),
+    CompositorLost(String),
     EntriesLoaded(Result<Vec<WallpaperProfileEntry>, String>),
     ThemeDetected(ThemePreference),
+    DependenciesProbed(Vec<String>),
+    AudioSinksLoaded(Vec<AudioSink>),
     SelectTab(usize),
+    /// The pointer entered (`true`) or left a tab's button.
+    TabHovered(usize, bool),
+    /// A file was dragged over, dropped on, or dragged out of the window.
+    FileDragged(window::Event),
+    /// A finger touched, moved on, or left the window.
+    Touched(touch::Event),
+    /// The page scrolled to this offset from the top, in logical pixels.
+    Scrolled(f32),
     PathChanged(usize, String),
     BrowsePressed(usize, PathSelection),
     PathPicked(usize, Result<Option<PathBuf>, String>),
+    /// A source's preview was made: the source, then the preview image.
+    ThumbnailLoaded(PathBuf, Result<PathBuf, String>),
+    /// A source after the main one was edited: (tab, slot in the list, text).
+    ExtraSourceChanged(usize, usize, String),
+    ExtraSourceRemoved(usize, usize),
+    /// Pick a file or folder to play after the listed sources.
+    AddSourcePressed(usize, PathSelection),
+    ExtraSourcePicked(usize, Result<Option<PathBuf>, String>),
+    /// Open the gallery picker on the tab's folder.
+    GalleryOpened(usize),
+    /// The gallery's folder was listed: its images and videos.
+    GalleryScanned(PathBuf, Result<Vec<PathBuf>, String>),
+    /// Select or deselect a file, by its place in the gallery.
+    GalleryToggled(usize),
+    /// Move a file this many places along the playing order.
+    GalleryMoved(usize, isize),
+    GalleryAllToggled(bool),
+    GalleryApplied,
+    GalleryClosed,
     EnabledToggled(usize, bool),
     ScaleChanged(usize, ScaleMode),
     OrderChanged(usize, SlideshowOrder),
     IntervalChanged(usize, String),
+    PathTagsChanged(usize, String),
+    SaveTagsPressed(usize),
+    SourceTagsChanged(usize, String),
+    SeasonalToggled(usize, bool),
+    AudioToggled(usize, bool),
+    AudioDeviceSelected(usize, String),
+    VolumeChanged(usize, u8),
+    MarginChanged(usize, MarginEdge, String),
+    MarginsDragged(usize, Margins),
+    ConflictResolved(usize, ConflictChoice),
+    /// Make a monitor of a shared entry follow the shared settings again.
+    OverridesReset(usize),
+    /// Play this monitor's entry on every monitor, or give each its own again.
+    MirrorToggled(usize, bool),
+    /// Copy a tab's source, scale, order, and interval onto the named monitor's tab.
+    CopySettingsTo(usize, String),
+    FindLoopPressed(usize),
+    LoopFound(usize, Result<(PathBuf, LoopPoints), String>),
+    ClearLoopPressed(usize),
     StartPressed,
+    /// Write the tabs to the config without touching the wallpapers.
+    SavePressed,
+    /// Save, then restart the running monitors whose entries changed.
+    ApplyPressed,
     StopPressed,
+    PreviewPressed,
+    /// Skip, go back, or favorite on the wallpaper playing on this monitor.
+    PlayerAction(Action, String),
+    /// Look for connected monitors again.
+    RefreshMonitors,
+    /// Show the monitor badges, or take them down.
+    BadgesToggled,
+    /// Ctrl+K: open or close the command palette.
+    PaletteToggled,
+    PaletteClosed,
+    PaletteQueryChanged(String),
+    /// Move the palette's highlight down (or, negative, up) this many matches.
+    PaletteMoved(isize),
+    /// Enter in the palette: run the highlighted command.
+    PaletteSubmitted,
+    /// A command was clicked in the palette.
+    PaletteChosen(Box<Message>),
+    /// A named profile was picked to switch to.
+    ProfileSelected(String),
+    ProfileNameChanged(String),
+    SaveProfilePressed,
+    CancelLaunchPressed,
+    /// A launch step finished: (launch generation, monitor, stage waited on, result).
+    LaunchStageFinished(u64, String, LaunchStage, Result<(), String>),
+    /// A wallpaper started outside a Start got through a stage: (monitor, stage, result).
+    StageFinished(String, LaunchStage, Result<(), String>),
+    WindowResized(Size),
+    /// The settings window closed; the GUI quits unless it sits in the tray.
+    WindowClosed(window::Id),
+    Tray(TrayEvent),
+    SettingsToggled,
+    OverlayAtLaunchToggled(bool),
+    DefaultScaleChanged(ScaleMode),
+    DefaultIntervalChanged(String),
+    ThemeOverrideChanged(ThemeOverride),
+    AccentTextChanged(String),
+    AccentChannelChanged(usize, u8),
+    AccentPicked(HexColor),
+    SettingsSaved,
+    FromWallpaperToggled(bool),
+    TouchModeToggled(bool),
+    WallpaperColors(Result<ThemeColors, String>),
+    SyncFinished(String, Result<(), String>),
+    DaemonReplied(Result<daemon::Reply, String>),
+    /// Outputs a fullscreen window now covers.
+    CoveredOutputs(BTreeSet<String>),
+    /// Whether the machine now runs from its battery.
+    PowerSourceChanged(bool),
+    /// The session went idle or active, or screens were powered down or up.
+    Idle(IdleEvent),
+    FrameCached(String, Result<(), String>),
+    /// A still of the monitor's wallpaper was captured for `[thermal]`.
+    StillCaptured(String, Result<(), String>),
+    /// Mounting finished for a launch: (launch generation, paths still missing).
+    SourcesMounted(u64, Vec<PathBuf>),
+    /// The players restarting in this launch generation had their frames held up
+    /// (or not, when `None`), so they can be stopped.
+    Covered(u64, Vec<(String, Option<Arc<Curtain>>)>),
+    /// These monitors' sources, missing at launch, can be reached again.
+    SourcesProbed(Vec<String>),
+    /// Ducking was brought up to date; the ducker comes back from its task.
+    DuckingUpdated(Ducker),
+    /// The config file was changed outside the GUI and read again.
+    ConfigChanged(Result<Vec<WallpaperProfileEntry>, String>),
     Tick,
 }
@@ -1,9 +1,19 @@
 pub use app::launch;
+pub use overlay::flash;
 
 mod app;
+mod appearance;
+mod apply;
+mod commands;
+mod crossfade;
+mod drops;
 mod editor;
+mod gallery;
+mod gestures;
 mod helpers;
+mod margins;
 mod message;
 mod overlay;
+mod settings;
 mod style;
 mod types;
@@ -1,11 +1,28 @@
-/// Draw a compositor-level overlay that labels every detected monitor.
-
-use std::{collections::HashMap, thread};
+//! Draw a compositor-level overlay that labels every detected monitor.
+//!
+//! Each badge says what `[gui] badge_label` asks for, in the output's
+//! `[gui.outputs]` color (the accent when it has none), matching its tab in the GUI.
+//! The badges stay up for as long as the [`Overlay`] handle is kept, which the GUI
+//! holds until its window closes. [`flash`] puts up a short-lived badge low in the
+//! middle of every output instead, for `wpe toggle`. Labels are set in the system's
+//! sans-serif font with cosmic-text, at the output's scale.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    io::ErrorKind,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
+use cosmic_text::{
+    Attrs, Buffer, Color as TextColor, Family, FontSystem, Metrics, Shaping, SwashCache, Weight,
+};
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, CompositorState},
     delegate_compositor, delegate_layer, delegate_output, delegate_registry, delegate_shm,
-    output::{OutputHandler, OutputState},
+    output::{OutputHandler, OutputInfo, OutputState},
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
     shell::{
@@ -18,29 +35,141 @@ use smithay_client_toolkit::{
     shm::{Shm, ShmHandler, slot::SlotPool},
 };
 use wayland_client::{
-    Connection, Proxy, QueueHandle,
+    Connection, EventQueue, Proxy, QueueHandle,
+    backend::WaylandError,
     globals::registry_queue_init,
     protocol::{wl_output, wl_shm, wl_surface},
 };
 
+use crate::{
+    config::{self, GuiSettings, HexColor},
+    monitors::{self, Monitor},
+};
+
+use super::style::DEFAULT_ACCENT;
+
+/// Narrowest badge; longer labels widen it up to [`MAX_OVERLAY_WIDTH`].
 const OVERLAY_WIDTH: u32 = 260;
+const MAX_OVERLAY_WIDTH: u32 = 1200;
 const OVERLAY_HEIGHT: u32 = 88;
-const GLYPH_WIDTH: u32 = 5;
-const GLYPH_SCALE: u32 = 4;
-const OVERLAY_BG: [u8; 4] = [0x6E, 0x00, 0x4B, 0xFF];
+const DEFAULT_LABEL: &str = "{name}";
+/// Height of the badge text, in surface pixels.
+const FONT_SIZE: f32 = 30.0;
 const TEXT_COLOR: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+/// How often the overlay thread checks whether it should take the badges down.
+const SHUTDOWN_POLL: Duration = Duration::from_millis(100);
+/// Gap between a [`flash`] badge and the bottom edge of its output.
+const FLASH_MARGIN: i32 = 120;
+
+/// Where a badge sits on its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    /// The top left corner, naming the output.
+    Corner,
+    /// Low in the middle, like a volume popup.
+    Flash,
+}
+
+/// What the badges say, which color each one is, and where they go.
+#[derive(Debug, Clone)]
+struct BadgeStyle {
+    label: String,
+    accent: HexColor,
+    outputs: BTreeMap<String, HexColor>,
+    placement: Placement,
+}
 
-/// Spawn a detached thread that paints overlays for every Wayland output.
-pub fn spawn_overlay() {
-    let _ = thread::Builder::new().name("wpe-overlay".into()).spawn(|| {
-        if let Err(err) = overlay_main() {
-            eprintln!("overlay error: {err}");
+impl BadgeStyle {
+    /// Argb8888 background for the badge on `output`.
+    fn background(&self, output: &str) -> [u8; 4] {
+        // Argb8888 is stored little-endian: blue, green, red, alpha.
+        let [r, g, b] = self.outputs.get(output).unwrap_or(&self.accent).0;
+        [b, g, r, 0xFF]
+    }
+}
+
+/// The running overlay thread. Dropping it removes the badges and waits for the
+/// thread to finish.
+pub struct Overlay {
+    shutdown: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        // Hanging up the channel is the shutdown signal.
+        self.shutdown.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
         }
+    }
+}
+
+/// Spawn a thread that paints overlays for every Wayland output until the returned
+/// handle is dropped.
+///
+/// The thread reconnects with backoff if the compositor goes away, recreating
+/// a badge for every output it finds on the new connection.
+pub fn spawn_overlay(gui: &GuiSettings, accent: Option<HexColor>) -> Overlay {
+    spawn(BadgeStyle {
+        label: gui
+            .badge_label
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LABEL.into()),
+        accent: accent.unwrap_or(DEFAULT_ACCENT),
+        outputs: gui.outputs.clone(),
+        placement: Placement::Corner,
+    })
+}
+
+/// Show `text` in the accent color low in the middle of every output for `duration`,
+/// then take it down. Blocks until it is gone.
+pub fn flash(accent: Option<HexColor>, text: &str, duration: Duration) {
+    let overlay = spawn(BadgeStyle {
+        label: text.to_string(),
+        accent: accent.unwrap_or(DEFAULT_ACCENT),
+        outputs: BTreeMap::new(),
+        placement: Placement::Flash,
     });
+    thread::sleep(duration);
+    drop(overlay);
+}
+
+fn spawn(style: BadgeStyle) -> Overlay {
+    let (shutdown, stop) = mpsc::channel();
+    let thread = thread::Builder::new()
+        .name("wpe-overlay".into())
+        .spawn(move || {
+            let mut attempt = 0;
+            loop {
+                let started = Instant::now();
+                match overlay_main(&style, &stop) {
+                    Ok(()) => return,
+                    Err(err) => eprintln!("overlay error: {err}"),
+                }
+                // A session that lasted a while was healthy, so restart the backoff.
+                if started.elapsed() > Duration::from_secs(30) {
+                    attempt = 0;
+                }
+                if stop.recv_timeout(monitors::reconnect_delay(attempt))
+                    != Err(RecvTimeoutError::Timeout)
+                {
+                    return;
+                }
+                attempt += 1;
+            }
+        })
+        .map_err(|err| eprintln!("overlay error: {err}"))
+        .ok();
+    Overlay {
+        shutdown: Some(shutdown),
+        thread,
+    }
 }
 
-/// Connect to Wayland and drive the layer-shell event loop.
-fn overlay_main() -> Result<(), Box<dyn std::error::Error>> {
+/// Connect to Wayland and drive the layer-shell event loop until `stop` hangs up,
+/// then take the badges down.
+fn overlay_main(style: &BadgeStyle, stop: &Receiver<()>) -> Result<(), Box<dyn Error>> {
     let conn = Connection::connect_to_env()?;
     let (globals, mut event_queue) = registry_queue_init(&conn)?;
     let qh = event_queue.handle();
@@ -49,12 +178,32 @@ fn overlay_main() -> Result<(), Box<dyn std::error::Error>> {
     let layer_shell = LayerShell::bind(&globals, &qh)?;
     let shm = Shm::bind(&globals, &qh)?;
 
-    let mut state = OverlayState::new(&globals, compositor, layer_shell, shm, &qh);
+    let mut state = OverlayState::new(&globals, compositor, layer_shell, shm, style, &qh);
     state.bootstrap_overlays(&qh);
 
-    loop {
-        event_queue.blocking_dispatch(&mut state)?;
+    while stop.recv_timeout(SHUTDOWN_POLL) == Err(RecvTimeoutError::Timeout) {
+        pump(&mut event_queue, &mut state)?;
     }
+    state.overlays.clear();
+    event_queue.flush()?;
+    Ok(())
+}
+
+/// Send queued requests and handle whatever the compositor sent, without blocking.
+fn pump(
+    event_queue: &mut EventQueue<OverlayState>,
+    state: &mut OverlayState,
+) -> Result<(), Box<dyn Error>> {
+    event_queue.flush()?;
+    if let Some(guard) = event_queue.prepare_read() {
+        match guard.read() {
+            Ok(_) => {}
+            Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    event_queue.dispatch_pending(state)?;
+    Ok(())
 }
 
 /// Tracks compositor globals plus the overlay surfaces we created.
@@ -64,6 +213,8 @@ struct OverlayState {
     compositor_state: CompositorState,
     layer_shell: LayerShell,
     shm: Shm,
+    style: BadgeStyle,
+    text: TextRenderer,
     overlays: HashMap<u32, OverlaySurface>,
 }
 
@@ -73,6 +224,7 @@ impl OverlayState {
         compositor_state: CompositorState,
         layer_shell: LayerShell,
         shm: Shm,
+        style: &BadgeStyle,
         qh: &QueueHandle<Self>,
     ) -> Self {
         Self {
@@ -81,6 +233,8 @@ impl OverlayState {
             compositor_state,
             layer_shell,
             shm,
+            style: style.clone(),
+            text: TextRenderer::new(),
             overlays: HashMap::new(),
         }
     }
@@ -90,21 +244,29 @@ impl OverlayState {
         let outputs: Vec<_> = self.output_state.outputs().collect();
         for output in outputs {
             if let Some(info) = self.output_state.info(&output) {
-                let name = info.name.clone().unwrap_or_else(|| {
-                    info.description.clone().unwrap_or_else(|| "Display".into())
-                });
-                self.create_overlay(output, name, qh);
+                self.create_overlay(output, &info, qh);
             }
         }
     }
 
-    /// Create a purple badge for the provided output name.
+    /// Create a badge in the output's color, labeled from its info.
     fn create_overlay(
         &mut self,
         output: wl_output::WlOutput,
-        name: String,
+        info: &OutputInfo,
         qh: &QueueHandle<Self>,
     ) {
+        let name = info
+            .name
+            .clone()
+            .unwrap_or_else(|| info.description.clone().unwrap_or_else(|| "Display".into()));
+        let label = match self.style.placement {
+            Placement::Corner => badge_label(&self.style.label, &name, info),
+            Placement::Flash => self.style.label.clone(),
+        };
+        let width =
+            (self.text.width(&label) + OVERLAY_HEIGHT).clamp(OVERLAY_WIDTH, MAX_OVERLAY_WIDTH);
+
         let surface = self.compositor_state.create_surface(qh);
         let layer = self.layer_shell.create_layer_surface(
             qh,
@@ -113,15 +275,24 @@ impl OverlayState {
             Some("wpe-overlay"),
             Some(&output),
         );
-        layer.set_size(OVERLAY_WIDTH, OVERLAY_HEIGHT);
-        layer.set_anchor(Anchor::TOP | Anchor::LEFT);
+        layer.set_size(width, OVERLAY_HEIGHT);
+        match self.style.placement {
+            Placement::Corner => {
+                layer.set_anchor(Anchor::TOP | Anchor::LEFT);
+                layer.set_margin(10, 0, 0, 10);
+            }
+            // Anchored to the bottom edge alone, the compositor centers it across.
+            Placement::Flash => {
+                layer.set_anchor(Anchor::BOTTOM);
+                layer.set_margin(0, 0, FLASH_MARGIN, 0);
+            }
+        }
         layer.set_exclusive_zone(0);
-        layer.set_margin(10, 0, 0, 10);
         layer.set_keyboard_interactivity(KeyboardInteractivity::None);
         layer.commit();
 
-        let pool = SlotPool::new((OVERLAY_WIDTH * OVERLAY_HEIGHT * 4) as usize, &self.shm)
-            .expect("slot pool");
+        let pool =
+            SlotPool::new((width * OVERLAY_HEIGHT * 4) as usize, &self.shm).expect("slot pool");
 
         let id = layer.wl_surface().id().protocol_id();
         self.overlays.insert(
@@ -130,9 +301,11 @@ impl OverlayState {
                 output,
                 layer,
                 pool,
-                width: OVERLAY_WIDTH,
+                width,
                 height: OVERLAY_HEIGHT,
-                name,
+                scale: 1,
+                background: self.style.background(&name),
+                label,
             },
         );
     }
@@ -148,7 +321,7 @@ impl OverlayState {
             .overlays
             .get_mut(&layer.wl_surface().id().protocol_id())
         {
-            surface.draw(qh);
+            surface.draw(&mut self.text, qh);
         }
     }
 }
@@ -160,13 +333,17 @@ struct OverlaySurface {
     pool: SlotPool,
     width: u32,
     height: u32,
-    name: String,
+    /// Buffer pixels per surface pixel, so the text stays sharp on HiDPI outputs.
+    scale: i32,
+    background: [u8; 4],
+    label: String,
 }
 
 impl OverlaySurface {
-    fn draw(&mut self, qh: &QueueHandle<OverlayState>) {
-        let width = self.width.max(1);
-        let height = self.height.max(1);
+    fn draw(&mut self, text: &mut TextRenderer, qh: &QueueHandle<OverlayState>) {
+        let scale = self.scale.max(1);
+        let width = self.width.max(1) * scale as u32;
+        let height = self.height.max(1) * scale as u32;
         let stride = width as i32 * 4;
 
         let (buffer, canvas) = self
@@ -179,12 +356,10 @@ impl OverlaySurface {
             )
             .expect("buffer");
 
-        {
-            let data = canvas.as_mut();
-            fill_capsule(data, width, height);
-            draw_text(data, width, height, &self.name);
-        }
+        fill_capsule(canvas, width, height, self.background);
+        text.draw(canvas, width, height, &self.label, scale);
 
+        self.layer.wl_surface().set_buffer_scale(scale);
         self.layer
             .wl_surface()
             .damage_buffer(0, 0, width as i32, height as i32);
@@ -198,177 +373,115 @@ impl OverlaySurface {
     }
 }
 
-/// Rasterise the monitor name using the tiny bitmap font.
-fn draw_text(buffer: &mut [u8], width: u32, height: u32, text: &str) {
-    let uppercase = text.to_uppercase();
-    let glyph_height = (7 * GLYPH_SCALE) as i32;
-    let text_width = text_pixel_width(&uppercase) as i32;
-    let start_x = ((width as i32 - text_width) / 2).max(8);
-    let start_y = ((height as i32 - glyph_height) / 2).max(4);
-    let mut cursor_x = start_x;
-    for ch in uppercase.chars() {
-        if cursor_x + (GLYPH_WIDTH * GLYPH_SCALE) as i32 >= width as i32 {
-            break;
-        }
-        if let Some(rows) = glyph_rows(ch) {
-            for (row, bits) in rows.iter().enumerate() {
-                for col in 0..GLYPH_WIDTH {
-                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
-                        for sy in 0..GLYPH_SCALE {
-                            for sx in 0..GLYPH_SCALE {
-                                let px = cursor_x + (col * GLYPH_SCALE + sx) as i32;
-                                let py = start_y + (row as u32 * GLYPH_SCALE + sy) as i32;
-                                if px >= 0 && py >= 0 && px < width as i32 && py < height as i32 {
-                                    let offset = (py as u32 * width + px as u32) as usize * 4;
-                                    buffer[offset..offset + 4].copy_from_slice(&TEXT_COLOR);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        cursor_x += (GLYPH_WIDTH * GLYPH_SCALE + GLYPH_SCALE) as i32;
+/// `template` for the output `name`: `{name}`, `{width}`, `{height}`, `{hz}`, and
+/// `{wallpaper}` (the file name of the wallpaper configured for it) are filled in.
+fn badge_label(template: &str, name: &str, info: &OutputInfo) -> String {
+    let monitor = Monitor::from_info(info);
+    let mut label = template
+        .replace("{name}", name)
+        .replace("{width}", &monitor.width.to_string())
+        .replace("{height}", &monitor.height.to_string())
+        .replace("{hz}", &monitor.refresh_rate.to_string());
+    // Only read the config when the label needs it.
+    if label.contains("{wallpaper}") {
+        let wallpaper = wallpaper_name(name).unwrap_or_else(|| "-".into());
+        label = label.replace("{wallpaper}", &wallpaper);
     }
+    label
 }
 
-/// Compute the rendered pixel width for a string so we can center it.
-fn text_pixel_width(text: &str) -> u32 {
-    let mut width = 0u32;
-    let mut first = true;
-    for ch in text.chars() {
-        if glyph_rows(ch).is_some() {
-            if !first {
-                width += GLYPH_SCALE;
-            }
-            width += GLYPH_WIDTH * GLYPH_SCALE;
-            first = false;
+/// File name of the wallpaper configured for `monitor`, if one is chosen.
+fn wallpaper_name(monitor: &str) -> Option<String> {
+    let entries = config::load_wallpaper_entries().ok()?;
+    let path = entries
+        .into_iter()
+        .find(|entry| entry.monitor.as_deref() == Some(monitor))?
+        .path
+        .filter(|path| !config::is_placeholder(path))?;
+    Some(path.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Lays out and rasterises badge labels with the system's fonts. Characters the
+/// sans-serif font lacks (CJK names, symbols) come from whichever installed font has them.
+struct TextRenderer {
+    fonts: FontSystem,
+    cache: SwashCache,
+}
+
+impl TextRenderer {
+    fn new() -> Self {
+        Self {
+            fonts: FontSystem::new(),
+            cache: SwashCache::new(),
         }
     }
-    width
+
+    /// `text` shaped on one line, `scale` times [`FONT_SIZE`].
+    fn layout(&mut self, text: &str, scale: i32) -> Buffer {
+        let size = FONT_SIZE * scale as f32;
+        let mut buffer = Buffer::new(&mut self.fonts, Metrics::new(size, size * 1.25));
+        buffer.set_size(&mut self.fonts, None, None);
+        buffer.set_text(
+            &mut self.fonts,
+            text,
+            Attrs::new().family(Family::SansSerif).weight(Weight::BOLD),
+            Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(&mut self.fonts, false);
+        buffer
+    }
+
+    /// How wide `text` draws at scale 1, to size the badge around it.
+    fn width(&mut self, text: &str) -> u32 {
+        line_width(&self.layout(text, 1)).ceil() as u32
+    }
+
+    /// Draw `text` centered on the Argb8888 `canvas`, blended over the background.
+    fn draw(&mut self, canvas: &mut [u8], width: u32, height: u32, text: &str, scale: i32) {
+        let buffer = self.layout(text, scale);
+        let left = ((width as f32 - line_width(&buffer)) / 2.0).max(0.0) as i32;
+        let top = ((height as f32 - buffer.metrics().line_height) / 2.0) as i32;
+        let [b, g, r, _] = TEXT_COLOR;
+        buffer.draw(
+            &mut self.fonts,
+            &mut self.cache,
+            TextColor::rgb(r, g, b),
+            |x, y, w, h, color| {
+                let alpha = color.a() as u32;
+                if alpha == 0 {
+                    return;
+                }
+                for py in top + y..top + y + h as i32 {
+                    for px in left + x..left + x + w as i32 {
+                        if px < 0 || py < 0 || px >= width as i32 || py >= height as i32 {
+                            continue;
+                        }
+                        let offset = (py as u32 * width + px as u32) as usize * 4;
+                        let pixel = &mut canvas[offset..offset + 4];
+                        for (channel, value) in
+                            pixel.iter_mut().zip([color.b(), color.g(), color.r()])
+                        {
+                            *channel = ((*channel as u32 * (255 - alpha) + value as u32 * alpha)
+                                / 255) as u8;
+                        }
+                        pixel[3] = pixel[3].max(color.a());
+                    }
+                }
+            },
+        );
+    }
 }
 
-/// Return the bitmap rows for the limited glyph set we support.
-fn glyph_rows(ch: char) -> Option<[u8; 7]> {
-    Some(match ch {
-        'A' => [
-            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
-        ],
-        'B' => [
-            0b11110, 0b10001, 0b11110, 0b10001, 0b10001, 0b10001, 0b11110,
-        ],
-        'C' => [
-            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
-        ],
-        'D' => [
-            0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
-        ],
-        'E' => [
-            0b11111, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000, 0b11111,
-        ],
-        'F' => [
-            0b11111, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000, 0b10000,
-        ],
-        'G' => [
-            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
-        ],
-        'H' => [
-            0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b10001,
-        ],
-        'I' => [
-            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
-        ],
-        'J' => [
-            0b00111, 0b00010, 0b00010, 0b00010, 0b10010, 0b10010, 0b01100,
-        ],
-        'K' => [
-            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
-        ],
-        'L' => [
-            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
-        ],
-        'M' => [
-            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
-        ],
-        'N' => [
-            0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001, 0b10001,
-        ],
-        'O' => [
-            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
-        ],
-        'P' => [
-            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
-        ],
-        'Q' => [
-            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
-        ],
-        'R' => [
-            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
-        ],
-        'S' => [
-            0b01110, 0b10001, 0b10000, 0b01110, 0b00001, 0b10001, 0b01110,
-        ],
-        'T' => [
-            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
-        ],
-        'U' => [
-            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
-        ],
-        'V' => [
-            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
-        ],
-        'W' => [
-            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
-        ],
-        'X' => [
-            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
-        ],
-        'Y' => [
-            0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100,
-        ],
-        'Z' => [
-            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
-        ],
-        '0' => [
-            0b01110, 0b10011, 0b10101, 0b10101, 0b11001, 0b10001, 0b01110,
-        ],
-        '1' => [
-            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
-        ],
-        '2' => [
-            0b01110, 0b10001, 0b00001, 0b00110, 0b01000, 0b10000, 0b11111,
-        ],
-        '3' => [
-            0b11110, 0b00001, 0b00001, 0b01110, 0b00001, 0b00001, 0b11110,
-        ],
-        '4' => [
-            0b10010, 0b10010, 0b10010, 0b11111, 0b00010, 0b00010, 0b00010,
-        ],
-        '5' => [
-            0b11111, 0b10000, 0b10000, 0b11110, 0b00001, 0b00001, 0b11110,
-        ],
-        '6' => [
-            0b01110, 0b10000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
-        ],
-        '7' => [
-            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
-        ],
-        '8' => [
-            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
-        ],
-        '9' => [
-            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00001, 0b01110,
-        ],
-        '-' => [
-            0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
-        ],
-        ' ' => [0; 7],
-        _ => return None,
-    })
+/// Width of the widest line of `buffer`.
+fn line_width(buffer: &Buffer) -> f32 {
+    buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0, f32::max)
 }
 
-/// Paint the purple squircle while masking out pixels outside the rounded ends.
-fn fill_capsule(buffer: &mut [u8], width: u32, height: u32) {
+/// Paint the accent squircle while masking out pixels outside the rounded ends.
+fn fill_capsule(buffer: &mut [u8], width: u32, height: u32, background: [u8; 4]) {
     let radius = (height as i32) / 2;
     let center_y = height as i32 / 2;
     let right_center = width as i32 - radius;
@@ -387,7 +500,7 @@ fn fill_capsule(buffer: &mut [u8], width: u32, height: u32) {
                 true
             };
             if inside {
-                buffer[offset..offset + 4].copy_from_slice(&OVERLAY_BG);
+                buffer[offset..offset + 4].copy_from_slice(&background);
             } else {
                 buffer[offset + 3] = 0;
             }
@@ -399,10 +512,16 @@ impl CompositorHandler for OverlayState {
     fn scale_factor_changed(
         &mut self,
         _conn: &Connection,
-        _qh: &QueueHandle<Self>,
-        _surface: &wl_surface::WlSurface,
-        _new_factor: i32,
+        qh: &QueueHandle<Self>,
+        surface: &wl_surface::WlSurface,
+        new_factor: i32,
     ) {
+        if let Some(overlay) = self.overlays.get_mut(&surface.id().protocol_id())
+            && overlay.scale != new_factor
+        {
+            overlay.scale = new_factor;
+            overlay.draw(&mut self.text, qh);
+        }
     }
 
     fn transform_changed(
@@ -454,11 +573,7 @@ impl OutputHandler for OverlayState {
         output: wl_output::WlOutput,
     ) {
         if let Some(info) = self.output_state.info(&output) {
-            let name = info
-                .name
-                .clone()
-                .unwrap_or_else(|| info.description.clone().unwrap_or_else(|| "Display".into()));
-            self.create_overlay(output, name, qh);
+            self.create_overlay(output, &info, qh);
         }
     }
 
@@ -469,12 +584,8 @@ impl OutputHandler for OverlayState {
         output: wl_output::WlOutput,
     ) {
         if let Some(info) = self.output_state.info(&output) {
-            let name = info
-                .name
-                .clone()
-                .unwrap_or_else(|| info.description.clone().unwrap_or_else(|| "Display".into()));
             self.remove_overlay(&output);
-            self.create_overlay(output, name, qh);
+            self.create_overlay(output, &info, qh);
         }
     }
 
@@ -0,0 +1,122 @@
+//! The Settings page: the preferences kept under `[settings]`, and the GUI colors.
+
+use iced::widget::{Column, Row, button, checkbox, radio, text, text_input};
+use iced::{Element, Length, alignment};
+
+use crate::config::{GuiSettings, Preferences, ScaleMode, ThemeOverride};
+
+use super::{
+    appearance::AppearancePanel,
+    message::Message,
+    style::{DEFAULT_ACCENT, accent_button_style},
+};
+
+const SCALES: [(&str, ScaleMode); 6] = [
+    ("Original", ScaleMode::Original),
+    ("Fit", ScaleMode::Fit),
+    ("Stretch", ScaleMode::Stretch),
+    ("Fill", ScaleMode::Fill),
+    ("Center", ScaleMode::Center),
+    ("Tile", ScaleMode::Tile),
+];
+
+const THEMES: [(&str, ThemeOverride); 3] = [
+    ("Follow the desktop", ThemeOverride::Auto),
+    ("Light", ThemeOverride::Light),
+    ("Dark", ThemeOverride::Dark),
+];
+
+/// State of the Settings page while it is being edited.
+#[derive(Debug)]
+pub(crate) struct SettingsPage {
+    pub open: bool,
+    pub appearance: AppearancePanel,
+    /// Interval field as typed, which may not be a number yet.
+    interval_text: String,
+}
+
+impl SettingsPage {
+    pub(crate) fn new(preferences: &Preferences) -> Self {
+        Self {
+            open: false,
+            appearance: AppearancePanel::new(preferences.accent.unwrap_or(DEFAULT_ACCENT)),
+            interval_text: preferences.interval_seconds.to_string(),
+        }
+    }
+
+    /// Update from the interval field, returning the interval once it is valid.
+    pub(crate) fn set_interval_text(&mut self, value: String) -> Option<u64> {
+        let seconds = parse_interval(&value);
+        self.interval_text = value;
+        seconds
+    }
+
+    /// Whether the interval field holds a usable number.
+    pub(crate) fn interval_is_valid(&self) -> bool {
+        parse_interval(&self.interval_text).is_some()
+    }
+
+    pub(crate) fn view(
+        &self,
+        gui: &GuiSettings,
+        preferences: &Preferences,
+    ) -> Element<'_, Message> {
+        let scales = SCALES
+            .iter()
+            .fold(Row::new().spacing(12), |row, (label, scale)| {
+                row.push(radio(
+                    *label,
+                    *scale,
+                    Some(preferences.scale),
+                    Message::DefaultScaleChanged,
+                ))
+            });
+        let themes = THEMES
+            .iter()
+            .fold(Row::new().spacing(12), |row, (label, theme)| {
+                row.push(radio(
+                    *label,
+                    *theme,
+                    Some(preferences.theme),
+                    Message::ThemeOverrideChanged,
+                ))
+            });
+        let mut interval = Row::new()
+            .spacing(12)
+            .align_y(alignment::Vertical::Center)
+            .push(text("Slideshow interval (seconds):"))
+            .push(
+                text_input("300", &self.interval_text)
+                    .on_input(Message::DefaultIntervalChanged)
+                    .width(Length::Fixed(100.0)),
+            );
+        if !self.interval_is_valid() {
+            interval = interval.push(text("Use a whole number of seconds").size(14));
+        }
+
+        Column::new()
+            .spacing(12)
+            .push(text("Settings").size(22))
+            .push(
+                checkbox("Show the monitor badges at launch", preferences.overlay)
+                    .on_toggle(Message::OverlayAtLaunchToggled),
+            )
+            .push(text("Theme"))
+            .push(themes)
+            .push(text("New monitors start with"))
+            .push(scales)
+            .push(interval)
+            .push(self.appearance.view(gui))
+            .push(
+                button(text("Save settings"))
+                    .on_press(Message::SettingsSaved)
+                    .style(accent_button_style())
+                    .padding([8, 20]),
+            )
+            .into()
+    }
+}
+
+fn parse_interval(value: &str) -> Option<u64> {
+    value.trim().parse().ok().filter(|seconds| *seconds > 0)
+}
@@ -3,17 +3,21 @@ use std::{env, path::PathBuf};
 use iced::{
     Background, Color, Theme,
     border::{self, Border},
+    theme::Palette,
     widget,
 };
 use walkdir::WalkDir;
 
-const BUTTON_COLOR: Color = Color {
-    r: 0x4B as f32 / 255.0,
-    g: 0x00 as f32 / 255.0,
-    b: 0x6E as f32 / 255.0,
-    a: 1.0,
+use crate::{
+    config::{GuiSettings, HexColor},
+    palette::ThemeColors,
 };
 
+use super::types::ThemePreference;
+
+/// WPE purple, used when `[settings] accent` is not set.
+pub(crate) const DEFAULT_ACCENT: HexColor = HexColor([0x4B, 0x00, 0x6E]);
+
 const FOLDER_ICON_NAMES: &[&str] = &[
     "folder-open-symbolic",
     "folder-open",
@@ -30,26 +34,97 @@ const FILE_ICON_NAMES: &[&str] = &[
     "document-new",
 ];
 
-/// Create a pill-shaped button style based on the WPE accent color.
-pub(crate) fn purple_button_style<'a>()
--> impl Fn(&Theme, widget::button::Status) -> widget::button::Style + Clone {
-    move |_, status| {
-        let mut base = BUTTON_COLOR;
-        if matches!(status, widget::button::Status::Hovered) {
-            base = lighten(base, 0.08);
-        } else if matches!(status, widget::button::Status::Pressed) {
-            base = lighten(base, -0.05);
+/// Light or dark theme with the `[gui]` colors applied; `accent` becomes the primary color.
+///
+/// Colors taken from the wallpaper replace the accent and background, but explicit
+/// `[gui.light]`/`[gui.dark]` overrides still win.
+pub(crate) fn build_theme(
+    preference: ThemePreference,
+    gui: &GuiSettings,
+    accent: Option<HexColor>,
+    wallpaper: Option<&ThemeColors>,
+) -> Theme {
+    let (name, mut palette, overrides) = match preference {
+        ThemePreference::Light => ("WPE Light", Palette::LIGHT, &gui.light),
+        ThemePreference::Dark => ("WPE Dark", Palette::DARK, &gui.dark),
+    };
+    let accent = wallpaper.and_then(|colors| colors.accent).or(accent);
+    palette.primary = to_color(overrides.accent.or(accent).unwrap_or(DEFAULT_ACCENT));
+    if let Some(colors) = wallpaper {
+        palette.background = to_color(match preference {
+            ThemePreference::Light => colors.background_light,
+            ThemePreference::Dark => colors.background_dark,
+        });
+    }
+    for (slot, color) in [
+        (&mut palette.background, overrides.background),
+        (&mut palette.text, overrides.text),
+        (&mut palette.success, overrides.success),
+        (&mut palette.danger, overrides.danger),
+    ] {
+        if let Some(color) = color {
+            *slot = to_color(color);
         }
+    }
+    Theme::custom(name.into(), palette)
+}
+
+pub(crate) fn to_color(HexColor([r, g, b]): HexColor) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+/// Create a pill-shaped button style based on the theme's accent color.
+pub(crate) fn accent_button_style()
+-> impl Fn(&Theme, widget::button::Status) -> widget::button::Style + Clone {
+    move |theme, status| pill_style(theme.palette().primary, status)
+}
+
+/// Pill button for a monitor tab, in the output's `[gui.outputs]` color when it has
+/// one so the tab matches its badge.
+pub(crate) fn output_button_style(
+    color: Option<HexColor>,
+) -> impl Fn(&Theme, widget::button::Status) -> widget::button::Style + Clone {
+    move |theme, status| {
+        pill_style(
+            color.map(to_color).unwrap_or(theme.palette().primary),
+            status,
+        )
+    }
+}
+
+fn pill_style(mut base: Color, status: widget::button::Status) -> widget::button::Style {
+    if matches!(status, widget::button::Status::Hovered) {
+        base = lighten(base, 0.08);
+    } else if matches!(status, widget::button::Status::Pressed) {
+        base = lighten(base, -0.05);
+    }
+
+    widget::button::Style {
+        background: Some(Background::Color(base)),
+        text_color: Color::WHITE,
+        border: Border {
+            radius: border::Radius::default().left(999.0).right(999.0),
+            ..Default::default()
+        },
+        shadow: Default::default(),
+    }
+}
 
-        widget::button::Style {
-            background: Some(Background::Color(base)),
-            text_color: Color::WHITE,
-            border: Border {
-                radius: border::Radius::default().left(999.0).right(999.0),
-                ..Default::default()
-            },
-            shadow: Default::default(),
+/// Faded [`output_button_style`], for tabs of monitors that have no wallpaper chosen yet.
+pub(crate) fn muted_button_style(
+    color: Option<HexColor>,
+) -> impl Fn(&Theme, widget::button::Status) -> widget::button::Style + Clone {
+    move |theme, status| {
+        let mut style = output_button_style(color)(theme, status);
+        let palette = theme.palette();
+        if let Some(Background::Color(color)) = style.background {
+            style.background = Some(Background::Color(Color { a: 0.35, ..color }));
         }
+        style.text_color = Color {
+            a: 0.7,
+            ..palette.text
+        };
+        style
     }
 }
 
@@ -94,10 +169,10 @@ fn find_icon_path(names: &[&str]) -> Option<PathBuf> {
             if !extension {
                 continue;
             }
-            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                if names.iter().any(|name| stem == *name) {
-                    return Some(path.to_path_buf());
-                }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+                && names.contains(&stem)
+            {
+                return Some(path.to_path_buf());
             }
         }
     }
@@ -0,0 +1,136 @@
+//! What each monitor showed before, and favorite files.
+//!
+//! The GUI and `wpe daemon` note each file that comes on screen (when they ask the
+//! players for the usage records, see [`crate::usage`]) in
+//! `$XDG_STATE_HOME/wpe/history.toml`, the newest [`LENGTH`] per monitor.
+//! `wpe previous` walks back through them, putting the file before the current one on
+//! screen ahead of the rest of the playlist. `wpe favorite` tags the file on screen
+//! `favorite` in the state file, and an entry with `favorites_only = true` plays only
+//! the favorite files of its folder.
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::warn;
+
+use crate::{
+    ipc,
+    state::{self, State},
+    tags,
+};
+
+/// Files remembered per monitor.
+const LENGTH: usize = 100;
+/// Tag that marks a favorite.
+pub const FAVORITE_TAG: &str = "favorite";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    /// Files each monitor showed, oldest first.
+    #[serde(default)]
+    monitors: BTreeMap<String, Vec<PathBuf>>,
+}
+
+fn history_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(state::state_dir()?.join("history.toml"))
+}
+
+fn read(path: &Path) -> HistoryFile {
+    let Ok(data) = fs::read_to_string(path) else {
+        return HistoryFile::default();
+    };
+    toml::from_str(&data).unwrap_or_else(|err| {
+        warn!(
+            "Ignoring unreadable history file {}: {}",
+            path.display(),
+            err
+        );
+        HistoryFile::default()
+    })
+}
+
+fn write(path: &Path, history: &HistoryFile) -> Result<(), Box<dyn Error>> {
+    Ok(fs::write(path, toml::to_string(history)?)?)
+}
+
+/// Add the files that just came on screen (from [`crate::usage::Tracker::record`]).
+pub fn record(changed: &[(String, PathBuf)]) {
+    if changed.is_empty() {
+        return;
+    }
+    let result = history_path().and_then(|path| {
+        let mut history = read(&path);
+        for (monitor, file) in changed {
+            let shown = history.monitors.entry(monitor.clone()).or_default();
+            // Going back puts a file on screen that is already last.
+            if shown.last() != Some(file) {
+                shown.push(file.clone());
+            }
+            let excess = shown.len().saturating_sub(LENGTH);
+            shown.drain(..excess);
+        }
+        write(&path, &history)
+    });
+    if let Err(err) = result {
+        warn!("Could not save the wallpaper history: {}", err);
+    }
+}
+
+/// The file `monitor`'s player shows now.
+fn on_screen(monitor: &str) -> Result<PathBuf, Box<dyn Error>> {
+    match ipc::get_property(monitor, "path")? {
+        Value::String(path) => Ok(PathBuf::from(path)),
+        _ => Err(format!("{monitor} is not showing a file").into()),
+    }
+}
+
+/// Show the file `monitor` had on before the current one, returning it.
+pub fn go_back(monitor: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = history_path()?;
+    let mut history = read(&path);
+    let current = on_screen(monitor)?;
+    let shown = history.monitors.entry(monitor.to_string()).or_default();
+    // The current file is only in the history once a sample saw it.
+    if shown.last() == Some(&current) {
+        shown.pop();
+    }
+    let previous = shown
+        .last()
+        .cloned()
+        .ok_or_else(|| format!("Nothing was shown on {monitor} before this wallpaper"))?;
+
+    // Played next rather than in place of the playlist, so the slideshow goes on after.
+    ipc::command(
+        monitor,
+        &[
+            json!("loadfile"),
+            json!(previous.to_string_lossy()),
+            json!("insert-next"),
+        ],
+    )?;
+    ipc::command(monitor, &[json!("playlist-next")])?;
+    write(&path, &history)?;
+    Ok(previous)
+}
+
+/// Tag the file on `monitor` as a favorite, returning it.
+pub fn favorite(monitor: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let file = on_screen(monitor)?;
+    let file = fs::canonicalize(&file).unwrap_or(file);
+    let mut state = state::load_state();
+    tags::add_tags(&mut state, &file, &[FAVORITE_TAG.to_string()]);
+    state::save_state(&state)?;
+    Ok(file)
+}
+
+/// Whether `file` is tagged as a favorite.
+pub fn is_favorite(state: &State, file: &Path) -> bool {
+    let canonical = fs::canonicalize(file);
+    tags::tags_for(state, canonical.as_deref().unwrap_or(file)).contains(FAVORITE_TAG)
+}
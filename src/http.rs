@@ -0,0 +1,532 @@
+//! `wpe daemon --http <address>`: a small HTTP API for home automation and macro pads.
+//!
+//! The server is off unless asked for. Each connection is answered on a thread of its
+//! own, with JSON, and only a few are answered at once. Like [`crate::bus`], it sets wallpapers through the daemon's own
+//! socket and skips or pauses players over mpv IPC, so it offers the same controls as
+//! the CLI.
+//!
+//! Every request needs `Authorization: Bearer <token>`, where the token is written to
+//! `$XDG_RUNTIME_DIR/wpe/http-token` (readable only by the user) each time the server
+//! starts. POST bodies must be sent as `application/json`. Requests from a web page
+//! (anything with an `Origin` header) are refused, as are those naming a host other
+//! than loopback or the address served on, so a browser tab cannot reach the API by
+//! rebinding a domain to 127.0.0.1:
+//!
+//! ```text
+//! auth="Authorization: Bearer $(cat $XDG_RUNTIME_DIR/wpe/http-token)"
+//! json='Content-Type: application/json'
+//! curl -s -H "$auth" localhost:9843/status
+//! curl -s -H "$auth" -H "$json" localhost:9843/set -d '{"monitor":"DP-1","path":"~/Videos/rain.mp4"}'
+//! curl -s -H "$auth" -H "$json" localhost:9843/next -d '{"monitor":"DP-1"}'
+//! curl -s -H "$auth" -H "$json" -X POST localhost:9843/pause
+//! ```
+
+use std::{
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    os::unix::fs::OpenOptionsExt,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use crate::{
+    control::{self, Action},
+    daemon::{self, Request},
+    ipc,
+};
+
+/// How long a client gets to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest request body read; a path and an output name fit many times over.
+const MAX_BODY: usize = 64 * 1024;
+/// Largest request line and headers read, together.
+const MAX_HEAD: u64 = 16 * 1024;
+/// Connections answered at once; more are turned away until one finishes.
+const MAX_CONNECTIONS: usize = 16;
+/// File in the IPC directory holding the bearer token.
+const TOKEN_FILE: &str = "http-token";
+
+/// A request as read off the connection.
+struct Incoming {
+    method: String,
+    /// The path, without a query.
+    path: String,
+    origin: Option<String>,
+    host: Option<String>,
+    content_type: Option<String>,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+/// The body of `POST /next`, `/pause`, and `/resume`.
+#[derive(Debug, Default, Deserialize)]
+struct Target {
+    monitor: Option<String>,
+}
+
+/// The body of `POST /set`.
+#[derive(Debug, Deserialize)]
+struct SetBody {
+    monitor: String,
+    path: PathBuf,
+}
+
+/// An answer: the status code and the JSON sent with it.
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Listen on `address` and answer each connection on a thread of its own.
+pub fn serve(address: SocketAddr) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(address)
+        .map_err(|err| format!("Unable to serve HTTP on {address}: {err}"))?;
+    let token = Arc::new(
+        write_token().map_err(|err| format!("Unable to write the HTTP API token: {err}"))?,
+    );
+    if !address.ip().is_loopback() {
+        warn!(
+            "The HTTP API on {} is reachable from the network; only its token keeps others out",
+            address
+        );
+    }
+    info!("wpe daemon serving HTTP on {}", address);
+    let active = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let Some(slot) = Slot::take(&active) else {
+                        let _ = stream.set_write_timeout(Some(REQUEST_TIMEOUT));
+                        respond(
+                            &stream,
+                            Response::error(503, "Too many requests at once; try again"),
+                        );
+                        continue;
+                    };
+                    let token = Arc::clone(&token);
+                    thread::spawn(move || {
+                        answer(stream, address, &token);
+                        drop(slot);
+                    });
+                }
+                Err(err) => warn!("Failed to accept an HTTP connection: {}", err),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// One of the `MAX_CONNECTIONS` answered at once, given back when dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(active: &Arc<AtomicUsize>) -> Option<Self> {
+        if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            active.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(Self(Arc::clone(active)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Make a fresh token and leave it where only this user can read it.
+fn write_token() -> Result<String, Box<dyn Error>> {
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    let path = ipc::socket_dir()?.join(TOKEN_FILE);
+    // A new file gets the mode below; an old one may have been readable by others.
+    let _ = fs::remove_file(&path);
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?
+        .write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Read one request from `stream` and write the response.
+fn answer(stream: TcpStream, address: SocketAddr, token: &str) {
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let response = match read_request(&stream) {
+        Ok(incoming) => match check(&incoming, address, token) {
+            Ok(()) => route(&incoming.method, &incoming.path, &incoming.body),
+            Err(response) => response,
+        },
+        Err(err) => Response::error(400, err),
+    };
+    respond(&stream, response);
+}
+
+/// Write `response` as the HTTP reply, closing the connection after it.
+fn respond(mut stream: &TcpStream, response: Response) {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        415 => "Unsupported Media Type",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    );
+}
+
+/// Turn away requests from browsers, for other hosts, without the token, or with a
+/// body that is not JSON.
+fn check(incoming: &Incoming, address: SocketAddr, token: &str) -> Result<(), Response> {
+    if incoming.origin.is_some() {
+        return Err(Response::error(
+            403,
+            "Requests from web pages are not accepted",
+        ));
+    }
+    if !incoming
+        .host
+        .as_deref()
+        .is_some_and(|host| host_is_allowed(host, address))
+    {
+        return Err(Response::error(
+            403,
+            "The Host header must name this machine",
+        ));
+    }
+    let bearer = incoming
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    if !bearer.is_some_and(|bearer| same_token(bearer, token)) {
+        return Err(Response::error(
+            401,
+            format!(
+                "Send Authorization: Bearer <token>, with the token from $XDG_RUNTIME_DIR/wpe/{TOKEN_FILE}"
+            ),
+        ));
+    }
+    let is_json = incoming.content_type.as_deref().is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/json"))
+    });
+    if incoming.method == "POST" && !is_json {
+        return Err(Response::error(415, "Send POST bodies as application/json"));
+    }
+    Ok(())
+}
+
+/// Compare in constant time, so how long it takes does not tell how much matched.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Whether `host`, a Host header, names loopback or the address served on.
+fn host_is_allowed(host: &str, address: SocketAddr) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    name.parse::<IpAddr>()
+        .is_ok_and(|ip| ip.is_loopback() || ip == address.ip())
+}
+
+/// The request on `stream`, with the headers the API looks at.
+fn read_request(stream: &TcpStream) -> Result<Incoming, String> {
+    // The limit is raised to the body's length once the headers are in.
+    let mut reader = BufReader::new(stream).take(MAX_HEAD);
+    let mut line = String::new();
+    read_head_line(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("Malformed request line".into());
+    };
+    let method = method.to_string();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let mut length = 0;
+    let (mut origin, mut host, mut content_type, mut authorization) = (None, None, None, None);
+    loop {
+        read_head_line(&mut reader, &mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => {
+                length = value
+                    .parse()
+                    .map_err(|_| "Invalid Content-Length".to_string())?;
+            }
+            "origin" => origin = Some(value.to_string()),
+            "host" => host = Some(value.to_string()),
+            "content-type" => content_type = Some(value.to_string()),
+            "authorization" => authorization = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if length > MAX_BODY {
+        return Err(format!("The body is larger than {MAX_BODY} bytes"));
+    }
+    let mut body = vec![0; length];
+    reader.set_limit(length as u64);
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| format!("Could not read the body: {err}"))?;
+    Ok(Incoming {
+        method,
+        path,
+        origin,
+        host,
+        content_type,
+        authorization,
+        body,
+    })
+}
+
+/// Read one line of the request line or headers into `line`, refusing one that
+/// `MAX_HEAD` or a closed connection cut off.
+fn read_head_line(reader: &mut impl BufRead, line: &mut String) -> Result<(), String> {
+    line.clear();
+    reader
+        .read_line(line)
+        .map_err(|err| format!("Could not read the request: {err}"))?;
+    if !line.ends_with('\n') {
+        return Err(format!(
+            "The request headers are cut off or longer than {MAX_HEAD} bytes"
+        ));
+    }
+    Ok(())
+}
+
+fn route(method: &str, path: &str, body: &[u8]) -> Response {
+    let action = match path.trim_end_matches('/') {
+        "/status" => {
+            return match method {
+                "GET" => match request(Request::Status) {
+                    Ok(reply) => Response::ok(json!(reply)),
+                    Err(err) => Response::error(500, err),
+                },
+                _ => Response::error(405, "Use GET for /status"),
+            };
+        }
+        "/set" => {
+            return match method {
+                "POST" => set(body),
+                _ => Response::error(405, "Use POST for /set"),
+            };
+        }
+        "/next" => Action::Next,
+        "/previous" => Action::Previous,
+        "/favorite" => Action::Favorite,
+        "/pause" => Action::Pause,
+        "/resume" => Action::Resume,
+        _ => return Response::error(404, format!("No such endpoint: {path}")),
+    };
+    if method != "POST" {
+        return Response::error(405, format!("Use POST for {path}"));
+    }
+    let target: Target = if body.is_empty() {
+        Target::default()
+    } else {
+        match serde_json::from_slice(body) {
+            Ok(target) => target,
+            Err(err) => return Response::error(400, format!("Invalid body: {err}")),
+        }
+    };
+    let result = match target.monitor {
+        Some(monitor) => action
+            .run(&monitor)
+            .map_err(|err| format!("{monitor}: {err}")),
+        None => control::act_on_all(action),
+    };
+    match result {
+        Ok(()) => Response::ok(json!({ "done": action.done() })),
+        Err(err) => Response::error(500, err),
+    }
+}
+
+fn set(body: &[u8]) -> Response {
+    let body: SetBody = match serde_json::from_slice(body) {
+        Ok(body) => body,
+        Err(err) => return Response::error(400, format!("Invalid body: {err}")),
+    };
+    match request(Request::Set {
+        monitor: body.monitor,
+        path: body.path,
+    }) {
+        Ok(reply) => match reply.failed.first() {
+            Some((monitor, err)) => Response::error(500, format!("{monitor}: {err}")),
+            None => Response::ok(json!(reply)),
+        },
+        Err(err) => Response::error(500, err),
+    }
+}
+
+/// Hand `request` to the daemon over its socket.
+fn request(request: Request) -> Result<daemon::Reply, String> {
+    let reply = daemon::send(&request).map_err(|err| err.to_string())?;
+    match reply.error {
+        Some(err) => Err(err),
+        None => Ok(reply),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    const TOKEN: &str = "00112233445566778899aabbccddeeff";
+
+    fn served() -> SocketAddr {
+        "127.0.0.1:9843".parse().unwrap()
+    }
+
+    /// A request that passes every check; tests change one thing about it.
+    fn status_request() -> Incoming {
+        Incoming {
+            method: "GET".into(),
+            path: "/status".into(),
+            origin: None,
+            host: Some("localhost:9843".into()),
+            content_type: None,
+            authorization: Some(format!("Bearer {TOKEN}")),
+            body: Vec::new(),
+        }
+    }
+
+    fn status(incoming: &Incoming) -> u16 {
+        match check(incoming, served(), TOKEN) {
+            Ok(()) => 200,
+            Err(response) => response.status,
+        }
+    }
+
+    #[test]
+    fn a_request_with_the_token_passes() {
+        assert_eq!(status(&status_request()), 200);
+    }
+
+    #[test]
+    fn requests_from_web_pages_are_refused() {
+        let incoming = Incoming {
+            origin: Some("http://localhost:9843".into()),
+            ..status_request()
+        };
+        assert_eq!(status(&incoming), 403);
+    }
+
+    #[test]
+    fn only_this_machine_is_a_valid_host() {
+        let network: SocketAddr = "192.168.1.5:9843".parse().unwrap();
+        assert!(host_is_allowed("localhost:9843", served()));
+        assert!(host_is_allowed("LOCALHOST", served()));
+        assert!(host_is_allowed("127.0.0.1:9843", served()));
+        assert!(host_is_allowed("[::1]:9843", served()));
+        assert!(host_is_allowed("[::1]", served()));
+        assert!(host_is_allowed("192.168.1.5:9843", network));
+        assert!(!host_is_allowed("192.168.1.5:9843", served()));
+        assert!(!host_is_allowed("[fe80::1]:9843", served()));
+        assert!(!host_is_allowed("attacker.example:9843", served()));
+
+        let incoming = Incoming {
+            host: None,
+            ..status_request()
+        };
+        assert_eq!(status(&incoming), 403);
+    }
+
+    #[test]
+    fn the_bearer_token_is_required() {
+        for authorization in [
+            None,
+            Some("Bearer ".to_string()),
+            Some("Bearer 00112233445566778899aabbccddeefe".to_string()),
+            Some(format!("Basic {TOKEN}")),
+        ] {
+            let incoming = Incoming {
+                authorization,
+                ..status_request()
+            };
+            assert_eq!(status(&incoming), 401);
+        }
+    }
+
+    #[test]
+    fn post_bodies_must_be_json() {
+        let post = |content_type: Option<&str>| Incoming {
+            method: "POST".into(),
+            path: "/pause".into(),
+            content_type: content_type.map(String::from),
+            ..status_request()
+        };
+        assert_eq!(status(&post(None)), 415);
+        assert_eq!(status(&post(Some("text/plain"))), 415);
+        assert_eq!(status(&post(Some("application/json"))), 200);
+        assert_eq!(status(&post(Some("Application/JSON; charset=utf-8"))), 200);
+    }
+
+    #[test]
+    fn an_endless_header_line_is_cut_off() {
+        let mut reader = Cursor::new(vec![b'a'; 1024]).take(64);
+        let mut line = String::new();
+        assert!(read_head_line(&mut reader, &mut line).is_err());
+        assert_eq!(line.len(), 64);
+    }
+}
@@ -0,0 +1,256 @@
+//! Pause wallpapers while nobody is looking: the session is idle or a screen is off.
+//!
+//! Idleness comes from `ext-idle-notify-v1` (Sway, Hyprland, river, labwc, niri,
+//! KDE, GNOME 48+), which also honours idle inhibitors, so a video playing in a
+//! browser keeps the session awake. Whether an output is powered down (DPMS off) is
+//! read from the DRM connectors in sysfs; the Wayland protocol for it grants control
+//! of an output to one client only, which would lock out tools like `wlopm`.
+
+use std::{collections::BTreeSet, error::Error, fs, thread, time::Duration};
+
+use futures::channel::mpsc::UnboundedSender;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use smithay_client_toolkit::{
+    reexports::client::{
+        Connection, Dispatch, QueueHandle,
+        globals::registry_queue_init,
+        protocol::wl_seat::{self, WlSeat},
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+};
+use tracing::{info, warn};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::{self, ExtIdleNotifierV1},
+};
+
+use crate::{monitors, runtime};
+
+/// How often the DRM connectors are checked for powered-down screens.
+const SCREEN_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `[idle]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct IdleSettings {
+    /// Seconds without input before every wallpaper pauses; 0 turns it off.
+    pub timeout_seconds: u32,
+    /// Pause wallpapers on outputs the compositor powered down.
+    pub screen_off: bool,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 0,
+            screen_off: true,
+        }
+    }
+}
+
+impl IdleSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether anything needs watching.
+    pub fn is_active(&self) -> bool {
+        self.timeout_seconds > 0 || self.screen_off
+    }
+}
+
+/// A change seen by [`watch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdleEvent {
+    /// The session went idle (`true`) or saw input again.
+    Idle(bool),
+    /// Outputs that are powered down now.
+    ScreensOff(BTreeSet<String>),
+}
+
+/// Outputs held by idleness: all of `running` while idle, plus the ones that are off.
+pub fn held<'a>(
+    idle: bool,
+    screens_off: &BTreeSet<String>,
+    running: impl IntoIterator<Item = &'a String>,
+) -> BTreeSet<String> {
+    let mut held = screens_off.clone();
+    if idle {
+        held.extend(running.into_iter().cloned());
+    }
+    held
+}
+
+/// Report idleness and powered-down outputs until the receiver is gone.
+pub fn watch(settings: IdleSettings, tx: UnboundedSender<IdleEvent>) {
+    if settings.screen_off {
+        runtime::spawn(watch_screens(tx.clone()));
+    }
+    if settings.timeout_seconds > 0 {
+        watch_idle(settings.timeout_seconds, tx);
+    }
+}
+
+/// Outputs whose DRM connector reports DPMS as anything but on.
+pub fn screens_off() -> BTreeSet<String> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return BTreeSet::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            // Connectors are named `card<N>-<output>`, e.g. `card1-DP-1`.
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let (_, output) = name.split_once('-')?;
+            let status = fs::read_to_string(entry.path().join("status")).ok()?;
+            let dpms = fs::read_to_string(entry.path().join("dpms")).ok()?;
+            (status.trim() == "connected" && dpms.trim() != "On").then(|| output.to_string())
+        })
+        .collect()
+}
+
+async fn watch_screens(tx: UnboundedSender<IdleEvent>) {
+    let mut last = BTreeSet::new();
+    loop {
+        let off = screens_off();
+        if off != last {
+            if !off.is_empty() {
+                info!(
+                    "Screens off: {}",
+                    off.iter().cloned().collect::<Vec<_>>().join(", ")
+                );
+            }
+            if tx
+                .unbounded_send(IdleEvent::ScreensOff(off.clone()))
+                .is_err()
+            {
+                return;
+            }
+            last = off;
+        }
+        tokio::time::sleep(SCREEN_POLL_INTERVAL).await;
+    }
+}
+
+/// Connection state for the idle watcher.
+struct IdleApp {
+    registry_state: RegistryState,
+    idle: bool,
+}
+
+smithay_client_toolkit::delegate_registry!(IdleApp);
+
+impl ProvidesRegistryState for IdleApp {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    smithay_client_toolkit::registry_handlers!();
+}
+
+impl Dispatch<WlSeat, ()> for IdleApp {
+    fn event(
+        _state: &mut Self,
+        _seat: &WlSeat,
+        _event: wl_seat::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for IdleApp {
+    fn event(
+        _state: &mut Self,
+        _notifier: &ExtIdleNotifierV1,
+        _event: ext_idle_notifier_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for IdleApp {
+    fn event(
+        state: &mut Self,
+        _notification: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.idle = true,
+            ext_idle_notification_v1::Event::Resumed => state.idle = false,
+            _ => {}
+        }
+    }
+}
+
+/// Follow the session's idle state, reconnecting with the output watcher's backoff.
+fn watch_idle(timeout_seconds: u32, tx: UnboundedSender<IdleEvent>) {
+    let mut attempt = 0;
+    loop {
+        match watch_idle_session(timeout_seconds, &tx, &mut attempt) {
+            Ok(()) => return,
+            Err(err) => {
+                warn!(
+                    "Lost the Wayland connection while watching for idle: {}",
+                    err
+                );
+                // Nobody can be seen as idle until the watcher is back.
+                if tx.unbounded_send(IdleEvent::Idle(false)).is_err() {
+                    return;
+                }
+                thread::sleep(monitors::reconnect_delay(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn watch_idle_session(
+    timeout_seconds: u32,
+    tx: &UnboundedSender<IdleEvent>,
+    attempt: &mut u32,
+) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut event_queue) = registry_queue_init::<IdleApp>(&conn)?;
+    let qh = event_queue.handle();
+    let mut app = IdleApp {
+        registry_state: RegistryState::new(&globals),
+        idle: false,
+    };
+    let Ok(notifier) = globals.bind::<ExtIdleNotifierV1, _, _>(&qh, 1..=1, ()) else {
+        info!("The compositor does not report idleness; wallpapers keep playing while idle.");
+        return Ok(());
+    };
+    let seat = globals
+        .bind::<WlSeat, _, _>(&qh, 1..=1, ())
+        .map_err(|_| "the compositor offers no seat to watch")?;
+    let _notification =
+        notifier.get_idle_notification(timeout_seconds.saturating_mul(1000), &seat, &qh, ());
+    *attempt = 0;
+
+    let mut last = false;
+    loop {
+        event_queue.blocking_dispatch(&mut app)?;
+        if app.idle != last {
+            info!(
+                "Session {}",
+                if app.idle {
+                    "is idle"
+                } else {
+                    "is active again"
+                }
+            );
+            if tx.unbounded_send(IdleEvent::Idle(app.idle)).is_err() {
+                return Ok(());
+            }
+            last = app.idle;
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Talk to running players over mpv's JSON IPC socket.
+//!
+//! Every mpvpaper is started with `--input-ipc-server` pointing at a per-output
+//! socket in `$XDG_RUNTIME_DIR/wpe/`, so wpe can pause, skip, or query a
+//! wallpaper after it has been launched.
+
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+
+use crate::{
+    error::{Source, WpeError},
+    state,
+};
+
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Directory holding the per-output IPC sockets, created if needed.
+pub fn socket_dir() -> Result<PathBuf, WpeError> {
+    let dir = match env::var("XDG_RUNTIME_DIR") {
+        Ok(runtime) => PathBuf::from(runtime).join("wpe"),
+        Err(_) => state::state_dir()
+            .map_err(|err| WpeError::backend(None, err))?
+            .join("ipc"),
+    };
+    fs::create_dir_all(&dir).map_err(|err| {
+        WpeError::backend(None, format!("Unable to create {}", dir.display())).with_source(err)
+    })?;
+    Ok(dir)
+}
+
+/// Socket path for the player on `monitor`.
+pub fn socket_path(monitor: &str) -> Result<PathBuf, WpeError> {
+    Ok(socket_dir()?.join(format!("mpv-{}.sock", file_safe(monitor))))
+}
+
+/// Where the player on `monitor` writes its stdout/stderr.
+pub fn log_path(monitor: &str) -> Result<PathBuf, WpeError> {
+    Ok(socket_dir()?.join(format!("mpvpaper-{}.log", file_safe(monitor))))
+}
+
+/// `monitor` reduced to characters safe in a file name.
+pub fn file_safe(monitor: &str) -> String {
+    monitor
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Send one command (e.g. `["cycle", "pause"]`) and return mpv's `data` field.
+pub fn command(monitor: &str, args: &[Value]) -> Result<Value, WpeError> {
+    request(monitor, args).map_err(|err| {
+        WpeError::ipc(monitor, format!("IPC request to {monitor} failed")).with_source(err)
+    })
+}
+
+fn request(monitor: &str, args: &[Value]) -> Result<Value, Source> {
+    let path = socket_path(monitor)?;
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|err| format!("No player listening for {monitor}: {err}"))?;
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+
+    let request = json!({ "command": args, "request_id": 1 });
+    writeln!(stream, "{request}")?;
+
+    // mpv interleaves asynchronous events with replies; skip until our reply shows up.
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(format!("Player for {monitor} closed the IPC connection").into());
+        }
+        let reply: Value = serde_json::from_str(&line)?;
+        if reply.get("request_id") != Some(&json!(1)) {
+            continue;
+        }
+        return match reply.get("error").and_then(Value::as_str) {
+            Some("success") | None => Ok(reply.get("data").cloned().unwrap_or(Value::Null)),
+            Some(err) => Err(format!("mpv rejected {args:?}: {err}").into()),
+        };
+    }
+}
+
+/// Read a property such as `pause` or `path`.
+pub fn get_property(monitor: &str, name: &str) -> Result<Value, WpeError> {
+    command(monitor, &[json!("get_property"), json!(name)])
+}
+
+/// Set a property such as `pause` or `volume`.
+pub fn set_property(monitor: &str, name: &str, value: Value) -> Result<(), WpeError> {
+    command(monitor, &[json!("set_property"), json!(name), value]).map(|_| ())
+}
+
+/// Whether a player is accepting IPC connections for `monitor`. mpv only opens the
+/// socket once mpvpaper has mapped its layer surface and initialised the player.
+pub fn is_listening(monitor: &str) -> bool {
+    socket_path(monitor).is_ok_and(|path| UnixStream::connect(path).is_ok())
+}
+
+/// Block until the player on `monitor` has actually started rendering, i.e. mpv
+/// sent `playback-restart`. A file mpv cannot play fails with mpv's own reason.
+pub fn wait_for_playback(monitor: &str, timeout: Duration) -> Result<(), WpeError> {
+    await_playback(monitor, timeout).map_err(|err| {
+        WpeError::ipc(monitor, format!("Waiting for {monitor} to play failed")).with_source(err)
+    })
+}
+
+fn await_playback(monitor: &str, timeout: Duration) -> Result<(), Source> {
+    let path = socket_path(monitor)?;
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|err| format!("No player listening for {monitor}: {err}"))?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+
+    // Playback may have restarted before we connected; the position answers that case.
+    let request = json!({ "command": ["get_property", "playback-time"], "request_id": 1 });
+    writeln!(stream, "{request}")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Player for {monitor} did not start playing within {}s",
+                timeout.as_secs()
+            )
+            .into());
+        }
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                return Err(format!("Player for {monitor} exited before playback started").into());
+            }
+            Ok(_) => {}
+            // Partial lines stay in `line` until the rest arrives.
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        let message: Value = serde_json::from_str(&line).unwrap_or(Value::Null);
+        line.clear();
+        match message.get("event").and_then(Value::as_str) {
+            Some("playback-restart") => return Ok(()),
+            Some("end-file") if message.get("reason") == Some(&json!("error")) => {
+                let cause = message
+                    .get("file_error")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                return Err(format!("mpv could not play the source for {monitor}: {cause}").into());
+            }
+            Some(_) => continue,
+            None => {}
+        }
+        if message.get("request_id") == Some(&json!(1))
+            && message.get("data").is_some_and(Value::is_number)
+        {
+            return Ok(());
+        }
+    }
+}
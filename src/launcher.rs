@@ -0,0 +1,465 @@
+//! One output's wallpaper from spawn to stop, shared by `wpe -c`, the GUI, and the daemon.
+//!
+//! A player goes Stopped → Launching (through the [`LaunchStage`]s) → Running, or
+//! Degraded when it came up showing a stand-in instead of its source, such as one
+//! of the entry's `fallbacks` when its own source could not be shown. Every
+//! frontend drives the same transitions; they differ only in how they wait (the
+//! CLI and daemon block in [`Instance::confirm`], the GUI finishes stages from
+//! async tasks).
+
+use std::{fmt, path::PathBuf, process::Child, time::Duration};
+
+use tracing::{info, warn};
+
+use crate::{
+    backend::{self, WallpaperBackend},
+    config::{RuntimeConfig, Settings, WallpaperProfileEntry},
+    monitors::Monitor,
+    mpris::{self, MprisHandle},
+    mpvpaper::LaunchStage,
+    slideshow::Driver,
+};
+
+/// What a degraded player shows instead of its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandIn {
+    /// The last-known-good frame, because the source is unavailable.
+    CachedFrame,
+    /// A still of the wallpaper, while `[thermal]` or `[power]` holds video back.
+    StillFrame,
+    /// One of the entry's `fallbacks`, because its source could not be shown.
+    Fallback,
+}
+
+impl fmt::Display for StandIn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StandIn::CachedFrame => "showing its last cached frame",
+            StandIn::StillFrame => "showing a still frame",
+            StandIn::Fallback => "showing a fallback",
+        })
+    }
+}
+
+/// Where one output's player is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchState {
+    Stopped,
+    /// Spawned and waiting on `stage`; `stand_in` is what it will show.
+    Launching {
+        stage: LaunchStage,
+        stand_in: Option<StandIn>,
+    },
+    Running,
+    Degraded(StandIn),
+}
+
+impl fmt::Display for LaunchState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchState::Stopped => f.write_str("stopped"),
+            LaunchState::Launching { stage, .. } => write!(f, "launching ({stage})"),
+            LaunchState::Running => f.write_str("running"),
+            LaunchState::Degraded(stand_in) => write!(f, "degraded ({stand_in})"),
+        }
+    }
+}
+
+/// Something that moves a player between [`LaunchState`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchEvent {
+    /// The player was started, showing `stand_in` when set.
+    Spawned(Option<StandIn>),
+    /// The player got through this stage.
+    Finished(LaunchStage),
+    /// The player failed to come up, exited, or was stopped.
+    Stopped,
+}
+
+impl LaunchState {
+    /// The state after `event`, or why `event` cannot happen now.
+    pub fn next(self, event: LaunchEvent) -> Result<Self, String> {
+        match (self, event) {
+            (LaunchState::Stopped, LaunchEvent::Spawned(stand_in)) => Ok(LaunchState::Launching {
+                stage: LaunchStage::WaitingForSurface,
+                stand_in,
+            }),
+            (LaunchState::Launching { stage, stand_in }, LaunchEvent::Finished(done))
+                if done == stage =>
+            {
+                Ok(match stage {
+                    LaunchStage::Spawning => LaunchState::Launching {
+                        stage: LaunchStage::WaitingForSurface,
+                        stand_in,
+                    },
+                    LaunchStage::WaitingForSurface => LaunchState::Launching {
+                        stage: LaunchStage::Loading,
+                        stand_in,
+                    },
+                    LaunchStage::Loading | LaunchStage::Playing => match stand_in {
+                        Some(stand_in) => LaunchState::Degraded(stand_in),
+                        None => LaunchState::Running,
+                    },
+                })
+            }
+            (_, LaunchEvent::Stopped) => Ok(LaunchState::Stopped),
+            (state, event) => Err(format!("a {state} player cannot take {event:?}")),
+        }
+    }
+}
+
+/// A player we spawned, with the entry it was started from.
+pub struct Instance {
+    pub entry: WallpaperProfileEntry,
+    pub child: Child,
+    backend: &'static dyn WallpaperBackend,
+    /// The spawned process hands off and exits on purpose (`--fork`, `swww img`).
+    detached: bool,
+    /// MPRIS player registration, present while an audible wallpaper plays.
+    _mpris: Option<MprisHandle>,
+    /// How long the slideshow lets each video play, when capped.
+    video_limit: Option<Duration>,
+    /// Enforces `video_limit` once the player is up.
+    slideshow: Option<Driver>,
+    state: LaunchState,
+    /// What it takes to move on to the entry's next fallback, if it has any left.
+    chain: Option<Chain>,
+}
+
+/// An entry's remaining `fallbacks`, and the output and settings to start them with.
+struct Chain {
+    next: usize,
+    output: Option<Monitor>,
+    settings: Settings,
+}
+
+impl Instance {
+    /// Spawn `entry` on `output`, showing `still` (a frame captured from it) instead
+    /// of its source when given. When the source cannot be shown, the entry's
+    /// fallbacks are tried in order.
+    pub fn spawn(
+        entry: &WallpaperProfileEntry,
+        output: Option<&Monitor>,
+        settings: &Settings,
+        still: Option<PathBuf>,
+    ) -> Result<Self, String> {
+        match Self::spawn_source(entry, entry, output, settings, still, false) {
+            Err(err) if !entry.fallbacks.is_empty() => {
+                warn!(
+                    "{}: {}; trying its fallbacks",
+                    entry.monitor.as_deref().unwrap_or_default(),
+                    err
+                );
+                Self::spawn_fallback(entry, output, settings, 0)
+                    .ok_or_else(|| format!("{err}; none of its fallbacks could be shown either"))
+            }
+            result => result.map(|mut instance| {
+                instance.chain = (!entry.fallbacks.is_empty()).then(|| Chain {
+                    next: 0,
+                    output: output.cloned(),
+                    settings: settings.clone(),
+                });
+                instance
+            }),
+        }
+    }
+
+    /// Spawn the first of `entry`'s fallbacks from `from` on that can be started.
+    fn spawn_fallback(
+        entry: &WallpaperProfileEntry,
+        output: Option<&Monitor>,
+        settings: &Settings,
+        from: usize,
+    ) -> Option<Self> {
+        let monitor = entry.monitor.as_deref().unwrap_or_default();
+        for index in from..entry.fallbacks.len() {
+            let started = entry
+                .fallback_entry(index)
+                .map_err(|err| err.to_string())
+                .and_then(|source| {
+                    Self::spawn_source(entry, &source, output, settings, None, true)
+                });
+            match started {
+                Ok(mut instance) => {
+                    info!("{}: showing fallback {}", monitor, index + 1);
+                    instance.chain = Some(Chain {
+                        next: index + 1,
+                        output: output.cloned(),
+                        settings: settings.clone(),
+                    });
+                    return Some(instance);
+                }
+                Err(err) => warn!("{}: fallback {} failed: {}", monitor, index + 1, err),
+            }
+        }
+        None
+    }
+
+    /// Spawn `source` on `output` for `entry`, which the instance keeps as its own;
+    /// `fallback` marks `source` as one of the entry's fallbacks.
+    fn spawn_source(
+        entry: &WallpaperProfileEntry,
+        source: &WallpaperProfileEntry,
+        output: Option<&Monitor>,
+        settings: &Settings,
+        still: Option<PathBuf>,
+        fallback: bool,
+    ) -> Result<Self, String> {
+        let mut runtime =
+            RuntimeConfig::from_profile_entry(source).map_err(|err| err.to_string())?;
+        if let Some(output) = output {
+            runtime.set_output(output);
+            runtime.select_variant(output, &settings.variants);
+        }
+        // With reduced motion a time-lapse set or slideshow cuts to its next image.
+        if settings.motion.reduced() {
+            if let Some(timelapse) = runtime.timelapse.as_mut() {
+                timelapse.fade_seconds = 0;
+            }
+            runtime.transition = None;
+        }
+        let stand_in = if runtime.from_cache {
+            Some(StandIn::CachedFrame)
+        } else if let Some(frame) = still {
+            runtime.show_still(frame);
+            Some(StandIn::StillFrame)
+        } else if fallback {
+            Some(StandIn::Fallback)
+        } else {
+            None
+        };
+        let child = backend::spawn_instance(&runtime, settings).map_err(|err| err.to_string())?;
+        let backend = backend::for_config(&runtime, settings);
+        let video_limit = runtime
+            .slideshow
+            .video_limit
+            .filter(|_| runtime.media.is_playlist() && runtime.mpvpaper.slideshow)
+            .filter(|_| backend.has_ipc());
+        let mut instance = Self {
+            entry: entry.clone(),
+            child,
+            backend,
+            detached: backend.detaches(&runtime),
+            _mpris: None,
+            video_limit,
+            slideshow: None,
+            state: LaunchState::Stopped,
+            chain: None,
+        };
+        instance.transition(LaunchEvent::Spawned(stand_in));
+        Ok(instance)
+    }
+
+    pub fn monitor(&self) -> &str {
+        self.entry.monitor.as_deref().unwrap_or_default()
+    }
+
+    pub fn state(&self) -> LaunchState {
+        self.state
+    }
+
+    /// What draws this wallpaper.
+    pub fn backend(&self) -> &'static dyn WallpaperBackend {
+        self.backend
+    }
+
+    /// What the player shows instead of its source, launching or up.
+    pub fn stand_in(&self) -> Option<StandIn> {
+        match self.state {
+            LaunchState::Launching { stand_in, .. } => stand_in,
+            LaunchState::Degraded(stand_in) => Some(stand_in),
+            LaunchState::Stopped | LaunchState::Running => None,
+        }
+    }
+
+    /// Process to watch for an early exit; a detached launcher exits on purpose.
+    pub fn pid(&self) -> Option<u32> {
+        (!self.detached).then(|| self.child.id())
+    }
+
+    /// The stage the player is waiting on, if it is still launching.
+    pub fn pending_stage(&self) -> Option<LaunchStage> {
+        match self.state {
+            LaunchState::Launching { stage, .. } => Some(stage),
+            _ => None,
+        }
+    }
+
+    /// Record that the player got through `stage`; once it plays, an audible
+    /// wallpaper is offered to media keys and a capped slideshow starts being timed.
+    pub fn finish(&mut self, stage: LaunchStage) {
+        self.transition(LaunchEvent::Finished(stage));
+        if self.state == LaunchState::Running
+            && self.slideshow.is_none()
+            && let Some(limit) = self.video_limit
+        {
+            self.slideshow = Some(Driver::start(self.monitor(), limit));
+        }
+        if self.state == LaunchState::Running && self.entry.audio && self._mpris.is_none() {
+            let monitor = self.monitor().to_string();
+            self._mpris = mpris::register(&monitor)
+                .map_err(|err| warn!("Could not register MPRIS player for {}: {}", monitor, err))
+                .ok();
+        }
+    }
+
+    /// Wait for the player to come up, reporting each stage as it begins. A player
+    /// that fails is replaced by the entry's next fallback, if it has one.
+    pub fn confirm(&mut self, mut report: impl FnMut(LaunchStage)) -> Result<(), String> {
+        while let Some(stage) = self.pending_stage() {
+            report(stage);
+            if let Err(err) = self
+                .backend
+                .wait_for_stage(self.monitor(), self.pid(), stage)
+            {
+                let err = err.to_string();
+                let Some(chain) = self.chain.take() else {
+                    return Err(err);
+                };
+                warn!("{}: {}; trying its next fallback", self.monitor(), err);
+                self.halt();
+                let next = Self::spawn_fallback(
+                    &self.entry,
+                    chain.output.as_ref(),
+                    &chain.settings,
+                    chain.next,
+                )
+                .ok_or_else(|| format!("{err}; none of its fallbacks could be shown either"))?;
+                *self = next;
+                continue;
+            }
+            self.finish(stage);
+        }
+        report(LaunchStage::Playing);
+        Ok(())
+    }
+
+    /// Whether the player went away on its own, leaving it Stopped if so.
+    pub fn has_exited(&mut self) -> bool {
+        let alive = matches!(self.child.try_wait(), Ok(None)) || self.detached;
+        if !alive {
+            warn!("The wallpaper on {} exited", self.monitor());
+            self.transition(LaunchEvent::Stopped);
+        }
+        !alive
+    }
+
+    /// Terminate the player and reap it.
+    pub fn stop(mut self) {
+        self.halt();
+    }
+
+    fn halt(&mut self) {
+        info!("Stopping {} for {}", self.backend.program(), self.monitor());
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        // Forked or sandboxed players outlive the process we hold, so match them by output.
+        let _ = self.backend.stop(self.monitor());
+        self.transition(LaunchEvent::Stopped);
+    }
+
+    fn transition(&mut self, event: LaunchEvent) {
+        match self.state.next(event) {
+            Ok(state) => self.state = state,
+            Err(err) => warn!("Ignoring a launch event for {}: {}", self.monitor(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `events` from Stopped, failing on the first one refused.
+    fn run(events: &[LaunchEvent]) -> Result<LaunchState, String> {
+        events
+            .iter()
+            .try_fold(LaunchState::Stopped, |state, event| state.next(*event))
+    }
+
+    #[test]
+    fn stopped_launches_and_runs() {
+        let launching = LaunchState::Stopped.next(LaunchEvent::Spawned(None));
+        assert_eq!(
+            launching,
+            Ok(LaunchState::Launching {
+                stage: LaunchStage::WaitingForSurface,
+                stand_in: None,
+            })
+        );
+        let loading = launching
+            .unwrap()
+            .next(LaunchEvent::Finished(LaunchStage::WaitingForSurface));
+        assert_eq!(
+            loading,
+            Ok(LaunchState::Launching {
+                stage: LaunchStage::Loading,
+                stand_in: None,
+            })
+        );
+        assert_eq!(
+            loading
+                .unwrap()
+                .next(LaunchEvent::Finished(LaunchStage::Loading)),
+            Ok(LaunchState::Running)
+        );
+    }
+
+    #[test]
+    fn a_stand_in_ends_degraded() {
+        for stand_in in [StandIn::CachedFrame, StandIn::StillFrame, StandIn::Fallback] {
+            assert_eq!(
+                run(&[
+                    LaunchEvent::Spawned(Some(stand_in)),
+                    LaunchEvent::Finished(LaunchStage::WaitingForSurface),
+                    LaunchEvent::Finished(LaunchStage::Loading),
+                ]),
+                Ok(LaunchState::Degraded(stand_in))
+            );
+        }
+    }
+
+    #[test]
+    fn stopped_is_reachable_from_anywhere() {
+        for state in [
+            LaunchState::Stopped,
+            LaunchState::Launching {
+                stage: LaunchStage::Loading,
+                stand_in: None,
+            },
+            LaunchState::Running,
+            LaunchState::Degraded(StandIn::Fallback),
+        ] {
+            assert_eq!(state.next(LaunchEvent::Stopped), Ok(LaunchState::Stopped));
+        }
+    }
+
+    #[test]
+    fn out_of_order_events_are_refused() {
+        // Finishing a stage the player is not in.
+        assert!(
+            run(&[
+                LaunchEvent::Spawned(None),
+                LaunchEvent::Finished(LaunchStage::Loading),
+            ])
+            .is_err()
+        );
+        // Nothing to finish before it was spawned.
+        assert!(
+            LaunchState::Stopped
+                .next(LaunchEvent::Finished(LaunchStage::WaitingForSurface))
+                .is_err()
+        );
+        // Spawning again without stopping first.
+        assert!(
+            LaunchState::Running
+                .next(LaunchEvent::Spawned(None))
+                .is_err()
+        );
+        assert!(
+            LaunchState::Degraded(StandIn::CachedFrame)
+                .next(LaunchEvent::Finished(LaunchStage::Loading))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Per-user guard so only one `wpe -c` launches wallpapers at a time.
+
+use std::{
+    env,
+    error::Error,
+    fs::{File, OpenOptions, TryLockError},
+    path::PathBuf,
+};
+
+use crate::state;
+
+/// Held for as long as the launcher runs; the lock is released when dropped.
+pub struct LaunchLock {
+    _file: File,
+}
+
+/// Try to take the launch lock, returning `None` if another wpe already holds it.
+pub fn try_acquire() -> Result<Option<LaunchLock>, Box<dyn Error>> {
+    let path = lock_file_path()?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|err| format!("Unable to open lock file {}: {}", path.display(), err))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(LaunchLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+/// Prefer $XDG_RUNTIME_DIR (cleared on logout), falling back to the state directory.
+fn lock_file_path() -> Result<PathBuf, Box<dyn Error>> {
+    if let Ok(runtime) = env::var("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(runtime).join("wpe.lock"));
+    }
+    Ok(state::state_dir()?.join("wpe.lock"))
+}
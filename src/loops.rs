@@ -0,0 +1,114 @@
+//! Find seamless loop points in a video by comparing downscaled frames.
+//!
+//! ffmpeg decodes the clip at a low framerate into tiny grayscale frames; the
+//! pair of frames (one near the start, one near the end) that differ the least
+//! becomes the loop, so mpv can A-B loop between them without a visible jump.
+
+use std::{
+    error::Error,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::deps;
+
+/// Analysis framerate; loop points are accurate to 1/SAMPLE_FPS seconds.
+const SAMPLE_FPS: u32 = 10;
+const FRAME_WIDTH: usize = 32;
+const FRAME_HEIGHT: usize = 18;
+/// Only look for the start in the first quarter and the end in the last quarter.
+const SEARCH_FRACTION: usize = 4;
+
+/// Start/end of the loop, in seconds from the beginning of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoopPoints {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Largest speed change made to fit a loop to the refresh rate; mpv's
+/// `display-resample` stays within the same bound by default.
+const MAX_SYNC_CHANGE: f64 = 0.01;
+
+/// Playback speed that makes a loop of `length` seconds last a whole number of
+/// refreshes at `hz`, or `None` when it already does or would need too big a change.
+pub fn sync_speed(length: f64, hz: u32) -> Option<f64> {
+    let refreshes = length * f64::from(hz);
+    let whole = refreshes.round();
+    if whole < 1.0 {
+        return None;
+    }
+    let speed = refreshes / whole;
+    ((speed - 1.0).abs() > 1e-6 && (speed - 1.0).abs() <= MAX_SYNC_CHANGE).then_some(speed)
+}
+
+/// Scan `path` and return the most seamless loop points.
+pub fn find_loop_points(path: &Path) -> Result<LoopPoints, Box<dyn Error>> {
+    if !deps::probe().ffmpeg.installed {
+        return Err("Loop detection needs ffmpeg, which was not found in PATH".into());
+    }
+
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .arg("-vf")
+        .arg(format!(
+            "fps={SAMPLE_FPS},scale={FRAME_WIDTH}:{FRAME_HEIGHT},format=gray"
+        ))
+        .args(["-f", "rawvideo", "-"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "ffmpeg could not decode {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    let frames: Vec<&[u8]> = output
+        .stdout
+        .chunks_exact(FRAME_WIDTH * FRAME_HEIGHT)
+        .collect();
+    best_loop(&frames).ok_or_else(|| "Video is too short to find a loop".into())
+}
+
+/// Pick the start/end frame pair with the smallest difference, favouring longer loops on ties.
+fn best_loop(frames: &[&[u8]]) -> Option<LoopPoints> {
+    let window = frames.len() / SEARCH_FRACTION;
+    if window == 0 {
+        return None;
+    }
+
+    let mut best: Option<(u64, usize, usize)> = None;
+    for start in 0..window {
+        for end in frames.len() - window..frames.len() {
+            let diff = frame_difference(frames[start], frames[end]);
+            let better = match best {
+                None => true,
+                Some((best_diff, best_start, best_end)) => {
+                    diff < best_diff || (diff == best_diff && end - start > best_end - best_start)
+                }
+            };
+            if better {
+                best = Some((diff, start, end));
+            }
+        }
+    }
+
+    best.map(|(_, start, end)| LoopPoints {
+        start: start as f64 / SAMPLE_FPS as f64,
+        end: end as f64 / SAMPLE_FPS as f64,
+    })
+}
+
+fn frame_difference(a: &[u8], b: &[u8]) -> u64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| u64::from(x.abs_diff(*y)))
+        .sum()
+}
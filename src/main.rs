@@ -1,29 +1,484 @@
+mod audio;
+mod backend;
+mod battery;
+mod bench;
+#[cfg(feature = "builtin")]
+mod builtin;
+mod bus;
 mod cli;
+mod colorscheme;
+mod compose;
 mod config;
+mod config_watch;
+mod control;
+mod crash;
+mod daemon;
+mod deps;
+mod doctor;
+mod error;
+mod fallback;
+mod fullscreen;
+mod grid;
 mod gui;
+mod history;
+mod http;
+mod idle;
+mod ipc;
+mod launcher;
+mod lock;
+mod loops;
 mod monitors;
+mod motion;
+mod mounts;
+mod mpris;
 mod mpvpaper;
+mod notify;
+mod output;
+mod palette;
+mod parallax;
+mod pip;
+mod power;
+mod probe;
+mod procedural;
 mod profile_launcher;
+mod queue;
+mod runtime;
+mod sandbox;
+mod seasons;
+mod service;
+mod shader;
+mod slideshow;
+mod sources;
+mod state;
+mod sync;
+mod tags;
+mod thermal;
+mod thumbnails;
+mod timelapse;
+mod transition;
+mod tray;
+mod tweaks;
+mod usage;
+mod variants;
+mod watchdog;
+mod web;
+mod worker;
+mod workshop;
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
 
 use clap::Parser;
-use cli::Args;
+use cli::{Args, Command, ConfigCommand, DaemonCommand, ProfileCommand, TagCommand};
+use output::{CliError, Failure};
 use tracing_subscriber::EnvFilter;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
+fn main() -> ExitCode {
+    // Initialize logging on stderr so stdout stays clean for --porcelain
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(crash::LogWriter)
         .init();
+    crash::install_hook();
 
     let args = Args::parse();
+    output::set_mode(args.output_mode());
+    config::set_kiosk(args.kiosk);
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => err.report(),
+    }
+}
 
-    if args.use_config {
+fn run(args: Args) -> Result<(), CliError> {
+    if let Some(command) = args.command {
+        run_command(command)
+    } else if args.use_config {
         // Launch wallpapers from config.toml with -c (--config)
-        profile_launcher::launch_from_profile()?;
+        profile_launcher::launch_from_profile(Duration::from_secs(args.wait_timeout))
     } else {
         // Launch the GUI
-        gui::launch()?;
+        Ok(gui::launch()?)
+    }
+}
+
+fn run_command(command: Command) -> Result<(), CliError> {
+    match command {
+        Command::Status { json: true } => {
+            control::status_json(&deps::probe())?;
+            check_backend()?;
+        }
+        Command::Status { json: false } => {
+            control::status()?;
+            let deps = deps::probe();
+            for line in deps.summary() {
+                output::say(line);
+            }
+            for tool in deps.tools() {
+                output::record(
+                    "tool",
+                    &[
+                        tool.name,
+                        if tool.installed {
+                            "installed"
+                        } else {
+                            "missing"
+                        },
+                        tool.version.as_deref().unwrap_or("-"),
+                    ],
+                );
+            }
+            for issue in deps.issues() {
+                output::say(format!("warning: {}", issue));
+                output::record("warning", &[&issue]);
+            }
+            check_backend()?;
+        }
+        Command::Monitors { json } => control::monitors(json)?,
+        Command::Next(target) => control::act(control::Action::Next, target.monitor.as_deref())?,
+        Command::Previous(target) => {
+            control::act(control::Action::Previous, target.monitor.as_deref())?
+        }
+        Command::Favorite(target) => {
+            control::act(control::Action::Favorite, target.monitor.as_deref())?
+        }
+        Command::Pause(target) => control::act(control::Action::Pause, target.monitor.as_deref())?,
+        Command::Resume(target) => {
+            control::act(control::Action::Resume, target.monitor.as_deref())?
+        }
+        Command::Toggle { no_osd } => control::toggle(!no_osd)?,
+        Command::Doctor { json } => doctor::run(json)?,
+        Command::PowerReport { seconds } => {
+            power::run_report(Duration::from_secs(seconds.max(1)))?;
+        }
+        Command::Config {
+            action: ConfigCommand::Convert { to, force },
+        } => {
+            let (from, target) = config::convert_config(to, force).map_err(CliError::config)?;
+            let backup = format!("{}.bak", from.display());
+            output::say(format!(
+                "Converted {} to {}.",
+                from.display(),
+                target.display()
+            ));
+            output::say(format!("The previous file was kept as {}.", backup));
+            output::record(
+                "converted",
+                &[
+                    &from.display().to_string(),
+                    &target.display().to_string(),
+                    &backup,
+                ],
+            );
+        }
+        Command::Config {
+            action: ConfigCommand::Schema,
+        } => output::emit(config::config_schema().map_err(CliError::config)?),
+        Command::Bench {
+            file,
+            monitor,
+            seconds,
+            apply,
+        } => bench::run(
+            &file,
+            monitor.as_deref(),
+            Duration::from_secs(seconds.max(1)),
+            apply,
+        )?,
+        Command::Sync { name, due } => run_sync(name.as_deref(), due)?,
+        Command::Tag { action } => run_tag(action)?,
+        Command::Profile { action } => run_profile(action)?,
+        Command::Import { path, monitor } => run_import(path, monitor.as_deref())?,
+        Command::Stats { top } => run_stats(top)?,
+        Command::InstallService {
+            mode,
+            autostart,
+            now,
+        } => service::install(mode, autostart, now)?,
+        Command::UninstallService => service::uninstall()?,
+        Command::Daemon { http, action: None } => daemon::run(http)?,
+        Command::Daemon {
+            action: Some(action),
+            ..
+        } => {
+            let request = match action {
+                DaemonCommand::Start => daemon::Request::Start,
+                DaemonCommand::Stop { monitor } => daemon::Request::Stop { monitor },
+                DaemonCommand::Reload => daemon::Request::Reload,
+                DaemonCommand::Set { monitor, path } => daemon::Request::Set {
+                    monitor,
+                    // The daemon runs elsewhere; URLs are kept as they are.
+                    path: fs::canonicalize(&path).unwrap_or(path),
+                },
+                DaemonCommand::Status => daemon::Request::Status,
+                DaemonCommand::Quit => daemon::Request::Quit,
+            };
+            daemon::report(&daemon::send(&request)?)?;
+        }
+        #[cfg(feature = "builtin")]
+        Command::Render {
+            options,
+            slideshow,
+            timelapse,
+            timelapse_start,
+            parallax,
+            parallax_shift,
+            shader,
+            shader_speed,
+            transition,
+            transition_ms,
+            monitor,
+            path,
+        } => builtin::run(
+            &monitor,
+            &path,
+            options.as_deref(),
+            slideshow,
+            timelapse.map(|fade_seconds| timelapse::TimelapseSettings {
+                fade_seconds,
+                start_hour: timelapse_start,
+            }),
+            parallax.map(|depth_map| parallax::ParallaxSettings {
+                depth_map,
+                shift_percent: parallax_shift,
+            }),
+            shader.map(|fps| shader::ShaderSettings {
+                fps,
+                speed_percent: shader_speed,
+            }),
+            transition.map(|effect| transition::TransitionSettings {
+                effect,
+                milliseconds: transition_ms,
+            }),
+        )?,
+        Command::Worker { task, path } => worker::serve(task, &path)?,
+    }
+    Ok(())
+}
+
+fn run_sync(name: Option<&str>, due: bool) -> Result<(), CliError> {
+    let collections = config::load_settings()?.sync;
+    if let Some(name) = name
+        && !collections.iter().any(|collection| collection.name == name)
+    {
+        return Err(CliError::config(format!(
+            "No [[sync]] collection named {name}"
+        )));
+    }
+
+    let mut state = state::load_state();
+    let selected: Vec<_> = collections
+        .iter()
+        .filter(|collection| name.is_none_or(|name| collection.name == name))
+        .filter(|collection| !due || collection.is_due(&state))
+        .collect();
+    let mut failures = 0usize;
+    for collection in selected {
+        match sync::pull(collection, &mut state) {
+            Ok(()) => {
+                output::say(format!("Synced {}.", collection.name));
+                output::record("synced", &[&collection.name]);
+            }
+            Err(err) => {
+                failures += 1;
+                output::say(format!("Failed to sync {}: {}", collection.name, err));
+                output::record("failed", &[&collection.name, &err.to_string()]);
+            }
+        }
+    }
+    state::save_state(&state)?;
+
+    if failures > 0 {
+        return Err(CliError::new(
+            Failure::General,
+            format!("{failures} collection(s) failed to sync"),
+        ));
+    }
+    Ok(())
+}
+
+fn run_profile(action: ProfileCommand) -> Result<(), CliError> {
+    let mut state = state::load_state();
+    match action {
+        ProfileCommand::Save { name } => {
+            let path = config::save_named_profile(&name).map_err(CliError::config)?;
+            output::say(format!("Saved profile {} to {}.", name, path.display()));
+            output::record("saved", &[&name, &path.display().to_string()]);
+            state.active_profile = Some(name);
+        }
+        ProfileCommand::Load { name } => {
+            config::load_named_profile(&name).map_err(CliError::config)?;
+            output::say(format!("Switched to profile {}.", name));
+            output::record("loaded", &[&name]);
+            state.active_profile = Some(name);
+            state::save_state(&state)?;
+            // Wallpapers already on screen follow the switch; otherwise `wpe -c` applies it.
+            if daemon::is_running() {
+                return daemon::report(&daemon::send(&daemon::Request::Reload)?);
+            }
+            if !power::find_instances().is_empty() {
+                return profile_launcher::launch_from_profile(Duration::from_secs(10));
+            }
+            return Ok(());
+        }
+        ProfileCommand::List => {
+            for name in config::list_profiles()? {
+                let active = state.active_profile.as_deref() == Some(name.as_str());
+                output::say(if active {
+                    format!("{name} (active)")
+                } else {
+                    name.clone()
+                });
+                output::record("profile", &[&name, if active { "active" } else { "-" }]);
+            }
+            return Ok(());
+        }
+    }
+    state::save_state(&state)?;
+    Ok(())
+}
+
+fn run_stats(top: usize) -> Result<(), CliError> {
+    let records = usage::load();
+    let mut shown: Vec<_> = records.iter().collect();
+    shown.sort_by(|a, b| {
+        b.1.seconds
+            .cmp(&a.1.seconds)
+            .then(b.1.shows.cmp(&a.1.shows))
+    });
+    output::say("Most shown:");
+    if shown.is_empty() {
+        output::say("  nothing yet; wpe counts while the GUI or daemon runs the wallpapers");
+    }
+    for (file, record) in shown.into_iter().take(top) {
+        output::say(format!(
+            "  {:>9}  {:>4}x  {}",
+            usage::format_seconds(record.seconds),
+            record.shows,
+            file.display()
+        ));
+        output::record(
+            "shown",
+            &[
+                &file.display().to_string(),
+                &record.shows.to_string(),
+                &record.seconds.to_string(),
+            ],
+        );
     }
 
+    let never = usage::never_shown(&config::load_wallpaper_entries()?, &records);
+    output::say(format!("Never shown ({}):", never.len()));
+    for file in &never {
+        output::say(format!("  {}", file.display()));
+        output::record("never", &[&file.display().to_string()]);
+    }
+    Ok(())
+}
+
+fn run_import(path: Option<PathBuf>, monitor: Option<&str>) -> Result<(), CliError> {
+    let root = path.or_else(workshop::default_library).ok_or_else(|| {
+        CliError::config("No Steam workshop folder for Wallpaper Engine found; pass its path")
+    })?;
+    let import = workshop::import(&root, monitor)?;
+    for (dir, reason) in &import.skipped {
+        output::say(format!("Skipped {}: {}", dir.display(), reason));
+        output::record("skipped", &[&dir.display().to_string(), reason]);
+    }
+    for project in &import.imported {
+        output::say(format!(
+            "Imported {} ({})",
+            project.title,
+            project.media.display()
+        ));
+        output::record(
+            "imported",
+            &[&project.title, &project.media.display().to_string()],
+        );
+    }
+    if import.imported.is_empty() {
+        return Err(CliError::new(
+            Failure::General,
+            format!("No video projects to import in {}", root.display()),
+        ));
+    }
+    match (&import.monitor, import.imported.as_slice()) {
+        (Some(monitor), [project]) => {
+            output::say(format!("{} now plays {}.", monitor, project.title));
+        }
+        (Some(monitor), projects) => output::say(format!(
+            "{} now draws from the {} imported wallpapers (tag {}).",
+            monitor,
+            projects.len(),
+            workshop::IMPORT_TAG
+        )),
+        (None, _) => output::say(format!(
+            "Play them with source = {{ tags = [\"{}\"] }}, or rerun with --monitor.",
+            workshop::IMPORT_TAG
+        )),
+    }
+    Ok(())
+}
+
+fn run_tag(action: TagCommand) -> Result<(), CliError> {
+    let mut state = state::load_state();
+    match action {
+        TagCommand::Add { path, tags } => {
+            let path = fs::canonicalize(&path).map_err(|err| {
+                CliError::new(
+                    Failure::General,
+                    format!("Cannot tag {}: {}", path.display(), err),
+                )
+            })?;
+            tags::add_tags(&mut state, &path, &tags);
+            print_tags(&path, &tags::tags_for(&state, &path));
+        }
+        TagCommand::Remove { path, tags } => {
+            let path = fs::canonicalize(&path).unwrap_or(path);
+            tags::remove_tags(&mut state, &path, &tags);
+            print_tags(&path, &tags::tags_for(&state, &path));
+        }
+        TagCommand::List { tag } => {
+            for (path, tags) in &state.tags {
+                if tag
+                    .as_deref()
+                    .and_then(tags::normalize_tag)
+                    .is_none_or(|tag| tags.contains(&tag))
+                {
+                    print_tags(path, tags);
+                }
+            }
+            return Ok(());
+        }
+    }
+    state::save_state(&state)?;
+    Ok(())
+}
+
+fn print_tags(path: &Path, tags: &BTreeSet<String>) {
+    let joined = tags.iter().cloned().collect::<Vec<_>>().join(", ");
+    output::say(format!("{}: {}", path.display(), joined));
+    output::record("tagged", &[&path.display().to_string(), &joined]);
+}
+
+/// `wpe status` fails when the configured backend is not installed.
+fn check_backend() -> Result<(), CliError> {
+    let backend = backend::get(
+        config::load_settings()
+            .map(|settings| settings.backend)
+            .unwrap_or_default(),
+    );
+    if !backend.installed() {
+        return Err(CliError::new(
+            Failure::BackendMissing,
+            format!("{} was not found in PATH", backend.program()),
+        ));
+    }
     Ok(())
 }
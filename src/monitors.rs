@@ -1,16 +1,23 @@
-use futures::SinkExt;
 use futures::channel::mpsc::UnboundedSender;
-use std::error::Error;
+use std::{
+    error::Error,
+    hash::{DefaultHasher, Hash, Hasher},
+    thread,
+    time::Duration,
+};
+use tracing::warn;
 
 use smithay_client_toolkit::{
-    output::{OutputHandler, OutputState},
+    output::{OutputHandler, OutputInfo, OutputState},
     reexports::client::{
         Connection, QueueHandle, globals::registry_queue_init, protocol::wl_output::WlOutput,
     },
     registry::{ProvidesRegistryState, RegistryState},
 };
 
-#[derive(Debug, Clone)]
+use crate::error::WpeError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Monitor {
     pub name: String,
     pub description: String,
@@ -19,6 +26,56 @@ pub struct Monitor {
     pub refresh_rate: u32,
 }
 
+impl Monitor {
+    /// The output as the compositor describes it, in its current mode.
+    pub fn from_info(info: &OutputInfo) -> Self {
+        // Prefer the current mode, otherwise just pick the first mode.
+        let mode = info
+            .modes
+            .iter()
+            .find(|m| m.current)
+            .or_else(|| info.modes.first());
+
+        let (width, height, refresh_rate) = mode
+            .map(|m| {
+                let (w, h) = m.dimensions;
+                // refresh_rate is in millihertz; fall back to 60 Hz if 0.
+                let hz = if m.refresh_rate > 0 {
+                    (m.refresh_rate / 1000).max(1)
+                } else {
+                    60
+                };
+                (w as u32, h as u32, hz as u32)
+            })
+            .unwrap_or((1920, 1080, 60));
+
+        Monitor {
+            name: info.name.clone().unwrap_or_else(|| "unknown".into()),
+            description: info
+                .description
+                .clone()
+                .unwrap_or_else(|| "No description".into()),
+            width,
+            height,
+            refresh_rate,
+        }
+    }
+}
+
+/// Updates pushed by the output watcher.
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    /// Fresh list of connected outputs.
+    Outputs(Vec<Monitor>),
+    /// The compositor connection dropped; a reconnect is being attempted.
+    ConnectionLost(String),
+}
+
+/// Exponential backoff for reconnect attempts, capped at 30 seconds.
+pub fn reconnect_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.min(5)).min(Duration::from_secs(30))
+}
+
 /// Minimal app state just for querying outputs.
 struct MonitorApp {
     registry_state: RegistryState,
@@ -58,10 +115,11 @@ impl ProvidesRegistryState for MonitorApp {
     smithay_client_toolkit::registry_handlers!(OutputState);
 }
 
-pub fn list_monitors() -> Result<Vec<Monitor>, Box<dyn Error>> {
+pub fn list_monitors() -> Result<Vec<Monitor>, WpeError> {
     // Connect and grab the initial global list + a queue.
-    let conn = Connection::connect_to_env()?;
-    let (globals, mut event_queue) = registry_queue_init::<MonitorApp>(&conn)?;
+    let conn = connect()?;
+    let (globals, mut event_queue) = registry_queue_init::<MonitorApp>(&conn)
+        .map_err(|err| WpeError::wayland("Unable to list the Wayland globals").with_source(err))?;
 
     // Create our app state and bind outputs via OutputState.
     let qh = event_queue.handle();
@@ -71,52 +129,58 @@ pub fn list_monitors() -> Result<Vec<Monitor>, Box<dyn Error>> {
     };
 
     // Process events once so OutputState receives output info
-    event_queue.blocking_dispatch(&mut app)?;
+    event_queue
+        .blocking_dispatch(&mut app)
+        .map_err(|err| WpeError::wayland("Lost the Wayland connection").with_source(err))?;
 
-    // Read out all outputs from OutputState.
-    let mut monitors = Vec::new();
-
-    for wl_output in app.output_state.outputs() {
-        if let Some(info) = app.output_state.info(&wl_output) {
-            // Prefer the current mode, otherwise just pick the first mode.
-            let mode = info
-                .modes
-                .iter()
-                .find(|m| m.current)
-                .or_else(|| info.modes.first());
-
-            let (width, height, refresh_rate) = mode
-                .map(|m| {
-                    let (w, h) = m.dimensions;
-                    // refresh_rate is in millihertz; fall back to 60 Hz if 0.
-                    let hz = if m.refresh_rate > 0 {
-                        (m.refresh_rate / 1000).max(1)
-                    } else {
-                        60
-                    };
-                    (w as u32, h as u32, hz as u32)
-                })
-                .unwrap_or((1920, 1080, 60));
-
-            monitors.push(Monitor {
-                name: info.name.clone().unwrap_or_else(|| "unknown".into()),
-                description: info
-                    .description
-                    .clone()
-                    .unwrap_or_else(|| "No description".into()),
-                width,
-                height,
-                refresh_rate,
-            });
-        }
-    }
+    Ok(collect_monitors(&app.output_state))
+}
+
+/// Whether the compositor advertises the Wayland global `interface`.
+pub fn has_global(interface: &str) -> Result<bool, WpeError> {
+    let conn = connect()?;
+    let (globals, _queue) = registry_queue_init::<MonitorApp>(&conn)
+        .map_err(|err| WpeError::wayland("Unable to list the Wayland globals").with_source(err))?;
+    Ok(globals
+        .contents()
+        .with_list(|list| list.iter().any(|global| global.interface == interface)))
+}
 
-    Ok(monitors)
+fn connect() -> Result<Connection, WpeError> {
+    Connection::connect_to_env().map_err(|err| {
+        WpeError::wayland("Unable to connect to the Wayland compositor").with_source(err)
+    })
 }
 
 /// Watch outputs and push updates to an async channel (unbounded).
-pub fn watch_monitors_unbounded(
-    mut tx: UnboundedSender<Vec<Monitor>>,
+///
+/// If the compositor goes away (e.g. it restarts), a `ConnectionLost` event is
+/// sent and the watcher keeps reconnecting with backoff; the first snapshot after
+/// a successful reconnect re-enumerates every output.
+pub fn watch_monitors_unbounded(tx: UnboundedSender<MonitorEvent>) {
+    let mut attempt = 0;
+    loop {
+        match watch_session(&tx, &mut attempt) {
+            Ok(()) => return,
+            Err(err) => {
+                warn!("Lost Wayland connection while watching outputs: {}", err);
+                if tx
+                    .unbounded_send(MonitorEvent::ConnectionLost(err.to_string()))
+                    .is_err()
+                {
+                    return;
+                }
+                thread::sleep(reconnect_delay(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Run one connection's worth of output watching; returns Ok once the receiver is gone.
+fn watch_session(
+    tx: &UnboundedSender<MonitorEvent>,
+    attempt: &mut u32,
 ) -> Result<(), Box<dyn Error>> {
     let conn = Connection::connect_to_env()?;
     let (globals, mut event_queue) = registry_queue_init::<MonitorApp>(&conn)?;
@@ -128,59 +192,53 @@ pub fn watch_monitors_unbounded(
     };
 
     event_queue.blocking_dispatch(&mut app)?;
-    if !futures::executor::block_on(send_snapshot_async(&app.output_state, &mut tx)) {
+    *attempt = 0;
+    // The first snapshot of a session always goes out, so a reconnect re-enumerates.
+    let mut last = snapshot_hash(&app.output_state);
+    if !send_snapshot(&app.output_state, tx) {
         return Ok(());
     }
 
     loop {
         event_queue.blocking_dispatch(&mut app)?;
-        if !futures::executor::block_on(send_snapshot_async(&app.output_state, &mut tx)) {
+        // Most dispatches (xdg-output done events, unrelated globals) change nothing.
+        let hash = snapshot_hash(&app.output_state);
+        if hash == last {
+            continue;
+        }
+        last = hash;
+        if !send_snapshot(&app.output_state, tx) {
             return Ok(());
         }
     }
 }
 
-fn send_snapshot_async(
-    output_state: &OutputState,
-    tx: &mut UnboundedSender<Vec<Monitor>>,
-) -> futures::future::BoxFuture<'static, bool> {
-    let monitors = collect_monitors(output_state);
-    let mut tx = tx.clone();
-    Box::pin(async move { tx.send(monitors).await.is_ok() })
+/// Send the current outputs, returning whether anyone is still listening.
+fn send_snapshot(output_state: &OutputState, tx: &UnboundedSender<MonitorEvent>) -> bool {
+    tx.unbounded_send(MonitorEvent::Outputs(collect_monitors(output_state)))
+        .is_ok()
 }
 
-fn collect_monitors(output_state: &OutputState) -> Vec<Monitor> {
-    let mut monitors = Vec::new();
+/// Fingerprint of everything `collect_monitors` reads, without copying it.
+fn snapshot_hash(output_state: &OutputState) -> u64 {
+    let mut hasher = DefaultHasher::new();
     for wl_output in output_state.outputs() {
-        if let Some(info) = output_state.info(&wl_output) {
-            let mode = info
-                .modes
-                .iter()
-                .find(|m| m.current)
-                .or_else(|| info.modes.first());
-            let (width, height, refresh_rate) = mode
-                .map(|m| {
-                    let (w, h) = m.dimensions;
-                    let hz = if m.refresh_rate > 0 {
-                        (m.refresh_rate / 1000).max(1)
-                    } else {
-                        60
-                    };
-                    (w as u32, h as u32, hz as u32)
-                })
-                .unwrap_or((1920, 1080, 60));
-
-            monitors.push(Monitor {
-                name: info.name.clone().unwrap_or_else(|| "unknown".into()),
-                description: info
-                    .description
-                    .clone()
-                    .unwrap_or_else(|| "No description".into()),
-                width,
-                height,
-                refresh_rate,
-            });
+        let Some(info) = output_state.info(&wl_output) else {
+            continue;
+        };
+        info.name.hash(&mut hasher);
+        info.description.hash(&mut hasher);
+        for mode in &info.modes {
+            (mode.dimensions, mode.refresh_rate, mode.current).hash(&mut hasher);
         }
     }
-    monitors
+    hasher.finish()
+}
+
+fn collect_monitors(output_state: &OutputState) -> Vec<Monitor> {
+    output_state
+        .outputs()
+        .filter_map(|wl_output| output_state.info(&wl_output))
+        .map(|info| Monitor::from_info(&info))
+        .collect()
 }
@@ -0,0 +1,71 @@
+//! Reduced motion, following the desktop's accessibility setting.
+//!
+//! With reduced motion on, moving wallpapers show a still frame of themselves and
+//! changes cut instead of fading. `[motion] reduce` decides when that is: "system"
+//! asks the desktop portal (its `reduced-motion` appearance key, or GNOME's
+//! `enable-animations` where the portal passes that through), "always" and "never"
+//! override it.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::OwnedValue,
+};
+
+/// `reduce`: when wallpapers hold still.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReduceMotion {
+    /// Whenever the desktop asks apps to reduce motion.
+    #[default]
+    System,
+    /// Always show still frames.
+    Always,
+    /// Keep wallpapers moving whatever the desktop says.
+    Never,
+}
+
+/// `[motion]` table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct MotionSettings {
+    pub reduce: ReduceMotion,
+}
+
+impl MotionSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether wallpapers should hold still now; asks the portal for "system".
+    pub fn reduced(&self) -> bool {
+        match self.reduce {
+            ReduceMotion::System => desktop_prefers_reduced().unwrap_or(false),
+            ReduceMotion::Always => true,
+            ReduceMotion::Never => false,
+        }
+    }
+}
+
+/// The desktop's reduced motion preference; `None` without a portal that knows it.
+fn desktop_prefers_reduced() -> Option<bool> {
+    let connection = Connection::session().ok()?;
+    let proxy = Proxy::new(
+        &connection,
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Settings",
+    )
+    .ok()?;
+
+    let read = |namespace: &str, key: &str| -> Option<OwnedValue> {
+        proxy.call("Read", &(namespace, key)).ok()
+    };
+    // 0 is no preference, 1 asks for reduced motion.
+    if let Some(value) = read("org.freedesktop.appearance", "reduced-motion") {
+        return u32::try_from(value).ok().map(|code| code == 1);
+    }
+    let animations = read("org.gnome.desktop.interface", "enable-animations")?;
+    bool::try_from(animations).ok().map(|enabled| !enabled)
+}
@@ -0,0 +1,279 @@
+//! Bring up the filesystem an entry's path lives on before launching it.
+//!
+//! At login, network shares and removable drives are often not mounted yet. For a
+//! missing path wpe works out which mount it belongs to, triggers that mount the
+//! way it is normally brought up, and waits for the path to appear:
+//!
+//! - GVFS (`$XDG_RUNTIME_DIR/gvfs/...`): `gio mount <uri>`
+//! - `/etc/fstab` entries (NFS, CIFS, USB, ...): `x-systemd.automount` points are
+//!   simply touched, `user` mounts use `mount`, anything else asks systemd
+//! - Removable drives under `/run/media/$USER/<label>`: `udisksctl mount`
+
+use std::{
+    collections::BTreeSet,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
+
+/// How a missing path's filesystem gets mounted.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mount {
+    /// A GVFS share, mounted from its URI.
+    Gvfs(String),
+    /// An `/etc/fstab` entry that is not mounted yet.
+    Fstab {
+        mount_point: PathBuf,
+        automount: bool,
+        user: bool,
+    },
+    /// A removable drive the desktop mounts by filesystem label.
+    Removable(String),
+}
+
+impl fmt::Display for Mount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mount::Gvfs(uri) => write!(f, "{uri}"),
+            Mount::Fstab { mount_point, .. } => write!(f, "{}", mount_point.display()),
+            Mount::Removable(label) => write!(f, "drive \"{label}\""),
+        }
+    }
+}
+
+impl Mount {
+    /// Ask the system to mount it. The mount may still take a while to show up.
+    fn trigger(&self) -> Result<(), String> {
+        let mut command = match self {
+            Mount::Gvfs(uri) => {
+                let mut command = Command::new("gio");
+                command.args(["mount", uri]);
+                command
+            }
+            Mount::Fstab {
+                mount_point,
+                automount: true,
+                ..
+            } => {
+                // Looking inside an automount point is what mounts it.
+                let _ = fs::read_dir(mount_point);
+                return Ok(());
+            }
+            Mount::Fstab {
+                mount_point,
+                user: true,
+                ..
+            } => {
+                let mut command = Command::new("mount");
+                command.arg(mount_point);
+                command
+            }
+            Mount::Fstab { mount_point, .. } => {
+                // systemctl maps a path to its `.mount` unit.
+                let mut command = Command::new("systemctl");
+                command
+                    .args(["start", "--no-block", "--no-ask-password"])
+                    .arg(mount_point);
+                command
+            }
+            Mount::Removable(label) => {
+                let device = Path::new("/dev/disk/by-label").join(label);
+                if !device.exists() {
+                    return Err(format!("drive \"{label}\" is not plugged in"));
+                }
+                let mut command = Command::new("udisksctl");
+                command
+                    .args(["mount", "--no-user-interaction", "-b"])
+                    .arg(device);
+                command
+            }
+        };
+
+        let program = command.get_program().to_string_lossy().into_owned();
+        let output = command
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| format!("failed to run {program}: {err}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{program} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The not-yet-mounted filesystem `path` would live on, if it can be identified.
+pub fn mount_for(path: &Path) -> Option<Mount> {
+    if path.exists() {
+        return None;
+    }
+    gvfs_mount(path)
+        .or_else(|| removable_mount(path))
+        .or_else(|| fstab_mount(path))
+}
+
+/// Whether `path` is missing only because its filesystem is not mounted yet.
+pub fn is_mountable(path: &Path) -> bool {
+    mount_for(path).is_some()
+}
+
+/// Trigger the mounts behind `paths` and wait up to `timeout` for them to appear.
+/// Returns the paths that are still missing.
+pub fn ensure_mounted(paths: &[PathBuf], timeout: Duration) -> Vec<PathBuf> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    let mounts: BTreeSet<Mount> = paths.iter().filter_map(|path| mount_for(path)).collect();
+    for mount in &mounts {
+        info!("Mounting {} for a configured wallpaper.", mount);
+        if let Err(err) = mount.trigger() {
+            // Keep waiting: the desktop or the user may still mount it.
+            warn!("Could not mount {}: {}", mount, err);
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let missing: Vec<PathBuf> = paths
+            .iter()
+            .filter(|path| !path.exists())
+            .cloned()
+            .collect();
+        if missing.is_empty() || mounts.is_empty() || Instant::now() >= deadline {
+            return missing;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/gvfs/<spec>/...` where the share directory is missing.
+fn gvfs_mount(path: &Path) -> Option<Mount> {
+    let root = PathBuf::from(env::var("XDG_RUNTIME_DIR").ok()?).join("gvfs");
+    let spec = path.strip_prefix(&root).ok()?.components().next()?;
+    let spec = spec.as_os_str().to_str()?;
+    if root.join(spec).exists() {
+        return None;
+    }
+    gvfs_uri(spec).map(Mount::Gvfs)
+}
+
+/// Turn a GVFS directory name such as `smb-share:server=nas,share=media` into a URI.
+fn gvfs_uri(spec: &str) -> Option<String> {
+    let (kind, fields) = spec.split_once(':')?;
+    let field = |key: &str| {
+        fields
+            .split(',')
+            .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+    };
+    let user = field("user")
+        .map(|user| format!("{user}@"))
+        .unwrap_or_default();
+    let port = field("port")
+        .map(|port| format!(":{port}"))
+        .unwrap_or_default();
+    match kind {
+        "smb-share" => Some(format!(
+            "smb://{user}{}/{}",
+            field("server")?,
+            field("share")?
+        )),
+        "sftp" | "ftp" => Some(format!("{kind}://{user}{}{port}", field("host")?)),
+        "dav" => {
+            let scheme = if field("ssl") == Some("true") {
+                "davs"
+            } else {
+                "dav"
+            };
+            let prefix = field("prefix").unwrap_or_default().replace("%2F", "/");
+            Some(format!("{scheme}://{user}{}{port}{prefix}", field("host")?))
+        }
+        "nfs" => Some(format!(
+            "nfs://{}{}",
+            field("host")?,
+            field("prefix").unwrap_or_default().replace("%2F", "/")
+        )),
+        _ => None,
+    }
+}
+
+/// `/run/media/$USER/<label>/...` (or `/media/$USER/<label>`) with the drive unmounted.
+fn removable_mount(path: &Path) -> Option<Mount> {
+    let user = env::var("USER").ok()?;
+    ["/run/media", "/media"].iter().find_map(|base| {
+        let root = Path::new(base).join(&user);
+        let label = path.strip_prefix(&root).ok()?.components().next()?;
+        let label = label.as_os_str().to_str()?;
+        (!root.join(label).exists()).then(|| Mount::Removable(label.to_string()))
+    })
+}
+
+/// The deepest `/etc/fstab` mount point containing `path` that is not mounted.
+fn fstab_mount(path: &Path) -> Option<Mount> {
+    let fstab = fs::read_to_string("/etc/fstab").ok()?;
+    let mounted = mounted_points();
+    fstab
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _source = fields.next()?;
+            let mount_point = PathBuf::from(unescape_octal(fields.next()?));
+            let _kind = fields.next()?;
+            let options: Vec<&str> = fields.next().unwrap_or("defaults").split(',').collect();
+            Some(Mount::Fstab {
+                automount: options.contains(&"x-systemd.automount"),
+                user: options.contains(&"user") || options.contains(&"users"),
+                mount_point,
+            })
+        })
+        .filter(|mount| match mount {
+            Mount::Fstab { mount_point, .. } => {
+                mount_point != Path::new("/")
+                    && path.starts_with(mount_point)
+                    && !mounted.contains(mount_point)
+            }
+            _ => false,
+        })
+        .max_by_key(|mount| match mount {
+            Mount::Fstab { mount_point, .. } => mount_point.components().count(),
+            _ => 0,
+        })
+}
+
+/// Mount points currently in use, from `/proc/self/mountinfo`.
+fn mounted_points() -> BTreeSet<PathBuf> {
+    fs::read_to_string("/proc/self/mountinfo")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(4))
+        .map(|point| PathBuf::from(unescape_octal(point)))
+        .collect()
+}
+
+/// fstab and mountinfo write spaces and tabs in paths as `\040` and `\011`.
+fn unescape_octal(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('\\') {
+        out.push_str(&rest[..index]);
+        let digits = rest.get(index + 1..index + 4).unwrap_or_default();
+        match u8::from_str_radix(digits, 8) {
+            Ok(byte) if digits.len() == 3 => {
+                out.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            _ => {
+                out.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,241 @@
+//! MPRIS2 player for wallpapers that play audio.
+//!
+//! Each audible wallpaper claims `org.mpris.MediaPlayer2.wpe.<output>` on the
+//! session bus so media keys and player widgets can pause or skip it. Commands
+//! are forwarded to the player over its IPC socket.
+
+use std::{collections::HashMap, error::Error, path::Path};
+
+use serde_json::{Value, json};
+use zbus::{blocking::Connection, interface, zvariant::OwnedValue};
+
+use crate::ipc;
+
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Keeps the bus name registered; dropping it removes the player.
+pub struct MprisHandle {
+    _connection: Connection,
+}
+
+/// Register an MPRIS player that controls the wallpaper on `monitor`.
+pub fn register(monitor: &str) -> Result<MprisHandle, Box<dyn Error>> {
+    let connection = zbus::blocking::connection::Builder::session()?
+        .name(format!("org.mpris.MediaPlayer2.wpe.{}", bus_safe(monitor)))?
+        .serve_at(
+            OBJECT_PATH,
+            Root {
+                monitor: monitor.to_string(),
+            },
+        )?
+        .serve_at(
+            OBJECT_PATH,
+            Player {
+                monitor: monitor.to_string(),
+            },
+        )?
+        .build()?;
+    Ok(MprisHandle {
+        _connection: connection,
+    })
+}
+
+/// Bus name elements may only contain `[A-Za-z0-9_]` and must not start with a digit.
+fn bus_safe(monitor: &str) -> String {
+    let mut name: String = monitor
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect();
+    if name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+struct Root {
+    monitor: String,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        format!("WallPaper Engine ({})", self.monitor)
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        "io.melechtna.wpe".into()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct Player {
+    monitor: String,
+}
+
+impl Player {
+    fn send(&self, args: &[Value]) {
+        if let Err(err) = ipc::command(&self.monitor, args) {
+            tracing::warn!("MPRIS command for {} failed: {}", self.monitor, err);
+        }
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn play(&self) {
+        self.send(&[json!("set_property"), json!("pause"), json!(false)]);
+    }
+
+    fn pause(&self) {
+        self.send(&[json!("set_property"), json!("pause"), json!(true)]);
+    }
+
+    fn play_pause(&self) {
+        self.send(&[json!("cycle"), json!("pause")]);
+    }
+
+    fn stop(&self) {
+        self.pause();
+    }
+
+    fn next(&self) {
+        self.send(&[json!("playlist-next"), json!("force")]);
+    }
+
+    fn previous(&self) {
+        self.send(&[json!("playlist-prev"), json!("force")]);
+    }
+
+    fn seek(&self, _offset: i64) {}
+
+    fn set_position(&self, _track_id: zbus::zvariant::ObjectPath<'_>, _position: i64) {}
+
+    fn open_uri(&self, _uri: String) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        match ipc::get_property(&self.monitor, "pause") {
+            Ok(Value::Bool(true)) => "Paused".into(),
+            Ok(_) => "Playing".into(),
+            Err(_) => "Stopped".into(),
+        }
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut metadata = HashMap::new();
+        let track_id = zbus::zvariant::ObjectPath::try_from("/io/melechtna/wpe/track")
+            .map(|path| OwnedValue::from(path.into_owned()));
+        if let Ok(track_id) = track_id {
+            metadata.insert("mpris:trackid".into(), track_id);
+        }
+        if let Ok(Value::String(path)) = ipc::get_property(&self.monitor, "path") {
+            let title = Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or(path);
+            if let Ok(title) = OwnedValue::try_from(zbus::zvariant::Value::from(title)) {
+                metadata.insert("xesam:title".into(), title);
+            }
+        }
+        metadata
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        ipc::get_property(&self.monitor, "volume")
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map(|volume| volume / 100.0)
+            .unwrap_or(1.0)
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        let _ = ipc::set_property(&self.monitor, "volume", json!(volume.max(0.0) * 100.0));
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        ipc::get_property(&self.monitor, "time-pos")
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map(|seconds| (seconds * 1_000_000.0) as i64)
+            .unwrap_or(0)
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
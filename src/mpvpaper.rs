@@ -1,63 +1,475 @@
 use std::{
+    env,
     error::Error,
-    process::{Child, Command, Stdio},
+    ffi::OsString,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::config::{MediaKind, RuntimeConfig, ScaleMode, SlideshowOrder};
+use crate::{
+    audio, backend, compose,
+    config::{MediaKind, RuntimeConfig, ScaleMode, Settings, SlideshowOrder},
+    deps::{self, Feature},
+    error::WpeError,
+    ipc, loops, probe, queue, sandbox, slideshow,
+};
 
-/// Spawn mpvpaper
-pub fn spawn_instance(config: &RuntimeConfig) -> Result<Child, Box<dyn Error>> {
-    let monitor = config
-        .monitor
-        .as_deref()
-        .ok_or_else(|| "Wallpaper entry is missing a monitor assignment".to_string())?;
+/// How long a new instance gets to map its layer surface.
+pub const SURFACE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a mapped instance gets to start playing (large 4K files can be slow to open).
+pub const PLAYBACK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Steps one wallpaper goes through after Start, reported as progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchStage {
+    Spawning,
+    WaitingForSurface,
+    Loading,
+    Playing,
+}
+
+impl fmt::Display for LaunchStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LaunchStage::Spawning => "spawning",
+            LaunchStage::WaitingForSurface => "waiting for surface map",
+            LaunchStage::Loading => "loading media",
+            LaunchStage::Playing => "playing",
+        })
+    }
+}
+
+/// The full mpvpaper (or, when `builtin`, `wpe render`) command line for `config` on
+/// `monitor`, with its log attached, inside the bubblewrap sandbox when
+/// `[security] sandbox` is set.
+pub fn build_command(
+    config: &RuntimeConfig,
+    settings: &Settings,
+    monitor: &str,
+    builtin: bool,
+) -> Result<Command, WpeError> {
+    command_line(config, settings, monitor, builtin)
+        .map_err(|err| WpeError::backend(Some(monitor), err))
+}
+
+fn command_line(
+    config: &RuntimeConfig,
+    settings: &Settings,
+    monitor: &str,
+    builtin: bool,
+) -> Result<Command, Box<dyn Error>> {
     let input_path = config.media.path();
+    // `wpe render` takes the same arguments as mpvpaper.
+    let program = if builtin {
+        env::current_exe()?
+    } else {
+        PathBuf::from("mpvpaper")
+    };
 
-    let mut command = Command::new("mpvpaper");
+    // taskset/nice/ionice exec into the next program, so they stack in front of bwrap/mpvpaper.
+    let mut argv: Vec<OsString> = settings
+        .process
+        .wrapper_args()
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+    if settings.security.sandbox {
+        let mut media: Vec<&Path> = match (&config.grid, &config.media) {
+            (Some(grid), _) => grid.tiles.iter().map(MediaKind::path).collect(),
+            (None, MediaKind::Stream(_)) => Vec::new(),
+            // Playlist entries usually sit next to the playlist.
+            (None, MediaKind::Playlist(list)) => vec![list.parent().unwrap_or(list)],
+            (None, MediaKind::Mix(items)) => items
+                .iter()
+                .filter_map(|item| match item {
+                    MediaKind::Stream(_) => None,
+                    MediaKind::Playlist(list) => Some(list.parent().unwrap_or(list)),
+                    other => Some(other.path()),
+                })
+                .collect(),
+            (None, _) => vec![input_path],
+        };
+        if let Some(pip) = &config.pip
+            && !pip.source.contains("://")
+        {
+            media.push(Path::new(&pip.source));
+        }
+        if let Some(parallax) = &config.parallax {
+            media.push(&parallax.depth_map);
+        }
+        if builtin {
+            media.push(&program);
+        }
+        argv.extend(sandbox::wrap(
+            &program.to_string_lossy(),
+            &media,
+            config.audio,
+        )?);
+    } else {
+        argv.push(program.into_os_string());
+    }
+    if builtin {
+        argv.push("render".into());
+    }
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
 
-    if let MediaKind::Folder(_) = &config.media {
-        let seconds = config.slideshow.interval.as_secs().max(1);
-        command.arg("-n").arg(seconds.to_string());
+    // The remaining mpvpaper switches have no `wpe render` counterpart.
+    if !builtin {
+        if config.mpvpaper.fork {
+            command.arg("--fork");
+        }
+        let occlusion = deps::probe().supports(Feature::OcclusionPause);
+        if config.mpvpaper.auto_pause && occlusion {
+            command.arg("--auto-pause");
+        }
+        if config.mpvpaper.auto_stop && occlusion {
+            command.arg("--auto-stop");
+        }
+        if (config.mpvpaper.auto_pause || config.mpvpaper.auto_stop) && !occlusion {
+            warn!(
+                "Installed mpvpaper has no --auto-pause/--auto-stop; playing {monitor} unpaused."
+            );
+        }
+    }
+    // Only `wpe render` takes time-lapse sets, parallax, and shaders; mpvpaper refuses
+    // them.
+    if let Some(timelapse) = config.timelapse {
+        command
+            .arg(format!("--timelapse={}", timelapse.fade_seconds))
+            .arg(format!("--timelapse-start={}", timelapse.start_hour));
     }
+    if let Some(parallax) = &config.parallax {
+        command
+            .arg("--parallax")
+            .arg(&parallax.depth_map)
+            .arg(format!("--parallax-shift={}", parallax.shift_percent));
+    }
+    if let Some(shader) = config.shader {
+        command
+            .arg(format!("--shader={}", shader.fps))
+            .arg(format!("--shader-speed={}", shader.speed_percent));
+    }
+    match config.transition {
+        Some(transition) if builtin => {
+            command
+                .arg(format!("--transition={}", transition.effect))
+                .arg(format!("--transition-ms={}", transition.milliseconds));
+        }
+        Some(_) => info!(
+            "mpvpaper cuts between files; {monitor} plays its transition only with backend = \"builtin\"."
+        ),
+        None => {}
+    }
+
+    // Folders in wpe's slideshow, shuffled ones (so the cycle survives restarts, or
+    // to follow the usage records), and filtered ones (mpv knows nothing of the
+    // globs) play a wpe-made playlist.
+    let order = config.slideshow.order;
+    let playlist = match &config.media {
+        folder @ MediaKind::Folder(_)
+            if config.timelapse.is_none()
+                && (config.mpvpaper.slideshow
+                    || order != SlideshowOrder::Sequential
+                    || !config.folder_filter.is_default()) =>
+        {
+            slideshow::playlist(monitor, folder, order, &config.folder_filter)
+                .map_err(|err| warn!("Falling back to mpv's own order for {monitor}: {err}"))
+                .ok()
+        }
+        // mpvpaper takes a single path, so a mix only ever plays as a playlist.
+        mix @ MediaKind::Mix(_) => Some(slideshow::playlist(
+            monitor,
+            mix,
+            order,
+            &config.folder_filter,
+        )?),
+        _ => None,
+    };
 
-    let mpv_options = build_mpv_options(config);
+    let mut mpv_options = build_mpv_options(config, settings.hwdec.as_deref(), playlist.is_some());
+    if config.grid.is_some() || config.pip.is_some() {
+        let include = compose::write_include(
+            monitor,
+            config.grid.as_ref(),
+            config.pip.as_ref(),
+            config.output_size,
+        )?;
+        mpv_options.push(format!("--include={}", include.display()));
+    }
     if !mpv_options.is_empty() {
         let joined = mpv_options.join(" ");
         command.arg("-o").arg(joined);
     }
 
     command.arg(monitor);
-    command.arg(input_path);
-    command.stdout(Stdio::null());
-    command.stderr(Stdio::null());
-
-    info!(
-        "Launching mpvpaper for {} with source {}",
-        monitor,
-        input_path.display()
+    match &playlist {
+        Some(playlist) => command.arg(playlist),
+        None => command.arg(input_path),
+    };
+    backend::attach_log(&mut command, monitor)?;
+    // A socket left behind by an earlier player would look like this one is ready.
+    if let Ok(socket) = ipc::socket_path(monitor) {
+        let _ = fs::remove_file(socket);
+    }
+    Ok(command)
+}
+
+/// Wait for a freshly spawned instance on `monitor` to get through `stage`.
+///
+/// `pid` is the spawned process when it is expected to stay alive (not `--fork`ed), so
+/// an early exit is noticed right away. Failures carry the error mpvpaper/mpv printed.
+pub fn wait_for_stage(monitor: &str, pid: Option<u32>, stage: LaunchStage) -> Result<(), WpeError> {
+    let result = match stage {
+        LaunchStage::WaitingForSurface => wait_for_surface(monitor, pid),
+        LaunchStage::Loading => {
+            ipc::wait_for_playback(monitor, PLAYBACK_TIMEOUT).map_err(Into::into)
+        }
+        LaunchStage::Spawning | LaunchStage::Playing => Ok(()),
+    };
+    result.map_err(|err| backend::launch_failed(monitor, err))
+}
+
+fn wait_for_surface(monitor: &str, pid: Option<u32>) -> Result<(), Box<dyn Error>> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    let deadline = Instant::now() + SURFACE_TIMEOUT;
+    loop {
+        if ipc::is_listening(monitor) {
+            return Ok(());
+        }
+        if let Some(pid) = pid
+            && !process_alive(pid)
+        {
+            return Err(format!("mpvpaper for {monitor} exited before mapping its surface").into());
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "mpvpaper for {monitor} did not map a surface within {}s",
+                SURFACE_TIMEOUT.as_secs()
+            )
+            .into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Running (or sleeping) rather than gone or a zombie waiting to be reaped.
+pub fn process_alive(pid: u32) -> bool {
+    fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat.rsplit_once(')')
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .is_some_and(|state| state != "Z" && state != "X")
+    })
+}
+
+/// The last error-looking line in the player's log, if any.
+pub fn last_error(monitor: &str) -> Option<String> {
+    let log = fs::read_to_string(ipc::log_path(monitor).ok()?).ok()?;
+    log.lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| {
+            let lower = line.to_ascii_lowercase();
+            lower.contains("error") || lower.contains("fail") || lower.contains("cannot")
+        })
+        .map(str::to_string)
+}
+
+/// Kill the mpvpaper bound to a single output. Used for `--fork`ed and sandboxed
+/// instances, where the process we hold a handle to is not mpvpaper itself.
+pub fn stop_for_monitor(monitor: &str) -> Result<bool, WpeError> {
+    // mpvpaper's command line ends in `<output> <path>`, and options are joined
+    // into a single `-o` argument, so the output name stands on its own.
+    queue::record(monitor);
+    let pattern = format!(
+        "^(mpvpaper|[^ ]*wpe render)( .*)? {} ",
+        escape_regex(monitor)
     );
+    Command::new("pkill")
+        .arg("-f")
+        .arg(pattern)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .map_err(|err| WpeError::backend(Some(monitor), "Failed to issue pkill").with_source(err))
+}
+
+pub fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if !ch.is_ascii_alphanumeric() && ch != '-' && ch != '_' {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Kill every mpvpaper and `wpe render` on the session, returning whether any were
+/// running.
+pub fn stop_all() -> Result<bool, WpeError> {
+    queue::record_all();
+    let mut stopped = false;
+    for args in [&["mpvpaper"][..], &["-f", "^[^ ]*wpe render "][..]] {
+        stopped |= Command::new("pkill")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .map_err(|err| WpeError::backend(None, "Failed to issue pkill").with_source(err))?;
+    }
+    Ok(stopped)
+}
+
+/// `--video-sync=display-resample` plus the refresh rate and loop speed to pace to.
+/// mpvpaper renders through libmpv, which cannot see the output's refresh rate itself.
+fn display_sync_options(config: &RuntimeConfig, path: &Path) -> Vec<String> {
+    let mut options = vec!["--video-sync=display-resample".to_string()];
+    let Some(hz) = config.refresh_rate else {
+        warn!(
+            "Refresh rate for {} is unknown; leaving display sync to mpv.",
+            config.monitor.as_deref().unwrap_or("the wallpaper")
+        );
+        return options;
+    };
+    options.push(format!("--display-fps-override={hz}"));
+    let length = match config.loop_points {
+        Some(points) => Some(points.end - points.start),
+        None => probe::duration(path),
+    };
+    if let Some(speed) = length.and_then(|length| loops::sync_speed(length, hz)) {
+        info!(
+            "Playing {} at {:.4}x so its loop lines up with {} Hz",
+            path.display(),
+            speed,
+            hz
+        );
+        options.push(format!("--speed={speed:.6}"));
+    }
+    options
+}
 
-    command
-        .spawn()
-        .map_err(|err| format!("Failed to launch mpvpaper for {monitor}: {err}").into())
+/// Height to scale a single video down to, when it is taller than the entry allows.
+fn decode_cap(config: &RuntimeConfig) -> Option<u32> {
+    let MediaKind::Video(path) = &config.media else {
+        return None;
+    };
+    // Grids build their own filter graph.
+    if config.grid.is_some() {
+        return None;
+    }
+    let cap = match config.tuning.max_height {
+        Some(0) => return None,
+        Some(height) => height,
+        None => config.output_size.map(|(_, height)| height)?,
+    };
+    let height = probe::video_height(path)?;
+    (height > cap).then_some(cap)
 }
 
-fn build_mpv_options(config: &RuntimeConfig) -> Vec<String> {
-    let mut options = Vec::new();
-    options.push("--no-audio".into());
-    options.push("--osc=no".into());
-    options.push("--no-osd-bar".into());
-    options.push("--hwdec=auto-safe".into());
+/// `queued` means a folder is played through a wpe-made playlist, already in order.
+/// `default_hwdec` is the top-level `hwdec`, for entries without their own.
+fn build_mpv_options(
+    config: &RuntimeConfig,
+    default_hwdec: Option<&str>,
+    queued: bool,
+) -> Vec<String> {
+    let cap = decode_cap(config);
+    // Filters need the decoded frames in system memory, so copy them back from the GPU.
+    let hwdec = match (config.tuning.hwdec.as_deref().or(default_hwdec), cap) {
+        // mpv spells it `no`; `none` reads better in a config.
+        (Some("none"), _) => "no",
+        (Some(hwdec), _) => hwdec,
+        (None, Some(_)) => "auto-copy-safe",
+        (None, None) => "auto-safe",
+    };
+    let mut options: Vec<String> = vec![
+        "--osc=no".into(),
+        "--no-osd-bar".into(),
+        format!("--hwdec={hwdec}"),
+    ];
+    if let Some(height) = cap {
+        options.push(format!("--vf-append=scale=w=-2:h={height}"));
+    }
+    if let Some(profile) = &config.tuning.profile {
+        options.push(format!("--profile={profile}"));
+    }
+    options.extend(config.picture.mpv_options());
+    if !config.audio {
+        options.push("--no-audio".into());
+    } else {
+        options.push(format!("--volume={}", config.volume));
+        if let Some(device) = &config.audio_device {
+            options.push(format!("--audio-device={}", audio::device_arg(device)));
+        }
+    }
+    if let Some(socket) = config
+        .monitor
+        .as_deref()
+        .and_then(|monitor| ipc::socket_path(monitor).ok())
+    {
+        options.push(format!("--input-ipc-server={}", socket.display()));
+    }
 
-    match config.media {
-        MediaKind::Folder(_) => match config.slideshow.order {
-            SlideshowOrder::Random => options.push("--shuffle".into()),
+    if config.timelapse.is_some() {
+        // `wpe render` loads each image of the set itself, when its time comes.
+        options.push("--image-display-duration=inf".into());
+    } else if config.media.is_playlist() {
+        match config.slideshow.order {
+            SlideshowOrder::Random | SlideshowOrder::LeastShown if !queued => {
+                options.push("--shuffle".into())
+            }
+            SlideshowOrder::Random | SlideshowOrder::LeastShown => {
+                options.push("--no-shuffle".into())
+            }
             SlideshowOrder::Sequential => options.push("--no-shuffle".into()),
-        },
-        _ => {
-            options.push("--loop-file=inf".into());
+        }
+        if config.mpvpaper.slideshow {
+            options.extend(slideshow::mpv_options(&config.slideshow));
+        }
+    } else {
+        options.push("--loop-file=inf".into());
+        if let Some(points) = config.loop_points {
+            options.push(format!("--start={:.3}", points.start));
+            options.push(format!("--ab-loop-a={:.3}", points.start));
+            options.push(format!("--ab-loop-b={:.3}", points.end));
+        }
+    }
+
+    if config.tuning.display_sync
+        && let MediaKind::Video(path) = &config.media
+    {
+        options.extend(display_sync_options(config, path));
+    }
+
+    if !config.margins.is_zero() {
+        match config.output_size {
+            Some((width, height)) if width > 0 && height > 0 => {
+                let margins = config.margins.clamped(width, height);
+                for (edge, pixels, extent) in [
+                    ("left", margins.left, width),
+                    ("right", margins.right, width),
+                    ("top", margins.top, height),
+                    ("bottom", margins.bottom, height),
+                ] {
+                    options.push(format!(
+                        "--video-margin-ratio-{edge}={:.4}",
+                        pixels as f64 / extent as f64
+                    ));
+                }
+            }
+            _ => warn!(
+                "Output size for {} is unknown; ignoring its margins.",
+                config.monitor.as_deref().unwrap_or("the wallpaper")
+            ),
         }
     }
 
@@ -68,7 +480,45 @@ fn build_mpv_options(config: &RuntimeConfig) -> Vec<String> {
             options.push("--keepaspect=yes".into());
             options.push("--video-unscaled=downscale-big".into());
         }
+        ScaleMode::Fill => {
+            options.push("--keepaspect=yes".into());
+            options.push("--panscan=1.0".into());
+        }
+        ScaleMode::Center => {
+            options.push("--keepaspect=yes".into());
+            options.push("--video-unscaled=yes".into());
+        }
+        ScaleMode::Tile => {
+            options.push("--keepaspect=yes".into());
+            options.push("--video-unscaled=yes".into());
+            options.extend(tile_filter(config));
+        }
     }
 
     options
 }
+
+/// A filter repeating a single image across the output for `scale = "tile"`: the
+/// image is looped into as many frames as there are tiles, laid out in a grid, and
+/// cropped to the output. Anything else is only centered.
+fn tile_filter(config: &RuntimeConfig) -> Option<String> {
+    let monitor = config.monitor.as_deref().unwrap_or("the wallpaper");
+    let MediaKind::Image(path) = &config.media else {
+        warn!("Only a single image can tile; centering {monitor} instead.");
+        return None;
+    };
+    if config.grid.is_some() || config.pip.is_some() {
+        return None;
+    }
+    let Some((width, height)) = config.output_size else {
+        warn!("Output size for {monitor} is unknown; centering its image instead of tiling.");
+        return None;
+    };
+    let (tile_width, tile_height) = probe::video_size(path)?;
+    let columns = width.div_ceil(tile_width);
+    let rows = height.div_ceil(tile_height);
+    Some(format!(
+        "--vf-append=lavfi=[loop=loop={}:size=1,tile={columns}x{rows},crop={width}:{height}:0:0]",
+        columns * rows - 1
+    ))
+}
@@ -0,0 +1,111 @@
+//! Desktop notifications, and the summary `wpe -c` and the daemon give at startup.
+//!
+//! Notifications go to the `org.freedesktop.Notifications` service on the session
+//! bus (mako, dunst, swaync, and the big desktops all provide it). After launching,
+//! `wpe -c` and the daemon log one line saying how many monitors were found and
+//! which of them got no wallpaper and why, and show the same as a notification
+//! unless `[notifications] startup = false`.
+
+use std::{collections::HashMap, error::Error};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::Value,
+};
+
+/// Icon named in the notifications, from the freedesktop icon theme.
+const ICON: &str = "preferences-desktop-wallpaper";
+
+/// `[notifications]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Show the startup summary as a notification; it is logged either way.
+    pub startup: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self { startup: true }
+    }
+}
+
+impl NotificationSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Show a notification with `summary` as its title.
+pub fn send(summary: &str, body: &str) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::session()?;
+    let proxy = Proxy::new(
+        &connection,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )?;
+    let actions: Vec<&str> = Vec::new();
+    let hints: HashMap<&str, Value> = HashMap::new();
+    // -1 leaves the timeout to the notification server.
+    let _id: u32 = proxy.call(
+        "Notify",
+        &("wpe", 0u32, ICON, summary, body, actions, hints, -1i32),
+    )?;
+    Ok(())
+}
+
+/// What a launch came to.
+#[derive(Debug, Default)]
+pub struct StartupSummary {
+    /// Outputs the compositor reported.
+    pub monitors: usize,
+    /// Wallpapers that are playing (or showing a stand-in).
+    pub launched: usize,
+    /// Outputs that got no wallpaper, with why.
+    pub skipped: Vec<(String, String)>,
+}
+
+impl StartupSummary {
+    /// "2 monitors detected, 1 wallpaper launched, 1 skipped".
+    pub fn headline(&self) -> String {
+        let mut line = format!(
+            "{} monitor{} detected, {} wallpaper{} launched",
+            self.monitors,
+            plural(self.monitors),
+            self.launched,
+            plural(self.launched)
+        );
+        if !self.skipped.is_empty() {
+            line.push_str(&format!(", {} skipped", self.skipped.len()));
+        }
+        line
+    }
+
+    /// Log the summary, and show it as a notification when `settings` ask for it.
+    pub fn report(&self, settings: &NotificationSettings) {
+        let headline = self.headline();
+        let reasons: Vec<String> = self
+            .skipped
+            .iter()
+            .map(|(monitor, reason)| format!("{monitor}: {reason}"))
+            .collect();
+        if reasons.is_empty() {
+            info!("{}", headline);
+        } else {
+            warn!("{} ({})", headline, reasons.join("; "));
+        }
+        if settings.startup
+            && let Err(err) = send(&headline, &reasons.join("\n"))
+        {
+            warn!("Could not show the startup notification: {}", err);
+        }
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
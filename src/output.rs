@@ -0,0 +1,173 @@
+//! Exit codes and output modes shared by every CLI subcommand.
+//!
+//! Scripts and compositor keybindings branch on the exit code, so its values are
+//! stable: 0 ok, 1 any other failure, 2 config error, 3 no monitors, 4 backend
+//! missing. `--quiet` silences stdout; `--porcelain` replaces the human text with
+//! tab-separated records whose first field names the record type.
+
+use std::{
+    error::Error,
+    fmt,
+    process::ExitCode,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::error::WpeError;
+
+/// How subcommands write to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    Human = 0,
+    Quiet = 1,
+    Porcelain = 2,
+}
+
+static MODE: AtomicU8 = AtomicU8::new(OutputMode::Human as u8);
+
+/// Select the output mode for the rest of the process.
+pub fn set_mode(mode: OutputMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn mode() -> OutputMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => OutputMode::Quiet,
+        2 => OutputMode::Porcelain,
+        _ => OutputMode::Human,
+    }
+}
+
+/// Print a line of human-readable output; dropped in quiet and porcelain modes.
+pub fn say(line: impl fmt::Display) {
+    if mode() == OutputMode::Human {
+        println!("{line}");
+    }
+}
+
+/// Print what a command generates, such as a file's content, in human and porcelain
+/// modes alike; dropped in quiet mode.
+pub fn emit(text: impl fmt::Display) {
+    if mode() != OutputMode::Quiet {
+        println!("{text}");
+    }
+}
+
+/// Print a tab-separated record; only shown in porcelain mode.
+pub fn record(kind: &str, fields: &[&str]) {
+    if mode() != OutputMode::Porcelain {
+        return;
+    }
+    let mut line = kind.to_string();
+    for field in fields {
+        line.push('\t');
+        // Tabs and newlines would break the record framing.
+        line.extend(
+            field
+                .chars()
+                .map(|ch| if ch == '\t' || ch == '\n' { ' ' } else { ch }),
+        );
+    }
+    println!("{line}");
+}
+
+/// Category of a failed command, mapped to a stable exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    General = 1,
+    Config = 2,
+    NoMonitors = 3,
+    BackendMissing = 4,
+}
+
+impl Failure {
+    /// The exit code for a failure of this kind.
+    fn of(err: &WpeError) -> Self {
+        match err {
+            WpeError::Config { .. } => Failure::Config,
+            WpeError::Wayland { .. } => Failure::NoMonitors,
+            WpeError::Backend { missing: true, .. } => Failure::BackendMissing,
+            WpeError::Backend { .. } | WpeError::Ipc { .. } => Failure::General,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Failure::General => "error",
+            Failure::Config => "config",
+            Failure::NoMonitors => "no-monitors",
+            Failure::BackendMissing => "backend-missing",
+        }
+    }
+}
+
+/// Error returned from CLI commands, carrying the exit code to use.
+#[derive(Debug)]
+pub struct CliError {
+    pub failure: Failure,
+    pub message: String,
+    /// Advice printed under the message.
+    pub hint: Option<String>,
+}
+
+impl CliError {
+    pub fn new(failure: Failure, message: impl fmt::Display) -> Self {
+        Self {
+            failure,
+            message: message.to_string(),
+            hint: None,
+        }
+    }
+
+    pub fn config(err: impl fmt::Display) -> Self {
+        Self::new(Failure::Config, err)
+    }
+
+    /// Print the error to stderr and turn it into the process exit code.
+    pub fn report(&self) -> ExitCode {
+        if mode() == OutputMode::Porcelain {
+            eprintln!(
+                "error\t{}\t{}",
+                self.failure.label(),
+                self.message.replace(['\t', '\n'], " ")
+            );
+        } else {
+            eprintln!("wpe: {}", self.message);
+            if let Some(hint) = &self.hint {
+                eprintln!("hint: {hint}");
+            }
+        }
+        ExitCode::from(self.failure as u8)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for CliError {}
+
+impl From<WpeError> for CliError {
+    fn from(err: WpeError) -> Self {
+        let failure = Failure::of(&err);
+        Self {
+            failure,
+            hint: Some(err.hint()),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for CliError {
+    fn from(err: Box<dyn Error>) -> Self {
+        match WpeError::find(err.as_ref()) {
+            Some(kind) => Self {
+                failure: Failure::of(kind),
+                hint: Some(kind.hint()),
+                message: err.to_string(),
+            },
+            None => Self::new(Failure::General, err),
+        }
+    }
+}
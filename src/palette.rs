@@ -0,0 +1,137 @@
+//! Dominant colors of a wallpaper frame.
+//!
+//! Pixels of a small thumbnail are grouped into coarse RGB buckets, the most
+//! populated buckets become the palette, and near-duplicates are merged. The GUI
+//! derives its theme from the result.
+
+use std::{error::Error, path::Path};
+
+use crate::config::HexColor;
+
+/// Side of the thumbnail the colors are counted on.
+const THUMBNAIL_SIZE: u32 = 64;
+/// Palette entries closer than this (Euclidean distance in RGB) count as one color.
+const MERGE_DISTANCE: f32 = 40.0;
+
+/// Up to `count` dominant colors of the image at `path`, most common first.
+pub fn extract(path: &Path, count: usize) -> Result<Vec<HexColor>, Box<dyn Error>> {
+    let image = image::open(path)?
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8();
+
+    // 4 bits per channel: 4096 buckets, each remembering the sum of its pixels.
+    let mut buckets = vec![([0u64; 3], 0u64); 16 * 16 * 16];
+    for pixel in image.pixels() {
+        let [r, g, b] = pixel.0;
+        let index = (r as usize >> 4) << 8 | (g as usize >> 4) << 4 | (b as usize >> 4);
+        let (sum, pixels) = &mut buckets[index];
+        sum[0] += r as u64;
+        sum[1] += g as u64;
+        sum[2] += b as u64;
+        *pixels += 1;
+    }
+    buckets.retain(|(_, pixels)| *pixels > 0);
+    buckets.sort_by_key(|(_, pixels)| std::cmp::Reverse(*pixels));
+
+    let mut palette: Vec<HexColor> = Vec::with_capacity(count);
+    for (sum, pixels) in buckets {
+        let color = HexColor(sum.map(|channel| (channel / pixels) as u8));
+        if palette
+            .iter()
+            .all(|kept| distance(*kept, color) >= MERGE_DISTANCE)
+        {
+            palette.push(color);
+            if palette.len() == count {
+                break;
+            }
+        }
+    }
+    if palette.is_empty() {
+        return Err(format!("{} has no pixels", path.display()).into());
+    }
+    Ok(palette)
+}
+
+/// Below this, a wallpaper counts as grey and keeps the configured accent.
+const MIN_COLORFULNESS: f32 = 0.08;
+
+/// Colors for the GUI picked from a wallpaper palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeColors {
+    /// Saturated enough to stand out, dark enough for white button text; `None` for
+    /// a grey wallpaper.
+    pub accent: Option<HexColor>,
+    pub background_dark: HexColor,
+    pub background_light: HexColor,
+}
+
+impl ThemeColors {
+    /// Pick an accent and backgrounds from `palette` (most common color first).
+    pub fn from_palette(palette: &[HexColor]) -> Option<Self> {
+        let dominant = to_hsl(*palette.first()?);
+        // Prefer colorful entries, but let a much more common one win over a speck.
+        let accent = palette
+            .iter()
+            .enumerate()
+            .map(|(rank, color)| {
+                let hsl = to_hsl(*color);
+                let colorfulness = hsl.1 * (1.0 - (2.0 * hsl.2 - 1.0).abs());
+                (hsl, colorfulness / (1.0 + rank as f32 * 0.15))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, colorfulness)| *colorfulness >= MIN_COLORFULNESS)
+            .map(|(hsl, _)| hsl);
+
+        Some(Self {
+            accent: accent.map(|(hue, saturation, lightness)| {
+                from_hsl(hue, saturation.max(0.35), lightness.clamp(0.3, 0.45))
+            }),
+            background_dark: from_hsl(dominant.0, dominant.1.min(0.25), 0.1),
+            background_light: from_hsl(dominant.0, dominant.1.min(0.25), 0.95),
+        })
+    }
+}
+
+fn distance(a: HexColor, b: HexColor) -> f32 {
+    a.0.iter()
+        .zip(b.0)
+        .map(|(x, y)| (*x as f32 - y as f32).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Hue in degrees, saturation and lightness in 0..=1.
+fn to_hsl(HexColor(rgb): HexColor) -> (f32, f32, f32) {
+    let [r, g, b] = rgb.map(|channel| channel as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, saturation.min(1.0), lightness)
+}
+
+fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> HexColor {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    HexColor([r, g, b].map(|channel| ((channel + m) * 255.0).round().clamp(0.0, 255.0) as u8))
+}
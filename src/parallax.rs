@@ -0,0 +1,59 @@
+//! Depth parallax: a still image that shifts with the pointer, by its depth map.
+//!
+//! An entry with a `parallax` table names a depth map next to its image: a
+//! grayscale picture of the same framing where white is near and black is far.
+//! `wpe render` moves each pixel of the image by its depth while the pointer moves
+//! over the desktop, so near things slide further than the background. Both
+//! pictures are cropped to fill the output the same way, so they have to share
+//! an aspect ratio. Experimental: the shift is computed on the CPU for every frame
+//! while the view moves.
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `parallax` table of an entry whose `path` is an image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ParallaxSettings {
+    /// Grayscale image the same shape as the wallpaper; white is near, black far.
+    pub depth_map: PathBuf,
+    /// How far the nearest parts move, in percent of the output's width (1-10).
+    #[serde(default = "default_shift_percent")]
+    pub shift_percent: u32,
+}
+
+fn default_shift_percent() -> u32 {
+    2
+}
+
+impl ParallaxSettings {
+    /// Check that the image and its depth map can be read and have the same shape.
+    pub fn validate(&self, image: &Path) -> Result<(), Box<dyn Error>> {
+        if !(1..=10).contains(&self.shift_percent) {
+            return Err("parallax shift_percent has to be between 1 and 10".into());
+        }
+        let (width, height) = image::image_dimensions(image)
+            .map_err(|err| format!("Could not read {}: {}", image.display(), err))?;
+        let (depth_width, depth_height) = image::image_dimensions(&self.depth_map)
+            .map_err(|err| format!("Could not read {}: {}", self.depth_map.display(), err))?;
+        // Compare width/height against depth_width/depth_height, within a percent.
+        let image_ratio = u64::from(width) * u64::from(depth_height);
+        let depth_ratio = u64::from(depth_width) * u64::from(height);
+        if image_ratio.abs_diff(depth_ratio) * 100 > image_ratio {
+            return Err(format!(
+                "The depth map {} is {}x{}, which does not match the {}x{} image",
+                self.depth_map.display(),
+                depth_width,
+                depth_height,
+                width,
+                height
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+//! A small secondary source drawn in a corner over the wallpaper.
+//!
+//! The source is an extra external file in the same player (see `compose`), scaled
+//! relative to the wallpaper and overlaid in the chosen corner. Stream URLs work too,
+//! e.g. a webcam pointed at a fish tank.
+
+use std::fmt::Write as _;
+
+use crate::config::PipCorner;
+
+/// A picture-in-picture source resolved for launch.
+#[derive(Debug, Clone)]
+pub struct Pip {
+    /// Local path or stream URL.
+    pub source: String,
+    /// Still images are repeated so the overlay keeps going.
+    pub is_image: bool,
+    pub corner: PipCorner,
+    /// Width as a percentage of the wallpaper.
+    pub size_percent: u32,
+    /// Gap to the edges in output pixels.
+    pub margin: u32,
+}
+
+impl Pip {
+    /// Graph overlaying video track `track` on `[base]` into `[vo]`.
+    pub fn filter_graph(&self, base: &str, track: usize, output_width: u32) -> String {
+        let source = if self.is_image {
+            "loop=loop=-1:size=1"
+        } else {
+            "null"
+        };
+        let width = self.size_percent.clamp(5, 50) as f64 / 100.0;
+        // Margins are measured in output pixels but applied in wallpaper pixels.
+        let gap = format!(
+            "main_w*{:.4}",
+            self.margin as f64 / output_width.max(1) as f64
+        );
+        let (x, y) = match self.corner {
+            PipCorner::TopLeft => (gap.clone(), gap),
+            PipCorner::TopRight => (format!("main_w-overlay_w-{gap}"), gap),
+            PipCorner::BottomLeft => (gap.clone(), format!("main_h-overlay_h-{gap}")),
+            PipCorner::BottomRight => (
+                format!("main_w-overlay_w-{gap}"),
+                format!("main_h-overlay_h-{gap}"),
+            ),
+        };
+
+        let mut graph = String::new();
+        let _ = write!(
+            graph,
+            "[vid{track}]{source}[pipsrc];\
+             [pipsrc][{base}]scale2ref=w=main_w*{width:.3}:h=ow/a[pip][pipbase];\
+             [pipbase][pip]overlay=x={x}:y={y}[vo]"
+        );
+        graph
+    }
+}
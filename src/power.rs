@@ -0,0 +1,256 @@
+//! `wpe power-report`: estimate what the running wallpapers cost.
+//!
+//! CPU time is read per mpvpaper process from `/proc/<pid>/stat` at the start and
+//! end of a sampling window. GPU load and VRAM are global, so they are reported
+//! per card from sysfs when the driver exposes them (amdgpu does; others may not).
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::output;
+
+/// Kernel clock ticks per second for `/proc/<pid>/stat` (USER_HZ, 100 on Linux).
+const CLOCK_TICKS: f64 = 100.0;
+
+/// One running mpvpaper found in /proc.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub pid: u32,
+    pub monitor: String,
+    pub source: String,
+}
+
+/// CPU usage of one instance over the sampling window.
+#[derive(Debug, Clone)]
+pub struct InstanceSample {
+    pub instance: Instance,
+    /// Percent of one CPU core, averaged over the window.
+    pub cpu_percent: f64,
+}
+
+/// GPU counters for one DRM card.
+#[derive(Debug, Clone)]
+pub struct GpuSample {
+    pub card: String,
+    pub busy_percent: Option<f64>,
+    pub vram_used: Option<u64>,
+}
+
+/// Sample every running wallpaper for `window` and print a report to stdout.
+pub fn run_report(window: Duration) -> Result<(), Box<dyn Error>> {
+    let instances = find_instances();
+    if instances.is_empty() {
+        output::say("No running mpvpaper instances found.");
+        return Ok(());
+    }
+
+    output::say(format!(
+        "Sampling {} mpvpaper instance(s) for {}s...",
+        instances.len(),
+        window.as_secs()
+    ));
+    let (samples, gpus) = sample(instances, window);
+
+    let total: f64 = samples.iter().map(|sample| sample.cpu_percent).sum();
+    output::say("");
+    output::say(format!(
+        "{:<12} {:>7} {:>7}  SOURCE",
+        "MONITOR", "CPU%", "SHARE"
+    ));
+    for sample in &samples {
+        let share = if total > 0.0 {
+            sample.cpu_percent / total * 100.0
+        } else {
+            0.0
+        };
+        output::say(format!(
+            "{:<12} {:>6.1}% {:>6.0}%  {}",
+            sample.instance.monitor, sample.cpu_percent, share, sample.instance.source
+        ));
+        output::record(
+            "instance",
+            &[
+                &sample.instance.monitor,
+                &format!("{:.1}", sample.cpu_percent),
+                &format!("{:.0}", share),
+                &sample.instance.source,
+            ],
+        );
+    }
+
+    if gpus.is_empty() {
+        output::say("\nNo GPU counters available in sysfs.");
+    } else {
+        output::say("");
+        for gpu in &gpus {
+            let busy = gpu
+                .busy_percent
+                .map(|busy| format!("{:.0}% busy", busy))
+                .unwrap_or_else(|| "busy n/a".into());
+            let vram = gpu
+                .vram_used
+                .map(|bytes| format!("{:.1} MiB VRAM used", bytes as f64 / (1024.0 * 1024.0)))
+                .unwrap_or_else(|| "VRAM n/a".into());
+            output::say(format!("{}: {}, {}", gpu.card, busy, vram));
+            output::record(
+                "gpu",
+                &[
+                    &gpu.card,
+                    &gpu.busy_percent
+                        .map(|busy| format!("{:.0}", busy))
+                        .unwrap_or_else(|| "-".into()),
+                    &gpu.vram_used
+                        .map(|bytes| bytes.to_string())
+                        .unwrap_or_else(|| "-".into()),
+                ],
+            );
+        }
+    }
+
+    if let Some(heaviest) = samples.first()
+        && total > 0.0
+    {
+        output::say(format!(
+            "\n{} is the most expensive wallpaper ({:.0}% of wallpaper CPU time).",
+            heaviest.instance.monitor,
+            heaviest.cpu_percent / total * 100.0
+        ));
+        output::say("Lower resolution or framerate sources, or still images, cost the least.");
+    }
+    Ok(())
+}
+
+/// Measure CPU time across `window`, sorted from most to least expensive.
+pub fn sample(instances: Vec<Instance>, window: Duration) -> (Vec<InstanceSample>, Vec<GpuSample>) {
+    let before: Vec<Option<u64>> = instances
+        .iter()
+        .map(|instance| cpu_ticks(instance.pid))
+        .collect();
+    let started = Instant::now();
+    let mut gpu_busy: Vec<(PathBuf, Vec<f64>)> = drm_devices()
+        .into_iter()
+        .map(|device| (device, Vec::new()))
+        .collect();
+
+    // Sample GPU load once a second; it is an instantaneous counter.
+    while started.elapsed() < window {
+        for (device, readings) in &mut gpu_busy {
+            if let Some(busy) = read_number(&device.join("gpu_busy_percent")) {
+                readings.push(busy as f64);
+            }
+        }
+        thread::sleep(Duration::from_secs(1).min(window.saturating_sub(started.elapsed())));
+    }
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+
+    let mut samples: Vec<InstanceSample> = instances
+        .into_iter()
+        .zip(before)
+        .filter_map(|(instance, before)| {
+            let after = cpu_ticks(instance.pid)?;
+            let ticks = after.saturating_sub(before?);
+            Some(InstanceSample {
+                cpu_percent: ticks as f64 / CLOCK_TICKS / elapsed * 100.0,
+                instance,
+            })
+        })
+        .collect();
+    samples.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+
+    let gpus = gpu_busy
+        .into_iter()
+        .map(|(device, readings)| GpuSample {
+            card: device
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "gpu".into()),
+            busy_percent: (!readings.is_empty())
+                .then(|| readings.iter().sum::<f64>() / readings.len() as f64),
+            vram_used: read_number(&device.join("mem_info_vram_used")),
+        })
+        .filter(|gpu| gpu.busy_percent.is_some() || gpu.vram_used.is_some())
+        .collect();
+
+    (samples, gpus)
+}
+
+/// Scan /proc for mpvpaper and `wpe render` processes, recovering the output and
+/// source from argv.
+pub fn find_instances() -> Vec<Instance> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut instances = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        let Ok(cmdline) = fs::read(entry.path().join("cmdline")) else {
+            continue;
+        };
+        let argv: Vec<String> = cmdline
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+        let program = argv
+            .first()
+            .and_then(|program| Path::new(program).file_name());
+        // `wpe render` (the builtin backend) ends in the same `<output> <path>`.
+        let is_player = program.is_some_and(|name| name == "mpvpaper")
+            || (program.is_some_and(|name| name == "wpe")
+                && argv.get(1).is_some_and(|arg| arg == "render"));
+        if !is_player || argv.len() < 3 {
+            continue;
+        }
+        instances.push(Instance {
+            pid,
+            monitor: argv[argv.len() - 2].clone(),
+            source: argv[argv.len() - 1].clone(),
+        });
+    }
+    instances
+}
+
+/// utime + stime (fields 14 and 15) from /proc/<pid>/stat.
+fn cpu_ticks(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name may contain spaces, so split after its closing paren.
+    let rest = &stat[stat.rfind(')')? + 2..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+fn drm_devices() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut devices: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("card") && !name.contains('-')
+        })
+        .map(|entry| entry.path().join("device"))
+        .collect();
+    devices.sort();
+    devices
+}
+
+fn read_number(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
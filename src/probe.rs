@@ -0,0 +1,61 @@
+//! Quick facts about a media file from `ffprobe`.
+//!
+//! Every helper returns `None` when ffprobe is missing or cannot read the file, so
+//! callers fall back to their defaults instead of failing the launch. ffprobe runs
+//! as a [`worker`], so a file that hangs it only costs the probe.
+
+use std::{path::Path, process::Command, time::Duration};
+
+use crate::worker;
+
+/// How long ffprobe may take to read a file's headers.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Length of the media in `path`, in seconds.
+pub fn duration(path: &Path) -> Option<f64> {
+    ffprobe(path, &["-show_entries", "format=duration"])?
+        .parse()
+        .ok()
+        .filter(|seconds: &f64| seconds.is_finite() && *seconds > 0.0)
+}
+
+/// Height in pixels of the first video stream in `path`.
+pub fn video_height(path: &Path) -> Option<u32> {
+    ffprobe(
+        path,
+        &["-select_streams", "v:0", "-show_entries", "stream=height"],
+    )?
+    .parse()
+    .ok()
+    .filter(|height| *height > 0)
+}
+
+/// Width and height in pixels of the first video stream in `path`; an image has one too.
+pub fn video_size(path: &Path) -> Option<(u32, u32)> {
+    let value = ffprobe(
+        path,
+        &[
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height",
+        ],
+    )?;
+    let (width, height) = value.split_once(',')?;
+    let size = (width.trim().parse().ok()?, height.trim().parse().ok()?);
+    (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+/// Run ffprobe with `args` and return the single value it prints.
+fn ffprobe(path: &Path, args: &[&str]) -> Option<String> {
+    let mut command = worker::command(Path::new("ffprobe"), path).ok()?;
+    command
+        .args(["-v", "error"])
+        .args(args)
+        .args(["-of", "csv=p=0"])
+        .arg(path);
+    let output = worker::output(command, TIMEOUT).ok()?;
+    let value = String::from_utf8_lossy(&output);
+    let value = value.lines().next()?.trim().trim_end_matches(',');
+    (!value.is_empty()).then(|| value.to_string())
+}
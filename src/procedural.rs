@@ -0,0 +1,227 @@
+//! Procedural wallpapers shipped with wpe: live backgrounds without any media file.
+//!
+//! An entry with a `procedural` table needs no `path`. Each kind is a WGSL shader
+//! built into wpe; the entry's colors are written into a copy of it in the cache
+//! directory, which then plays like any `shader` entry on the built-in renderer.
+//! `speed_percent` runs the animation slower or faster; the clock always shows the
+//! local time.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    error::Error,
+    fmt::Write as _,
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{shader::ShaderSettings, state};
+
+/// Soft blobs of three colors drifting into each other.
+const GRADIENT: &str = "
+fn main_image(coord: vec2<f32>) -> vec4<f32> {
+    let uv = coord / wpe.resolution.xy;
+    let t = wpe.time * 0.05;
+    let a = 0.5 + 0.5 * sin(uv.x * 2.0 + t * 1.3 + sin(uv.y * 1.5 + t));
+    let b = 0.5 + 0.5 * sin(uv.y * 2.5 - t * 0.9 + cos(uv.x * 1.7 - t * 1.1));
+    var color = mix(COLOR_0, COLOR_1, a);
+    color = mix(color, COLOR_2, b * 0.6);
+    return vec4<f32>(color, 1.0);
+}
+";
+
+/// Glowing motes rising slowly over a darkening background.
+const PARTICLES: &str = "
+const COUNT: i32 = 80;
+
+fn hash(n: f32) -> f32 {
+    return fract(sin(n) * 43758.5453);
+}
+
+fn main_image(coord: vec2<f32>) -> vec4<f32> {
+    let aspect = wpe.resolution.x / wpe.resolution.y;
+    let uv = coord / wpe.resolution.y;
+    var glow = 0.0;
+    for (var i = 0; i < COUNT; i = i + 1) {
+        let n = f32(i) + 1.0;
+        let rise = 0.01 + 0.03 * hash(n * 7.3);
+        let x = hash(n * 1.7) * aspect + 0.03 * sin(wpe.time * 0.3 + n);
+        let y = fract(hash(n * 3.1) + wpe.time * rise) * 1.2 - 0.1;
+        let size = 0.002 + 0.005 * hash(n * 5.9);
+        let d = distance(uv, vec2<f32>(x, y));
+        glow = glow + (1.0 - smoothstep(0.0, size * 3.0, d)) * (0.5 + 0.5 * hash(n * 9.1));
+    }
+    let background = COLOR_0 * (0.6 + 0.4 * (coord.y / wpe.resolution.y));
+    return vec4<f32>(mix(background, COLOR_1, clamp(glow, 0.0, 1.0)), 1.0);
+}
+";
+
+/// An analog clock face with hour marks and a ticking second hand.
+const CLOCK: &str = "
+const PI: f32 = 3.14159265;
+
+fn hand(p: vec2<f32>, angle: f32, reach: f32, width: f32, px: f32) -> f32 {
+    let dir = vec2<f32>(sin(angle), cos(angle));
+    let along = clamp(dot(p, dir), -0.08, reach);
+    let d = length(p - dir * along);
+    return 1.0 - smoothstep(width, width + px, d);
+}
+
+fn main_image(coord: vec2<f32>) -> vec4<f32> {
+    let scale = 0.4 * min(wpe.resolution.x, wpe.resolution.y);
+    let p = (coord - wpe.resolution.xy * 0.5) / scale;
+    let px = 1.5 / scale;
+    let r = length(p);
+    let seconds = wpe.date.w;
+    let hour = (seconds / 3600.0) % 12.0;
+    let minute = (seconds / 60.0) % 60.0;
+    let second = floor(seconds % 60.0);
+
+    var color = COLOR_0 * (1.0 - 0.15 * r);
+    let ring = 1.0 - smoothstep(0.0, px, abs(r - 1.0) - 0.015);
+    color = mix(color, COLOR_1, ring);
+    // Twelve marks: the nearest one's direction, and how far off it the pixel is.
+    let step_angle = PI / 6.0;
+    let mark_angle = round(atan2(p.x, p.y) / step_angle) * step_angle;
+    let mark_dir = vec2<f32>(sin(mark_angle), cos(mark_angle));
+    let along = dot(p, mark_dir);
+    let across = abs(dot(p, vec2<f32>(mark_dir.y, -mark_dir.x)));
+    let mark = (1.0 - smoothstep(0.0, px, across - 0.012)) * step(0.82, along) * step(along, 0.93);
+    color = mix(color, COLOR_1, mark);
+
+    color = mix(color, COLOR_1, hand(p, hour / 12.0 * 2.0 * PI, 0.5, 0.03, px));
+    color = mix(color, COLOR_1, hand(p, minute / 60.0 * 2.0 * PI, 0.78, 0.02, px));
+    color = mix(color, COLOR_2, hand(p, second / 60.0 * 2.0 * PI, 0.85, 0.008, px));
+    color = mix(color, COLOR_2, 1.0 - smoothstep(0.04, 0.04 + px, r));
+    return vec4<f32>(color, 1.0);
+}
+";
+
+/// Which built-in wallpaper to draw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProceduralKind {
+    /// Three colors drifting into each other.
+    #[default]
+    Gradient,
+    /// Glowing particles rising over a background color.
+    Particles,
+    /// An analog clock showing the local time.
+    Clock,
+}
+
+impl ProceduralKind {
+    fn name(self) -> &'static str {
+        match self {
+            ProceduralKind::Gradient => "gradient",
+            ProceduralKind::Particles => "particles",
+            ProceduralKind::Clock => "clock",
+        }
+    }
+
+    fn body(self) -> &'static str {
+        match self {
+            ProceduralKind::Gradient => GRADIENT,
+            ProceduralKind::Particles => PARTICLES,
+            ProceduralKind::Clock => CLOCK,
+        }
+    }
+
+    /// The colors used where the entry lists fewer; their number is how many the
+    /// kind takes.
+    fn default_colors(self) -> &'static [&'static str] {
+        match self {
+            ProceduralKind::Gradient => &["#4B006E", "#1E5AA8", "#E0A458"],
+            // Background, then the particles.
+            ProceduralKind::Particles => &["#0B1026", "#9AD1FF"],
+            // Face, then the marks and hands, then the second hand and hub.
+            ProceduralKind::Clock => &["#14161F", "#E8E6E3", "#E0584B"],
+        }
+    }
+
+    /// Frames a second unless the entry sets `fps`; the clock only ticks.
+    fn default_fps(self) -> u32 {
+        match self {
+            ProceduralKind::Clock => 4,
+            _ => 30,
+        }
+    }
+}
+
+/// `procedural` table: a built-in wallpaper instead of `path`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ProceduralSettings {
+    pub kind: ProceduralKind,
+    /// `#RRGGBB` colors, in the order the kind uses them; left out ones keep the default.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub colors: Vec<String>,
+    /// How fast the animation runs, in percent (1-1000, default 100).
+    pub speed_percent: Option<u32>,
+    /// Most frames drawn a second (1-240); 30, or 4 for the clock.
+    pub fps: Option<u32>,
+}
+
+impl ProceduralSettings {
+    /// Write the shader for these settings to the cache and return its path, with
+    /// the settings to play it at.
+    pub fn materialize(&self) -> Result<(PathBuf, ShaderSettings), Box<dyn Error>> {
+        let defaults = self.kind.default_colors();
+        if self.colors.len() > defaults.len() {
+            return Err(format!(
+                "A {} wallpaper takes at most {} colors",
+                self.kind.name(),
+                defaults.len()
+            )
+            .into());
+        }
+        let mut code = String::new();
+        for (index, default) in defaults.iter().enumerate() {
+            let color = self.colors.get(index).map_or(*default, String::as_str);
+            let (red, green, blue) = parse_color(color)?;
+            let _ = writeln!(
+                code,
+                "const COLOR_{index}: vec3<f32> = vec3<f32>({red:.4}, {green:.4}, {blue:.4});"
+            );
+        }
+        code.push_str(self.kind.body());
+
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        let dir = state::cache_dir()?.join("procedural");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "{}-{:016x}.wgsl",
+            self.kind.name(),
+            hasher.finish()
+        ));
+        if !path.is_file() {
+            fs::write(&path, &code)
+                .map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+        }
+
+        let shader = ShaderSettings {
+            fps: self.fps.unwrap_or(self.kind.default_fps()),
+            speed_percent: self.speed_percent.unwrap_or(100),
+        };
+        shader.validate(&path)?;
+        Ok((path, shader))
+    }
+}
+
+/// `#RRGGBB` as red, green, and blue from 0 to 1.
+fn parse_color(color: &str) -> Result<(f32, f32, f32), Box<dyn Error>> {
+    let hex = color.trim().trim_start_matches('#');
+    let channel = |at: usize| {
+        hex.get(at..at + 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            .map(|value| f32::from(value) / 255.0)
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(red), Some(green), Some(blue)) => Ok((red, green, blue)),
+        _ => Err(format!("{color} is not a #RRGGBB color").into()),
+    }
+}
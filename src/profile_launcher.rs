@@ -1,60 +1,351 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
 
-use tracing::info;
+use serde_json::json;
 
 use crate::{
-    config::{self, RuntimeConfig, WallpaperProfileEntry},
-    monitors, mpvpaper,
+    audio, backend,
+    battery::{self, BatteryPolicy},
+    config::{self, WallpaperProfileEntry},
+    daemon, fallback, ipc,
+    launcher::{Instance, StandIn},
+    lock,
+    monitors::{self, Monitor},
+    mounts,
+    mpvpaper::LaunchStage,
+    notify::StartupSummary,
+    output::{self, CliError, Failure},
+    power, tweaks,
 };
 
 /// Launch a wallpaper instance for each configured entry in config.toml.
 /// mpvpaper processes are spawned directly and left running so they can be
 /// stopped later with a simple `pkill mpvpaper`.
-pub fn launch_from_profile() -> Result<(), Box<dyn Error>> {
+///
+/// Configured monitors that have not enumerated yet are waited on for up to `wait`
+/// before their entries are skipped.
+pub fn launch_from_profile(wait: Duration) -> Result<(), CliError> {
+    // The daemon owns the players; ask it to apply the config instead.
+    if daemon::is_running() {
+        output::say("Handing the launch to the running wpe daemon.");
+        return daemon::report(&daemon::send(&daemon::Request::Reload)?);
+    }
+
+    let Some(_lock) = lock::try_acquire()? else {
+        output::say("Another `wpe -c` is already launching wallpapers; nothing to do.");
+        output::record("locked", &[]);
+        return Ok(());
+    };
+
+    let backend = backend::get(
+        config::load_settings()
+            .map(|settings| settings.backend)
+            .unwrap_or_default(),
+    );
+    if !backend.installed() {
+        return Err(CliError::new(
+            Failure::BackendMissing,
+            format!(
+                "{} was not found in PATH; install it to launch wallpapers",
+                backend.program()
+            ),
+        ));
+    }
+
+    // Picture tweaks made on the wallpapers about to be replaced survive the relaunch.
+    if config::config_file_path().is_ok_and(|path| path.exists()) {
+        let running: Vec<String> = power::find_instances()
+            .into_iter()
+            .map(|instance| instance.monitor)
+            .collect();
+        if !running.is_empty() {
+            tweaks::remember(running.iter().map(String::as_str));
+        }
+    }
+
     let monitors = monitors::list_monitors()?;
-    let (entries, created, path) = config::ensure_profile_for_monitors(&monitors)?;
+    let (mut entries, created, path) =
+        config::ensure_profile_for_monitors(&monitors).map_err(CliError::config)?;
 
     if created {
-        println!("Created default config at {}.", path.display());
-        println!("Edit this file to choose wallpapers, then rerun `wpe -c`.");
-        return Ok(());
+        output::say(format!("Created default config at {}.", path.display()));
+        return Err(CliError::config(
+            "Edit this file to choose wallpapers, then rerun `wpe -c`.",
+        ));
     }
 
-    let targets = select_targets(&entries);
+    let unconfigured: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.enabled && entry.is_unconfigured())
+        .map(|entry| entry.monitor.as_deref().unwrap_or("unassigned"))
+        .collect();
+    let mut summary = StartupSummary::default();
+    for monitor in &unconfigured {
+        output::say(format!("Skipping {monitor}: no wallpaper chosen yet."));
+        output::record("unconfigured", &[monitor]);
+        summary
+            .skipped
+            .push((monitor.to_string(), "no wallpaper chosen yet".into()));
+    }
+
+    let mut targets = select_targets(&entries);
+    if targets.is_empty() && !unconfigured.is_empty() {
+        return Err(CliError::config(format!(
+            "No wallpaper is chosen yet for {}. Replace the placeholder path in {} \
+             with an image, video, or folder, then rerun `wpe -c`.",
+            unconfigured.join(", "),
+            path.display()
+        )));
+    }
     if targets.is_empty() {
-        println!(
-            "No enabled wallpaper entries in {} have a configured path.",
+        return Err(CliError::config(format!(
+            "No enabled wallpaper entries in {} have a configured path. \
+             Set `enabled = true` and provide a valid path, then rerun `wpe -c`.",
             path.display()
+        )));
+    }
+
+    let (monitors, missing) = wait_for_monitors(&entries, &targets, monitors, wait)?;
+    if monitors.is_empty() {
+        return Err(CliError::new(Failure::NoMonitors, "No monitors detected"));
+    }
+    if !missing.is_empty() {
+        warn!(
+            "Gave up waiting for monitor(s) {}; skipping their entries.",
+            missing.join(", ")
         );
-        println!("Set `enabled = true` and provide a valid path, then rerun `wpe -c`.");
-        return Ok(());
+        for name in &missing {
+            output::record("skipped", &[name]);
+            summary
+                .skipped
+                .push((name.clone(), "monitor not connected".into()));
+        }
+        targets.retain(|index| {
+            entries[*index]
+                .monitor
+                .as_ref()
+                .is_none_or(|name| !missing.contains(name))
+        });
+        if targets.is_empty() {
+            return Err(CliError::new(
+                Failure::NoMonitors,
+                format!(
+                    "None of the configured monitors connected ({})",
+                    missing.join(", ")
+                ),
+            ));
+        }
     }
 
-    for index in &targets {
-        let runtime = match RuntimeConfig::from_entry(*index) {
-            Ok(runtime) => runtime,
-            Err(err) => return Err(err),
+    // Network shares and removable drives are often still mounting at login.
+    let unmounted: Vec<PathBuf> = targets
+        .iter()
+        .flat_map(|index| entries[*index].configured_paths())
+        .map(config::normalize_entry_path)
+        .filter(|path| mounts::is_mountable(path))
+        .collect();
+    if !unmounted.is_empty() {
+        output::say(format!(
+            "Waiting up to {}s for {} wallpaper source(s) to mount.",
+            wait.as_secs(),
+            unmounted.len()
+        ));
+        for path in mounts::ensure_mounted(&unmounted, wait) {
+            warn!("{} did not become available.", path.display());
+            output::record("unmounted", &[&path.to_string_lossy()]);
+        }
+    }
+
+    let settings = config::load_settings()?;
+    for monitor in audio::limit_audible(&mut entries, &settings.audio) {
+        output::record("muted", &[&monitor]);
+    }
+    // `wpe -c` exits after launching, so the battery policy only applies as launched;
+    // the GUI and daemon follow the power source live.
+    let policy = match settings.power.on_battery {
+        BatteryPolicy::Continue => BatteryPolicy::Continue,
+        policy if battery::on_battery() => policy,
+        _ => BatteryPolicy::Continue,
+    };
+    let reduced_motion = settings.motion.reduced();
+
+    // Replace wallpapers from an earlier run instead of stacking a second set on top.
+    if backend::stop_all()? {
+        info!("Stopped wallpaper players left over from a previous launch.");
+    }
+
+    // Spawn everything first so the players open their files in parallel, then confirm each.
+    let total = targets.len();
+    let mut launched = Vec::with_capacity(total);
+    for (position, index) in targets.iter().enumerate() {
+        let entry = &entries[*index];
+        let monitor = entry.monitor.clone().unwrap_or_default();
+        let output = monitors.iter().find(|output| output.name == monitor);
+        let wants_still = policy == BatteryPolicy::StaticFrame || reduced_motion;
+        let still = if wants_still && !entry.is_still_image() {
+            fallback::cached_frame(&monitor)
+        } else {
+            None
         };
+        report_progress(position, total, &monitor, LaunchStage::Spawning);
+        let instance = Instance::spawn(entry, output, &settings, still)
+            .map_err(|err| CliError::new(Failure::General, err))?;
+        match instance.stand_in() {
+            Some(StandIn::CachedFrame) => {
+                output::say(format!(
+                    "[{}/{}] {}: source unavailable, showing its last cached frame",
+                    position + 1,
+                    total,
+                    monitor
+                ));
+                output::record("cached", &[&monitor]);
+            }
+            Some(StandIn::StillFrame) => {
+                output::say(format!(
+                    "[{}/{}] {}: {}, showing a still frame",
+                    position + 1,
+                    total,
+                    monitor,
+                    if reduced_motion {
+                        "reduced motion"
+                    } else {
+                        "on battery"
+                    }
+                ));
+                output::record("still", &[&monitor]);
+            }
+            Some(StandIn::Fallback) => {
+                output::say(format!(
+                    "[{}/{}] {}: source failed, showing a fallback",
+                    position + 1,
+                    total,
+                    monitor
+                ));
+                output::record("fallback", &[&monitor]);
+            }
+            None => {}
+        }
+        launched.push(instance);
+    }
 
-        mpvpaper::spawn_instance(&runtime)?;
+    let mut failed = 0usize;
+    for (position, mut instance) in launched.into_iter().enumerate() {
+        let monitor = instance.monitor().to_string();
+        match instance.confirm(|stage| report_progress(position, total, &monitor, stage)) {
+            Ok(()) => {
+                if instance.stand_in().is_none()
+                    && let Err(err) = fallback::capture(&monitor)
+                {
+                    warn!("Could not cache a frame for {}: {}", monitor, err);
+                }
+                if policy == BatteryPolicy::Pause {
+                    match ipc::set_property(&monitor, "pause", json!(true)) {
+                        Ok(()) => {
+                            output::say(format!(
+                                "[{}/{}] {}: on battery, paused",
+                                position + 1,
+                                total,
+                                monitor
+                            ));
+                            output::record("paused", &[&monitor]);
+                        }
+                        Err(err) => warn!("Could not pause {} on battery: {}", monitor, err),
+                    }
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                // Don't leave a player behind that never got to the screen.
+                instance.stop();
+                output::say(format!(
+                    "[{}/{}] {}: failed: {}",
+                    position + 1,
+                    total,
+                    monitor,
+                    err
+                ));
+                output::record("failed", &[&monitor, &err]);
+                summary.skipped.push((monitor, format!("failed: {err}")));
+            }
+        }
     }
 
-    info!(
-        "Launched {} wallpaper instance(s) based on config entries.",
-        targets.len()
-    );
-    println!(
+    summary.monitors = monitors.len();
+    summary.launched = total - failed;
+    summary.report(&settings.notifications);
+    output::say(format!(
         "Started {} mpvpaper instance(s). Stop them with `pkill mpvpaper`.",
-        targets.len()
-    );
+        total - failed
+    ));
+    output::record("started", &[&(total - failed).to_string()]);
+    if failed > 0 {
+        return Err(CliError::new(
+            Failure::General,
+            format!("{} of {} wallpaper(s) did not start playing", failed, total),
+        ));
+    }
     Ok(())
 }
 
+fn report_progress(position: usize, total: usize, monitor: &str, stage: LaunchStage) {
+    output::say(format!(
+        "[{}/{}] {}: {}",
+        position + 1,
+        total,
+        monitor,
+        stage
+    ));
+    output::record("progress", &[monitor, &stage.to_string()]);
+}
+
+/// Poll the compositor until every targeted monitor is connected or `wait` elapses,
+/// returning the latest monitor list and the names that never showed up.
+fn wait_for_monitors(
+    entries: &[WallpaperProfileEntry],
+    targets: &[usize],
+    mut monitors: Vec<Monitor>,
+    wait: Duration,
+) -> Result<(Vec<Monitor>, Vec<String>), Box<dyn Error>> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    let deadline = Instant::now() + wait;
+    let mut logged = false;
+    loop {
+        let missing: Vec<String> = targets
+            .iter()
+            .filter_map(|index| entries[*index].monitor.clone())
+            .filter(|name| !monitors.iter().any(|monitor| &monitor.name == name))
+            .collect();
+
+        if missing.is_empty() || Instant::now() >= deadline {
+            return Ok((monitors, missing));
+        }
+
+        if !logged {
+            info!(
+                "Waiting up to {}s for monitor(s) {} to connect.",
+                wait.as_secs(),
+                missing.join(", ")
+            );
+            logged = true;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+        monitors = monitors::list_monitors()?;
+    }
+}
+
 fn select_targets(entries: &[WallpaperProfileEntry]) -> Vec<usize> {
     entries
         .iter()
         .enumerate()
-        .filter(|(_, entry)| entry.enabled && entry.path.is_some())
+        .filter(|(_, entry)| entry.enabled && entry.has_source())
         .map(|(index, _)| index)
         .collect()
 }
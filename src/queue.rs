@@ -0,0 +1,218 @@
+//! Random folder order that survives restarts.
+//!
+//! mpv's `--shuffle` draws a new order on every launch, so a reboot starts the
+//! cycle over and favourites come back early. For folders in random order, wpe
+//! shuffles the listing itself and hands mpv a playlist: the files not yet shown in
+//! the current cycle, then a fresh shuffle for the next one. How far playback got is
+//! saved in `$XDG_STATE_HOME/wpe/queues/` whenever the wallpaper is stopped (and
+//! periodically by the GUI), under a hash of the folder listing, so adding or
+//! removing files starts a new cycle.
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use walkdir::WalkDir;
+
+use crate::{config::FolderFilter, history, ipc, state};
+
+/// One monitor's position in its shuffled folder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Queue {
+    monitor: String,
+    /// The playlist handed to mpv: the rest of the cycle, then the next cycle.
+    order: Vec<PathBuf>,
+    /// Where the current cycle ends in `order`.
+    cycle_end: usize,
+    /// Entries of `order` already shown.
+    played: usize,
+}
+
+impl Queue {
+    /// Files of the current cycle that have not been shown yet.
+    fn remaining(&self) -> &[PathBuf] {
+        let end = if self.played < self.cycle_end {
+            self.cycle_end
+        } else {
+            self.order.len()
+        };
+        self.order.get(self.played..end).unwrap_or_default()
+    }
+}
+
+fn queue_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let dir = state::state_dir()?.join("queues");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Build the shuffled playlist of `listing` (a folder's files, or a mix of sources) on
+/// `monitor`, continuing the saved cycle when the listing is the same. Returns the
+/// playlist's path.
+pub fn playlist(monitor: &str, listing: Vec<PathBuf>) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = queue_dir()?;
+    let name = format!(
+        "{}-{:016x}.toml",
+        ipc::file_safe(monitor),
+        listing_hash(&listing)
+    );
+    let path = dir.join(&name);
+
+    // Another folder (or a changed listing) means a new cycle; drop the old queue.
+    for stale in queue_files(&dir, monitor) {
+        if stale
+            .file_name()
+            .is_some_and(|stale| stale != name.as_str())
+        {
+            let _ = fs::remove_file(stale);
+        }
+    }
+
+    let mut pool: Vec<PathBuf> = read_queue(&path)
+        .map(|queue| queue.remaining().to_vec())
+        .unwrap_or_default();
+    pool.retain(|file| file.is_file());
+    if pool.is_empty() {
+        pool = listing.clone();
+        fastrand::shuffle(&mut pool);
+    }
+    let mut next = listing;
+    fastrand::shuffle(&mut next);
+    // Don't show the same file twice in a row across the cycle boundary.
+    if next.len() > 1 && next.first() == pool.last() {
+        let last = next.len() - 1;
+        next.swap(0, last);
+    }
+
+    let queue = Queue {
+        monitor: monitor.to_string(),
+        cycle_end: pool.len(),
+        order: pool.into_iter().chain(next).collect(),
+        played: 0,
+    };
+    fs::write(&path, toml::to_string(&queue)?)?;
+    write_playlist(&format!("queue-{}", ipc::file_safe(monitor)), &queue.order)
+}
+
+/// Write `files` as the M3U playlist `<name>.m3u` next to the IPC sockets, which the
+/// sandbox lets the player read. Returns its path.
+pub fn write_playlist(name: &str, files: &[PathBuf]) -> Result<PathBuf, Box<dyn Error>> {
+    let mut content = String::from("#EXTM3U\n");
+    for file in files {
+        content.push_str(&file.to_string_lossy());
+        content.push('\n');
+    }
+    let playlist = ipc::socket_dir()?.join(format!("{name}.m3u"));
+    fs::write(&playlist, content)?;
+    Ok(playlist)
+}
+
+/// Save how far the player on `monitor` got through its queue, if it has one.
+pub fn record(monitor: &str) {
+    let Ok(dir) = queue_dir() else {
+        return;
+    };
+    for path in queue_files(&dir, monitor) {
+        let Some(mut queue) = read_queue(&path).filter(|queue| queue.monitor == monitor) else {
+            continue;
+        };
+        let Some(position) = ipc::get_property(monitor, "playlist-pos")
+            .ok()
+            .and_then(|value| value.as_u64())
+        else {
+            continue;
+        };
+        // The file on screen counts as shown. A wrapped playlist never moves back.
+        let played = (position as usize + 1).min(queue.order.len());
+        if played <= queue.played {
+            continue;
+        }
+        queue.played = played;
+        if let Err(err) = toml::to_string(&queue)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|data| Ok(fs::write(&path, data)?))
+        {
+            warn!("Could not save the shuffle queue for {}: {}", monitor, err);
+        }
+    }
+}
+
+/// [`record`] every monitor that has a queue.
+pub fn record_all() {
+    let Ok(entries) = queue_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        return;
+    };
+    let monitors: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| read_queue(&entry.path()))
+        .map(|queue| queue.monitor)
+        .collect();
+    for monitor in monitors {
+        record(&monitor);
+    }
+}
+
+fn read_queue(path: &Path) -> Option<Queue> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// Queue files named for `monitor`: `<monitor>-<16 hex digits>.toml`.
+fn queue_files(dir: &Path, monitor: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}-", ipc::file_safe(monitor));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(".toml"))
+                .is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        })
+        .collect()
+}
+
+/// Files of `folder` that pass `filter`, sorted. The default filter lists what mpv
+/// would play (it descends into subfolders).
+pub fn list_files(folder: &Path, filter: &FolderFilter) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let matcher = filter.matcher()?;
+    let mut walk = WalkDir::new(folder).follow_links(true);
+    if !filter.recursive {
+        walk = walk.max_depth(1);
+    }
+    let mut files: Vec<PathBuf> = walk
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|file| matcher.matches(file.strip_prefix(folder).unwrap_or(file)))
+        .collect();
+    if filter.favorites_only {
+        let state = state::load_state();
+        files.retain(|file| history::is_favorite(&state, file));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// FNV-1a over the sorted listing; stable across builds, unlike `DefaultHasher`.
+fn listing_hash(listing: &[PathBuf]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for file in listing {
+        for byte in file.as_os_str().as_encoded_bytes().iter().chain([&0]) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
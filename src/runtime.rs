@@ -0,0 +1,55 @@
+//! The tokio runtime every background job runs on.
+//!
+//! The output, window, battery, and idle watchers, the daemon's control socket, and
+//! the daemon's player supervision all share it instead of each owning a detached
+//! thread. Watchers that block on a Wayland or D-Bus connection, and the GUI's
+//! blocking queries, go to its blocking pool; everything that can wait
+//! asynchronously runs as a task. A watcher stops
+//! once the receiving end of its channel is dropped.
+
+use std::{future::Future, sync::OnceLock};
+
+use tokio::{
+    runtime::{Builder, Runtime},
+    task::JoinHandle,
+};
+
+/// Worker threads for async tasks; blocking watchers get their own pool on top.
+const WORKER_THREADS: usize = 2;
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The shared runtime, started on first use.
+pub fn get() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(WORKER_THREADS)
+            .thread_name("wpe-runtime")
+            .enable_all()
+            .build()
+            .expect("failed to start the tokio runtime")
+    })
+}
+
+/// Run `task` on the runtime.
+pub fn spawn<F>(task: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    get().spawn(task)
+}
+
+/// Run blocking `work` on the runtime's blocking pool and wait for it, so an async
+/// caller such as a GUI task does not hold up the thread polling it.
+pub async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    get()
+        .spawn_blocking(work)
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// Run a watcher that blocks on its connection until its receiver is gone.
+pub fn spawn_watcher(watch: impl FnOnce() + Send + 'static) -> JoinHandle<()> {
+    get().spawn_blocking(watch)
+}
@@ -0,0 +1,159 @@
+//! Optional bubblewrap confinement for spawned players.
+//!
+//! The sandbox gets a read-only view of the system libraries, the user's mpv
+//! config, and the media it plays, plus the Wayland socket and GPU nodes it
+//! needs to render, and the directory for its IPC socket. Everything else in
+//! `$HOME` is invisible and the network is unshared.
+//!
+//! An entry with `audio = true` also gets the PipeWire and PulseAudio sockets;
+//! without them the unshared player is silent.
+
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use tracing::warn;
+
+use crate::{deps, ipc};
+
+/// Sockets under `$XDG_RUNTIME_DIR` a player with sound reaches its audio server by.
+const AUDIO_SOCKETS: &[&str] = &["pipewire-0", "pulse"];
+
+/// Read-only system paths a dynamically linked mpv needs to start.
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib64",
+    "/etc",
+    "/sys",
+    "/nix/store",
+    "/run/current-system",
+];
+
+/// Build the `bwrap ... <program>` argument vector that confines `program` to `media`,
+/// letting it reach the audio server when `audible`.
+pub fn wrap(
+    program: &str,
+    media: &[&Path],
+    audible: bool,
+) -> Result<Vec<OsString>, Box<dyn Error>> {
+    let mut argv = system_view()?;
+    argv.extend([
+        "--dev-bind-try".into(),
+        "/dev/dri".into(),
+        "/dev/dri".into(),
+    ]);
+
+    if let Some(mpv_config) = mpv_config_dir() {
+        argv.extend([
+            "--ro-bind-try".into(),
+            mpv_config.clone().into(),
+            mpv_config.into(),
+        ]);
+    }
+
+    let socket = wayland_socket()?;
+    argv.extend(["--bind".into(), socket.clone().into(), socket.into()]);
+
+    // The player creates its own IPC socket here so wpe can control it.
+    let ipc_dir = ipc::socket_dir()?;
+    argv.extend(["--bind".into(), ipc_dir.clone().into(), ipc_dir.into()]);
+
+    if audible {
+        argv.extend(audio_binds());
+    }
+
+    for path in media {
+        argv.extend(["--ro-bind".into(), path.into(), path.into()]);
+    }
+
+    argv.extend([
+        "--unshare-all".into(),
+        "--new-session".into(),
+        program.into(),
+    ]);
+    Ok(argv)
+}
+
+/// Build the `bwrap ... <program>` argument vector for a `wpe worker` that reads
+/// `media` and answers on stdout: no Wayland, GPU, or IPC socket, and nowhere to write
+/// but a private `/tmp`.
+pub fn wrap_worker(program: &Path, media: &[&Path]) -> Result<Vec<OsString>, Box<dyn Error>> {
+    let mut argv = system_view()?;
+    // wpe itself may live outside the system paths, e.g. in ~/.cargo/bin; a bare
+    // name like "ffprobe" is looked up in the system paths already bound.
+    let program_path = program.is_absolute().then_some(program);
+    for path in program_path.into_iter().chain(media.iter().copied()) {
+        argv.extend(["--ro-bind".into(), path.into(), path.into()]);
+    }
+    argv.extend([
+        "--unshare-all".into(),
+        "--new-session".into(),
+        "--die-with-parent".into(),
+        program.into(),
+    ]);
+    Ok(argv)
+}
+
+/// `bwrap` with the read-only system paths, `/dev`, `/proc`, and an empty `/tmp`.
+fn system_view() -> Result<Vec<OsString>, Box<dyn Error>> {
+    if !deps::probe().bwrap.installed {
+        return Err("Sandboxing is enabled but bwrap (bubblewrap) is not installed".into());
+    }
+
+    let mut argv: Vec<OsString> = vec!["bwrap".into()];
+    for path in SYSTEM_PATHS {
+        argv.extend(["--ro-bind-try".into(), path.into(), path.into()]);
+    }
+    for arg in ["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"] {
+        argv.push(arg.into());
+    }
+    Ok(argv)
+}
+
+/// Locate the compositor socket the child has to reach.
+fn wayland_socket() -> Result<PathBuf, Box<dyn Error>> {
+    let display = env::var("WAYLAND_DISPLAY").unwrap_or_else(|_| "wayland-0".into());
+    let display = PathBuf::from(display);
+    if display.is_absolute() {
+        return Ok(display);
+    }
+    let runtime = env::var("XDG_RUNTIME_DIR")
+        .map_err(|_| "XDG_RUNTIME_DIR is not set; cannot expose the Wayland socket")?;
+    Ok(PathBuf::from(runtime).join(display))
+}
+
+/// Binds for the audio server sockets that exist, warning when there are none.
+fn audio_binds() -> Vec<OsString> {
+    let sockets: Vec<PathBuf> = env::var("XDG_RUNTIME_DIR")
+        .map(|runtime| {
+            AUDIO_SOCKETS
+                .iter()
+                .map(|socket| Path::new(&runtime).join(socket))
+                .filter(|socket| socket.exists())
+                .collect()
+        })
+        .unwrap_or_default();
+    if sockets.is_empty() {
+        warn!(
+            "No PipeWire or PulseAudio socket in $XDG_RUNTIME_DIR; the sandboxed wallpaper will be silent"
+        );
+    }
+    sockets
+        .into_iter()
+        .flat_map(|socket| ["--bind".into(), socket.clone().into(), socket.into()])
+        .collect()
+}
+
+fn mpv_config_dir() -> Option<PathBuf> {
+    let base = env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok()?;
+    Some(base.join("mpv"))
+}
@@ -0,0 +1,111 @@
+//! Date rules that switch tag sources with the calendar.
+//!
+//! Each `[[seasons]]` rule maps a set of months, days of the month, and weekdays
+//! to tags. Entries with `source = { seasonal = true }` draw from the tags of the
+//! first rule that matches today, so the desktop follows the season or holiday
+//! without switching profiles. Weekdays are named ("sat", "sunday"), or given as
+//! "weekdays" (Monday to Friday) or "weekend".
+
+use std::borrow::Cow;
+
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Serialize};
+
+/// One item of a rule's `weekdays`: a single day, or a shortcut for several.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DayName {
+    Day(Weekday),
+    /// Monday to Friday.
+    Weekdays,
+    /// Saturday and Sunday.
+    Weekend,
+}
+
+impl DayName {
+    pub fn contains(self, day: Weekday) -> bool {
+        match self {
+            DayName::Day(named) => named == day,
+            DayName::Weekdays => !matches!(day, Weekday::Sat | Weekday::Sun),
+            DayName::Weekend => matches!(day, Weekday::Sat | Weekday::Sun),
+        }
+    }
+}
+
+impl TryFrom<String> for DayName {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "weekdays" | "weekday" => Ok(DayName::Weekdays),
+            "weekend" | "weekends" => Ok(DayName::Weekend),
+            name => name
+                .parse()
+                .map(DayName::Day)
+                .map_err(|_| format!("{text} is not a weekday, \"weekdays\", or \"weekend\"")),
+        }
+    }
+}
+
+impl From<DayName> for String {
+    fn from(day: DayName) -> Self {
+        match day {
+            DayName::Day(day) => day.to_string().to_ascii_lowercase(),
+            DayName::Weekdays => "weekdays".into(),
+            DayName::Weekend => "weekend".into(),
+        }
+    }
+}
+
+impl JsonSchema for DayName {
+    fn schema_name() -> Cow<'static, str> {
+        "DayName".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A weekday such as \"fri\" or \"saturday\", \"weekdays\", or \"weekend\"."
+        })
+    }
+}
+
+/// One `[[seasons]]` rule. Empty conditions match any date.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SeasonRule {
+    /// Months, 1 (January) to 12 (December).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub months: Vec<u32>,
+    /// Days of the month, 1 to 31.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<u32>,
+    /// Weekdays such as "fri" or "saturday", or "weekdays" / "weekend".
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weekdays: Vec<DayName>,
+    /// Tags to draw from while the rule matches.
+    pub tags: Vec<String>,
+}
+
+impl SeasonRule {
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        (self.months.is_empty() || self.months.contains(&date.month()))
+            && (self.days.is_empty() || self.days.contains(&date.day()))
+            && (self.weekdays.is_empty()
+                || self.weekdays.iter().any(|day| day.contains(date.weekday())))
+    }
+}
+
+/// Today's date in the local timezone.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Tags of the first rule that matches `date`.
+pub fn active_tags(rules: &[SeasonRule], date: NaiveDate) -> Option<&[String]> {
+    rules
+        .iter()
+        .find(|rule| !rule.tags.is_empty() && rule.matches(date))
+        .map(|rule| rule.tags.as_slice())
+}
@@ -0,0 +1,185 @@
+//! `wpe install-service` / `uninstall-service`: start wpe with the session.
+//!
+//! A systemd user unit bound to `graphical-session.target` starts the daemon (or a
+//! one-shot `wpe -c`) once the compositor is up, instead of an `exec` line in the
+//! compositor's config that can run before the outputs are announced. For sessions
+//! that run XDG autostart entries but never reach `graphical-session.target`, an
+//! optional autostart entry hands the Wayland environment to the user manager and
+//! starts the unit from there.
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::output::{self, CliError, Failure};
+
+const UNIT_NAME: &str = "wpe.service";
+const AUTOSTART_NAME: &str = "wpe-service.desktop";
+/// `wpe -c` started by the unit waits this long for outputs and mounts.
+const LAUNCH_WAIT_SECONDS: u64 = 30;
+
+/// What the unit runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ServiceMode {
+    /// `wpe daemon`, which keeps the wallpapers and restarts crashed ones.
+    Daemon,
+    /// `wpe -c` once, leaving the players running.
+    Launch,
+}
+
+/// Write the user unit (and with `autostart`, the autostart entry) and enable it;
+/// with `now`, start it too.
+pub fn install(mode: ServiceMode, autostart: bool, now: bool) -> Result<(), CliError> {
+    let exe = env::current_exe().map_err(|err| {
+        CliError::new(
+            Failure::General,
+            format!("Could not find the wpe executable: {err}"),
+        )
+    })?;
+    let unit = unit_path()?;
+    write(&unit, &unit_file(&exe, mode))?;
+    output::say(format!("Wrote {}.", unit.display()));
+    output::record("installed", &[&unit.display().to_string()]);
+
+    if autostart {
+        let entry = autostart_path()?;
+        write(&entry, &autostart_file())?;
+        output::say(format!("Wrote {}.", entry.display()));
+        output::record("installed", &[&entry.display().to_string()]);
+    }
+
+    let mut enable = vec!["enable"];
+    if now {
+        enable.push("--now");
+    }
+    enable.push(UNIT_NAME);
+    match systemctl(&["daemon-reload"]).and_then(|()| systemctl(&enable)) {
+        Ok(()) if now => output::say(format!("Enabled and started {UNIT_NAME}.")),
+        Ok(()) => output::say(format!(
+            "Enabled {UNIT_NAME}; it starts with your next graphical session."
+        )),
+        Err(err) => output::say(format!(
+            "Could not enable {UNIT_NAME} ({err}); run `systemctl --user enable {UNIT_NAME}` yourself."
+        )),
+    }
+    output::say(
+        "The unit needs WAYLAND_DISPLAY in the systemd user environment; most compositors \
+         import it, otherwise add `systemctl --user import-environment WAYLAND_DISPLAY` to \
+         their startup, or install with --autostart.",
+    );
+    Ok(())
+}
+
+/// Disable and remove the unit and the autostart entry.
+pub fn uninstall() -> Result<(), CliError> {
+    let unit = unit_path()?;
+    let entry = autostart_path()?;
+    if !unit.exists() && !entry.exists() {
+        output::say("No wpe service is installed.");
+        return Ok(());
+    }
+    if unit.exists()
+        && let Err(err) = systemctl(&["disable", "--now", UNIT_NAME])
+    {
+        output::say(format!("Could not disable {UNIT_NAME}: {err}"));
+    }
+    for path in [&unit, &entry] {
+        if path.exists() {
+            fs::remove_file(path).map_err(|err| {
+                CliError::new(
+                    Failure::General,
+                    format!("Could not remove {}: {}", path.display(), err),
+                )
+            })?;
+            output::say(format!("Removed {}.", path.display()));
+            output::record("removed", &[&path.display().to_string()]);
+        }
+    }
+    let _ = systemctl(&["daemon-reload"]);
+    Ok(())
+}
+
+fn unit_file(exe: &Path, mode: ServiceMode) -> String {
+    let exe = exe.display();
+    let service = match mode {
+        ServiceMode::Daemon => format!(
+            "ExecStart={exe} daemon\n\
+             ExecStop={exe} daemon quit\n\
+             Restart=on-failure\n\
+             RestartSec=2\n"
+        ),
+        ServiceMode::Launch => format!(
+            "Type=oneshot\n\
+             RemainAfterExit=yes\n\
+             ExecStart={exe} -c --wait-timeout {LAUNCH_WAIT_SECONDS}\n"
+        ),
+    };
+    format!(
+        "# Written by `wpe install-service`; `wpe uninstall-service` removes it.\n\
+         [Unit]\n\
+         Description=WallPaper Engine wallpapers\n\
+         PartOf=graphical-session.target\n\
+         After=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         {service}\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n"
+    )
+}
+
+fn autostart_file() -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=WPE service\n\
+         Comment=Start the wpe user service with the session\n\
+         Exec=sh -c \"systemctl --user import-environment WAYLAND_DISPLAY XDG_CURRENT_DESKTOP && systemctl --user start {UNIT_NAME}\"\n\
+         Icon=io.melechtna.wpe\n\
+         Terminal=false\n\
+         NoDisplay=true\n"
+    )
+}
+
+fn write(path: &Path, content: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+        .map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    Ok(())
+}
+
+fn systemctl(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("could not run systemctl: {err}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn config_home() -> Result<PathBuf, Box<dyn Error>> {
+    env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map_err(|_| "HOME environment variable not set".into())
+}
+
+fn unit_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(config_home()?.join("systemd/user").join(UNIT_NAME))
+}
+
+fn autostart_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(config_home()?.join("autostart").join(AUTOSTART_NAME))
+}
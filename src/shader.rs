@@ -0,0 +1,131 @@
+//! Shader wallpapers: a fragment shader drawn over the whole output, Shadertoy style.
+//!
+//! An entry with a `shader` table has a GLSL (`.glsl`, `.frag`) or WGSL (`.wgsl`)
+//! file as its `path`. `wpe render` compiles it with wgpu and draws it on the GPU
+//! every frame, so no video or image is involved. GLSL files are written like on
+//! Shadertoy: a `mainImage(out vec4 fragColor, in vec2 fragCoord)` reading
+//! `iResolution`, `iTime`, `iTimeDelta`, `iFrame`, `iMouse`, and `iDate`. WGSL files
+//! define `fn main_image(coord: vec2<f32>) -> vec4<f32>` and read the same values
+//! from `wpe.resolution`, `wpe.time`, and so on. Coordinates start at the bottom
+//! left, as on Shadertoy; texture channels (`iChannel0`...) are not provided.
+
+use std::{error::Error, fs, path::Path};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// File extensions taken as GLSL, in the Shadertoy dialect.
+const GLSL_EXTENSIONS: &[&str] = &["glsl", "frag"];
+/// File extensions taken as WGSL.
+const WGSL_EXTENSIONS: &[&str] = &["wgsl"];
+
+/// Declarations put before a GLSL shader. Both preludes lay the uniforms out the
+/// same way, std140: 64 bytes, with `iMouse` starting at 16 and `iDate` at 48.
+const GLSL_PRELUDE: &str = "\
+#version 450
+layout(set = 0, binding = 0) uniform WpeUniforms {
+    vec3 iResolution;
+    float iTime;
+    vec4 iMouse;
+    float iTimeDelta;
+    int iFrame;
+    vec4 iDate;
+};
+layout(location = 0) out vec4 wpe_color;
+";
+
+/// The entry point after a GLSL shader, flipping to Shadertoy's coordinates.
+const GLSL_MAIN: &str = "
+void main() {
+    mainImage(wpe_color, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+}
+";
+
+const WGSL_PRELUDE: &str = "\
+struct WpeUniforms {
+    resolution: vec3<f32>,
+    time: f32,
+    mouse: vec4<f32>,
+    time_delta: f32,
+    frame: i32,
+    date: vec4<f32>,
+};
+@group(0) @binding(0) var<uniform> wpe: WpeUniforms;
+";
+
+const WGSL_MAIN: &str = "
+@fragment
+fn wpe_fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return main_image(vec2<f32>(position.x, wpe.resolution.y - position.y));
+}
+";
+
+/// `shader` table of an entry whose `path` is a shader file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ShaderSettings {
+    /// Most frames drawn a second (1-240).
+    pub fps: u32,
+    /// How fast `iTime` runs, in percent of real time (1-1000).
+    pub speed_percent: u32,
+}
+
+impl Default for ShaderSettings {
+    fn default() -> Self {
+        Self {
+            fps: 30,
+            speed_percent: 100,
+        }
+    }
+}
+
+impl ShaderSettings {
+    /// Check the ranges and that `path` is a shader file wpe can read.
+    pub fn validate(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if !(1..=240).contains(&self.fps) {
+            return Err("shader fps has to be between 1 and 240".into());
+        }
+        if !(1..=1000).contains(&self.speed_percent) {
+            return Err("shader speed_percent has to be between 1 and 1000".into());
+        }
+        source(path).map(|_| ())
+    }
+}
+
+/// The language a shader file is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Glsl,
+    Wgsl,
+}
+
+impl Language {
+    /// The language of `path`, by its extension.
+    pub fn of(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        if GLSL_EXTENSIONS.contains(&ext.as_str()) {
+            Some(Language::Glsl)
+        } else if WGSL_EXTENSIONS.contains(&ext.as_str()) {
+            Some(Language::Wgsl)
+        } else {
+            None
+        }
+    }
+}
+
+/// The shader at `path` with wpe's uniforms and entry point around it.
+pub fn source(path: &Path) -> Result<(Language, String), Box<dyn Error>> {
+    let language = Language::of(path).ok_or_else(|| {
+        format!(
+            "{} is not a shader; use a .glsl, .frag, or .wgsl file",
+            path.display()
+        )
+    })?;
+    let body = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    let (prelude, main) = match language {
+        Language::Glsl => (GLSL_PRELUDE, GLSL_MAIN),
+        Language::Wgsl => (WGSL_PRELUDE, WGSL_MAIN),
+    };
+    Ok((language, format!("{prelude}{body}\n{main}")))
+}
@@ -0,0 +1,136 @@
+//! wpe's own slideshow for folder, playlist, and mixed wallpapers.
+//!
+//! mpvpaper's `--slideshow` timer skips to the next file every N seconds whatever it
+//! is, cutting videos off mid-play. Instead wpe hands mpv a playlist it built itself
+//! (sorted, the shuffled [`queue`], or weighted by [`usage`]), shows each image for
+//! the entry's interval through mpv's `--image-display-duration`, and lets videos
+//! play to their end. When `video_seconds` caps videos, a [`Driver`] watches the
+//! player over IPC and moves on once a video has played that long; it runs as long as
+//! whoever launched the player (the GUI or `wpe daemon`) does.
+
+use std::{
+    error::Error,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use crate::{
+    config::{FolderFilter, MediaKind, SlideshowOrder, SlideshowSettings},
+    ipc, queue, runtime, usage,
+};
+
+/// How often a [`Driver`] checks how far the current video got.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The playlist `media` (a folder or a mix of sources) plays on `monitor` in `order`,
+/// keeping the folder files `filter` lets through. Returns its path.
+pub fn playlist(
+    monitor: &str,
+    media: &MediaKind,
+    order: SlideshowOrder,
+    filter: &FolderFilter,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let files = list(media, filter)?;
+    if files.is_empty() {
+        return Err(format!("{} has no files to play", media.path().display()).into());
+    }
+    match order {
+        SlideshowOrder::Random => queue::playlist(monitor, files),
+        SlideshowOrder::LeastShown => queue::write_playlist(
+            &format!("slideshow-{}", ipc::file_safe(monitor)),
+            &usage::least_shown_order(files),
+        ),
+        SlideshowOrder::Sequential => {
+            queue::write_playlist(&format!("slideshow-{}", ipc::file_safe(monitor)), &files)
+        }
+    }
+}
+
+/// What `media` plays, in order: a folder's files, and anything else as it is (mpv
+/// opens playlists and streams inside a playlist itself).
+fn list(media: &MediaKind, filter: &FolderFilter) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    match media {
+        MediaKind::Folder(folder) => queue::list_files(folder, filter),
+        MediaKind::Mix(items) => {
+            let mut files = Vec::new();
+            for item in items {
+                files.extend(list(item, filter)?);
+            }
+            Ok(files)
+        }
+        other => Ok(vec![other.path().to_path_buf()]),
+    }
+}
+
+/// mpv options that time a slideshow: images for one (jittered) interval, videos
+/// through to their end, and the playlist over again.
+pub fn mpv_options(settings: &SlideshowSettings) -> Vec<String> {
+    let seconds = settings.draw_interval().as_secs().max(1);
+    vec![
+        format!("--image-display-duration={seconds}"),
+        "--loop-playlist=inf".into(),
+    ]
+}
+
+/// Moves a slideshow on from videos that played for `video_seconds`, until dropped.
+pub struct Driver {
+    stop: Arc<AtomicBool>,
+}
+
+impl Driver {
+    /// Start watching the slideshow on `monitor`.
+    pub fn start(monitor: &str, limit: Duration) -> Self {
+        info!(
+            "Moving the slideshow on {} past videos after {}s",
+            monitor,
+            limit.as_secs()
+        );
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        let monitor = monitor.to_string();
+        runtime::spawn_watcher(move || drive(&monitor, limit, &stopped));
+        Self { stop }
+    }
+}
+
+impl Drop for Driver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn drive(monitor: &str, limit: Duration, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(POLL_INTERVAL);
+        if stop.load(Ordering::Relaxed) || !playing_video(monitor) {
+            continue;
+        }
+        // `time-pos` stands still while the wallpaper is paused, so paused time
+        // does not count.
+        let played = ipc::get_property(monitor, "time-pos")
+            .ok()
+            .and_then(|value| value.as_f64())
+            .unwrap_or_default();
+        if played >= limit.as_secs_f64()
+            && let Err(err) = ipc::command(monitor, &[json!("playlist-next"), json!("force")])
+        {
+            warn!("Could not move the slideshow on {} along: {}", monitor, err);
+        }
+    }
+}
+
+/// Whether the file on screen is a video rather than an image (or a player that is gone).
+fn playing_video(monitor: &str) -> bool {
+    matches!(
+        ipc::get_property(monitor, "current-tracks/video/image"),
+        Ok(Value::Bool(false))
+    )
+}
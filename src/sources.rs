@@ -0,0 +1,295 @@
+//! Where a wallpaper's media comes from.
+//!
+//! Each kind of source (a file, a folder, a playlist file, a stream URL, the tag
+//! library) implements [`WallpaperSource`], which the launchers, the GUI's checks,
+//! and the slideshow handling go through. [`for_path`] tries [`PARSERS`] in order to
+//! find the kind a configured path names; a new kind of source adds its type and a
+//! parser there. A list of paths is a [`Mix`] of those sources.
+
+use std::{
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{self, MediaKind},
+    state, tags,
+};
+
+/// Playlist formats mpv reads itself.
+const PLAYLIST_EXTENSIONS: &[&str] = &["m3u", "m3u8", "pls"];
+
+/// What a source is, for the GUI and logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    File,
+    Folder,
+    Playlist,
+    Stream,
+    Tags,
+    Mix,
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SourceKind::File => "file",
+            SourceKind::Folder => "folder",
+            SourceKind::Playlist => "playlist",
+            SourceKind::Stream => "stream",
+            SourceKind::Tags => "tags",
+            SourceKind::Mix => "mix",
+        })
+    }
+}
+
+/// Something a wallpaper can play.
+pub trait WallpaperSource {
+    fn kind(&self) -> SourceKind;
+
+    /// The file or folder on this machine, for mounting, sandboxing, and tagging.
+    fn local_path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Why the source cannot play right now; cheap enough to run on every Start.
+    fn validate(&self) -> Result<(), String>;
+
+    /// The media to hand to mpv.
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>>;
+}
+
+type Parser = fn(&Path) -> Option<Box<dyn WallpaperSource>>;
+
+/// Source kinds that can be named by a path, most specific first.
+const PARSERS: &[Parser] = &[
+    Stream::parse,
+    Playlist::parse,
+    LocalFolder::parse,
+    LocalFile::parse,
+];
+
+/// The source a configured path names, or `None` when no kind accepts it (e.g. a
+/// local path that does not exist).
+pub fn for_path(path: &Path) -> Option<Box<dyn WallpaperSource>> {
+    PARSERS.iter().find_map(|parse| parse(path))
+}
+
+/// [`for_path`], failing with the reason a launcher reports.
+pub fn resolve_path(path: &Path) -> Result<MediaKind, Box<dyn Error>> {
+    match for_path(path) {
+        Some(source) => source.resolve(),
+        None => Err(format!(
+            "Unable to access {}",
+            config::normalize_entry_path(path).display()
+        )
+        .into()),
+    }
+}
+
+/// A single image or video.
+#[derive(Debug)]
+pub struct LocalFile(PathBuf);
+
+impl LocalFile {
+    fn parse(path: &Path) -> Option<Box<dyn WallpaperSource>> {
+        let path = config::normalize_entry_path(path);
+        path.is_file()
+            .then(|| Box::new(Self(path)) as Box<dyn WallpaperSource>)
+    }
+}
+
+impl WallpaperSource for LocalFile {
+    fn kind(&self) -> SourceKind {
+        SourceKind::File
+    }
+
+    fn local_path(&self) -> Option<&Path> {
+        Some(&self.0)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.0.is_file() {
+            Ok(())
+        } else {
+            Err(format!("Invalid path or file ({})", self.0.display()))
+        }
+    }
+
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>> {
+        self.validate()?;
+        Ok(if config::is_probably_video(&self.0) {
+            MediaKind::Video(self.0.clone())
+        } else {
+            MediaKind::Image(self.0.clone())
+        })
+    }
+}
+
+/// A folder played as a slideshow.
+#[derive(Debug)]
+pub struct LocalFolder(PathBuf);
+
+impl LocalFolder {
+    fn parse(path: &Path) -> Option<Box<dyn WallpaperSource>> {
+        let path = config::normalize_entry_path(path);
+        path.is_dir()
+            .then(|| Box::new(Self(path)) as Box<dyn WallpaperSource>)
+    }
+}
+
+impl WallpaperSource for LocalFolder {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Folder
+    }
+
+    fn local_path(&self) -> Option<&Path> {
+        Some(&self.0)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.0.is_dir() {
+            Ok(())
+        } else {
+            Err(format!("Invalid folder ({})", self.0.display()))
+        }
+    }
+
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>> {
+        self.validate()?;
+        Ok(MediaKind::Folder(self.0.clone()))
+    }
+}
+
+/// An `.m3u`/`.m3u8`/`.pls` file, played in order like a folder.
+#[derive(Debug)]
+pub struct Playlist(PathBuf);
+
+impl Playlist {
+    fn parse(path: &Path) -> Option<Box<dyn WallpaperSource>> {
+        let is_playlist = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| PLAYLIST_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        let path = config::normalize_entry_path(path);
+        (is_playlist && path.is_file()).then(|| Box::new(Self(path)) as Box<dyn WallpaperSource>)
+    }
+}
+
+impl WallpaperSource for Playlist {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Playlist
+    }
+
+    fn local_path(&self) -> Option<&Path> {
+        Some(&self.0)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.0.is_file() {
+            Ok(())
+        } else {
+            Err(format!("Invalid playlist ({})", self.0.display()))
+        }
+    }
+
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>> {
+        self.validate()?;
+        Ok(MediaKind::Playlist(self.0.clone()))
+    }
+}
+
+/// A URL mpv opens itself (through its stream handlers or yt-dlp).
+#[derive(Debug)]
+pub struct Stream(String);
+
+impl Stream {
+    fn parse(path: &Path) -> Option<Box<dyn WallpaperSource>> {
+        let url = path.to_str()?.trim();
+        let (scheme, rest) = url.split_once("://")?;
+        let is_scheme = !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'));
+        (is_scheme && scheme != "file" && !rest.is_empty())
+            .then(|| Box::new(Self(url.to_string())) as Box<dyn WallpaperSource>)
+    }
+}
+
+impl WallpaperSource for Stream {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Stream
+    }
+
+    /// Reachability is only known once mpv connects; a dead URL fails the launch.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>> {
+        Ok(MediaKind::Stream(self.0.clone()))
+    }
+}
+
+/// Several paths (`path = [...]`), each any of the kinds above, played as one playlist.
+#[derive(Debug)]
+pub struct Mix(pub Vec<PathBuf>);
+
+impl WallpaperSource for Mix {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Mix
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.0.is_empty() {
+            return Err("The list of sources is empty".into());
+        }
+        for path in &self.0 {
+            match for_path(path) {
+                Some(source) => source.validate()?,
+                None => {
+                    return Err(format!(
+                        "Unable to access {}",
+                        config::normalize_entry_path(path).display()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>> {
+        self.validate()?;
+        let items = self
+            .0
+            .iter()
+            .map(|path| resolve_path(path))
+            .collect::<Result<_, _>>()?;
+        Ok(MediaKind::Mix(items))
+    }
+}
+
+/// A random file or folder carrying every one of `tags`.
+#[derive(Debug)]
+pub struct Tagged(pub Vec<String>);
+
+impl WallpaperSource for Tagged {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Tags
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if tags::matching(&state::load_state(), &self.0).is_empty() {
+            return Err(format!(
+                "Nothing is tagged with all of: {}",
+                self.0.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    fn resolve(&self) -> Result<MediaKind, Box<dyn Error>> {
+        resolve_path(&tags::pick(&self.0)?)
+    }
+}
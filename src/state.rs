@@ -0,0 +1,158 @@
+//! Mutable runtime state kept apart from the user-authored config.
+//!
+//! Anything wpe writes on its own (window geometry, loop points, tags, and
+//! later history or queue positions) lives in `$XDG_STATE_HOME/wpe/state.toml` so
+//! the config file can be managed declaratively and left read-only.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    error::Error,
+    fs,
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::loops::LoopPoints;
+
+/// Everything wpe remembers between runs that is not user configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub window: Option<WindowGeometry>,
+    /// Seamless loop points found by the GUI's loop tool, keyed by video path.
+    #[serde(default)]
+    pub loops: BTreeMap<PathBuf, LoopPoints>,
+    /// User tags on wallpaper files and folders, keyed by canonical path.
+    #[serde(default)]
+    pub tags: BTreeMap<PathBuf, BTreeSet<String>>,
+    /// Unix time of the last successful pull per `[[sync]]` collection.
+    #[serde(default)]
+    pub last_sync: BTreeMap<String, u64>,
+    /// Newest crash report the GUI has already pointed out.
+    #[serde(default)]
+    pub last_crash_seen: Option<String>,
+    /// Named profile last saved or loaded, if the config came from one.
+    #[serde(default)]
+    pub active_profile: Option<String>,
+}
+
+impl State {
+    /// Apply what changed from `base` to `edited` on top of `self`, so whatever
+    /// another process saved since `base` was read (e.g. tags and favorites added
+    /// from the CLI) is kept. Maps merge per key, and tags per tag.
+    pub fn merge_changes(&mut self, base: &State, edited: &State) {
+        merge_value(&mut self.window, &base.window, &edited.window);
+        merge_map(&mut self.loops, &base.loops, &edited.loops);
+        merge_map(&mut self.last_sync, &base.last_sync, &edited.last_sync);
+        merge_value(
+            &mut self.last_crash_seen,
+            &base.last_crash_seen,
+            &edited.last_crash_seen,
+        );
+        merge_value(
+            &mut self.active_profile,
+            &base.active_profile,
+            &edited.active_profile,
+        );
+
+        let none = BTreeSet::new();
+        for path in base.tags.keys().chain(edited.tags.keys()) {
+            let before = base.tags.get(path).unwrap_or(&none);
+            let after = edited.tags.get(path).unwrap_or(&none);
+            if before == after {
+                continue;
+            }
+            let tags = self.tags.entry(path.clone()).or_default();
+            tags.retain(|tag| after.contains(tag) || !before.contains(tag));
+            tags.extend(after.difference(before).cloned());
+            if tags.is_empty() {
+                self.tags.remove(path);
+            }
+        }
+    }
+}
+
+fn merge_value<T: Clone + PartialEq>(into: &mut T, base: &T, edited: &T) {
+    if edited != base {
+        *into = edited.clone();
+    }
+}
+
+fn merge_map<K: Ord + Clone, V: Clone + PartialEq>(
+    into: &mut BTreeMap<K, V>,
+    base: &BTreeMap<K, V>,
+    edited: &BTreeMap<K, V>,
+) {
+    for key in base.keys().chain(edited.keys()) {
+        match (base.get(key), edited.get(key)) {
+            (before, after) if before == after => {}
+            (_, Some(value)) => {
+                into.insert(key.clone(), value.clone());
+            }
+            (_, None) => {
+                into.remove(key);
+            }
+        }
+    }
+}
+
+/// Last known size of the GUI window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Resolve $XDG_STATE_HOME/wpe (or ~/.local/state/wpe), creating it if needed.
+pub fn state_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let base = if let Ok(custom) = env::var("XDG_STATE_HOME") {
+        PathBuf::from(custom)
+    } else {
+        let home = env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+        PathBuf::from(home).join(".local/state")
+    };
+    let dir = base.join("wpe");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Resolve $XDG_CACHE_HOME/wpe (or ~/.cache/wpe), creating it if needed.
+pub fn cache_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let base = if let Ok(custom) = env::var("XDG_CACHE_HOME") {
+        PathBuf::from(custom)
+    } else {
+        let home = env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+        PathBuf::from(home).join(".cache")
+    };
+    let dir = base.join("wpe");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn state_file_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(state_dir()?.join("state.toml"))
+}
+
+/// Read the state file, falling back to defaults when it is missing or unreadable.
+pub fn load_state() -> State {
+    let Ok(path) = state_file_path() else {
+        return State::default();
+    };
+    let Ok(data) = fs::read_to_string(&path) else {
+        return State::default();
+    };
+    toml::from_str(&data).unwrap_or_else(|err| {
+        warn!("Ignoring unreadable state file {}: {}", path.display(), err);
+        State::default()
+    })
+}
+
+pub fn save_state(state: &State) -> Result<(), Box<dyn Error>> {
+    let path = state_file_path()?;
+    let data = toml::to_string_pretty(state)?;
+    fs::write(path, data)?;
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! Pull shared wallpaper collections from a remote.
+//!
+//! Each `[[sync]]` table mirrors a remote into a local folder with the matching
+//! tool: rsync for `host:/path` style remotes, git for repositories, and rclone for
+//! WebDAV. Point a wallpaper entry (or tags) at the local folder to use it.
+
+use std::{
+    error::Error,
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, state::State};
+
+/// Transport used to fetch a collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncKind {
+    Rsync,
+    Git,
+    Webdav,
+}
+
+/// One `[[sync]]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SyncCollection {
+    /// Name used by `wpe sync <name>` and in status messages.
+    pub name: String,
+    pub kind: SyncKind,
+    /// rsync source, git URL, or WebDAV URL.
+    pub remote: String,
+    /// Local folder the collection is mirrored into.
+    pub path: PathBuf,
+    /// Minutes between automatic pulls; 0 only syncs when asked.
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+}
+
+fn default_interval_minutes() -> u64 {
+    60
+}
+
+impl SyncCollection {
+    fn local_path(&self) -> PathBuf {
+        config::normalize_entry_path(&self.path)
+    }
+
+    /// Whether the interval has passed since the last successful pull.
+    pub fn is_due(&self, state: &State) -> bool {
+        if self.interval_minutes == 0 {
+            return false;
+        }
+        let Some(last) = state.last_sync.get(&self.name) else {
+            return true;
+        };
+        now_secs().saturating_sub(*last) >= self.interval_minutes * 60
+    }
+}
+
+/// Pull `collection` into its local folder and record the time in `state`.
+pub fn pull(collection: &SyncCollection, state: &mut State) -> Result<(), Box<dyn Error>> {
+    run(collection)?;
+    mark_synced(collection, state);
+    Ok(())
+}
+
+/// Remember that `collection` was just pulled successfully.
+pub fn mark_synced(collection: &SyncCollection, state: &mut State) {
+    state.last_sync.insert(collection.name.clone(), now_secs());
+}
+
+/// Run the transfer without touching state, for background callers that save state themselves.
+pub fn run(collection: &SyncCollection) -> Result<(), Box<dyn Error>> {
+    let target = &collection.local_path();
+    let mut command = match collection.kind {
+        SyncKind::Rsync => {
+            fs::create_dir_all(target)?;
+            let mut command = Command::new("rsync");
+            // Trailing slashes copy the remote folder's contents rather than the folder itself.
+            command
+                .args(["-a", "--delete"])
+                .arg(with_trailing_slash(&collection.remote))
+                .arg(with_trailing_slash(&target.to_string_lossy()));
+            command
+        }
+        SyncKind::Git if target.join(".git").is_dir() => {
+            let mut command = Command::new("git");
+            command.arg("-C").arg(target).args(["pull", "--ff-only"]);
+            command
+        }
+        SyncKind::Git => {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut command = Command::new("git");
+            command
+                .args(["clone", "--depth", "1"])
+                .arg(&collection.remote)
+                .arg(target);
+            command
+        }
+        SyncKind::Webdav => {
+            fs::create_dir_all(target)?;
+            let mut command = Command::new("rclone");
+            command
+                .arg("sync")
+                .arg(format!("--webdav-url={}", collection.remote))
+                .arg(":webdav:")
+                .arg(target);
+            command
+        }
+    };
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("Failed to run {program} for {}: {err}", collection.name))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{program} failed for {}: {}",
+            collection.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn with_trailing_slash(value: &str) -> String {
+    if value.ends_with('/') {
+        value.to_string()
+    } else {
+        format!("{value}/")
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
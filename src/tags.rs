@@ -0,0 +1,80 @@
+//! User tags on wallpaper files and folders, and drawing a source from them.
+//!
+//! Tags live in the state file keyed by canonical path, so tagging works even when
+//! the config is read-only. An entry with `source = { tags = [...] }` plays a random
+//! file or folder carrying every listed tag instead of a fixed `path`.
+
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::state::{self, State};
+
+/// Lowercase and trim a tag, rejecting empty ones.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Split comma- or space-separated user input into normalized tags.
+pub fn parse_tags(input: &str) -> BTreeSet<String> {
+    input
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter_map(normalize_tag)
+        .collect()
+}
+
+/// Tags currently on `path`.
+pub fn tags_for(state: &State, path: &Path) -> BTreeSet<String> {
+    state.tags.get(path).cloned().unwrap_or_default()
+}
+
+/// Replace the tags on `path`, dropping the path from the store when none are left.
+pub fn set_tags(state: &mut State, path: &Path, tags: BTreeSet<String>) {
+    if tags.is_empty() {
+        state.tags.remove(path);
+    } else {
+        state.tags.insert(path.to_path_buf(), tags);
+    }
+}
+
+/// Add `tags` to `path`.
+pub fn add_tags(state: &mut State, path: &Path, tags: &[String]) {
+    let mut current = tags_for(state, path);
+    current.extend(tags.iter().filter_map(|tag| normalize_tag(tag)));
+    set_tags(state, path, current);
+}
+
+/// Remove `tags` from `path`.
+pub fn remove_tags(state: &mut State, path: &Path, tags: &[String]) {
+    let mut current = tags_for(state, path);
+    for tag in tags.iter().filter_map(|tag| normalize_tag(tag)) {
+        current.remove(&tag);
+    }
+    set_tags(state, path, current);
+}
+
+/// Existing paths that carry every tag in `tags`.
+pub fn matching(state: &State, tags: &[String]) -> Vec<PathBuf> {
+    let wanted: BTreeSet<String> = tags.iter().filter_map(|tag| normalize_tag(tag)).collect();
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    state
+        .tags
+        .iter()
+        .filter(|(path, tags)| wanted.is_subset(tags) && path.exists())
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Pick a random tagged file or folder for an entry's tag source.
+pub fn pick(tags: &[String]) -> Result<PathBuf, Box<dyn Error>> {
+    let candidates = matching(&state::load_state(), tags);
+    if candidates.is_empty() {
+        return Err(format!("Nothing is tagged with all of: {}", tags.join(", ")).into());
+    }
+    Ok(candidates[fastrand::usize(..candidates.len())].clone())
+}
@@ -0,0 +1,152 @@
+//! CPU and GPU temperatures for `[thermal]` power saving.
+//!
+//! Sensors are read from `/sys/class/hwmon/*/temp*_input` (millidegrees Celsius).
+//! Each hwmon device names its driver, which is how CPU and GPU sensors are told
+//! apart from drives, chargers, and other chips that report a temperature.
+
+use std::{fmt, fs, path::Path};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// hwmon drivers counted as CPU or GPU when `[thermal] sensors` is empty.
+const KNOWN_SENSORS: &[&str] = &[
+    "amdgpu",
+    "radeon",
+    "nouveau",
+    "i915",
+    "xe",
+    "k10temp",
+    "zenpower",
+    "coretemp",
+    "cpu_thermal",
+];
+
+/// `[thermal]` table: show still frames instead of video while the machine runs hot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ThermalSettings {
+    pub enabled: bool,
+    /// Swap to still frames at or above this temperature (°C).
+    pub threshold: f64,
+    /// Bring the video back once every sensor is below this temperature (°C).
+    pub resume_below: f64,
+    /// hwmon driver names to watch (`cat /sys/class/hwmon/*/name`); empty watches
+    /// the common CPU and GPU drivers.
+    pub sensors: Vec<String>,
+}
+
+impl Default for ThermalSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 85.0,
+            resume_below: 75.0,
+            sensors: Vec::new(),
+        }
+    }
+}
+
+impl ThermalSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn watches(&self, sensor: &str) -> bool {
+        if self.sensors.is_empty() {
+            KNOWN_SENSORS.contains(&sensor)
+        } else {
+            self.sensors.iter().any(|name| name == sensor)
+        }
+    }
+}
+
+/// The hottest watched sensor at one point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    /// hwmon driver name, e.g. `amdgpu`.
+    pub sensor: String,
+    pub celsius: f64,
+}
+
+impl fmt::Display for Reading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {:.0}°C", self.sensor, self.celsius)
+    }
+}
+
+/// The hottest sensor `settings` watches, or `None` when none can be read.
+pub fn hottest(settings: &ThermalSettings) -> Option<Reading> {
+    let devices = fs::read_dir("/sys/class/hwmon").ok()?;
+    let mut hottest: Option<Reading> = None;
+    for device in devices.filter_map(Result::ok) {
+        let device = device.path();
+        let Ok(name) = fs::read_to_string(device.join("name")) else {
+            continue;
+        };
+        let name = name.trim();
+        if !settings.watches(name) {
+            continue;
+        }
+        let Some(celsius) = max_temperature(&device) else {
+            continue;
+        };
+        if hottest
+            .as_ref()
+            .is_none_or(|reading| celsius > reading.celsius)
+        {
+            hottest = Some(Reading {
+                sensor: name.to_string(),
+                celsius,
+            });
+        }
+    }
+    hottest
+}
+
+/// Highest `temp*_input` of one hwmon device.
+fn max_temperature(device: &Path) -> Option<f64> {
+    fs::read_dir(device)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("temp") && name.ends_with("_input")
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|value| value.trim().parse::<i64>().ok())
+        .map(|millidegrees| millidegrees as f64 / 1000.0)
+        .max_by(f64::total_cmp)
+}
+
+/// Tracks whether the machine is running hot, with hysteresis between the two limits.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    hot: bool,
+}
+
+impl Throttle {
+    pub fn is_hot(&self) -> bool {
+        self.hot
+    }
+
+    /// Feed a new reading; returns the new state when it flipped.
+    pub fn update(
+        &mut self,
+        settings: &ThermalSettings,
+        reading: Option<&Reading>,
+    ) -> Option<bool> {
+        let hot = match reading {
+            Some(_) if !settings.enabled => false,
+            Some(reading) if self.hot => reading.celsius >= settings.resume_below,
+            Some(reading) => reading.celsius >= settings.threshold,
+            // Sensors vanished or the feature was turned off: stop holding the video back.
+            None => false,
+        };
+        (hot != self.hot).then(|| {
+            self.hot = hot;
+            hot
+        })
+    }
+}
@@ -0,0 +1,137 @@
+//! Small previews of wallpaper sources, cached on disk.
+//!
+//! Images are decoded and scaled down directly; videos get a representative frame
+//! picked by ffmpeg; a folder shows its first image or video. Previews are kept as
+//! PNGs in `$XDG_CACHE_HOME/wpe/thumbnails/`, named after a hash of the source's
+//! path, size, and modification time, so an edited file gets a new one.
+//!
+//! The decoding itself happens in a `wpe worker` process (see [`crate::worker`]), so
+//! a file that hangs or crashes its decoder only costs that preview.
+
+use std::{
+    error::Error,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use image::ImageOutputFormat;
+
+use crate::{
+    config::{self, FolderFilter},
+    queue, state,
+    worker::{self, WorkerTask},
+};
+
+/// Longest side of a preview, in pixels.
+pub const SIZE: u32 = 320;
+
+/// Image formats wpe can decode itself.
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif"];
+/// How long ffmpeg may look for a frame; shorter than the worker's own timeout so
+/// its error, not the worker's, is the one reported.
+const FFMPEG_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The preview of `source` (an image, a video, or a folder), made if it is not
+/// cached yet. Returns the PNG's path.
+pub fn thumbnail(source: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let source = if source.is_dir() {
+        first_media(source)?
+    } else {
+        source.to_path_buf()
+    };
+    let target = cache_path(&source)?;
+    if target.is_file() {
+        return Ok(target);
+    }
+
+    if !config::is_probably_video(&source) && !is_image(&source) {
+        return Err(format!("{} is not an image or video", source.display()).into());
+    }
+    let png = worker::run(WorkerTask::Thumbnail, &source)?;
+    // Write beside the target and rename, so a half-written preview is never used.
+    let partial = target.with_extension("partial.png");
+    fs::write(&partial, png)?;
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
+
+/// Decode `source` (an image or a video) and encode its preview as a PNG. Runs in
+/// the worker process.
+pub fn render(source: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    if config::is_probably_video(source) {
+        return video_frame(source);
+    }
+    let mut png = Cursor::new(Vec::new());
+    image::open(source)?
+        .thumbnail(SIZE, SIZE)
+        .write_to(&mut png, ImageOutputFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// Whether wpe can decode `path` as an image.
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// The first image or video of `folder`, in playing order.
+fn first_media(folder: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    queue::list_files(folder, &FolderFilter::default())?
+        .into_iter()
+        .find(|file| is_image(file) || config::is_probably_video(file))
+        .ok_or_else(|| format!("{} has no images or videos", folder.display()).into())
+}
+
+/// Have ffmpeg encode a representative frame of the first seconds of `video` as a PNG.
+fn video_frame(video: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-v", "error", "-i"])
+        .arg(video)
+        .arg("-vf")
+        .arg(format!(
+            "thumbnail,scale={SIZE}:{SIZE}:force_original_aspect_ratio=decrease"
+        ))
+        .args(["-frames:v", "1", "-f", "image2pipe", "-c:v", "png", "-"]);
+    let png = worker::output(command, FFMPEG_TIMEOUT)
+        .map_err(|err| format!("ffmpeg could not decode {}: {err}", video.display()))?;
+    if png.is_empty() {
+        return Err(format!("ffmpeg found no frame in {}", video.display()).into());
+    }
+    Ok(png)
+}
+
+/// Where the preview of `source` is cached.
+fn cache_path(source: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let metadata = fs::metadata(source)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |age| age.as_secs());
+    let dir = state::cache_dir()?.join("thumbnails");
+    fs::create_dir_all(&dir)?;
+    let key = fnv1a(
+        source
+            .as_os_str()
+            .as_encoded_bytes()
+            .iter()
+            .chain(&metadata.len().to_le_bytes())
+            .chain(&modified.to_le_bytes()),
+    );
+    Ok(dir.join(format!("{key:016x}.png")))
+}
+
+/// FNV-1a; stable across builds, unlike `DefaultHasher`.
+fn fnv1a<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
@@ -0,0 +1,63 @@
+//! Time-lapse sets: a folder of images spread across the day.
+//!
+//! Like macOS's dynamic wallpapers, an entry with a `timelapse` table splits the day
+//! into as many equal slots as its folder has images, in name order, starting at
+//! `start_hour`: sixteen images change every hour and a half. `wpe render` shows the
+//! image for the current time and crossfades to the next one over `fade_seconds`
+//! when its slot begins. Apple's `.heic` sets keep their schedule in XMP metadata
+//! that wpe does not read, so their images have to be extracted into a folder, in
+//! order, first.
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::FolderFilter, queue};
+
+/// Image formats a set may be made of; mpv decodes them.
+const EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "webp", "avif", "jxl", "bmp", "tif", "tiff", "heic", "heif",
+];
+
+/// `timelapse` table of an entry whose `path` is a folder of images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TimelapseSettings {
+    /// Seconds each crossfade to the next image takes.
+    pub fade_seconds: u64,
+    /// Hour of the day (0-23) the first image comes up at.
+    pub start_hour: u32,
+}
+
+impl Default for TimelapseSettings {
+    fn default() -> Self {
+        Self {
+            fade_seconds: 60,
+            start_hour: 0,
+        }
+    }
+}
+
+/// The images of the set in `folder`, in the order they come up.
+pub fn frames(folder: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let filter = FolderFilter {
+        recursive: false,
+        ..FolderFilter::default()
+    };
+    let frames: Vec<PathBuf> = queue::list_files(folder, &filter)?
+        .into_iter()
+        .filter(|file| {
+            file.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        })
+        .collect();
+    if frames.is_empty() {
+        return Err(format!("{} has no images for a time-lapse set", folder.display()).into());
+    }
+    Ok(frames)
+}
@@ -0,0 +1,61 @@
+//! Transitions between the files of a slideshow.
+//!
+//! A slideshow cuts from one file to the next unless its entry has a `transition`
+//! table. `wpe render` (`backend = "builtin"`) then keeps the last frame of the old
+//! file up until the new one has drawn, and fades or slides from the one to the other
+//! over `milliseconds`. mpvpaper has no way to show two files at once, so its
+//! slideshows keep cutting.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `effect`: how one file gives way to the next.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransitionEffect {
+    /// Cut straight to the next file.
+    None,
+    /// Blend the old file into the new one.
+    Fade,
+    /// Push the old file out to the left as the new one comes in from the right.
+    Slide,
+}
+
+impl fmt::Display for TransitionEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TransitionEffect::None => "none",
+            TransitionEffect::Fade => "fade",
+            TransitionEffect::Slide => "slide",
+        })
+    }
+}
+
+/// `transition` table of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TransitionSettings {
+    pub effect: TransitionEffect,
+    /// How long the change takes.
+    pub milliseconds: u64,
+}
+
+impl Default for TransitionSettings {
+    fn default() -> Self {
+        Self {
+            effect: TransitionEffect::Fade,
+            milliseconds: 800,
+        }
+    }
+}
+
+impl TransitionSettings {
+    /// Whether changes show at all, rather than cutting.
+    pub fn is_active(&self) -> bool {
+        self.effect != TransitionEffect::None && self.milliseconds > 0
+    }
+}
@@ -0,0 +1,240 @@
+//! Tray icon for the GUI, as a StatusNotifierItem.
+//!
+//! With `[gui] tray = true`, the GUI shows an icon in the panel's tray (KDE Plasma,
+//! waybar's `tray` module, and any other StatusNotifierWatcher host) and closing its
+//! window only hides it, so the GUI keeps looking after the wallpapers it started.
+//! Clicking the icon opens the window again; its menu also skips every monitor to its
+//! next file, pauses or resumes them all, and quits. The menu is served over
+//! `com.canonical.dbusmenu`, which is what those hosts read.
+
+use std::{collections::HashMap, error::Error, process, thread, time::Duration};
+
+use futures::channel::mpsc::UnboundedSender;
+use tracing::{info, warn};
+use zbus::{
+    blocking::Connection,
+    fdo, interface,
+    zvariant::{ObjectPath, OwnedValue, Value},
+};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+/// How often the icon checks that the GUI still listens.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What happened to the tray icon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayEvent {
+    /// The icon is in the tray; closing the window can hide it.
+    Shown,
+    /// No tray took the icon, e.g. the panel has no tray.
+    Unavailable(String),
+    /// The icon or its Open item was clicked.
+    Open,
+    /// Skip every monitor to its next file.
+    Next,
+    /// Pause every wallpaper, or resume them all if none plays.
+    TogglePause,
+    Quit,
+}
+
+/// The menu's items: dbusmenu id, label, and what choosing it does. Id 0 is the root.
+const ITEMS: [(i32, &str, Option<TrayEvent>); 5] = [
+    (1, "Open wpe", Some(TrayEvent::Open)),
+    (2, "Next wallpaper", Some(TrayEvent::Next)),
+    (3, "Pause or resume", Some(TrayEvent::TogglePause)),
+    (4, "", None),
+    (5, "Quit", Some(TrayEvent::Quit)),
+];
+
+/// Show the icon and send what is done with it, until the receiver is gone.
+pub fn watch(tx: UnboundedSender<TrayEvent>) {
+    let _connection = match serve(&tx) {
+        Ok(connection) => connection,
+        Err(err) => {
+            warn!("Could not add the tray icon: {}", err);
+            let _ = tx.unbounded_send(TrayEvent::Unavailable(err.to_string()));
+            return;
+        }
+    };
+    info!("Added the tray icon");
+    if tx.unbounded_send(TrayEvent::Shown).is_err() {
+        return;
+    }
+    // The connection answers the tray on threads of its own.
+    while !tx.is_closed() {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn serve(tx: &UnboundedSender<TrayEvent>) -> Result<Connection, Box<dyn Error>> {
+    let name = format!("org.kde.StatusNotifierItem-{}-1", process::id());
+    let connection = zbus::blocking::connection::Builder::session()?
+        .name(name.as_str())?
+        .serve_at(ITEM_PATH, Item { tx: tx.clone() })?
+        .serve_at(MENU_PATH, Menu { tx: tx.clone() })?
+        .build()?;
+    connection
+        .call_method(
+            Some(WATCHER),
+            "/StatusNotifierWatcher",
+            Some(WATCHER),
+            "RegisterStatusNotifierItem",
+            &(name.as_str(),),
+        )
+        .map_err(|err| format!("no system tray is running ({err})"))?;
+    Ok(connection)
+}
+
+struct Item {
+    tx: UnboundedSender<TrayEvent>,
+}
+
+#[interface(name = "org.kde.StatusNotifierItem")]
+impl Item {
+    fn activate(&self, _x: i32, _y: i32) {
+        let _ = self.tx.unbounded_send(TrayEvent::Open);
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {
+        let _ = self.tx.unbounded_send(TrayEvent::TogglePause);
+    }
+
+    fn context_menu(&self, _x: i32, _y: i32) {}
+
+    fn scroll(&self, _delta: i32, _orientation: String) {}
+
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "ApplicationStatus"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        "wpe"
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        "WallPaper Engine"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "Active"
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        "preferences-desktop-wallpaper"
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> ObjectPath<'static> {
+        ObjectPath::from_static_str_unchecked(MENU_PATH)
+    }
+}
+
+/// A menu item's properties, as dbusmenu hands them out.
+type Properties = HashMap<&'static str, Value<'static>>;
+
+struct Menu {
+    tx: UnboundedSender<TrayEvent>,
+}
+
+fn properties(id: i32) -> Properties {
+    let mut properties = Properties::new();
+    match ITEMS.iter().find(|(item, ..)| *item == id) {
+        None => {
+            properties.insert("children-display", Value::from("submenu"));
+        }
+        Some((_, _, None)) => {
+            properties.insert("type", Value::from("separator"));
+        }
+        Some((_, label, Some(_))) => {
+            properties.insert("label", Value::from(*label));
+        }
+    }
+    properties
+}
+
+#[interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    /// The whole menu; it is small and never changes, so depth is ignored.
+    fn get_layout(
+        &self,
+        _parent_id: i32,
+        _recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> (u32, (i32, Properties, Vec<Value<'static>>)) {
+        let children = ITEMS
+            .iter()
+            .map(|(id, ..)| Value::from((*id, properties(*id), Vec::<Value<'static>>::new())))
+            .collect();
+        (1, (0, properties(0), children))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, Properties)> {
+        ids.into_iter().map(|id| (id, properties(id))).collect()
+    }
+
+    fn get_property(&self, id: i32, name: String) -> fdo::Result<Value<'static>> {
+        properties(id)
+            .remove(name.as_str())
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("Item {id} has no {name}")))
+    }
+
+    fn event(&self, id: i32, event_id: String, _data: OwnedValue, _timestamp: u32) {
+        if event_id != "clicked" {
+            return;
+        }
+        if let Some((_, _, Some(event))) = ITEMS.iter().find(|(item, ..)| *item == id) {
+            let _ = self.tx.unbounded_send(event.clone());
+        }
+    }
+
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        for (id, event_id, data, timestamp) in events {
+            self.event(id, event_id, data, timestamp);
+        }
+        Vec::new()
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,184 @@
+//! Picture adjustments per entry, and remembering the ones made live.
+//!
+//! mpv's video equalizer (`brightness`, `contrast`, `saturation`, `gamma`) and
+//! `video-zoom` can be changed on a running wallpaper over its IPC socket, e.g. with
+//! `socat` or a compositor keybinding. Those changes die with the player. With
+//! `[live_tweaks] remember = true`, wpe reads them back from each player before it
+//! restarts or stops it and writes them into the entry's `[wallpapers.picture]`.
+
+use std::{collections::BTreeMap, error::Error};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::{config, ipc};
+
+/// Zoom as mpv starts it, in percent.
+const DEFAULT_ZOOM: u16 = 100;
+
+/// `[wallpapers.picture]`: mpv's equalizer (-100 to 100, 0 unchanged) and zoom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Picture {
+    pub brightness: i8,
+    pub contrast: i8,
+    pub saturation: i8,
+    pub gamma: i8,
+    /// Percent of the scaled size; 100 leaves it as `scale` fits it.
+    pub zoom: u16,
+}
+
+impl Default for Picture {
+    fn default() -> Self {
+        Self {
+            brightness: 0,
+            contrast: 0,
+            saturation: 0,
+            gamma: 0,
+            zoom: DEFAULT_ZOOM,
+        }
+    }
+}
+
+impl Picture {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// mpv options setting this picture; none for the defaults.
+    pub fn mpv_options(&self) -> Vec<String> {
+        let mut options: Vec<String> = self
+            .equalizer()
+            .into_iter()
+            .filter(|(_, value)| *value != 0)
+            .map(|(name, value)| format!("--{name}={value}"))
+            .collect();
+        if self.zoom != DEFAULT_ZOOM {
+            options.push(format!("--video-zoom={:.4}", zoom_to_mpv(self.zoom)));
+        }
+        options
+    }
+
+    fn equalizer(&self) -> [(&'static str, i8); 4] {
+        [
+            ("brightness", self.brightness),
+            ("contrast", self.contrast),
+            ("saturation", self.saturation),
+            ("gamma", self.gamma),
+        ]
+    }
+
+    /// What the player on `monitor` shows now.
+    pub fn read(monitor: &str) -> Result<Self, Box<dyn Error>> {
+        let level = |name: &str| -> Result<i8, Box<dyn Error>> {
+            let value = ipc::get_property(monitor, name)?;
+            let level = value
+                .as_f64()
+                .ok_or_else(|| format!("mpv reported {name} as {value}"))?;
+            Ok(level.round().clamp(-100.0, 100.0) as i8)
+        };
+        let zoom = ipc::get_property(monitor, "video-zoom")?;
+        Ok(Self {
+            brightness: level("brightness")?,
+            contrast: level("contrast")?,
+            saturation: level("saturation")?,
+            gamma: level("gamma")?,
+            zoom: zoom
+                .as_f64()
+                .map(zoom_from_mpv)
+                .ok_or_else(|| format!("mpv reported video-zoom as {zoom}"))?,
+        })
+    }
+}
+
+/// mpv's `video-zoom` is a power of two: 0 is unchanged, 1 doubles the size.
+fn zoom_to_mpv(percent: u16) -> f64 {
+    (f64::from(percent.max(1)) / 100.0).log2()
+}
+
+fn zoom_from_mpv(value: f64) -> u16 {
+    (value.exp2() * 100.0)
+        .round()
+        .clamp(1.0, f64::from(u16::MAX)) as u16
+}
+
+/// `[live_tweaks]` table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LiveTweakSettings {
+    /// Save picture changes made over IPC into the entry before its player goes away.
+    pub remember: bool,
+}
+
+impl LiveTweakSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Pictures of the players on `monitors` that differ from their saved entry. Players
+/// that do not answer (still launching, or already gone) are left out.
+pub fn changed<'a>(
+    entries: &[config::WallpaperProfileEntry],
+    monitors: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, Picture> {
+    let mut changed = BTreeMap::new();
+    for monitor in monitors {
+        let Some(entry) = entries
+            .iter()
+            .find(|entry| entry.monitor.as_deref() == Some(monitor))
+        else {
+            continue;
+        };
+        match Picture::read(monitor) {
+            Ok(picture) if picture != entry.picture => {
+                changed.insert(monitor.to_string(), picture);
+            }
+            Ok(_) => {}
+            Err(err) => debug!("Could not read the picture of {}: {}", monitor, err),
+        }
+    }
+    changed
+}
+
+/// Write the live pictures of the players on `monitors` into the config when
+/// `[live_tweaks] remember` is on and the config is not locked, returning the
+/// pictures that were saved.
+pub fn remember<'a>(monitors: impl IntoIterator<Item = &'a str>) -> BTreeMap<String, Picture> {
+    // A locked config keeps what it says, however the players were adjusted.
+    if !config::load_settings()
+        .is_ok_and(|settings| settings.live_tweaks.remember && !settings.locked)
+    {
+        return BTreeMap::new();
+    }
+    let mut entries = match config::load_wallpaper_entries() {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Could not load the config to remember live tweaks: {}", err);
+            return BTreeMap::new();
+        }
+    };
+    let changed = changed(&entries, monitors);
+    if changed.is_empty() {
+        return changed;
+    }
+    for entry in &mut entries {
+        if let Some(picture) = entry
+            .monitor
+            .as_deref()
+            .and_then(|monitor| changed.get(monitor))
+        {
+            entry.picture = *picture;
+        }
+    }
+    if let Err(err) = config::save_wallpaper_entries(&entries) {
+        warn!("Could not save live tweaks: {}", err);
+        return BTreeMap::new();
+    }
+    info!(
+        "Remembered live picture tweaks for {}",
+        changed.keys().cloned().collect::<Vec<_>>().join(", ")
+    );
+    changed
+}
@@ -0,0 +1,255 @@
+//! How often and how long each wallpaper file has been on screen.
+//!
+//! Whoever keeps the players running (the GUI or `wpe daemon`) asks each one over
+//! IPC which file it shows every [`SAMPLE_INTERVAL`], and adds the time to that
+//! file's record in `$XDG_STATE_HOME/wpe/usage.toml`. Paused time does not count,
+//! and players without IPC (the still-image backends) are not tracked.
+//! `wpe stats` reads the records back, and `order = "least-shown"` uses them to
+//! bring rarely shown files forward.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{config::WallpaperProfileEntry, ipc, queue, state};
+
+/// How often the players are asked what they show.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the collected time is written out.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One file's record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileUsage {
+    /// Times the file came on screen.
+    pub shows: u64,
+    /// Seconds it was on screen, unpaused.
+    pub seconds: u64,
+    /// Unix time it was last on screen.
+    pub last_shown: Option<u64>,
+}
+
+impl FileUsage {
+    fn add(&mut self, other: &FileUsage) {
+        self.shows += other.shows;
+        self.seconds += other.seconds;
+        self.last_shown = self.last_shown.max(other.last_shown);
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UsageFile {
+    #[serde(default)]
+    files: BTreeMap<PathBuf, FileUsage>,
+}
+
+/// What a monitor's player shows at one sample.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub monitor: String,
+    /// The file on screen; `None` when the player cannot be asked.
+    pub file: Option<PathBuf>,
+    /// Paused time does not count towards the file.
+    pub paused: bool,
+}
+
+/// Ask the players on `monitors` what they show. Blocks on each player's IPC socket.
+pub fn sample(monitors: Vec<String>) -> Vec<Sample> {
+    monitors
+        .into_iter()
+        .map(|monitor| {
+            let paused =
+                ipc::get_property(&monitor, "pause").is_ok_and(|value| value == Value::Bool(true));
+            let file = ipc::get_property(&monitor, "path")
+                .ok()
+                .and_then(|value| value.as_str().map(PathBuf::from));
+            Sample {
+                monitor,
+                file,
+                paused,
+            }
+        })
+        .collect()
+}
+
+/// Turns samples into time per file, and hands it out for saving now and then.
+#[derive(Debug)]
+pub struct Tracker {
+    /// Each monitor's file at the last sample, and when that was.
+    on_screen: HashMap<String, (PathBuf, Instant)>,
+    pending: BTreeMap<PathBuf, FileUsage>,
+    sampled: Instant,
+    flushed: Instant,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            on_screen: HashMap::new(),
+            pending: BTreeMap::new(),
+            sampled: now,
+            flushed: now,
+        }
+    }
+}
+
+impl Tracker {
+    /// Whether the players are due to be asked again.
+    pub fn is_due(&self) -> bool {
+        self.sampled.elapsed() >= SAMPLE_INTERVAL
+    }
+
+    /// Count the time since the last sample towards the files still on screen, and a
+    /// new show for each file that came on since. Returns those monitors with their
+    /// new file.
+    pub fn record(&mut self, samples: Vec<Sample>) -> Vec<(String, PathBuf)> {
+        let now = Instant::now();
+        self.sampled = now;
+        let unix_now = unix_now();
+        let mut on_screen = HashMap::new();
+        let mut changed = Vec::new();
+        for Sample {
+            monitor,
+            file,
+            paused,
+        } in samples
+        {
+            let Some(file) = file else {
+                continue;
+            };
+            let usage = self.pending.entry(file.clone()).or_default();
+            usage.last_shown = Some(unix_now);
+            let since = match self.on_screen.get(&monitor) {
+                Some((previous, _)) if *previous == file && paused => now,
+                Some((previous, since)) if *previous == file => {
+                    // Whole seconds are counted; the rest carries over to the next sample.
+                    let counted = Duration::from_secs(now.duration_since(*since).as_secs());
+                    usage.seconds += counted.as_secs();
+                    *since + counted
+                }
+                _ => {
+                    usage.shows += 1;
+                    changed.push((monitor.clone(), file.clone()));
+                    now
+                }
+            };
+            on_screen.insert(monitor, (file, since));
+        }
+        self.on_screen = on_screen;
+        changed
+    }
+
+    /// The time collected since the last flush, once [`FLUSH_INTERVAL`] has passed or
+    /// when `now` is set.
+    pub fn flush(&mut self, now: bool) -> Option<BTreeMap<PathBuf, FileUsage>> {
+        if self.pending.is_empty() || (!now && self.flushed.elapsed() < FLUSH_INTERVAL) {
+            return None;
+        }
+        self.flushed = Instant::now();
+        Some(std::mem::take(&mut self.pending))
+    }
+}
+
+fn usage_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(state::state_dir()?.join("usage.toml"))
+}
+
+/// Every file's record.
+pub fn load() -> BTreeMap<PathBuf, FileUsage> {
+    let Ok(path) = usage_path() else {
+        return BTreeMap::new();
+    };
+    read(&path).files
+}
+
+fn read(path: &Path) -> UsageFile {
+    let Ok(data) = fs::read_to_string(path) else {
+        return UsageFile::default();
+    };
+    toml::from_str(&data).unwrap_or_else(|err| {
+        warn!("Ignoring unreadable usage file {}: {}", path.display(), err);
+        UsageFile::default()
+    })
+}
+
+/// Add `collected` (from [`Tracker::flush`]) to the records on disk.
+pub fn save(collected: BTreeMap<PathBuf, FileUsage>) {
+    let result = usage_path().and_then(|path| {
+        let mut usage = read(&path);
+        for (file, added) in &collected {
+            usage.files.entry(file.clone()).or_default().add(added);
+        }
+        Ok(fs::write(path, toml::to_string(&usage)?)?)
+    });
+    if let Err(err) = result {
+        warn!("Could not save wallpaper usage: {}", err);
+    }
+}
+
+/// `files` shuffled so that rarely shown ones tend to come first: each is weighted by
+/// 1 / (1 + times shown), so a file never shown is drawn early twice as often as one
+/// shown once.
+pub fn least_shown_order(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let usage = load();
+    // Weighted shuffle (Efraimidis-Spirakis): sort by u^(1/weight), largest first.
+    let mut keyed: Vec<(f64, PathBuf)> = files
+        .into_iter()
+        .map(|file| {
+            let shows = usage.get(&file).map_or(0, |usage| usage.shows);
+            let key = fastrand::f64().powf(1.0 + shows as f64);
+            (key, file)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().map(|(_, file)| file).collect()
+}
+
+/// Files the entries play that have no record: single files, and the files of folders.
+pub fn never_shown(
+    entries: &[WallpaperProfileEntry],
+    records: &BTreeMap<PathBuf, FileUsage>,
+) -> Vec<PathBuf> {
+    let mut files = BTreeSet::new();
+    for entry in entries {
+        for path in entry.configured_paths() {
+            if path.is_dir() {
+                match queue::list_files(path, &entry.folder_filter) {
+                    Ok(listing) => files.extend(listing),
+                    Err(err) => warn!("Could not list {}: {}", path.display(), err),
+                }
+            } else if path.is_file() {
+                files.insert(path.to_path_buf());
+            }
+        }
+    }
+    files
+        .into_iter()
+        .filter(|file| !records.contains_key(file))
+        .collect()
+}
+
+/// `seconds` as hours and minutes, e.g. "3h 05m", or just minutes under an hour.
+pub fn format_seconds(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{minutes}m")
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |age| age.as_secs())
+}
@@ -0,0 +1,121 @@
+//! Pick the encode of a wallpaper that best matches a monitor's mode.
+//!
+//! A folder holding several encodes of the same wallpaper, named like
+//! `ocean-1080p60.mp4` and `ocean-4k30.webm`, is collapsed to the single file
+//! whose resolution and framerate fit the target output best, instead of being
+//! played as a slideshow of near-identical clips.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{self, VariantSettings},
+    monitors::Monitor,
+};
+
+/// Resolution/framerate parsed from a file name suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VariantTag {
+    height: u32,
+    fps: Option<u32>,
+}
+
+/// If the tagged videos in `folder` are encodes of the same wallpaper, return the best one for
+/// `monitor`. Other files are ignored.
+pub fn select_variant(
+    folder: &Path,
+    monitor: &Monitor,
+    settings: &VariantSettings,
+) -> Option<PathBuf> {
+    if !settings.enabled {
+        return None;
+    }
+
+    let mut base_name: Option<String> = None;
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(folder).ok()?.filter_map(Result::ok) {
+        let path = entry.path();
+        // Cover art, subtitles, and clips without a tag can sit beside the encodes.
+        if !path.is_file() || !config::is_probably_video(&path) {
+            continue;
+        }
+        let Some((base, tag)) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit_once(settings.separator.as_str()))
+            .and_then(|(base, tag)| Some((base, parse_tag(tag)?)))
+        else {
+            continue;
+        };
+        // Encodes of different wallpapers make a slideshow.
+        match &base_name {
+            Some(existing) if existing != base => return None,
+            Some(_) => {}
+            None => base_name = Some(base.to_string()),
+        }
+        candidates.push((path, tag));
+    }
+
+    if candidates.len() < 2 {
+        return None;
+    }
+
+    candidates
+        .into_iter()
+        .min_by_key(|(path, tag)| (score(*tag, monitor), path.clone()))
+        .map(|(path, _)| path)
+}
+
+/// Lower is better: resolution fit first, then framerate fit.
+fn score(tag: VariantTag, monitor: &Monitor) -> (u32, u32) {
+    // Upscaling looks worse than decoding a little more than needed, so weigh it double.
+    let resolution = if tag.height >= monitor.height {
+        tag.height - monitor.height
+    } else {
+        (monitor.height - tag.height) * 2
+    };
+
+    let refresh = monitor.refresh_rate.max(1);
+    let framerate = match tag.fps {
+        // An even divisor of the refresh rate paces cleanly; anything above it is wasted decode.
+        Some(fps) if fps > refresh => (fps - refresh) * 4,
+        Some(fps) if refresh.is_multiple_of(fps.max(1)) => refresh / fps.max(1) - 1,
+        Some(fps) => refresh - fps + refresh,
+        None => refresh,
+    };
+
+    (resolution, framerate)
+}
+
+/// Parse tags like `1080p`, `1080p60`, `4k30`, or `2160p60fps`.
+fn parse_tag(tag: &str) -> Option<VariantTag> {
+    let lower = tag.to_ascii_lowercase();
+    let digits_end = lower.find(|ch: char| !ch.is_ascii_digit())?;
+    let (number, rest) = lower.split_at(digits_end);
+    let number: u32 = number.parse().ok()?;
+
+    let (height, rest) = if let Some(rest) = rest.strip_prefix('p') {
+        (number, rest)
+    } else if let Some(rest) = rest.strip_prefix('k') {
+        let height = match number {
+            2 => 1440,
+            4 => 2160,
+            5 => 2880,
+            8 => 4320,
+            _ => return None,
+        };
+        (height, rest)
+    } else {
+        return None;
+    };
+
+    let rest = rest.strip_suffix("fps").unwrap_or(rest);
+    let fps = if rest.is_empty() {
+        None
+    } else {
+        Some(rest.parse().ok()?)
+    };
+    Some(VariantTag { height, fps })
+}
@@ -0,0 +1,196 @@
+//! Restarting players that crash.
+//!
+//! Whoever keeps the players running (the GUI or `wpe daemon`) tells the watchdog
+//! when one exits on its own. The watchdog waits before each restart, twice as long
+//! as the last time, and gives up on an output after `[watchdog] max_restarts`
+//! restarts in a row. A player that keeps running for [`STABLE_AFTER`] wipes the
+//! slate, so a crash a day from now starts again at the shortest wait. Players
+//! stopped on purpose are never restarted, and `wpe -c` leaves nothing behind to
+//! watch, so wallpapers launched that way need the daemon for this.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::config::WallpaperProfileEntry;
+
+/// How long a restarted player has to keep running to count as recovered.
+const STABLE_AFTER: Duration = Duration::from_secs(120);
+/// The longest wait between two restarts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// `[watchdog]` table: restart wallpapers whose player crashed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    /// Restarts in a row before an output is given up on.
+    pub max_restarts: u32,
+    /// Seconds before the first restart; each further one waits twice as long.
+    pub backoff_seconds: u64,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_restarts: 5,
+            backoff_seconds: 2,
+        }
+    }
+}
+
+impl WatchdogSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// How long to wait before restart number `attempt` (counting from 1).
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_secs(self.backoff_seconds.saturating_mul(factor)).min(MAX_BACKOFF)
+    }
+}
+
+/// What the watchdog makes of a player exiting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Restart number `attempt` comes after `after`.
+    Restart { attempt: u32, after: Duration },
+    /// `[watchdog] max_restarts` restarts did not help, or the watchdog is off.
+    GiveUp { restarts: u32 },
+}
+
+/// One output's crash history.
+#[derive(Debug)]
+struct Record {
+    entry: WallpaperProfileEntry,
+    /// Restarts in a row so far.
+    restarts: u32,
+    /// When the next restart is due; `None` once restarted or given up on.
+    due: Option<Instant>,
+    /// When the last restart came up.
+    restarted: Option<Instant>,
+    gave_up: bool,
+}
+
+/// Crashed players waiting for their restart, per output.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    records: HashMap<String, Record>,
+}
+
+impl Watchdog {
+    /// Note that the player showing `entry` on `monitor` exited on its own.
+    pub fn exited(
+        &mut self,
+        monitor: &str,
+        entry: &WallpaperProfileEntry,
+        settings: &WatchdogSettings,
+    ) -> Verdict {
+        let record = self
+            .records
+            .entry(monitor.to_string())
+            .or_insert_with(|| Record {
+                entry: entry.clone(),
+                restarts: 0,
+                due: None,
+                restarted: None,
+                gave_up: false,
+            });
+        if record
+            .restarted
+            .is_some_and(|restarted| restarted.elapsed() >= STABLE_AFTER)
+        {
+            record.restarts = 0;
+        }
+        record.entry = entry.clone();
+        record.restarted = None;
+
+        if !settings.enabled || record.restarts >= settings.max_restarts {
+            record.due = None;
+            record.gave_up = true;
+            error!(
+                "The wallpaper on {} exited; not restarting it after {} restart(s)",
+                monitor, record.restarts
+            );
+            return Verdict::GiveUp {
+                restarts: record.restarts,
+            };
+        }
+        let attempt = record.restarts + 1;
+        let after = settings.backoff(attempt);
+        record.due = Some(Instant::now() + after);
+        warn!(
+            "The wallpaper on {} exited; restarting it in {}s (attempt {} of {})",
+            monitor,
+            after.as_secs(),
+            attempt,
+            settings.max_restarts
+        );
+        Verdict::Restart { attempt, after }
+    }
+
+    /// The entries whose restart is due, by output. Each is handed out once; report
+    /// the outcome with [`Watchdog::restarted`] or another [`Watchdog::exited`].
+    pub fn due(&mut self) -> Vec<(String, WallpaperProfileEntry)> {
+        let now = Instant::now();
+        self.records
+            .iter_mut()
+            .filter(|(_, record)| record.due.is_some_and(|due| due <= now))
+            .map(|(monitor, record)| {
+                record.due = None;
+                record.restarts += 1;
+                (monitor.clone(), record.entry.clone())
+            })
+            .collect()
+    }
+
+    /// The restart of `monitor` came up.
+    pub fn restarted(&mut self, monitor: &str) {
+        if let Some(record) = self.records.get_mut(monitor) {
+            record.restarted = Some(Instant::now());
+        }
+    }
+
+    /// Drop `monitor`'s history, e.g. because its wallpaper was stopped or replaced on
+    /// purpose. Returns whether a restart was pending.
+    pub fn forget(&mut self, monitor: &str) -> bool {
+        self.records
+            .remove(monitor)
+            .is_some_and(|record| record.due.is_some())
+    }
+
+    /// Drop every output's history.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Outputs waiting for a restart or given up on, with what happened.
+    pub fn troubles(&self) -> Vec<(String, String)> {
+        let now = Instant::now();
+        let mut troubles: Vec<(String, String)> = self
+            .records
+            .iter()
+            .filter_map(|(monitor, record)| {
+                let note = if record.gave_up {
+                    format!("crashed; gave up after {} restart(s)", record.restarts)
+                } else {
+                    let due = record.due?;
+                    format!(
+                        "crashed; restarting in {}s",
+                        due.saturating_duration_since(now).as_secs()
+                    )
+                };
+                Some((monitor.clone(), note))
+            })
+            .collect();
+        troubles.sort();
+        troubles
+    }
+}
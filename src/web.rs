@@ -0,0 +1,143 @@
+//! Web wallpapers: HTML/WebGL pages drawn by an external renderer.
+//!
+//! wpe carries no browser engine. With `backend = "web"`, each output runs the
+//! `[web] command` the user configured, some program that opens a page on a
+//! background layer surface (a WebKitGTK or Chromium-embedded viewer built with
+//! gtk-layer-shell, for instance). The entry's `path` is the page: a URL, a local
+//! `.html` file, or a folder with an `index.html` such as a Wallpaper Engine web
+//! project. The players are marked with [`OUTPUT_ENV`] in their environment,
+//! which is how they are found again to be stopped.
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MediaKind;
+
+/// Names the output a web renderer was started for, in its environment.
+pub const OUTPUT_ENV: &str = "WPE_WEB_OUTPUT";
+
+/// `[web]` table: the program that draws web pages on an output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WebSettings {
+    /// Program and arguments; `{output}`, `{url}`, `{width}`, and `{height}` are
+    /// filled in for each wallpaper.
+    pub command: Vec<String>,
+}
+
+impl WebSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the configured program is in PATH, or at the path given.
+    pub fn installed(&self) -> bool {
+        let Some(program) = self.command.first() else {
+            return false;
+        };
+        if program.contains('/') {
+            return Path::new(program).is_file();
+        }
+        env::var_os("PATH")
+            .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+    }
+
+    /// The command line showing `url` on `monitor`, `size` pixels large if known.
+    pub fn command(
+        &self,
+        url: &str,
+        monitor: &str,
+        size: Option<(u32, u32)>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        if self.command.is_empty() {
+            return Err("backend = \"web\" needs a [web] command to draw the page".into());
+        }
+        let (width, height) = size.unwrap_or_default();
+        Ok(self
+            .command
+            .iter()
+            .map(|arg| {
+                arg.replace("{output}", monitor)
+                    .replace("{url}", url)
+                    .replace("{width}", &width.to_string())
+                    .replace("{height}", &height.to_string())
+            })
+            .collect())
+    }
+}
+
+/// The page `media` points at: a URL as it is, or a local page as a `file://` URL.
+pub fn page_url(media: &MediaKind) -> Result<String, String> {
+    let page = match media {
+        MediaKind::Stream(url) => return Ok(url.clone()),
+        MediaKind::Folder(folder) => folder.join("index.html"),
+        MediaKind::Image(path) | MediaKind::Video(path) if is_page(path) => path.clone(),
+        other => {
+            return Err(format!(
+                "the web renderer shows web pages, not {}; point path at a URL, an .html file, or a folder with an index.html",
+                other.path().display()
+            ));
+        }
+    };
+    if !page.is_file() {
+        return Err(format!("{} does not exist", page.display()));
+    }
+    Ok(format!("file://{}", page.display()))
+}
+
+fn is_page(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+/// Stop the web renderers started for `monitor`, or all of them, returning whether
+/// any were running.
+pub fn stop(monitor: Option<&str>) -> Result<bool, Box<dyn Error>> {
+    let pids = renderers(monitor);
+    if pids.is_empty() {
+        return Ok(false);
+    }
+    Command::new("kill")
+        .args(pids.iter().map(u32::to_string))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .map_err(|err| format!("Failed to issue kill: {}", err).into())
+}
+
+/// Processes whose environment names them a web renderer for `monitor` (any with
+/// `None`).
+fn renderers(monitor: Option<&str>) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let marker = match monitor {
+        Some(monitor) => format!("{OUTPUT_ENV}={monitor}"),
+        None => format!("{OUTPUT_ENV}="),
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            // Other users' environments are unreadable, which leaves them out as well.
+            let environ = fs::read(entry.path().join("environ")).ok()?;
+            environ
+                .split(|byte| *byte == 0)
+                .any(|var| match monitor {
+                    Some(_) => var == marker.as_bytes(),
+                    None => var.starts_with(marker.as_bytes()),
+                })
+                .then_some(pid)
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! Short-lived worker processes for reading untrusted media.
+//!
+//! Decoding a corrupt or hostile file can hang a decoder or crash it outright, so
+//! wpe never does it in the process that runs the GUI or the daemon. Media probes
+//! and previews run in a child at idle priority that is killed when it outlives
+//! its timeout; with `[security] sandbox` on, the child is also confined with
+//! bubblewrap to the file it reads, without the compositor, the GPU, or the
+//! network.
+
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    io::{self, ErrorKind, Read, Write},
+    path::Path,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    config::{self, IoClass, ProcessSettings},
+    sandbox, thumbnails,
+};
+
+/// How long a worker may take before it is killed.
+pub const TIMEOUT: Duration = Duration::from_secs(20);
+/// How often a running worker is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What a `wpe worker` process does with its file.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum WorkerTask {
+    /// Write a PNG preview of the image or video to stdout.
+    Thumbnail,
+}
+
+/// A command running `program` at idle priority, sandboxed to `media` when
+/// `[security] sandbox` is on.
+pub fn command(program: &Path, media: &Path) -> Result<Command, Box<dyn Error>> {
+    let mut argv: Vec<OsString> = ProcessSettings {
+        nice: Some(19),
+        ionice: Some(IoClass::Idle),
+        ..ProcessSettings::default()
+    }
+    .wrapper_args()
+    .into_iter()
+    .map(OsString::from)
+    .collect();
+    if config::load_settings()?.security.sandbox {
+        argv.extend(sandbox::wrap_worker(program, &[media])?);
+    } else {
+        argv.push(program.into());
+    }
+
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    Ok(command)
+}
+
+/// Run `wpe worker <task> <media>` and return what it wrote.
+pub fn run(task: WorkerTask, media: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut command = command(&env::current_exe()?, media)?;
+    command
+        .arg("worker")
+        .arg(
+            task.to_possible_value()
+                .expect("no task is skipped")
+                .get_name(),
+        )
+        .arg(media);
+    output(command, TIMEOUT)
+}
+
+/// The body of `wpe worker`: do `task` for `path` and write the result to stdout.
+pub fn serve(task: WorkerTask, path: &Path) -> Result<(), Box<dyn Error>> {
+    let bytes = match task {
+        WorkerTask::Thumbnail => thumbnails::render(path)?,
+    };
+    io::stdout().lock().write_all(&bytes)?;
+    Ok(())
+}
+
+/// Run `command` to completion and return its stdout, killing it after `timeout`.
+pub fn output(mut command: Command, timeout: Duration) -> Result<Vec<u8>, Box<dyn Error>> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            ErrorKind::NotFound => format!("{program} was not found in PATH"),
+            _ => format!("Could not run {program}: {err}"),
+        })?;
+    // Drain both pipes meanwhile, so a chatty child never blocks on a full one.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = wait(&mut child, timeout).map_err(|err| format!("{program} {err}"))?;
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(format!(
+            "{program} failed ({status}): {}",
+            String::from_utf8_lossy(&stderr).trim()
+        )
+        .into());
+    }
+    Ok(stdout)
+}
+
+/// Wait for `child`, killing it once `timeout` has passed.
+fn wait(child: &mut Child, timeout: Duration) -> Result<ExitStatus, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("did not finish within {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
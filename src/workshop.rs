@@ -0,0 +1,163 @@
+//! Import Wallpaper Engine projects from a Steam workshop library.
+//!
+//! A project is a folder holding a `project.json` that names its media file, title,
+//! type, and tags. Video projects play in mpv as they are; scene, web, and
+//! application projects need Wallpaper Engine's own renderer and are skipped.
+//! Imported files are tagged [`IMPORT_TAG`] plus the project's own tags, so a whole
+//! library can play through `source = { tags = ["workshop"] }`.
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    config::{self, EntrySource},
+    state, tags,
+};
+
+const PROJECT_FILE: &str = "project.json";
+/// Steam app id of Wallpaper Engine, naming its workshop folder.
+const APP_ID: &str = "431960";
+/// Tag every imported file carries.
+pub const IMPORT_TAG: &str = "workshop";
+
+/// The parts of `project.json` wpe uses.
+#[derive(Debug, Deserialize)]
+struct ProjectFile {
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A video project ready to play.
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub title: String,
+    pub media: PathBuf,
+    pub tags: Vec<String>,
+}
+
+/// What [`import`] did.
+#[derive(Debug, Default)]
+pub struct Import {
+    pub imported: Vec<Project>,
+    /// Project folders left out, with the reason.
+    pub skipped: Vec<(PathBuf, String)>,
+    /// The output whose entry now plays the import.
+    pub monitor: Option<String>,
+}
+
+/// Steam's workshop folder for Wallpaper Engine, native or Flatpak.
+pub fn default_library() -> Option<PathBuf> {
+    let home = PathBuf::from(env::var_os("HOME")?);
+    let content = Path::new("steamapps/workshop/content").join(APP_ID);
+    [
+        home.join(".local/share/Steam"),
+        home.join(".steam/steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ]
+    .into_iter()
+    .map(|steam| steam.join(&content))
+    .find(|library| library.is_dir())
+}
+
+/// Read one project folder.
+pub fn read_project(dir: &Path) -> Result<Project, Box<dyn Error>> {
+    let data = fs::read_to_string(dir.join(PROJECT_FILE))?;
+    let project: ProjectFile = serde_json::from_str(&data)
+        .map_err(|err| format!("Failed to parse {}: {}", PROJECT_FILE, err))?;
+    let title = project
+        .title
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| dir.file_name().unwrap_or_default().to_string_lossy().into());
+    let kind = project.kind.unwrap_or_default().to_ascii_lowercase();
+    if !kind.is_empty() && kind != "video" {
+        return Err(format!(
+            "{title} is a {kind} project; only video projects play outside Wallpaper Engine"
+        )
+        .into());
+    }
+    let file = project
+        .file
+        .ok_or_else(|| format!("{title} does not name a media file"))?;
+    let media = dir.join(file);
+    if !media.is_file() || !config::is_probably_video(&media) {
+        return Err(format!("{title}'s video {} is missing", media.display()).into());
+    }
+    Ok(Project {
+        title,
+        media: fs::canonicalize(&media).unwrap_or(media),
+        tags: project.tags,
+    })
+}
+
+/// `root` itself when it is a project, otherwise the project folders directly in it.
+fn find_projects(root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if root.join(PROJECT_FILE).is_file() {
+        return Ok(vec![root.to_path_buf()]);
+    }
+    let mut projects: Vec<PathBuf> = fs::read_dir(root)
+        .map_err(|err| format!("Unable to read {}: {}", root.display(), err))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join(PROJECT_FILE).is_file())
+        .collect();
+    projects.sort();
+    Ok(projects)
+}
+
+/// Tag the video projects under `root` and, with `monitor`, make its entry play them:
+/// a single project as its `path`, several through the [`IMPORT_TAG`] tag.
+pub fn import(root: &Path, monitor: Option<&str>) -> Result<Import, Box<dyn Error>> {
+    let dirs = find_projects(root)?;
+    if dirs.is_empty() {
+        return Err(format!("No {} found in {}", PROJECT_FILE, root.display()).into());
+    }
+
+    let mut import = Import::default();
+    for dir in dirs {
+        match read_project(&dir) {
+            Ok(project) => import.imported.push(project),
+            Err(err) => import.skipped.push((dir, err.to_string())),
+        }
+    }
+    if import.imported.is_empty() {
+        return Ok(import);
+    }
+
+    let mut state = state::load_state();
+    for project in &import.imported {
+        let mut labels = project.tags.clone();
+        labels.push(IMPORT_TAG.to_string());
+        tags::add_tags(&mut state, &project.media, &labels);
+    }
+    state::save_state(&state)?;
+
+    if let Some(monitor) = monitor {
+        let mut entries = config::load_wallpaper_entries()?;
+        let entry = config::entry_for_monitor(&mut entries, monitor);
+        entry.enabled = true;
+        if let [project] = import.imported.as_slice() {
+            entry.path = Some(project.media.clone());
+            entry.source = None;
+        } else {
+            entry.source = Some(EntrySource {
+                tags: vec![IMPORT_TAG.to_string()],
+                ..EntrySource::default()
+            });
+        }
+        config::save_wallpaper_entries(&entries)?;
+        import.monitor = Some(monitor.to_string());
+    }
+    Ok(import)
+}